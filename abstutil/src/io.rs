@@ -1,3 +1,4 @@
+use crate::paths::legacy_paths;
 use crate::time::{clear_current_line, prettyprint_time};
 use crate::{elapsed_seconds, prettyprint_usize, MultiMap, Timer, PROGRESS_FREQUENCY_SECONDS};
 use bincode;
@@ -11,6 +12,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::{stdout, BufReader, BufWriter, Error, ErrorKind, Read, Write};
 use std::path::Path;
+use std::time::SystemTime;
 
 #[cfg(target_arch = "wasm32")]
 static SYSTEM_DATA: include_dir::Dir = include_dir::include_dir!("../data/system");
@@ -19,17 +21,114 @@ pub fn to_json<T: Serialize>(obj: &T) -> String {
     serde_json::to_string_pretty(obj).unwrap()
 }
 
+// Writing the player's own files keeps the previous version around as this.
+pub fn backup_path(path: &str) -> String {
+    format!("{}.bak", path)
+}
+
+// Hidden, so listing objects skips it
+fn temp_path(path: &str) -> String {
+    let path = Path::new(path);
+    path.with_file_name(format!(
+        ".{}.tmp",
+        path.file_name().unwrap().to_string_lossy()
+    ))
+    .to_string_lossy()
+    .to_string()
+}
+
+// Backups and half-written temporary files aren't objects themselves.
+fn is_scratch_file(filename: &str) -> bool {
+    filename.starts_with('.') || filename.ends_with(".bak")
+}
+
+// Edits, savestates, and the like are worth a backup. Everything else can be regenerated or
+// downloaded again.
+fn is_player_data(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .any(|c| c.as_os_str() == "player")
+}
+
+// Writes to a temporary file in the same directory, then renames it over the destination, so a
+// crash partway through never leaves a corrupt file behind. The destination always exists, even
+// while the backup's being made.
+pub(crate) fn write_atomically<F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>>(
+    path: &str,
+    write: F,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
+        .expect("Creating parent dir failed");
+
+    let tmp = temp_path(path);
+    let result = File::create(&tmp).and_then(|file| {
+        let mut file = BufWriter::new(file);
+        write(&mut file)?;
+        file.flush()?;
+        file.get_ref().sync_all()
+    });
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(err);
+    }
+
+    if is_player_data(path) && Path::new(path).exists() {
+        let backup = backup_path(path);
+        let _ = std::fs::remove_file(&backup);
+        if std::fs::hard_link(path, &backup).is_err() {
+            std::fs::copy(path, &backup)?;
+        }
+    }
+    std::fs::rename(&tmp, path)
+}
+
+// Files saved before the data layout changed are still in their old place.
+fn resolve_legacy(path: &str) -> String {
+    if Path::new(path).exists() {
+        return path.to_string();
+    }
+    legacy_paths(path)
+        .into_iter()
+        .find(|p| Path::new(p).exists())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// If the file is missing or doesn't deserialize, try the backup from the previous write. Readers
+// return InvalidInput for files written by a newer version, which aren't corrupt, just unreadable.
+pub(crate) fn or_backup<T, F: Fn(&str, &mut Timer) -> Result<T, Error>>(
+    path: &str,
+    timer: &mut Timer,
+    read: F,
+) -> Result<T, Error> {
+    let path = &resolve_legacy(path);
+    let err = match read(path, timer) {
+        Ok(obj) => {
+            return Ok(obj);
+        }
+        Err(err) => err,
+    };
+    let backup = backup_path(path);
+    if err.kind() == ErrorKind::InvalidInput || !Path::new(&backup).exists() {
+        return Err(err);
+    }
+    match read(&backup, timer) {
+        Ok(obj) => {
+            timer.warn(format!(
+                "Couldn't read {} ({}), so using the backup {}",
+                path, err, backup
+            ));
+            Ok(obj)
+        }
+        Err(_) => Err(err),
+    }
+}
+
 // TODO Idea: Have a wrapper type DotJSON(...) and DotBin(...) to distinguish raw path strings
 fn maybe_write_json<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
     if !path.ends_with(".json") {
         panic!("write_json needs {} to end with .json", path);
     }
-    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
-        .expect("Creating parent dir failed");
-
-    let mut file = File::create(path)?;
-    file.write_all(to_json(obj).as_bytes())?;
-    Ok(())
+    write_atomically(path, |file| file.write_all(to_json(obj).as_bytes()))
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -70,13 +169,15 @@ pub fn maybe_read_json<T: DeserializeOwned>(path: String, timer: &mut Timer) ->
         panic!("read_json needs {} to end with .json or .geojson", path);
     }
 
-    timer.start(format!("parse {}", path));
-    // TODO timer.read_file isn't working here. And we need to call stop() if there's no file.
-    let result: Result<T, Error> = slurp_file(&path).and_then(|raw| {
-        serde_json::from_slice(&raw).map_err(|err| Error::new(ErrorKind::Other, err))
-    });
-    timer.stop(format!("parse {}", path));
-    result
+    or_backup(&path, timer, |path, timer| {
+        timer.start(format!("parse {}", path));
+        // TODO timer.read_file isn't working here. And we need to call stop() if there's no file.
+        let result: Result<T, Error> = slurp_file(path).and_then(|raw| {
+            serde_json::from_slice(&raw).map_err(|err| Error::new(ErrorKind::Other, err))
+        });
+        timer.stop(format!("parse {}", path));
+        result
+    })
 }
 
 pub fn read_json<T: DeserializeOwned>(path: String, timer: &mut Timer) -> T {
@@ -91,17 +192,19 @@ fn maybe_write_binary<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
         panic!("write_binary needs {} to end with .bin", path);
     }
 
-    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
-        .expect("Creating parent dir failed");
-
-    let file = BufWriter::new(File::create(path)?);
-    bincode::serialize_into(file, obj).map_err(|err| Error::new(ErrorKind::Other, err))
+    write_atomically(path, |file| {
+        bincode::serialize_into(file, obj).map_err(|err| Error::new(ErrorKind::Other, err))
+    })
 }
 
 pub fn serialized_size_bytes<T: Serialize>(obj: &T) -> usize {
     bincode::serialized_size(obj).unwrap() as usize
 }
 
+pub fn to_binary<T: Serialize>(obj: &T) -> Vec<u8> {
+    bincode::serialize(obj).unwrap()
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn write_binary<T: Serialize>(path: String, obj: &T) {
     if let Err(err) = maybe_write_binary(&path, obj) {
@@ -121,10 +224,14 @@ pub fn maybe_read_binary<T: DeserializeOwned>(path: String, timer: &mut Timer) -
         panic!("read_binary needs {} to end with .bin", path);
     }
 
-    timer.read_file(&path)?;
-    let obj: T =
-        bincode::deserialize_from(timer).map_err(|err| Error::new(ErrorKind::Other, err))?;
-    Ok(obj)
+    or_backup(&path, timer, |path, timer| {
+        timer.read_file(path)?;
+        let result = bincode::deserialize_from(&mut *timer);
+        result.map_err(|err| {
+            timer.stop_reading_file();
+            Error::new(ErrorKind::Other, err)
+        })
+    })
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -206,30 +313,144 @@ pub fn deserialize_multimap<
     Ok(map)
 }
 
-// Just list all things from a directory, return sorted by name, with file extension removed.
+// A saved object, without loading it
+#[derive(Clone, Debug)]
+pub struct ObjectMetadata {
+    // File extension removed
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    // Unknown in wasm
+    pub modified: Option<SystemTime>,
+}
+
+impl ObjectMetadata {
+    // Like "3 MB, modified 2 days ago"
+    pub fn describe(&self) -> String {
+        let size = if self.size_bytes >= 1024 * 1024 {
+            format!(
+                "{} MB",
+                prettyprint_usize((self.size_bytes / 1024 / 1024) as usize)
+            )
+        } else {
+            format!("{} KB", (self.size_bytes + 1023) / 1024)
+        };
+        match self
+            .modified
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+        {
+            Some(age) => format!("{}, modified {}", size, describe_age(age.as_secs())),
+            None => size,
+        }
+    }
+}
+
+fn describe_age(secs: u64) -> String {
+    if secs < 60 {
+        return "just now".to_string();
+    }
+    let (amount, unit) = if secs < 60 * 60 {
+        (secs / 60, "minute")
+    } else if secs < 24 * 60 * 60 {
+        (secs / 60 / 60, "hour")
+    } else {
+        (secs / 24 / 60 / 60, "day")
+    };
+    format!(
+        "{} {}{} ago",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+    )
+}
+
+// Sorted by name. Nothing is deserialized, so this is cheap even for big files.
 #[cfg(not(target_arch = "wasm32"))]
-pub fn list_all_objects(dir: String) -> Vec<String> {
-    let mut results: BTreeSet<String> = BTreeSet::new();
-    match std::fs::read_dir(dir) {
-        Ok(iter) => {
-            for entry in iter {
-                let filename = entry.unwrap().file_name();
-                let path = Path::new(&filename);
-                if path.to_string_lossy().starts_with('.') {
-                    continue;
+pub fn list_objects(dir: String) -> Vec<ObjectMetadata> {
+    let mut results = Vec::new();
+    let mut seen = BTreeSet::new();
+    // If a file is in both places, the new one wins
+    for (idx, d) in std::iter::once(dir.clone())
+        .chain(legacy_paths(&dir))
+        .enumerate()
+    {
+        match std::fs::read_dir(&d) {
+            Ok(iter) => {
+                for entry in iter {
+                    let entry = entry.unwrap();
+                    let filename = entry.file_name().to_string_lossy().to_string();
+                    if is_scratch_file(&filename) || seen.contains(&filename) {
+                        continue;
+                    }
+                    let metadata = entry.metadata().ok();
+                    // Old shared directories also hold the new per-map ones
+                    if idx > 0 && !metadata.as_ref().map(|m| m.is_file()).unwrap_or(false) {
+                        continue;
+                    }
+                    seen.insert(filename.clone());
+                    results.push(ObjectMetadata {
+                        name: basename(&filename),
+                        path: format!("{}/{}", d, filename),
+                        size_bytes: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                        modified: metadata.and_then(|m| m.modified().ok()),
+                    });
                 }
-                let name = path
-                    .file_stem()
-                    .unwrap()
-                    .to_os_string()
-                    .into_string()
-                    .unwrap();
-                results.insert(name);
             }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => panic!(e),
+        };
+    }
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    results
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn list_objects(dir: String) -> Vec<ObjectMetadata> {
+    let mut results = Vec::new();
+    if let Some(d) = SYSTEM_DATA.get_dir(dir.trim_start_matches("../data/system/")) {
+        for f in d.files() {
+            let filename = f.path().file_name().unwrap().to_string_lossy().to_string();
+            results.push(ObjectMetadata {
+                name: basename(&filename),
+                path: format!("{}/{}", dir, filename),
+                size_bytes: f.contents().len() as u64,
+                modified: None,
+            });
+        }
+    }
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    results
+}
+
+// Loads one thing listed by list_objects. Detects JSON or binary.
+pub fn load_object<T: DeserializeOwned>(
+    dir: String,
+    name: &str,
+    timer: &mut Timer,
+) -> Result<T, String> {
+    let path = match list_objects(dir.clone())
+        .into_iter()
+        .find(|m| m.name == name)
+    {
+        Some(m) => m.path,
+        None => {
+            return Err(format!("{} isn't in {}", name, dir));
         }
-        Err(ref e) if e.kind() == ErrorKind::NotFound => {}
-        Err(e) => panic!(e),
     };
+    let result = if path.ends_with(".json") {
+        maybe_read_json(path.clone(), timer)
+    } else if path.ends_with(".bin") {
+        maybe_read_binary(path.clone(), timer)
+    } else {
+        return Err(format!("Don't know what {} is", path));
+    };
+    result.map_err(|err| format!("Couldn't read {}: {}", path, err))
+}
+
+// Just list all things from a directory, return sorted by name, with file extension removed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_all_objects(dir: String) -> Vec<String> {
+    let results: BTreeSet<String> = list_objects(dir).into_iter().map(|m| m.name).collect();
     results.into_iter().collect()
 }
 
@@ -252,35 +473,16 @@ pub fn list_all_objects(dir: String) -> Vec<String> {
 pub fn load_all_objects<T: DeserializeOwned>(dir: String) -> Vec<(String, T)> {
     let mut timer = Timer::new(format!("load_all_objects from {}", dir));
     let mut tree: BTreeMap<String, T> = BTreeMap::new();
-    match std::fs::read_dir(&dir) {
-        Ok(iter) => {
-            for entry in iter {
-                let filename = entry.unwrap().file_name();
-                let path = Path::new(&filename);
-                let path_str = path.to_string_lossy();
-                if path_str.starts_with('.') {
-                    continue;
-                }
-                let full_path = format!("{}/{}", dir, path_str);
-                let name = path
-                    .file_stem()
-                    .unwrap()
-                    .to_os_string()
-                    .into_string()
-                    .unwrap();
-                let load: T = if path_str.ends_with(".json") {
-                    read_json(full_path, &mut timer)
-                } else if path_str.ends_with(".bin") {
-                    read_binary(full_path, &mut timer)
-                } else {
-                    panic!("Don't know what {} is", full_path);
-                };
-                tree.insert(name, load);
-            }
-        }
-        Err(ref e) if e.kind() == ErrorKind::NotFound => {}
-        Err(e) => panic!(e),
-    };
+    for m in list_objects(dir) {
+        let load: T = if m.path.ends_with(".json") {
+            read_json(m.path, &mut timer)
+        } else if m.path.ends_with(".bin") {
+            read_binary(m.path, &mut timer)
+        } else {
+            panic!("Don't know what {} is", m.path);
+        };
+        tree.insert(m.name, load);
+    }
     tree.into_iter().collect()
 }
 
@@ -393,7 +595,11 @@ pub fn list_dir(dir: &std::path::Path) -> Vec<String> {
     match std::fs::read_dir(dir) {
         Ok(iter) => {
             for entry in iter {
-                files.push(entry.unwrap().path().to_str().unwrap().to_string());
+                let entry = entry.unwrap();
+                if is_scratch_file(&entry.file_name().to_string_lossy()) {
+                    continue;
+                }
+                files.push(entry.path().to_str().unwrap().to_string());
             }
         }
         Err(ref e) if e.kind() == ErrorKind::NotFound => {}
@@ -415,3 +621,97 @@ pub fn basename(path: &str) -> String {
 pub fn file_exists(path: String) -> bool {
     Path::new(&path).exists()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only the player's files get backups
+    fn test_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("abstutil_io_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("player").to_string_lossy().to_string()
+    }
+
+    fn truncate(path: &str) {
+        let len = std::fs::metadata(path).unwrap().len();
+        let file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_len(len / 2).unwrap();
+    }
+
+    #[test]
+    fn test_write_keeps_backup() {
+        let dir = test_dir("backup");
+        let path = format!("{}/x.json", dir);
+        maybe_write_json(&path, &vec![1, 2, 3]).unwrap();
+        maybe_write_json(&path, &vec![4, 5, 6]).unwrap();
+        let mut timer = Timer::throwaway();
+        let current: Vec<usize> = maybe_read_json(path.clone(), &mut timer).unwrap();
+        assert_eq!(current, vec![4, 5, 6]);
+        let backup: Vec<usize> =
+            serde_json::from_slice(&slurp_file(&backup_path(&path)).unwrap()).unwrap();
+        assert_eq!(backup, vec![1, 2, 3]);
+        // Neither the backup nor the temporary file show up as objects
+        assert_eq!(list_all_objects(dir), vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn test_no_backup_outside_player_data() {
+        let dir = std::env::temp_dir().join("abstutil_io_system");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = format!("{}/x.json", dir.to_string_lossy());
+        maybe_write_json(&path, &vec![1, 2, 3]).unwrap();
+        maybe_write_json(&path, &vec![4, 5, 6]).unwrap();
+        // No backup or temporary file left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_truncated_json() {
+        let path = format!("{}/x.json", test_dir("truncated_json"));
+        maybe_write_json(&path, &vec!["first".to_string()]).unwrap();
+        maybe_write_json(&path, &vec!["second".to_string()]).unwrap();
+        truncate(&path);
+        let mut timer = Timer::throwaway();
+        let obj: Vec<String> = maybe_read_json(path, &mut timer).unwrap();
+        assert_eq!(obj, vec!["first".to_string()]);
+    }
+
+    #[test]
+    fn test_truncated_binary() {
+        let path = format!("{}/x.bin", test_dir("truncated_binary"));
+        maybe_write_binary(&path, &vec![1.0, 2.0]).unwrap();
+        maybe_write_binary(&path, &vec![3.0, 4.0, 5.0, 6.0]).unwrap();
+        truncate(&path);
+        let mut timer = Timer::throwaway();
+        let obj: Vec<f64> = maybe_read_binary(path, &mut timer).unwrap();
+        assert_eq!(obj, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_truncated_without_backup() {
+        let path = format!("{}/x.json", test_dir("no_backup"));
+        maybe_write_json(&path, &vec![1, 2, 3]).unwrap();
+        truncate(&path);
+        let mut timer = Timer::throwaway();
+        assert!(maybe_read_json::<Vec<usize>>(path, &mut timer).is_err());
+    }
+
+    #[test]
+    fn test_list_and_load_objects() {
+        let dir = test_dir("list");
+        maybe_write_json(&format!("{}/b.json", dir), &vec![1, 2]).unwrap();
+        maybe_write_binary(&format!("{}/a.bin", dir), &vec![3, 4]).unwrap();
+        let list = list_objects(dir.clone());
+        assert_eq!(
+            list.iter().map(|m| m.name.clone()).collect::<Vec<_>>(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert!(list[0].describe().starts_with("1 KB, modified just now"));
+
+        let mut timer = Timer::throwaway();
+        let b: Vec<usize> = load_object(dir.clone(), "b", &mut timer).unwrap();
+        assert_eq!(b, vec![1, 2]);
+        assert!(load_object::<Vec<usize>>(dir, "c", &mut timer).is_err());
+    }
+}
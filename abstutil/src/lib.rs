@@ -4,8 +4,10 @@ mod collections;
 mod error;
 mod io;
 mod logs;
+mod paths;
 mod random;
 mod time;
+mod versioning;
 
 pub use crate::cli::CmdArgs;
 pub use crate::clone::Cloneable;
@@ -15,16 +17,34 @@ pub use crate::collections::{
 };
 pub use crate::error::Error;
 pub use crate::io::{
-    basename, deserialize_btreemap, deserialize_multimap, file_exists, find_next_file,
-    find_prev_file, list_all_objects, list_dir, load_all_objects, maybe_read_binary,
-    maybe_read_json, read_binary, read_json, serialize_btreemap, serialize_multimap,
-    serialized_size_bytes, slurp_file, to_json, write_binary, write_json, FileWithProgress,
+    backup_path, basename, deserialize_btreemap, deserialize_multimap, file_exists, find_next_file,
+    find_prev_file, list_all_objects, list_dir, list_objects, load_all_objects, load_object,
+    maybe_read_binary, maybe_read_json, read_binary, read_json, serialize_btreemap,
+    serialize_multimap, serialized_size_bytes, slurp_file, to_binary, to_json, write_binary,
+    write_json, FileWithProgress, ObjectMetadata,
+};
+pub use crate::logs::{
+    filter_logs, flush_log_file, log, num_logs_recorded, set_max_log_lines, tee_logs_to_file,
+    LogLine, Severity, Warn, DEFAULT_MAX_LOG_LINES,
+};
+pub use crate::paths::{
+    data_dir, path, path_all_edits, path_all_logs, path_all_maps, path_all_neighborhoods,
+    path_all_proposals, path_all_raw_maps, path_all_reports, path_all_saves, path_all_savestates,
+    path_all_scenarios, path_all_stories, path_all_synthetic_maps, path_annotations,
+    path_camera_state, path_city, path_edits, path_frame_timings, path_input_recording,
+    path_keybindings, path_log_file, path_map, path_neighborhood, path_pending_screenshots,
+    path_popdat, path_prebaked_results, path_raw_map, path_report, path_save, path_scenario,
+    path_story, path_synthetic_map, set_data_dir,
 };
-pub use crate::logs::Warn;
 pub use crate::random::{fork_rng, WeightedUsizeChoice};
 pub use crate::time::{
-    elapsed_seconds, prettyprint_usize, start_profiler, stop_profiler, MeasureMemory, Profiler,
-    Timer, TimerSink,
+    elapsed_seconds, prettyprint_usize, start_profiler, stop_profiler, MeasureMemory, Phase,
+    Profiler, Timer, TimerSink,
+};
+pub use crate::versioning::{
+    check_versioned_file, file_version, from_versioned_binary, from_versioned_json,
+    load_all_versioned_objects, load_versioned_object, maybe_read_versioned, read_versioned,
+    to_versioned_binary, to_versioned_json, write_versioned, Versioned,
 };
 use std::collections::BTreeSet;
 use std::fmt::Write;
@@ -60,79 +80,3 @@ pub fn plain_list_names(names: BTreeSet<String>) -> String {
     }
     s
 }
-
-// System data (Players can't edit, needed at runtime)
-
-pub fn path_map(map_name: &str) -> String {
-    format!("../data/system/maps/{}.bin", map_name)
-}
-pub fn path_all_maps() -> String {
-    format!("../data/system/maps")
-}
-
-pub fn path_prebaked_results(map_name: &str, scenario_name: &str) -> String {
-    format!(
-        "../data/system/prebaked_results/{}/{}.bin",
-        map_name, scenario_name
-    )
-}
-
-pub fn path_scenario(map_name: &str, scenario_name: &str) -> String {
-    format!(
-        "../data/system/scenarios/{}/{}.bin",
-        map_name, scenario_name
-    )
-}
-pub fn path_all_scenarios(map_name: &str) -> String {
-    format!("../data/system/scenarios/{}", map_name)
-}
-
-pub fn path_synthetic_map(map_name: &str) -> String {
-    format!("../data/system/synthetic_maps/{}.json", map_name)
-}
-pub fn path_all_synthetic_maps() -> String {
-    format!("../data/system/synthetic_maps")
-}
-
-// Player data (Players edit this)
-
-pub fn path_camera_state(map_name: &str) -> String {
-    format!("../data/player/camera_state/{}.json", map_name)
-}
-
-pub fn path_edits(map_name: &str, edits_name: &str) -> String {
-    format!("../data/player/edits/{}/{}.json", map_name, edits_name)
-}
-pub fn path_all_edits(map_name: &str) -> String {
-    format!("../data/player/edits/{}", map_name)
-}
-
-pub fn path_save(map_name: &str, edits_name: &str, run_name: &str, time: String) -> String {
-    format!(
-        "../data/player/saves/{}/{}_{}/{}.bin",
-        map_name, edits_name, run_name, time
-    )
-}
-pub fn path_all_saves(map_name: &str, edits_name: &str, run_name: &str) -> String {
-    format!(
-        "../data/player/saves/{}/{}_{}",
-        map_name, edits_name, run_name
-    )
-}
-
-// Input data (For developers to build maps, not needed at runtime)
-
-pub fn path_pending_screenshots(map_name: &str) -> String {
-    format!("../data/input/screenshots/pending_{}", map_name)
-}
-
-pub fn path_popdat() -> String {
-    format!("../data/input/seattle/popdat.bin")
-}
-
-pub fn path_raw_map(map_name: &str) -> String {
-    format!("../data/input/raw_maps/{}.bin", map_name)
-}
-pub fn path_all_raw_maps() -> String {
-    format!("../data/input/raw_maps")
-}
@@ -1,4 +1,7 @@
 use crate::Timer;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{mpsc, Mutex};
 
 //
 // - If it doesn't make sense to plumb Timer to a library call, return Warn<T>.
@@ -31,26 +34,31 @@ impl<T> Warn<T> {
         Warn { value, warnings }
     }
 
+    #[track_caller]
     pub fn unwrap(self) -> T {
         if !self.warnings.is_empty() {
             println!("{} warnings:", self.warnings.len());
             for line in self.warnings {
                 println!("{}", line);
+                log(Severity::Warn, line);
             }
         }
         self.value
     }
 
+    #[track_caller]
     pub fn expect(self, context: String) -> T {
         if !self.warnings.is_empty() {
             println!("{} warnings ({}):", self.warnings.len(), context);
             for line in self.warnings {
                 println!("{}", line);
+                log(Severity::Warn, format!("{}: {}", context, line));
             }
         }
         self.value
     }
 
+    #[track_caller]
     pub fn get(self, timer: &mut Timer) -> T {
         // TODO Context from the current Timer phase, caller
         for line in self.warnings {
@@ -59,6 +67,7 @@ impl<T> Warn<T> {
         self.value
     }
 
+    #[track_caller]
     pub fn with_context(self, timer: &mut Timer, context: String) -> T {
         for line in self.warnings {
             timer.warn(format!("{}: {}", context, line));
@@ -79,3 +88,280 @@ impl Warn<()> {
         Warn::warnings((), warnings)
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Severity {
+    pub fn all() -> Vec<Severity> {
+        vec![
+            Severity::Error,
+            Severity::Warn,
+            Severity::Info,
+            Severity::Debug,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warn => "warn",
+            Severity::Info => "info",
+            Severity::Debug => "debug",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    // Increases with every recorded line, so it uniquely identifies a line even after older ones
+    // are evicted.
+    pub id: usize,
+    pub severity: Severity,
+    // A module path like "sim::mechanics::driving", figured out from the caller's source file.
+    pub target: String,
+    pub msg: String,
+}
+
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.severity.describe(),
+            self.target,
+            self.msg
+        )
+    }
+}
+
+impl LogLine {
+    // The first component of the target, like "sim" or "map_model"
+    pub fn crate_name(&self) -> &str {
+        self.target.split("::").next().unwrap()
+    }
+}
+
+pub const DEFAULT_MAX_LOG_LINES: usize = 10_000;
+
+// Everything that goes through Timer (and anybody calling log directly) is also retained here, so
+// the UI can display it later. The buffer is bounded; the oldest lines get evicted first.
+struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    max_lines: usize,
+    // Never decreases, even when lines are evicted. Lets callers cheaply detect new lines.
+    total_recorded: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref LOGS: Mutex<LogBuffer> = Mutex::new(LogBuffer {
+        lines: VecDeque::new(),
+        max_lines: DEFAULT_MAX_LOG_LINES,
+        total_recorded: 0,
+    });
+}
+
+fn with_logs<O, F: FnOnce(&mut LogBuffer) -> O>(f: F) -> O {
+    f(&mut lock(&LOGS))
+}
+
+// If some thread panicked while holding a lock, the data behind it is still fine.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// This doesn't print anything; it just records the line. The target comes from whoever called
+// this, or from their caller if they're also marked #[track_caller], like most Timer methods.
+#[track_caller]
+pub fn log(severity: Severity, msg: String) {
+    let target = source_file_to_target(std::panic::Location::caller().file());
+    with_logs(|logs| {
+        while logs.lines.len() >= logs.max_lines {
+            logs.lines.pop_front();
+        }
+        let line = LogLine {
+            id: logs.total_recorded,
+            severity,
+            target,
+            msg,
+        };
+        if let Some(ref tx) = *lock(&LOG_FILE) {
+            // If the writer thread is gone, there's nobody to tell.
+            let _ = tx.send(FileMsg::Line(line.to_string()));
+        }
+        logs.lines.push_back(line);
+        logs.total_recorded += 1;
+    });
+}
+
+pub fn set_max_log_lines(max_lines: usize) {
+    assert!(max_lines > 0);
+    with_logs(|logs| {
+        logs.max_lines = max_lines;
+        while logs.lines.len() > max_lines {
+            logs.lines.pop_front();
+        }
+    });
+}
+
+pub fn num_logs_recorded() -> usize {
+    with_logs(|logs| logs.total_recorded)
+}
+
+// Returns the retained lines (oldest first) that pass the filter, and the number of retained lines
+// that didn't.
+pub fn filter_logs<F: Fn(&LogLine) -> bool>(filter: F) -> (Vec<LogLine>, usize) {
+    with_logs(|logs| {
+        let mut matches = Vec::new();
+        let mut suppressed = 0;
+        for line in &logs.lines {
+            if filter(line) {
+                matches.push(line.clone());
+            } else {
+                suppressed += 1;
+            }
+        }
+        (matches, suppressed)
+    })
+}
+
+enum FileMsg {
+    Line(String),
+    // Reply once everything before this has been written out
+    Flush(mpsc::Sender<()>),
+}
+
+lazy_static::lazy_static! {
+    static ref LOG_FILE: Mutex<Option<mpsc::Sender<FileMsg>>> = Mutex::new(None);
+}
+
+// Everything passed to log() from now on is also appended to this file. The file (and its
+// directory) isn't created until the first line arrives. A background thread does the writing,
+// so a slow disk never blocks the caller. If the process panics, the panic message is logged and
+// the file flushed before the usual panic handling runs.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn tee_logs_to_file(path: String) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || write_log_file(path, rx));
+    *lock(&LOG_FILE) = Some(tx);
+
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Don't risk blocking forever if the panic happened while the lock was held.
+        if let Ok(maybe_tx) = LOG_FILE.try_lock() {
+            if let Some(ref tx) = *maybe_tx {
+                let _ = tx.send(FileMsg::Line(format!(
+                    "[{}] {}",
+                    Severity::Error.describe(),
+                    info
+                )));
+            }
+        }
+        flush_log_file();
+        prev_hook(info);
+    }));
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn tee_logs_to_file(_path: String) {}
+
+// Blocks until everything logged so far has been written to the file from tee_logs_to_file, but
+// gives up after a few seconds.
+pub fn flush_log_file() {
+    let (ack_tx, ack_rx) = mpsc::channel();
+    let sent = match LOG_FILE.try_lock() {
+        Ok(maybe_tx) => match *maybe_tx {
+            Some(ref tx) => tx.send(FileMsg::Flush(ack_tx)).is_ok(),
+            None => false,
+        },
+        Err(_) => false,
+    };
+    if sent {
+        let _ = ack_rx.recv_timeout(std::time::Duration::from_secs(5));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_log_file(path: String, rx: mpsc::Receiver<FileMsg>) {
+    use std::io::Write;
+
+    let mut file: Option<std::io::BufWriter<std::fs::File>> = None;
+    let mut broken = false;
+    loop {
+        // If nothing new shows up for a while, flush, so the file's useful even while the game's
+        // still running.
+        match rx.recv_timeout(std::time::Duration::from_secs(1)) {
+            Ok(FileMsg::Line(line)) => {
+                if file.is_none() && !broken {
+                    match open_log_file(&path) {
+                        Ok(f) => {
+                            file = Some(f);
+                        }
+                        Err(err) => {
+                            println!("Can't write logs to {}: {}", path, err);
+                            broken = true;
+                        }
+                    }
+                }
+                if let Some(ref mut f) = file {
+                    if let Err(err) = writeln!(f, "{}", line) {
+                        println!("Can't write logs to {}: {}", path, err);
+                        file = None;
+                        broken = true;
+                    }
+                }
+            }
+            Ok(FileMsg::Flush(ack)) => {
+                if let Some(ref mut f) = file {
+                    let _ = f.flush();
+                }
+                let _ = ack.send(());
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if let Some(ref mut f) = file {
+                    let _ = f.flush();
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_log_file(path: &str) -> std::io::Result<std::io::BufWriter<std::fs::File>> {
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
+    Ok(std::io::BufWriter::new(std::fs::File::create(path)?))
+}
+
+// Every crate in the workspace is a top-level directory, so "sim/src/mechanics/driving.rs" becomes
+// "sim::mechanics::driving". Anything else (like a file from some dependency) is left alone.
+fn source_file_to_target(file: &str) -> String {
+    let parts: Vec<&str> = file
+        .trim_end_matches(".rs")
+        .split(|c| c == '/' || c == '\\')
+        .collect();
+    if parts.len() < 3 || parts[1] != "src" {
+        return file.to_string();
+    }
+    let mut target = vec![parts[0]];
+    target.extend(&parts[2..]);
+    if target.len() > 1 {
+        match *target.last().unwrap() {
+            "lib" | "main" | "mod" => {
+                target.pop();
+            }
+            _ => {}
+        }
+    }
+    target.join("::")
+}
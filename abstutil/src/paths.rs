@@ -0,0 +1,229 @@
+use std::sync::Mutex;
+
+// Relative to the crate directories, where everything is run from by default
+const DEFAULT_DATA_DIR: &str = "../data";
+
+lazy_static::lazy_static! {
+    static ref DATA_DIR: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// Overrides the ABST_DATA_DIR environment variable. Call this before touching any paths.
+pub fn set_data_dir(dir: String) {
+    *DATA_DIR.lock().unwrap() = Some(dir.trim_end_matches('/').to_string());
+}
+
+pub fn data_dir() -> String {
+    if let Some(ref dir) = *DATA_DIR.lock().unwrap() {
+        return dir.clone();
+    }
+    match std::env::var("ABST_DATA_DIR") {
+        Ok(dir) if !dir.is_empty() => dir.trim_end_matches('/').to_string(),
+        _ => DEFAULT_DATA_DIR.to_string(),
+    }
+}
+
+// Anything without a more specific accessor, like "system/fonts"
+pub fn path(relative: &str) -> String {
+    format!("{}/{}", data_dir(), relative)
+}
+
+// For directories that other programs write into, which won't create them
+#[cfg(not(target_arch = "wasm32"))]
+fn on_demand(dir: String) -> String {
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        println!("Couldn't create {}: {}", dir, err);
+    }
+    dir
+}
+
+#[cfg(target_arch = "wasm32")]
+fn on_demand(dir: String) -> String {
+    dir
+}
+
+// Where a file or directory used to live, most likely first, so existing data keeps loading after
+// the layout changes. Writes always go to the new location.
+pub(crate) fn legacy_paths(path: &str) -> Vec<String> {
+    let mut results = Vec::new();
+
+    // Stories used to be shared by all maps
+    let stories = format!("{}/player/stories/", data_dir());
+    if let Some(rest) = path.strip_prefix(&stories) {
+        let without_map = match rest.find('/') {
+            Some(idx) => &rest[idx + 1..],
+            None => "",
+        };
+        results.push(
+            format!("{}{}", stories, without_map)
+                .trim_end_matches('/')
+                .to_string(),
+        );
+    }
+
+    // Before the data directory could be moved
+    let root = data_dir();
+    if root != DEFAULT_DATA_DIR {
+        for p in std::iter::once(path.to_string())
+            .chain(results.clone())
+            .collect::<Vec<_>>()
+        {
+            if let Some(rest) = p.strip_prefix(&root) {
+                results.push(format!("{}{}", DEFAULT_DATA_DIR, rest));
+            }
+        }
+    }
+
+    results
+}
+
+// System data (Players can't edit, needed at runtime)
+
+pub fn path_map(map_name: &str) -> String {
+    path(&format!("system/maps/{}.bin", map_name))
+}
+pub fn path_all_maps() -> String {
+    path("system/maps")
+}
+
+pub fn path_city(city_name: &str) -> String {
+    path(&format!("system/cities/{}.bin", city_name))
+}
+
+pub fn path_prebaked_results(map_name: &str, scenario_name: &str) -> String {
+    path(&format!(
+        "system/prebaked_results/{}/{}.bin",
+        map_name, scenario_name
+    ))
+}
+
+pub fn path_scenario(map_name: &str, scenario_name: &str) -> String {
+    path(&format!(
+        "system/scenarios/{}/{}.bin",
+        map_name, scenario_name
+    ))
+}
+pub fn path_all_scenarios(map_name: &str) -> String {
+    path(&format!("system/scenarios/{}", map_name))
+}
+
+pub fn path_synthetic_map(map_name: &str) -> String {
+    path(&format!("system/synthetic_maps/{}.json", map_name))
+}
+pub fn path_all_synthetic_maps() -> String {
+    path("system/synthetic_maps")
+}
+
+pub fn path_all_proposals() -> String {
+    path("system/proposals")
+}
+
+// Player data (Players edit this)
+
+pub fn path_camera_state(map_name: &str) -> String {
+    path(&format!("player/camera_state/{}.json", map_name))
+}
+
+pub fn path_edits(map_name: &str, edits_name: &str) -> String {
+    path(&format!("player/edits/{}/{}.json", map_name, edits_name))
+}
+pub fn path_all_edits(map_name: &str) -> String {
+    path(&format!("player/edits/{}", map_name))
+}
+
+pub fn path_save(map_name: &str, edits_name: &str, run_name: &str, time: String) -> String {
+    path(&format!(
+        "player/saves/{}/{}_{}/{}.bin",
+        map_name, edits_name, run_name, time
+    ))
+}
+pub fn path_all_saves(map_name: &str, edits_name: &str, run_name: &str) -> String {
+    path(&format!(
+        "player/saves/{}/{}_{}",
+        map_name, edits_name, run_name
+    ))
+}
+// Every savestate from every map lives somewhere in here
+pub fn path_all_savestates() -> String {
+    path("player/saves")
+}
+
+pub fn path_story(map_name: &str, story_name: &str) -> String {
+    path(&format!("player/stories/{}/{}.json", map_name, story_name))
+}
+pub fn path_all_stories(map_name: &str) -> String {
+    path(&format!("player/stories/{}", map_name))
+}
+
+pub fn path_report(map_name: &str, name: &str) -> String {
+    format!("{}/{}.html", path_all_reports(map_name), name)
+}
+pub fn path_all_reports(map_name: &str) -> String {
+    on_demand(path(&format!("reports/{}", map_name)))
+}
+
+pub fn path_annotations(map_name: &str) -> String {
+    path(&format!("player/annotations/{}.json", map_name))
+}
+
+pub fn path_keybindings() -> String {
+    path("player/keybindings.json")
+}
+
+// Input data (For developers to build maps, not needed at runtime)
+
+pub fn path_all_logs() -> String {
+    on_demand(path("logs"))
+}
+
+pub fn path_log_file(name: &str) -> String {
+    format!("{}/{}.txt", path_all_logs(), name)
+}
+
+pub fn path_input_recording(name: &str) -> String {
+    format!("{}/input_{}.json", path_all_logs(), name)
+}
+
+pub fn path_frame_timings(name: &str) -> String {
+    format!("{}/frame_timings_{}.csv", path_all_logs(), name)
+}
+
+pub fn path_pending_screenshots(map_name: &str) -> String {
+    on_demand(path(&format!("input/screenshots/pending_{}", map_name)))
+}
+
+pub fn path_neighborhood(city_name: &str, name: &str) -> String {
+    path(&format!("input/{}/polygons/{}.poly", city_name, name))
+}
+pub fn path_all_neighborhoods(city_name: &str) -> String {
+    path(&format!("input/{}/polygons", city_name))
+}
+
+pub fn path_popdat() -> String {
+    path("input/seattle/popdat.bin")
+}
+
+pub fn path_raw_map(map_name: &str) -> String {
+    path(&format!("input/raw_maps/{}.bin", map_name))
+}
+pub fn path_all_raw_maps() -> String {
+    path("input/raw_maps")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_paths() {
+        assert_eq!(path_map("montlake"), "../data/system/maps/montlake.bin");
+        assert_eq!(
+            legacy_paths(&path_story("montlake", "x")),
+            vec!["../data/player/stories/x.json".to_string()]
+        );
+        assert_eq!(
+            legacy_paths(&path_all_stories("montlake")),
+            vec!["../data/player/stories".to_string()]
+        );
+        assert!(legacy_paths(&path_map("montlake")).is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::logs::{log, Severity};
 use crate::PROGRESS_FREQUENCY_SECONDS;
 use instant::Instant;
 use std::collections::HashMap;
@@ -33,9 +34,10 @@ impl Progress {
     // Returns when done
     fn next<'a>(
         &mut self,
+        n: usize,
         maybe_sink: &mut Option<Box<dyn TimerSink + 'a>>,
     ) -> Option<(f64, String)> {
-        self.processed_items += 1;
+        self.processed_items += n;
         if self.processed_items > self.total_items {
             panic!(
                 "{} is too few items for {} progress",
@@ -97,6 +99,53 @@ enum StackEntry {
 pub trait TimerSink {
     fn println(&mut self, line: String);
     fn reprintln(&mut self, line: String);
+    // Called a few times per second with everything in progress, for showing a live tree of
+    // phases.
+    fn update_phases(&mut self, _phases: Vec<Phase>) {}
+}
+
+// Something a Timer is in the middle of. Nested phases come after their parent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    pub depth: usize,
+    pub elapsed_seconds: f64,
+    // (done, total) for phases with a known amount of work
+    pub progress: Option<(usize, usize)>,
+}
+
+impl Phase {
+    pub fn percent(&self) -> Option<f64> {
+        self.progress
+            .map(|(done, total)| 100.0 * (done as f64) / (total as f64))
+    }
+
+    // Seconds left, assuming the rest of the work goes as fast as it has so far
+    pub fn eta_seconds(&self) -> Option<f64> {
+        match self.progress {
+            Some((done, total)) if done > 0 => {
+                Some(self.elapsed_seconds * ((total - done) as f64) / (done as f64))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut line = format!("{}{}", "  ".repeat(self.depth), self.name);
+        if let Some((done, total)) = self.progress {
+            line.push_str(&format!(
+                ": {}/{} ({:.0}%)",
+                prettyprint_usize(done),
+                prettyprint_usize(total),
+                self.percent().unwrap()
+            ));
+        }
+        line.push_str(&format!(", {}", prettyprint_time(self.elapsed_seconds)));
+        if let Some(eta) = self.eta_seconds() {
+            line.push_str(&format!(", about {} left", prettyprint_time(eta)));
+        }
+        line
+    }
 }
 
 // Hierarchial magic
@@ -111,6 +160,8 @@ pub struct Timer<'a> {
     pub(crate) errors: Vec<String>,
 
     sink: Option<Box<dyn TimerSink + 'a>>,
+    // When the sink last heard about phases
+    phases_sent_at: Instant,
 }
 
 struct TimerSpan {
@@ -131,6 +182,7 @@ impl<'a> Timer<'a> {
             warnings: Vec::new(),
             errors: Vec::new(),
             sink: None,
+            phases_sent_at: Instant::now(),
         };
         t.start(name);
         t
@@ -168,6 +220,7 @@ impl<'a> Timer<'a> {
 
     // Log immediately, but also repeat at the end, to avoid having to scroll up and find
     // interesting debug stuff.
+    #[track_caller]
     pub fn note(&mut self, line: String) {
         // Interrupt the start_iter with a newline.
         if let Some(StackEntry::Progress(_)) = self.stack.last() {
@@ -175,20 +228,26 @@ impl<'a> Timer<'a> {
         }
 
         self.println(line.clone());
+        log(Severity::Info, line.clone());
         self.notes.push(line);
     }
 
+    #[track_caller]
     pub fn warn(&mut self, line: String) {
+        log(Severity::Warn, line.clone());
         self.warnings.push(line);
     }
 
+    #[track_caller]
     pub fn error(&mut self, line: String) {
+        log(Severity::Error, line.clone());
         self.errors.push(line);
     }
 
     // Used to end the scope of a timer early.
     pub fn done(self) {}
 
+    #[track_caller]
     pub fn start<S: Into<String>>(&mut self, raw_name: S) {
         if self.outermost_name == "throwaway" {
             return;
@@ -196,14 +255,17 @@ impl<'a> Timer<'a> {
 
         let name = raw_name.into();
         self.println(format!("{}...", name));
+        log(Severity::Debug, format!("{}...", name));
         self.stack.push(StackEntry::TimerSpan(TimerSpan {
             name,
             started_at: Instant::now(),
             nested_results: Vec::new(),
             nested_time: 0.0,
         }));
+        self.send_phases(true);
     }
 
+    #[track_caller]
     pub fn stop<S: Into<String>>(&mut self, raw_name: S) {
         if self.outermost_name == "throwaway" {
             return;
@@ -217,6 +279,7 @@ impl<'a> Timer<'a> {
         assert_eq!(span.name, name);
         let elapsed = elapsed_seconds(span.started_at);
         let line = format!("{} took {}", name, prettyprint_time(elapsed));
+        log(Severity::Debug, line.clone());
 
         let padding = "  ".repeat(self.stack.len());
         match self.stack.last_mut() {
@@ -260,6 +323,7 @@ impl<'a> Timer<'a> {
         }
 
         self.println(line);
+        self.send_phases(true);
     }
 
     pub fn start_iter<S: Into<String>>(&mut self, raw_name: S, total_items: usize) {
@@ -279,22 +343,72 @@ impl<'a> Timer<'a> {
 
         self.stack
             .push(StackEntry::Progress(Progress::new(name, total_items)));
+        self.send_phases(true);
     }
 
     pub fn next(&mut self) {
+        self.next_n(1);
+    }
+
+    // For tight loops, report progress every n items.
+    pub fn next_n(&mut self, n: usize) {
         if self.outermost_name == "throwaway" {
             return;
         }
         let maybe_result =
             if let Some(StackEntry::Progress(ref mut progress)) = self.stack.last_mut() {
-                progress.next(&mut self.sink)
+                progress.next(n, &mut self.sink)
             } else {
                 panic!("Can't next() while a TimerSpan is top of the stack");
             };
         if let Some((elapsed, result)) = maybe_result {
             self.stack.pop();
             self.add_result(elapsed, result);
+            self.send_phases(true);
+        } else {
+            self.send_phases(false);
+        }
+    }
+
+    // Everything in progress right now, outermost first
+    pub fn phases(&self) -> Vec<Phase> {
+        self.stack
+            .iter()
+            .enumerate()
+            .map(|(depth, entry)| match entry {
+                StackEntry::TimerSpan(ref s) => Phase {
+                    name: s.name.clone(),
+                    depth,
+                    elapsed_seconds: elapsed_seconds(s.started_at),
+                    progress: None,
+                },
+                StackEntry::Progress(ref p) => Phase {
+                    name: p.label.clone(),
+                    depth,
+                    elapsed_seconds: elapsed_seconds(p.started_at),
+                    progress: Some((p.processed_items, p.total_items)),
+                },
+                StackEntry::File(ref f) => Phase {
+                    name: format!("read {}", f.path),
+                    depth,
+                    elapsed_seconds: elapsed_seconds(f.started_at),
+                    progress: Some((f.processed_bytes, f.total_bytes)),
+                },
+            })
+            .collect()
+    }
+
+    // Without a sink, this costs nothing, so it's fine to call per item. Otherwise, unless forced,
+    // only every PROGRESS_FREQUENCY_SECONDS.
+    fn send_phases(&mut self, force: bool) {
+        if self.sink.is_none()
+            || (!force && elapsed_seconds(self.phases_sent_at) < PROGRESS_FREQUENCY_SECONDS)
+        {
+            return;
         }
+        self.phases_sent_at = Instant::now();
+        let phases = self.phases();
+        self.sink.as_mut().unwrap().update_phases(phases);
     }
 
     pub(crate) fn add_result(&mut self, elapsed: f64, line: String) {
@@ -368,8 +482,16 @@ impl<'a> Timer<'a> {
     pub fn read_file(&mut self, path: &str) -> Result<(), Error> {
         self.stack
             .push(StackEntry::File(TimedFileReader::new(path)?));
+        self.send_phases(true);
         Ok(())
     }
+
+    // If deserializing fails partway through a file, forget about it.
+    pub(crate) fn stop_reading_file(&mut self) {
+        if let Some(StackEntry::File(_)) = self.stack.last() {
+            self.stack.pop();
+        }
+    }
 }
 
 impl<'a> std::ops::Drop for Timer<'a> {
@@ -663,6 +785,7 @@ impl<'a> Read for Timer<'a> {
             }
             self.stack.pop();
             self.add_result(elapsed, line);
+            self.send_phases(true);
         } else if file.last_printed_at.is_none()
             || elapsed_seconds(file.last_printed_at.unwrap()) >= PROGRESS_FREQUENCY_SECONDS
         {
@@ -687,6 +810,7 @@ impl<'a> Read for Timer<'a> {
             }
 
             file.last_printed_at = Some(Instant::now());
+            self.send_phases(false);
         }
 
         Ok(bytes)
@@ -716,3 +840,49 @@ pub fn stop_profiler() {
 pub fn stop_profiler() {
     panic!("abstutil/profiler feature not enabled in Cargo.toml");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        let mut timer = Timer::new("outer");
+        timer.start("inner");
+        timer.start_iter("items", 10);
+        timer.next();
+        timer.next_n(3);
+        let phases = timer.phases();
+        assert_eq!(
+            phases
+                .iter()
+                .map(|p| (p.name.as_str(), p.depth, p.progress))
+                .collect::<Vec<_>>(),
+            vec![
+                ("outer", 0, None),
+                ("inner", 1, None),
+                ("items", 2, Some((4, 10)))
+            ]
+        );
+        timer.next_n(6);
+        timer.stop("inner");
+        assert_eq!(timer.phases().len(), 1);
+    }
+
+    #[test]
+    fn test_eta() {
+        let phase = Phase {
+            name: "x".to_string(),
+            depth: 0,
+            elapsed_seconds: 10.0,
+            progress: Some((25, 100)),
+        };
+        assert_eq!(phase.percent(), Some(25.0));
+        assert_eq!(phase.eta_seconds(), Some(30.0));
+        let phase = Phase {
+            progress: Some((0, 100)),
+            ..phase
+        };
+        assert_eq!(phase.eta_seconds(), None);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::io::{list_objects, or_backup, slurp_file, write_atomically, ObjectMetadata};
+use crate::Timer;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Write};
+
+// Starts every versioned binary file, followed by the version as a little-endian u32. Files
+// without it were written before versioning, and are version 0.
+const MAGIC: &[u8; 8] = b"abstver\0";
+const HEADER_BYTES: usize = 12;
+
+// Something saved by players or the importer that should keep loading as its format changes.
+pub trait Versioned: Serialize + DeserializeOwned {
+    // For error messages
+    const NAME: &'static str;
+    // Bump this whenever the serialized format changes, and teach the migrations about the old
+    // version.
+    const VERSION: u32;
+
+    // Upgrades JSON written by version `from` to `from + 1`. Called once per version in between.
+    fn migrate_json(_from: u32, _obj: &mut Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Binary formats aren't self-describing, so older versions have to be decoded straight into
+    // the current type. By default, the layout hasn't changed since version 0.
+    fn from_old_binary(_from: u32, raw: &[u8]) -> Result<Self, String> {
+        bincode::deserialize(raw).map_err(|err| err.to_string())
+    }
+}
+
+fn check_version<T: Versioned>(version: u32) -> Result<(), Error> {
+    if version > T::VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "this {} is version {}, but only versions 0 to {} are supported",
+                T::NAME,
+                version,
+                T::VERSION
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn other_err(err: String) -> Error {
+    Error::new(ErrorKind::Other, err)
+}
+
+pub fn to_versioned_json<T: Versioned>(obj: &T) -> String {
+    let mut value = serde_json::to_value(obj).unwrap();
+    value
+        .as_object_mut()
+        .expect("Versioned types must serialize to a JSON object")
+        .insert("version".to_string(), T::VERSION.into());
+    serde_json::to_string_pretty(&value).unwrap()
+}
+
+pub fn from_versioned_json<T: Versioned>(raw: &[u8]) -> Result<T, Error> {
+    let mut value: Value = serde_json::from_slice(raw).map_err(|err| other_err(err.to_string()))?;
+    let version = match value.as_object_mut().and_then(|obj| obj.remove("version")) {
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| other_err(format!("version {} isn't a number", v)))?
+            as u32,
+        None => 0,
+    };
+    check_version::<T>(version)?;
+    for from in version..T::VERSION {
+        T::migrate_json(from, &mut value).map_err(other_err)?;
+    }
+    serde_json::from_value(value).map_err(|err| other_err(err.to_string()))
+}
+
+pub fn to_versioned_binary<T: Versioned>(obj: &T) -> Vec<u8> {
+    let mut raw = Vec::new();
+    write_binary_header::<T, _>(&mut raw).unwrap();
+    bincode::serialize_into(&mut raw, obj).unwrap();
+    raw
+}
+
+pub fn from_versioned_binary<T: Versioned>(raw: &[u8]) -> Result<T, Error> {
+    let version = binary_version(raw);
+    check_version::<T>(version)?;
+    if version == 0 {
+        return T::from_old_binary(0, raw).map_err(other_err);
+    }
+    let payload = &raw[HEADER_BYTES..];
+    if version == T::VERSION {
+        bincode::deserialize(payload).map_err(|err| other_err(err.to_string()))
+    } else {
+        T::from_old_binary(version, payload).map_err(other_err)
+    }
+}
+
+fn write_binary_header<T: Versioned, W: Write>(w: &mut W) -> Result<(), Error> {
+    w.write_all(MAGIC)?;
+    w.write_all(&T::VERSION.to_le_bytes())
+}
+
+// Only looks at the header
+fn binary_version(raw: &[u8]) -> u32 {
+    if raw.len() < HEADER_BYTES || &raw[0..MAGIC.len()] != MAGIC {
+        return 0;
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&raw[MAGIC.len()..HEADER_BYTES]);
+    u32::from_le_bytes(version)
+}
+
+// Detects JSON or binary from the path. Older versions are upgraded, and a corrupt file falls
+// back to its backup.
+pub fn maybe_read_versioned<T: Versioned>(path: String, timer: &mut Timer) -> Result<T, String> {
+    or_backup(&path, timer, |path, timer| {
+        timer.start(format!("read {}", path));
+        let result = slurp_file(path).and_then(|raw| {
+            if path.ends_with(".json") {
+                from_versioned_json(&raw)
+            } else if path.ends_with(".bin") {
+                from_versioned_binary(&raw)
+            } else {
+                panic!("Don't know what {} is", path);
+            }
+        });
+        timer.stop(format!("read {}", path));
+        result
+    })
+    .map_err(|err| format!("Couldn't read {}: {}", path, err))
+}
+
+pub fn read_versioned<T: Versioned>(path: String, timer: &mut Timer) -> T {
+    match maybe_read_versioned(path, timer) {
+        Ok(obj) => obj,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_versioned<T: Versioned>(path: String, obj: &T) {
+    let result = if path.ends_with(".json") {
+        write_atomically(&path, |file| {
+            file.write_all(to_versioned_json(obj).as_bytes())
+        })
+    } else if path.ends_with(".bin") {
+        write_atomically(&path, |file| {
+            write_binary_header::<T, _>(file)?;
+            bincode::serialize_into(file, obj).map_err(|err| other_err(err.to_string()))
+        })
+    } else {
+        panic!("Don't know how to write {}", path);
+    };
+    if let Err(err) = result {
+        panic!("Can't write_versioned({}): {}", path, err);
+    }
+    println!("Wrote {}", path);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_versioned<T: Versioned>(_path: String, _obj: &T) {
+    // TODO
+}
+
+// Which version wrote a file, without loading the whole thing. Cheap even for big savestates.
+pub fn file_version(path: &str) -> Result<u32, String> {
+    if path.ends_with(".json") {
+        let raw = slurp_file(path).map_err(|err| err.to_string())?;
+        let value: Value = serde_json::from_slice(&raw).map_err(|err| err.to_string())?;
+        Ok(value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32)
+    } else {
+        let mut header = Vec::new();
+        File::open(path)
+            .and_then(|f| f.take(HEADER_BYTES as u64).read_to_end(&mut header))
+            .map_err(|err| err.to_string())?;
+        Ok(binary_version(&header))
+    }
+}
+
+// Catches files that obviously can't be loaded, like ones from a newer version, without reading
+// all of a big binary file. A file that passes can still turn out to be corrupt.
+pub fn check_versioned_file<T: Versioned>(path: &str) -> Result<(), String> {
+    let version = file_version(path)?;
+    check_version::<T>(version).map_err(|err| err.to_string())?;
+    if version > 0 && !path.ends_with(".json") {
+        let len = std::fs::metadata(path)
+            .map_err(|err| err.to_string())?
+            .len();
+        if len <= HEADER_BYTES as u64 {
+            return Err("the file is empty past the version".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Loads one thing listed by list_objects.
+pub fn load_versioned_object<T: Versioned>(
+    dir: String,
+    name: &str,
+    timer: &mut Timer,
+) -> Result<T, String> {
+    match list_objects(dir.clone())
+        .into_iter()
+        .find(|m| m.name == name)
+    {
+        Some(m) => maybe_read_versioned(m.path, timer),
+        None => Err(format!("{} isn't in {}", name, dir)),
+    }
+}
+
+// Like load_all_objects, but files that can't be read show up as errors, instead of crashing.
+pub fn load_all_versioned_objects<T: Versioned>(
+    dir: String,
+) -> Vec<(ObjectMetadata, Result<T, String>)> {
+    let mut timer = Timer::new(format!("load_all_versioned_objects from {}", dir));
+    list_objects(dir)
+        .into_iter()
+        .map(|m| {
+            let result = maybe_read_versioned(m.path.clone(), &mut timer);
+            (m, result)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Thing {
+        name: String,
+        size: usize,
+    }
+
+    impl Versioned for Thing {
+        const NAME: &'static str = "thing";
+        const VERSION: u32 = 2;
+
+        // Version 1 called size "count"
+        fn migrate_json(from: u32, obj: &mut Value) -> Result<(), String> {
+            if from == 1 {
+                let obj = obj.as_object_mut().unwrap();
+                let count = obj.remove("count").ok_or("no count")?;
+                obj.insert("size".to_string(), count);
+            }
+            Ok(())
+        }
+    }
+
+    fn thing() -> Thing {
+        Thing {
+            name: "x".to_string(),
+            size: 3,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = to_versioned_json(&thing());
+        assert_eq!(
+            from_versioned_json::<Thing>(json.as_bytes()).unwrap(),
+            thing()
+        );
+        let raw = to_versioned_binary(&thing());
+        assert_eq!(binary_version(&raw), 2);
+        assert_eq!(from_versioned_binary::<Thing>(&raw).unwrap(), thing());
+    }
+
+    #[test]
+    fn test_migrations() {
+        let v0 = r#"{"name": "x", "count": 3}"#;
+        assert_eq!(
+            from_versioned_json::<Thing>(v0.as_bytes()).unwrap(),
+            thing()
+        );
+        let v1 = r#"{"version": 1, "name": "x", "count": 3}"#;
+        assert_eq!(
+            from_versioned_json::<Thing>(v1.as_bytes()).unwrap(),
+            thing()
+        );
+        // Unversioned binary files have the same layout
+        let v0 = bincode::serialize(&thing()).unwrap();
+        assert_eq!(from_versioned_binary::<Thing>(&v0).unwrap(), thing());
+    }
+
+    #[test]
+    fn test_too_new() {
+        let v3 = r#"{"version": 3, "name": "x", "size": 3}"#;
+        let err = from_versioned_json::<Thing>(v3.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "this thing is version 3, but only versions 0 to 2 are supported"
+        );
+
+        let mut raw = to_versioned_binary(&thing());
+        raw[MAGIC.len()] = 3;
+        assert!(from_versioned_binary::<Thing>(&raw).is_err());
+    }
+}
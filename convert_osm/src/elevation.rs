@@ -0,0 +1,180 @@
+use crate::srtm;
+use geom::{Distance, LonLat};
+
+// Where elevation data comes from, picked by file extension
+pub enum Elevation {
+    Srtm(srtm::Tile),
+    Grid(Grid),
+}
+
+impl Elevation {
+    pub fn load(path: &str) -> Result<Elevation, String> {
+        if path.ends_with(".hgt") {
+            Ok(Elevation::Srtm(srtm::Tile::load(path)?))
+        } else if path.ends_with(".csv") {
+            println!("Reading elevation data from {}", path);
+            let contents = std::fs::read_to_string(path)
+                .map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            Ok(Elevation::Grid(
+                Grid::parse(&contents).map_err(|err| format!("{}: {}", path, err))?,
+            ))
+        } else {
+            Err(format!(
+                "Don't know how to read elevation data from {}",
+                path
+            ))
+        }
+    }
+
+    // None where the data doesn't cover
+    pub fn get(&self, pt: LonLat) -> Option<Distance> {
+        match self {
+            Elevation::Srtm(tile) => tile.get(pt),
+            Elevation::Grid(grid) => grid.get(pt),
+        }
+    }
+}
+
+// Lines of longitude,latitude,meters covering a grid. The spacing doesn't have to be even, but
+// every combination of longitude and latitude has to be present. A header line is skipped.
+pub struct Grid {
+    // Both sorted
+    lons: Vec<f64>,
+    lats: Vec<f64>,
+    // Indexed by lat, then lon
+    heights: Vec<f64>,
+}
+
+impl Grid {
+    fn parse(contents: &str) -> Result<Grid, String> {
+        let mut pts = Vec::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let parts: Vec<&str> = line.split(',').map(|x| x.trim()).collect();
+            if parts.len() != 3 {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                return Err(format!("line {} doesn't have 3 columns", idx + 1));
+            }
+            match (
+                parts[0].parse::<f64>(),
+                parts[1].parse::<f64>(),
+                parts[2].parse::<f64>(),
+            ) {
+                (Ok(lon), Ok(lat), Ok(height)) => {
+                    pts.push((lon, lat, height));
+                }
+                _ if idx == 0 => {}
+                _ => {
+                    return Err(format!("line {} has something other than numbers", idx + 1));
+                }
+            }
+        }
+
+        let lons = sorted_unique(pts.iter().map(|(lon, _, _)| *lon).collect());
+        let lats = sorted_unique(pts.iter().map(|(_, lat, _)| *lat).collect());
+        if lons.len() < 2 || lats.len() < 2 {
+            return Err("the grid needs at least 2 rows and columns".to_string());
+        }
+        if lons.len() * lats.len() != pts.len() {
+            return Err(format!(
+                "{} points don't make a {}x{} grid",
+                pts.len(),
+                lons.len(),
+                lats.len()
+            ));
+        }
+        let mut heights = vec![std::f64::NAN; pts.len()];
+        for (lon, lat, height) in pts {
+            let x = lons
+                .binary_search_by(|x| x.partial_cmp(&lon).unwrap())
+                .unwrap();
+            let y = lats
+                .binary_search_by(|y| y.partial_cmp(&lat).unwrap())
+                .unwrap();
+            heights[y * lons.len() + x] = height;
+        }
+        if heights.iter().any(|h| h.is_nan()) {
+            return Err("some points in the grid are missing or repeated".to_string());
+        }
+        Ok(Grid {
+            lons,
+            lats,
+            heights,
+        })
+    }
+
+    fn get(&self, pt: LonLat) -> Option<Distance> {
+        let x = fractional_idx(&self.lons, pt.x())?;
+        let y = fractional_idx(&self.lats, pt.y())?;
+        bilinear(x, y, self.lons.len(), self.lats.len(), |col, row| {
+            Some(self.heights[row * self.lons.len() + col])
+        })
+        .map(Distance::meters)
+    }
+}
+
+fn sorted_unique(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values.dedup();
+    values
+}
+
+// Where a value falls between the sorted breakpoints, like 2.5 for halfway between the 3rd and
+// 4th
+fn fractional_idx(breakpoints: &Vec<f64>, value: f64) -> Option<f64> {
+    let idx = breakpoints.iter().rposition(|x| *x <= value)?;
+    if idx == breakpoints.len() - 1 {
+        return if value == breakpoints[idx] {
+            Some(idx as f64)
+        } else {
+            None
+        };
+    }
+    Some(idx as f64 + (value - breakpoints[idx]) / (breakpoints[idx + 1] - breakpoints[idx]))
+}
+
+// x and y are fractional column and row indices into a grid at least 2x2. There's no answer
+// outside the grid or next to missing data.
+pub fn bilinear<F: Fn(usize, usize) -> Option<f64>>(
+    x: f64,
+    y: f64,
+    width: usize,
+    height: usize,
+    get: F,
+) -> Option<f64> {
+    if x < 0.0 || y < 0.0 || x > (width - 1) as f64 || y > (height - 1) as f64 {
+        return None;
+    }
+    let x0 = (x.floor() as usize).min(width - 2);
+    let y0 = (y.floor() as usize).min(height - 2);
+    let dx = x - x0 as f64;
+    let dy = y - y0 as f64;
+    let top = get(x0, y0)? * (1.0 - dx) + get(x0 + 1, y0)? * dx;
+    let bottom = get(x0, y0 + 1)? * (1.0 - dx) + get(x0 + 1, y0 + 1)? * dx;
+    Some(top * (1.0 - dy) + bottom * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse(
+            "longitude,latitude,meters\n\
+             -122.0,47.0,10\n\
+             -121.0,47.0,20\n\
+             -122.0,48.0,30\n\
+             -121.0,48.0,40\n",
+        )
+        .unwrap();
+        let get = |lon, lat| grid.get(LonLat::new(lon, lat)).map(|d| d.inner_meters());
+        assert_eq!(get(-122.0, 47.0), Some(10.0));
+        assert_eq!(get(-121.0, 48.0), Some(40.0));
+        assert_eq!(get(-121.5, 47.5), Some(25.0));
+        assert_eq!(get(-120.0, 47.5), None);
+
+        assert!(Grid::parse("-122.0,47.0,10\n-121.0,47.0,20\n-122.0,48.0,30\n").is_err());
+    }
+}
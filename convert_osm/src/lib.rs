@@ -1,16 +1,19 @@
 mod clip;
+mod elevation;
 mod osm_reader;
 mod split_ways;
 mod srtm;
 
 use abstutil::Timer;
-use geom::{Distance, FindClosest, PolyLine, Pt2D};
+use geom::{Distance, FindClosest, GPSBounds, PolyLine, Pt2D};
 use kml::ExtraShapes;
 use map_model::osm;
 use map_model::raw::{DrivingSide, OriginalBuilding, OriginalRoad, RawMap};
 
 // Just used for matching hints to different sides of a road.
 const DIRECTED_ROAD_THICKNESS: Distance = Distance::const_meters(2.5);
+// How often to sample elevation along a road
+const ELEVATION_SAMPLE_SPACING: Distance = Distance::const_meters(10.0);
 
 pub struct Options {
     pub osm_input: String,
@@ -338,10 +341,63 @@ fn use_amenities(map: &mut RawMap, amenities: Vec<(Pt2D, String, String)>, timer
 }
 
 fn use_elevation(map: &mut RawMap, path: &str, timer: &mut Timer) {
-    timer.start("apply elevation data to intersections");
-    let elevation = srtm::Elevation::load(path).unwrap();
+    timer.start("apply elevation data");
+    let elevation = elevation::Elevation::load(path).unwrap();
+    let mut uncovered = 0;
     for i in map.intersections.values_mut() {
-        i.elevation = elevation.get(i.point.forcibly_to_gps(&map.gps_bounds));
+        if let Some(height) = elevation.get(i.point.forcibly_to_gps(&map.gps_bounds)) {
+            i.elevation = height;
+        } else {
+            uncovered += 1;
+        }
+    }
+    if uncovered > 0 {
+        timer.warn(format!(
+            "{} intersections aren't covered by {}",
+            uncovered, path
+        ));
+    }
+
+    timer.start_iter("sample elevation along roads", map.roads.len());
+    for r in map.roads.values_mut() {
+        timer.next();
+        r.percent_grade = average_grade(
+            &elevation,
+            &PolyLine::new(r.center_points.clone()),
+            &map.gps_bounds,
+        );
+    }
+    timer.stop("apply elevation data");
+}
+
+// Stretches without data are skipped, so one void doesn't ruin a whole road.
+fn average_grade(elevation: &elevation::Elevation, center: &PolyLine, gps: &GPSBounds) -> f64 {
+    let len = center.length();
+    let mut samples = Vec::new();
+    let mut dist = Distance::ZERO;
+    loop {
+        if dist > len {
+            dist = len;
+        }
+        let (pt, _) = center.dist_along(dist);
+        samples.push((dist, elevation.get(pt.forcibly_to_gps(gps))));
+        if dist == len {
+            break;
+        }
+        dist += ELEVATION_SAMPLE_SPACING;
+    }
+
+    let mut rise = Distance::ZERO;
+    let mut run = Distance::ZERO;
+    for pair in samples.windows(2) {
+        if let ((dist1, Some(height1)), (dist2, Some(height2))) = (pair[0], pair[1]) {
+            rise += height2 - height1;
+            run += dist2 - dist1;
+        }
+    }
+    if run == Distance::ZERO {
+        0.0
+    } else {
+        rise / run
     }
-    timer.stop("apply elevation data to intersections");
 }
@@ -166,6 +166,7 @@ pub fn extract_osm(
                     osm_tags: tags,
                     turn_restrictions: Vec::new(),
                     complicated_turn_restrictions: Vec::new(),
+                    percent_grade: 0.0,
                 },
             ));
         } else if is_bldg(&tags) {
@@ -1,47 +1,94 @@
+use crate::elevation::bilinear;
 use byteorder::{BigEndian, ReadBytesExt};
 use geom::{Distance, LonLat};
 use std::fs::File;
-use std::io;
+use std::io::BufReader;
 
-// TODO Make sure this actually works, and link to references describing the format.
-// - It's VERY wrong! Look at 19th and Boyer, and Delmar.
-// TODO Use http://gis.ess.washington.edu/data/raster/tenmeter/byquad/seattle/index.html or
-// something else instead?
-
-// Assuming the 3-second arcs
-const GRID_DIM: usize = 1201;
-
-pub struct Elevation {
-    lon_offset: f64,
-    lat_offset: f64,
+// One SRTM tile in the .hgt format: a square grid of big-endian 16-bit heights in meters, with rows
+// running north to south. The filename, like N47W122.hgt, names the southwest corner. See
+// https://dds.cr.usgs.gov/srtm/version2_1/Documentation/SRTM_Topo.pdf
+pub struct Tile {
+    // The southwest corner
+    lon: f64,
+    lat: f64,
+    // 1201 for 3 arc-second data, 3601 for 1 arc-second
+    dim: usize,
     data: Vec<i16>,
 }
 
-impl Elevation {
-    pub fn load(path: &str) -> Result<Elevation, io::Error> {
+// Marks missing data
+const VOID: i16 = -32768;
+
+impl Tile {
+    pub fn load(path: &str) -> Result<Tile, String> {
         println!("Reading elevation data from {}", path);
-        let mut f = File::open(path).unwrap();
+        let (lon, lat) = parse_name(path)?;
+        let bytes = std::fs::metadata(path)
+            .map_err(|err| format!("Couldn't read {}: {}", path, err))?
+            .len() as usize;
+        let dim = ((bytes / 2) as f64).sqrt() as usize;
+        if dim < 2 || dim * dim * 2 != bytes {
+            return Err(format!("{} isn't a square grid of 16-bit samples", path));
+        }
 
-        let mut e = Elevation {
-            // TODO dont hardcode
-            lon_offset: -122.0,
-            lat_offset: 47.0,
-            data: Vec::with_capacity(GRID_DIM.pow(2)),
-        };
-        // TODO off by one?
-        for _ in 0..GRID_DIM.pow(2) {
-            e.data.push(f.read_i16::<BigEndian>().unwrap());
+        let mut f = BufReader::new(
+            File::open(path).map_err(|err| format!("Couldn't open {}: {}", path, err))?,
+        );
+        let mut data = Vec::with_capacity(dim * dim);
+        for _ in 0..dim * dim {
+            data.push(
+                f.read_i16::<BigEndian>()
+                    .map_err(|err| format!("Couldn't read {}: {}", path, err))?,
+            );
         }
-        Ok(e)
+        Ok(Tile {
+            lon,
+            lat,
+            dim,
+            data,
+        })
     }
 
-    pub fn get(&self, pt: LonLat) -> Distance {
-        // TODO assert the (lon, lat) match the offsets
-        // TODO not tons of confidence in any of this.
-        // TODO interpolate from the 4 matching tiles?
-        let x = ((pt.x() - self.lon_offset).abs() * (GRID_DIM as f64)) as usize;
-        let y = ((pt.y() - self.lat_offset).abs() * (GRID_DIM as f64)) as usize;
-        let i = x + (y * GRID_DIM);
-        Distance::meters(f64::from(self.data[i]))
+    // None outside the tile or near voids
+    pub fn get(&self, pt: LonLat) -> Option<Distance> {
+        let cells = (self.dim - 1) as f64;
+        let x = (pt.x() - self.lon) * cells;
+        // Rows start at the north edge
+        let y = (self.lat + 1.0 - pt.y()) * cells;
+        bilinear(x, y, self.dim, self.dim, |col, row| {
+            let height = self.data[row * self.dim + col];
+            if height == VOID {
+                None
+            } else {
+                Some(f64::from(height))
+            }
+        })
+        .map(Distance::meters)
+    }
+}
+
+// N47W122.hgt is (-122, 47)
+fn parse_name(path: &str) -> Result<(f64, f64), String> {
+    let err = || format!("{} isn't named like N47W122.hgt", path);
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(err)?
+        .to_uppercase();
+    if name.len() != 7 || !name.is_ascii() {
+        return Err(err());
     }
+    let lat = name[1..3].parse::<f64>().map_err(|_| err())?;
+    let lon = name[4..7].parse::<f64>().map_err(|_| err())?;
+    let lat = match &name[0..1] {
+        "N" => lat,
+        "S" => -lat,
+        _ => return Err(err()),
+    };
+    let lon = match &name[3..4] {
+        "E" => lon,
+        "W" => -lon,
+        _ => return Err(err()),
+    };
+    Ok((lon, lat))
 }
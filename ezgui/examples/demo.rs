@@ -202,6 +202,7 @@ impl GUI for App {
         if self.controls.is_checked("paused") {
             EventLoopMode::InputOnly
         } else {
+            ctx.request_animation_frames();
             EventLoopMode::Animation
         }
     }
@@ -7,6 +7,7 @@ use std::cell::Cell;
 
 pub fn setup(
     window_title: &str,
+    vsync: bool,
 ) -> (
     PrerenderInnards,
     winit::event_loop::EventLoop<()>,
@@ -16,13 +17,13 @@ pub fn setup(
     let display = match glium::Display::new(
         winit::window::WindowBuilder::new().with_title(window_title).with_maximized(true),
         // multisampling: 2 looks bad, 4 looks fine
-        glutin::ContextBuilder::new().with_multisampling(4).with_depth_buffer(2),
+        glutin::ContextBuilder::new().with_multisampling(4).with_depth_buffer(2).with_vsync(vsync),
         &event_loop)
     {
         Ok(d) => d,
         Err(err1) => match glium::Display::new(
             winit::window::WindowBuilder::new().with_title(window_title).with_maximized(true),
-            glutin::ContextBuilder::new(),
+            glutin::ContextBuilder::new().with_vsync(vsync),
             &event_loop)
         {
             Ok(d) => {
@@ -281,6 +282,18 @@ impl PrerenderInnards {
         (size.width.into(), size.height.into())
     }
 
+    pub fn toggle_fullscreen(&self) {
+        let gl_window = self.display.gl_window();
+        let window = gl_window.window();
+        if window.fullscreen().is_some() {
+            window.set_fullscreen(None);
+        } else {
+            window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(
+                window.current_monitor(),
+            )));
+        }
+    }
+
     pub fn set_window_icon(&self, icon: winit::window::Icon) {
         self.display
             .gl_window()
@@ -6,6 +6,7 @@ use std::cell::Cell;
 
 pub fn setup(
     window_title: &str,
+    vsync: bool,
 ) -> (
     PrerenderInnards,
     winit::event_loop::EventLoop<()>,
@@ -20,6 +21,7 @@ pub fn setup(
     let context = glutin::ContextBuilder::new()
         .with_multisampling(4)
         .with_depth_buffer(2)
+        .with_vsync(vsync)
         .build_windowed(window, &event_loop)
         .unwrap();
     let windowed_context = unsafe { context.make_current().unwrap() };
@@ -328,6 +330,17 @@ impl PrerenderInnards {
         (size.width.into(), size.height.into())
     }
 
+    pub fn toggle_fullscreen(&self) {
+        let window = self.windowed_context.window();
+        if window.fullscreen().is_some() {
+            window.set_fullscreen(None);
+        } else {
+            window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(
+                window.current_monitor(),
+            )));
+        }
+    }
+
     pub fn set_window_icon(&self, icon: winit::window::Icon) {
         self.windowed_context.window().set_window_icon(Some(icon));
     }
@@ -9,6 +9,8 @@ use winit::platform::web::WindowExtStdweb;
 
 pub fn setup(
     window_title: &str,
+    // The browser always paces frames
+    _vsync: bool,
 ) -> (
     PrerenderInnards,
     winit::event_loop::EventLoop<()>,
@@ -330,6 +332,17 @@ impl PrerenderInnards {
         (size.width.into(), size.height.into())
     }
 
+    pub fn toggle_fullscreen(&self) {
+        let window = &self.window;
+        if window.fullscreen().is_some() {
+            window.set_fullscreen(None);
+        } else {
+            window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(
+                window.current_monitor(),
+            )));
+        }
+    }
+
     pub fn set_window_icon(&self, icon: winit::window::Icon) {
         self.window.set_window_icon(Some(icon));
     }
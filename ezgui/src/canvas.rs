@@ -1,18 +1,46 @@
 use crate::assets::Assets;
-use crate::{hotkey, Key, ScreenDims, ScreenPt, ScreenRectangle, UserInput};
+use crate::{
+    hotkey, FramePacing, Key, KeyBindings, ScreenDims, ScreenPt, ScreenRectangle, UserInput,
+};
 use abstutil::Timer;
-use geom::{Bounds, Pt2D};
+use geom::{Bounds, Duration, Pt2D};
+use instant::Instant;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 
 // Click and release counts as a normal click, not a drag, if the distance between click and
 // release is less than this.
 const DRAG_THRESHOLD: f64 = 5.0;
 
+// Two clicks closer together than this (and DRAG_THRESHOLD apart) are a double click.
+const DOUBLE_CLICK_SECONDS: f64 = 0.4;
+
 const PAN_SPEED: f64 = 15.0;
 
 const PANNING_THRESHOLD: f64 = 25.0;
 
+// Holding shift while panning with the keyboard multiplies the speed by this
+const FAST_PAN_FACTOR: f64 = 3.0;
+// Kinetic panning loses about 63% of its speed this often
+const KINETIC_DECAY_SECONDS: f64 = 0.3;
+// In screen pixels per second. Slower than this, kinetic panning stops (or never starts).
+const MIN_KINETIC_SPEED: f64 = 50.0;
+// If the mouse stops moving this long before the drag is released, don't keep panning.
+const KINETIC_RELEASE_SECONDS: f64 = 0.1;
+
+const MAX_ZOOM: f64 = 150.0;
+// High-resolution wheels and touchpads can send huge deltas in one event. Limit how much one
+// event can zoom, in the same units as the scroll delta.
+const MAX_ZOOM_STEP: f64 = 3.0;
+// Smooth zooming closes about 63% of the remaining distance to the target zoom this often, so
+// it's visually done within a few frames.
+const SMOOTH_ZOOM_SECONDS: f64 = 0.05;
+// Screen pixels per second with a controller's stick pushed all the way
+const GAMEPAD_PAN_SPEED: f64 = 800.0;
+// Zoom levels (in the same units as the scroll delta) per second with a trigger pulled all the way
+const GAMEPAD_ZOOM_SPEED: f64 = 10.0;
+
 pub struct Canvas {
     // All of these f64's are in screen-space, so do NOT use Pt2D.
     // Public for saving/loading... should probably do better
@@ -28,6 +56,26 @@ pub struct Canvas {
     // Only for drags starting on the map. Only used to pan the map. (Last event, original)
     pub(crate) drag_canvas_from: Option<(ScreenPt, ScreenPt)>,
     pub(crate) drag_just_ended: bool,
+    // Panning with the middle mouse button never counts as a click, so it's tracked separately.
+    pub(crate) middle_drag_from: Option<ScreenPt>,
+    // Where smooth zooming is heading, and the screen-space point to keep fixed
+    pub(crate) zoom_target: Option<(f64, (f64, f64))>,
+    // Camera velocity in screen pixels per second, measured while dragging and then used for
+    // kinetic panning after release
+    drag_velocity: (f64, f64),
+    last_drag_sample: Instant,
+    pub(crate) kinetic_velocity: Option<(f64, f64)>,
+    // Set for one event when zooming or kinetic panning finishes, so the caller can recalculate
+    // whatever's under the cursor.
+    pub(crate) camera_just_settled: bool,
+    // When and where the left mouse button was last released
+    last_click: Option<(Instant, ScreenPt)>,
+    // True if the most recent release finished a double click
+    pub(crate) double_clicked: bool,
+    // How far a controller's stick and triggers are pushed, from -1 to 1. The camera moves
+    // continuously while these are held.
+    pub(crate) gamepad_pan: (f64, f64),
+    pub(crate) gamepad_zoom: f64,
 
     pub window_width: f64,
     pub window_height: f64,
@@ -39,6 +87,28 @@ pub struct Canvas {
     pub edge_auto_panning: bool,
     pub keys_to_pan: bool,
     pub gui_scroll_speed: usize,
+    pub smooth_zoom: bool,
+    pub kinetic_panning: bool,
+    // Screen pixels per arrow key press at zoom 1. Scaled by zoom.
+    pub keys_pan_speed: usize,
+    pub keybindings: KeyBindings,
+    pub frame_pacing: FramePacing,
+    // Update less often when there's been no input for a while and nothing's animating
+    pub idle_when_inactive: bool,
+    // Set during an event if something on screen is moving and needs smooth frames
+    pub(crate) needs_animation_frames: bool,
+    // If the player picked a UI scale factor, stop following the monitor's.
+    pub(crate) scale_factor_override: Option<f64>,
+    // True while recording or replaying input
+    pub(crate) input_recorded: bool,
+
+    // Something besides a button or hotkey, like a menu, can trigger an action by name. It fires
+    // during the next event, through the same path as the hotkey.
+    pub(crate) next_triggered_action: Option<String>,
+    pub(crate) triggered_action: Option<String>,
+    // Every action that could've been activated during the current and previous event
+    pub(crate) actions_available: BTreeSet<String>,
+    pub(crate) prev_actions_available: BTreeSet<String>,
 
     // TODO Bit weird and hacky to mutate inside of draw() calls.
     pub(crate) covered_areas: RefCell<Vec<ScreenRectangle>>,
@@ -61,9 +131,21 @@ impl Canvas {
 
             drag_canvas_from: None,
             drag_just_ended: false,
+            middle_drag_from: None,
+            zoom_target: None,
+            drag_velocity: (0.0, 0.0),
+            last_drag_sample: Instant::now(),
+            kinetic_velocity: None,
+            camera_just_settled: false,
+            last_click: None,
+            double_clicked: false,
+            gamepad_pan: (0.0, 0.0),
+            gamepad_zoom: 0.0,
 
             window_width: initial_width,
             window_height: initial_height,
+            scale_factor_override: None,
+            input_recorded: false,
 
             map_dims: (0.0, 0.0),
             invert_scroll: false,
@@ -71,6 +153,18 @@ impl Canvas {
             edge_auto_panning: false,
             keys_to_pan: false,
             gui_scroll_speed: 5,
+            smooth_zoom: true,
+            kinetic_panning: true,
+            keys_pan_speed: 15,
+            keybindings: KeyBindings::new(),
+            frame_pacing: FramePacing::Cap30,
+            idle_when_inactive: true,
+            needs_animation_frames: false,
+
+            next_triggered_action: None,
+            triggered_action: None,
+            actions_available: BTreeSet::new(),
+            prev_actions_available: BTreeSet::new(),
 
             covered_areas: RefCell::new(Vec::new()),
 
@@ -85,9 +179,13 @@ impl Canvas {
             .min(percent_window * self.window_height / self.map_dims.1)
     }
 
-    pub(crate) fn handle_event(&mut self, input: &mut UserInput) {
+    // The scale factor applies to the thresholds, which are in logical pixels.
+    pub(crate) fn handle_event(&mut self, input: &mut UserInput, scale_factor: f64) {
         // Can't start dragging or zooming on top of covered area
         if self.get_cursor_in_map_space().is_some() {
+            if input.middle_mouse_button_pressed() {
+                self.middle_drag_from = Some(self.get_cursor());
+            }
             if self.touchpad_to_move {
                 if let Some((scroll_x, scroll_y)) = input.get_mouse_scroll() {
                     if self.lctrl_held {
@@ -101,6 +199,8 @@ impl Canvas {
             } else {
                 if input.left_mouse_button_pressed() {
                     self.drag_canvas_from = Some((self.get_cursor(), self.get_cursor()));
+                    self.drag_velocity = (0.0, 0.0);
+                    self.last_drag_sample = Instant::now();
                 }
 
                 if let Some((_, scroll)) = input.get_mouse_scroll() {
@@ -109,39 +209,55 @@ impl Canvas {
             }
 
             if self.keys_to_pan {
-                if input.new_was_pressed(&hotkey(Key::LeftArrow).unwrap()) {
-                    self.cam_x -= PAN_SPEED;
+                let speed = self.key_pan_step();
+                if self.key_pressed(input, "pan left", Key::LeftArrow) {
+                    self.cam_x -= speed;
                 }
-                if input.new_was_pressed(&hotkey(Key::RightArrow).unwrap()) {
-                    self.cam_x += PAN_SPEED;
+                if self.key_pressed(input, "pan right", Key::RightArrow) {
+                    self.cam_x += speed;
                 }
-                if input.new_was_pressed(&hotkey(Key::UpArrow).unwrap()) {
-                    self.cam_y -= PAN_SPEED;
+                if self.key_pressed(input, "pan up", Key::UpArrow) {
+                    self.cam_y -= speed;
                 }
-                if input.new_was_pressed(&hotkey(Key::DownArrow).unwrap()) {
-                    self.cam_y += PAN_SPEED;
+                if self.key_pressed(input, "pan down", Key::DownArrow) {
+                    self.cam_y += speed;
                 }
-                if input.new_was_pressed(&hotkey(Key::Q).unwrap()) {
+                if self.key_pressed(input, "zoom in", Key::Q) {
                     self.zoom(1.0, (self.window_width / 2.0, self.window_height / 2.0));
                 }
-                if input.new_was_pressed(&hotkey(Key::W).unwrap()) {
+                if self.key_pressed(input, "zoom out", Key::W) {
                     self.zoom(-1.0, (self.window_width / 2.0, self.window_height / 2.0));
                 }
             }
         }
 
+        if let Some(from) = self.middle_drag_from {
+            let pt = self.get_cursor();
+            self.cam_x += from.x - pt.x;
+            self.cam_y += from.y - pt.y;
+            self.middle_drag_from = if input.middle_mouse_button_released() {
+                None
+            } else {
+                Some(pt)
+            };
+        }
+
         // If we start the drag on the map and move the mouse off the map, keep dragging.
         if let Some((click, orig)) = self.drag_canvas_from {
             let pt = self.get_cursor();
             self.cam_x += click.x - pt.x;
             self.cam_y += click.y - pt.y;
             self.drag_canvas_from = Some((pt, orig));
+            if input.get_moved_mouse().is_some() {
+                self.sample_drag_velocity(click.x - pt.x, click.y - pt.y);
+            }
 
             if input.left_mouse_button_released() {
                 let (_, orig) = self.drag_canvas_from.take().unwrap();
                 let dist = ((pt.x - orig.x).powi(2) + (pt.y - orig.y).powi(2)).sqrt();
-                if dist > DRAG_THRESHOLD {
+                if dist > DRAG_THRESHOLD * scale_factor {
                     self.drag_just_ended = true;
+                    self.start_kinetic_panning();
                 }
             }
         } else if self.drag_just_ended {
@@ -149,7 +265,7 @@ impl Canvas {
         } else {
             let cursor_screen_pt = self.get_cursor().to_pt();
             let cursor_map_pt = self.screen_to_map(self.get_cursor());
-            let inner_bounds = self.get_inner_bounds();
+            let inner_bounds = self.get_inner_bounds(scale_factor);
             let map_bounds = self.get_map_bounds();
             if self.edge_auto_panning
                 && !inner_bounds.contains(cursor_screen_pt)
@@ -169,13 +285,98 @@ impl Canvas {
         }
     }
 
+    // Zoomed out, each press covers more of the map; zoomed in, it moves less, so it's easier to
+    // line things up.
+    fn key_pan_step(&self) -> f64 {
+        let zoom_factor = (1.0 / self.cam_zoom).sqrt().max(0.5).min(4.0);
+        let mut speed = (self.keys_pan_speed as f64) * zoom_factor;
+        if self.lshift_held {
+            speed *= FAST_PAN_FACTOR;
+        }
+        speed
+    }
+
+    fn sample_drag_velocity(&mut self, dx: f64, dy: f64) {
+        let dt = abstutil::elapsed_seconds(self.last_drag_sample);
+        self.last_drag_sample = Instant::now();
+        if dt <= 0.0 {
+            return;
+        }
+        // Smooth out jittery mouse events
+        self.drag_velocity = (
+            0.5 * self.drag_velocity.0 + 0.5 * dx / dt,
+            0.5 * self.drag_velocity.1 + 0.5 * dy / dt,
+        );
+    }
+
+    fn start_kinetic_panning(&mut self) {
+        let (vx, vy) = self.drag_velocity;
+        self.drag_velocity = (0.0, 0.0);
+        if self.kinetic_panning
+            && abstutil::elapsed_seconds(self.last_drag_sample) < KINETIC_RELEASE_SECONDS
+            && (vx.powi(2) + vy.powi(2)).sqrt() > MIN_KINETIC_SPEED
+        {
+            self.kinetic_velocity = Some((vx, vy));
+        }
+    }
+
+    // Called every time the left mouse button is released.
+    pub(crate) fn record_click(&mut self) {
+        let pt = self.get_cursor();
+        self.double_clicked = match self.last_click {
+            Some((time, prev)) => {
+                abstutil::elapsed_seconds(time) < DOUBLE_CLICK_SECONDS
+                    && ((pt.x - prev.x).powi(2) + (pt.y - prev.y).powi(2)).sqrt() < DRAG_THRESHOLD
+            }
+            None => false,
+        };
+        // A third click starts over, instead of being another double click
+        self.last_click = if self.double_clicked {
+            None
+        } else {
+            Some((Instant::now(), pt))
+        };
+    }
+
+    // Keeps the same part of the map in the center of the screen, at the same scale.
+    pub(crate) fn window_resized(&mut self, width: f64, height: f64) {
+        let center = self.center_to_map_pt();
+        self.window_width = width;
+        self.window_height = height;
+        // The focus point of any zoom in progress is in the old screen space
+        self.zoom_target = None;
+        self.cancel_kinetic_panning();
+        self.center_on_map_pt(center);
+    }
+
+    // Any other input stops the camera from drifting.
+    pub(crate) fn cancel_kinetic_panning(&mut self) {
+        self.kinetic_velocity = None;
+    }
+
     fn zoom(&mut self, delta: f64, focus: (f64, f64)) {
-        let old_zoom = self.cam_zoom;
+        let delta = delta.max(-MAX_ZOOM_STEP).min(MAX_ZOOM_STEP);
+        // Scrolling again before smooth zooming finishes should keep going from the target, not
+        // the intermediate zoom.
+        let from = self
+            .zoom_target
+            .map(|(zoom, _)| zoom)
+            .unwrap_or(self.cam_zoom);
         // By popular request, some limits ;)
-        self.cam_zoom = 1.1_f64
-            .powf(old_zoom.log(1.1) + delta)
+        let target = 1.1_f64
+            .powf(from.log(1.1) + delta)
             .max(self.min_zoom())
-            .min(150.0);
+            .min(MAX_ZOOM);
+        if self.smooth_zoom {
+            self.zoom_target = Some((target, focus));
+        } else {
+            self.zoom_around(target, focus);
+        }
+    }
+
+    fn zoom_around(&mut self, new_zoom: f64, focus: (f64, f64)) {
+        let old_zoom = self.cam_zoom;
+        self.cam_zoom = new_zoom;
 
         // Make screen_to_map of the focus point still point to the same thing after
         // zooming.
@@ -183,6 +384,74 @@ impl Canvas {
         self.cam_y = ((self.cam_zoom / old_zoom) * (focus.1 + self.cam_y)) - focus.1;
     }
 
+    // Called on every update event.
+    pub(crate) fn animate(&mut self, dt: Duration) {
+        let was_moving = self.is_moving();
+        self.animate_zoom(dt);
+
+        if let Some((vx, vy)) = self.kinetic_velocity {
+            let dt = dt.inner_seconds();
+            self.cam_x += vx * dt;
+            self.cam_y += vy * dt;
+            let decay = (-dt / KINETIC_DECAY_SECONDS).exp();
+            let (vx, vy) = (vx * decay, vy * decay);
+            self.kinetic_velocity = if (vx.powi(2) + vy.powi(2)).sqrt() < MIN_KINETIC_SPEED {
+                None
+            } else {
+                Some((vx, vy))
+            };
+        }
+
+        if self.gamepad_pan != (0.0, 0.0) {
+            let dt = dt.inner_seconds();
+            self.cam_x += self.gamepad_pan.0 * GAMEPAD_PAN_SPEED * dt;
+            self.cam_y += self.gamepad_pan.1 * GAMEPAD_PAN_SPEED * dt;
+        }
+        if self.gamepad_zoom != 0.0 {
+            let zoom = 1.1_f64
+                .powf(
+                    self.cam_zoom.log(1.1)
+                        + self.gamepad_zoom * GAMEPAD_ZOOM_SPEED * dt.inner_seconds(),
+                )
+                .max(self.min_zoom())
+                .min(MAX_ZOOM);
+            self.zoom_around(zoom, (self.window_width / 2.0, self.window_height / 2.0));
+        }
+
+        if was_moving && !self.is_moving() {
+            self.camera_just_settled = true;
+        }
+    }
+
+    // Interpolates in log space, so zooming in and out feel the same.
+    fn animate_zoom(&mut self, dt: Duration) {
+        if let Some((target, focus)) = self.zoom_target {
+            let pct = 1.0 - (-dt.inner_seconds() / SMOOTH_ZOOM_SECONDS).exp();
+            let zoom = (self.cam_zoom.ln() + pct * (target.ln() - self.cam_zoom.ln())).exp();
+            if (zoom / target - 1.0).abs() < 0.01 {
+                self.zoom_around(target, focus);
+                self.zoom_target = None;
+            } else {
+                self.zoom_around(zoom, focus);
+            }
+        }
+    }
+
+    // True while smoothly zooming, kinetic panning, or moving with a controller. Figuring out
+    // what's under the cursor every frame while the camera's flying around is wasted work.
+    pub fn is_moving(&self) -> bool {
+        self.zoom_target.is_some()
+            || self.kinetic_velocity.is_some()
+            || self.gamepad_pan != (0.0, 0.0)
+            || self.gamepad_zoom != 0.0
+    }
+
+    // A replay feeds in the same events, but everything else runs at a different speed. While
+    // recording or replaying, the GUI shouldn't do anything that depends on how long work takes.
+    pub fn input_is_recorded(&self) -> bool {
+        self.input_recorded
+    }
+
     pub(crate) fn start_drawing(&self) {
         self.covered_areas.borrow_mut().clear();
     }
@@ -249,13 +518,14 @@ impl Canvas {
     }
 
     //the inner bound tells us whether auto-panning should or should not take place
-    fn get_inner_bounds(&self) -> Bounds {
+    fn get_inner_bounds(&self, scale_factor: f64) -> Bounds {
+        let threshold = PANNING_THRESHOLD * scale_factor;
         let mut b = Bounds::new();
-        b.update(ScreenPt::new(PANNING_THRESHOLD, PANNING_THRESHOLD).to_pt());
+        b.update(ScreenPt::new(threshold, threshold).to_pt());
         b.update(
             ScreenPt::new(
-                self.window_width - PANNING_THRESHOLD,
-                self.window_height - PANNING_THRESHOLD,
+                self.window_width - threshold,
+                self.window_height - threshold,
             )
             .to_pt(),
         );
@@ -285,6 +555,13 @@ impl Canvas {
         abstutil::write_json(abstutil::path_camera_state(map_name), &state);
     }
 
+    fn key_pressed(&self, input: &mut UserInput, action: &str, default: Key) -> bool {
+        match self.keybindings.resolve(action, hotkey(default)) {
+            Some(key) => input.new_was_pressed(&key),
+            None => false,
+        }
+    }
+
     // True if this succeeds
     pub fn load_camera_state(&mut self, map_name: &str) -> bool {
         match abstutil::maybe_read_json::<CameraState>(
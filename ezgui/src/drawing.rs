@@ -1,8 +1,8 @@
 use crate::assets::Assets;
 use crate::backend::{GfxCtxInnards, PrerenderInnards};
 use crate::{
-    Canvas, Color, Drawable, FancyColor, GeomBatch, ScreenDims, ScreenPt, ScreenRectangle, Style,
-    Text,
+    Canvas, Color, Drawable, FancyColor, GeomBatch, OverlayPanel, PanelAnchor, ScreenPt,
+    ScreenRectangle, Style, Text,
 };
 use geom::{ArrowCap, Bounds, Circle, Distance, Line, Polygon, Pt2D};
 use std::cell::Cell;
@@ -50,6 +50,8 @@ pub struct GfxCtx<'a> {
 
     pub num_draw_calls: usize,
     pub num_forks: usize,
+
+    pub(crate) overlay_panels: Vec<OverlayPanel>,
 }
 
 impl<'a> GfxCtx<'a> {
@@ -70,6 +72,7 @@ impl<'a> GfxCtx<'a> {
             num_forks: 0,
             screencap_mode,
             naming_hint: None,
+            overlay_panels: Vec::new(),
         }
     }
 
@@ -186,27 +189,17 @@ impl<'a> GfxCtx<'a> {
 
     // Canvas stuff.
 
+    // Tooltips are stacked with any other panels following the cursor, above them.
     pub fn draw_mouse_tooltip(&mut self, txt: Text) {
-        // Add some padding
-        let pad = 5.0;
-
-        let txt_batch = txt.render_g(self);
-        let raw_dims = txt_batch.get_dims();
-        let dims = ScreenDims::new(raw_dims.width + 2.0 * pad, raw_dims.height + 2.0 * pad);
+        self.declare_panel(OverlayPanel::new(PanelAnchor::FollowCursor, txt).priority(100));
+    }
 
-        // TODO Maybe also consider the cursor as a valid center
-        let pt = dims.top_left_for_corner(
-            ScreenPt::new(self.canvas.cursor_x, self.canvas.cursor_y + 20.0),
-            &self.canvas,
-        );
-        let mut batch = GeomBatch::new();
-        // TODO Outline?
-        batch.push(
-            Color::BLACK,
-            Polygon::rectangle(dims.width, dims.height).translate(pt.x, pt.y),
-        );
-        batch.append(txt_batch.translate(pt.x + pad, pt.y + pad));
+    // Drawn at the end of the frame, stacked with other panels, no matter when this is called.
+    pub fn declare_panel(&mut self, panel: OverlayPanel) {
+        self.overlay_panels.push(panel);
+    }
 
+    pub(crate) fn draw_overlay_batch(&mut self, batch: GeomBatch) {
         // fork_screenspace, but with an even more prominent Z
         self.uniforms.transform = [0.0, 0.0, 1.0];
         self.uniforms.window = [
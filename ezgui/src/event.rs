@@ -1,10 +1,11 @@
 use crate::ScreenPt;
 use geom::Duration;
+use serde::{Deserialize, Serialize};
 use winit::event::{
     ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Event {
     // Used to initialize the application and also to recalculate menu state when some other event
     // is used.
@@ -13,6 +14,8 @@ pub enum Event {
     LeftMouseButtonUp,
     RightMouseButtonDown,
     RightMouseButtonUp,
+    MiddleMouseButtonDown,
+    MiddleMouseButtonUp,
     // TODO KeyDown and KeyUp might be nicer, but piston (and probably X.org) hands over repeated
     // events while a key is held down.
     KeyPress(Key),
@@ -25,6 +28,8 @@ pub enum Event {
     WindowGainedCursor,
     MouseWheelScroll(f64, f64),
     WindowResized(f64, f64),
+    // The window moved to a monitor with a different scale factor
+    ScaleFactorChanged(f64),
 }
 
 impl Event {
@@ -35,6 +40,8 @@ impl Event {
                 (MouseButton::Left, ElementState::Released) => Some(Event::LeftMouseButtonUp),
                 (MouseButton::Right, ElementState::Pressed) => Some(Event::RightMouseButtonDown),
                 (MouseButton::Right, ElementState::Released) => Some(Event::RightMouseButtonUp),
+                (MouseButton::Middle, ElementState::Pressed) => Some(Event::MiddleMouseButtonDown),
+                (MouseButton::Middle, ElementState::Released) => Some(Event::MiddleMouseButtonUp),
                 _ => None,
             },
             WindowEvent::KeyboardInput { input, .. } => {
@@ -75,6 +82,9 @@ impl Event {
             WindowEvent::Resized(size) => {
                 Some(Event::WindowResized(size.width.into(), size.height.into()))
             }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                Some(Event::ScaleFactorChanged(scale_factor))
+            }
             WindowEvent::Focused(gained) => Some(if gained {
                 Event::WindowGainedCursor
             } else {
@@ -96,7 +106,7 @@ fn scroll_wheel_multiplier() -> f64 {
     1.0
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum Key {
     // Case is unspecified.
     // TODO Would be cool to represent A and UpperA, but then release semantics get weird... hold
@@ -163,6 +173,9 @@ pub enum Key {
     RightArrow,
     UpArrow,
     DownArrow,
+    Home,
+    End,
+    Delete,
     F1,
     F2,
     F3,
@@ -250,6 +263,9 @@ impl Key {
             | Key::RightArrow
             | Key::UpArrow
             | Key::DownArrow
+            | Key::Home
+            | Key::End
+            | Key::Delete
             | Key::F1
             | Key::F2
             | Key::F3
@@ -279,6 +295,9 @@ impl Key {
             Key::RightArrow => "→ arrow".to_string(),
             Key::UpArrow => "↑".to_string(),
             Key::DownArrow => "↓".to_string(),
+            Key::Home => "Home".to_string(),
+            Key::End => "End".to_string(),
+            Key::Delete => "Delete".to_string(),
             Key::F1 => "F1".to_string(),
             Key::F2 => "F2".to_string(),
             Key::F3 => "F3".to_string(),
@@ -358,6 +377,9 @@ impl Key {
             VirtualKeyCode::Right => Key::RightArrow,
             VirtualKeyCode::Up => Key::UpArrow,
             VirtualKeyCode::Down => Key::DownArrow,
+            VirtualKeyCode::Home => Key::Home,
+            VirtualKeyCode::End => Key::End,
+            VirtualKeyCode::Delete => Key::Delete,
             VirtualKeyCode::F1 => Key::F1,
             VirtualKeyCode::F2 => Key::F2,
             VirtualKeyCode::F3 => Key::F3,
@@ -379,7 +401,7 @@ impl Key {
 }
 
 // TODO This is not an ideal representation at all.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum MultiKey {
     Normal(Key),
     LCtrl(Key),
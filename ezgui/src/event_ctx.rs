@@ -1,8 +1,8 @@
 use crate::{
-    svg, text, Canvas, Color, Drawable, Event, GeomBatch, GfxCtx, Line, Prerender, ScreenPt, Style,
-    Text, UserInput,
+    svg, text, Canvas, Color, Drawable, Event, GeomBatch, GfxCtx, Line, MultiKey, Prerender,
+    ScreenPt, Style, Text, UserInput,
 };
-use abstutil::{elapsed_seconds, Timer, TimerSink};
+use abstutil::{elapsed_seconds, Phase, Timer, TimerSink};
 use geom::Polygon;
 use instant::Instant;
 use std::collections::VecDeque;
@@ -36,7 +36,42 @@ impl<'a> EventCtx<'a> {
     }
 
     pub fn canvas_movement(&mut self) {
-        self.canvas.handle_event(&mut self.input)
+        let scale_factor = self.get_scale_factor();
+        self.canvas.handle_event(&mut self.input, scale_factor)
+    }
+
+    // Like a button's hotkey, the player can rebind this by the action's name.
+    pub fn action_pressed(&mut self, action: &str, default: Option<MultiKey>) -> bool {
+        if self.check_triggered_action(action) {
+            return true;
+        }
+        match self.canvas.keybindings.resolve(action, default) {
+            Some(key) => self.input.new_was_pressed(&key),
+            None => false,
+        }
+    }
+
+    // During the next event, the button or action_pressed() call with this name will fire, as
+    // if its hotkey was pressed.
+    pub fn trigger_action(&mut self, action: String) {
+        self.canvas.next_triggered_action = Some(action);
+    }
+
+    // Could some button or action_pressed() call with this name have fired during the last event?
+    pub fn is_action_available(&self, action: &str) -> bool {
+        self.canvas.prev_actions_available.contains(action)
+    }
+
+    // Records that the action is available, and returns true (only once) if it was triggered.
+    pub(crate) fn check_triggered_action(&mut self, action: &str) -> bool {
+        if !self.canvas.actions_available.contains(action) {
+            self.canvas.actions_available.insert(action.to_string());
+        }
+        if self.canvas.triggered_action.as_ref().map(|a| a == action) == Some(true) {
+            self.canvas.triggered_action = None;
+            return true;
+        }
+        false
     }
 
     // Use to immediately plumb through an (empty) event to something
@@ -56,9 +91,15 @@ impl<'a> EventCtx<'a> {
     }
 
     pub fn redo_mouseover(&self) -> bool {
-        self.fake_mouseover
-            || self.input.window_lost_cursor()
-            || (!self.is_dragging() && self.input.get_moved_mouse().is_some())
+        if self.fake_mouseover || self.input.window_lost_cursor() || self.canvas.camera_just_settled
+        {
+            return true;
+        }
+        // Wait until the camera settles down
+        if self.canvas.is_moving() {
+            return false;
+        }
+        (!self.is_dragging() && self.input.get_moved_mouse().is_some())
             || self
                 .input
                 .get_mouse_scroll()
@@ -77,8 +118,25 @@ impl<'a> EventCtx<'a> {
         false
     }
 
+    // The second click of a double click is also a normal_left_click, so check this first.
+    pub fn double_left_click(&mut self) -> bool {
+        self.canvas.double_clicked && self.normal_left_click()
+    }
+
+    // Call this every event while something is animating, so the frame rate doesn't drop when the
+    // player isn't touching anything. Returning EventLoopMode::Animation isn't enough.
+    pub fn request_animation_frames(&mut self) {
+        self.canvas.needs_animation_frames = true;
+    }
+
+    pub fn is_ctrl_held(&self) -> bool {
+        self.canvas.lctrl_held
+    }
+
     fn is_dragging(&self) -> bool {
-        self.canvas.drag_canvas_from.is_some() || self.canvas.drag_just_ended
+        self.canvas.drag_canvas_from.is_some()
+            || self.canvas.drag_just_ended
+            || self.canvas.middle_drag_from.is_some()
     }
 
     // Delegation to assets
@@ -95,6 +153,16 @@ impl<'a> EventCtx<'a> {
         self.prerender.assets.set_scale_factor(scale)
     }
 
+    // None means to use the monitor's scale factor, even as the window moves between monitors.
+    pub fn override_scale_factor(&mut self, scale: Option<f64>) {
+        self.canvas.scale_factor_override = scale;
+        self.set_scale_factor(scale.unwrap_or_else(|| self.monitor_scale_factor()));
+    }
+
+    pub fn get_scale_factor_override(&self) -> Option<f64> {
+        self.canvas.scale_factor_override
+    }
+
     pub fn get_scale_factor(&self) -> f64 {
         *self.prerender.assets.scale_factor.borrow()
     }
@@ -135,6 +203,8 @@ pub struct LoadingScreen<'a> {
     prerender: &'a Prerender,
     lines: VecDeque<String>,
     max_capacity: usize,
+    // What the Timer is in the middle of, shown above the log lines
+    phases: Vec<Phase>,
     last_drawn: Instant,
     title: String,
 }
@@ -153,6 +223,7 @@ impl<'a> LoadingScreen<'a> {
             prerender,
             lines: VecDeque::new(),
             max_capacity,
+            phases: Vec::new(),
             // If the loading callback takes less than 0.5s, we don't redraw at all.
             last_drawn: Instant::now(),
             title,
@@ -169,7 +240,16 @@ impl<'a> LoadingScreen<'a> {
         self.last_drawn = Instant::now();
 
         let mut txt = Text::from(Line(&self.title).small_heading());
-        for l in &self.lines {
+        // Skip the outermost phase; it's usually the same as the title
+        for phase in self.phases.iter().skip(1) {
+            txt.add(Line(phase.describe()).fg(self.style.hotkey_color));
+        }
+        if self.phases.len() > 1 {
+            txt.add(Line(""));
+        }
+        // Make room for the phases
+        let skip = (self.lines.len() + self.phases.len()).saturating_sub(self.max_capacity);
+        for l in self.lines.iter().skip(skip) {
             txt.add(Line(l));
         }
 
@@ -206,4 +286,9 @@ impl<'a> TimerSink for LoadingScreen<'a> {
         self.lines.push_back(line);
         self.redraw();
     }
+
+    fn update_phases(&mut self, phases: Vec<Phase>) {
+        self.phases = phases;
+        self.redraw();
+    }
 }
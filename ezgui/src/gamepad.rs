@@ -0,0 +1,263 @@
+// Without the gilrs feature, no controller input ever arrives.
+#![cfg_attr(not(feature = "gilrs"), allow(dead_code))]
+
+use crate::{hotkey, Canvas, Event, Key, KeyBindings, MultiKey};
+use instant::Instant;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Ignore tiny stick movements; sticks rarely rest at exactly 0.
+const STICK_DEADZONE: f64 = 0.2;
+// Tilting the right stick past this acts like holding a direction on the d-pad
+const STICK_AS_BUTTON: f64 = 0.5;
+// Holding a direction repeats it, like holding an arrow key
+const REPEAT_DELAY_SECONDS: f64 = 0.4;
+const REPEAT_EVERY_SECONDS: f64 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Button {
+    A,
+    B,
+    X,
+    Y,
+    Up,
+    Down,
+    Left,
+    Right,
+    Start,
+    Select,
+}
+
+impl Button {
+    const ALL: [Button; 10] = [
+        Button::A,
+        Button::B,
+        Button::X,
+        Button::Y,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::Start,
+        Button::Select,
+    ];
+
+    // Each button acts like pressing some key. The mapping lives in the key bindings, under this
+    // action name, so players can change it like anything else.
+    fn action(self) -> (&'static str, Option<MultiKey>) {
+        match self {
+            Button::A => ("gamepad A", hotkey(Key::Enter)),
+            Button::B => ("gamepad B", hotkey(Key::Escape)),
+            Button::X => ("gamepad X", hotkey(Key::Space)),
+            Button::Y => ("gamepad Y", hotkey(Key::Tab)),
+            Button::Up => ("gamepad up", hotkey(Key::UpArrow)),
+            Button::Down => ("gamepad down", hotkey(Key::DownArrow)),
+            Button::Left => ("gamepad left", hotkey(Key::LeftArrow)),
+            Button::Right => ("gamepad right", hotkey(Key::RightArrow)),
+            Button::Start => ("gamepad start", None),
+            Button::Select => ("gamepad select", None),
+        }
+    }
+
+    fn repeats(self) -> bool {
+        match self {
+            Button::Up | Button::Down | Button::Left | Button::Right => true,
+            _ => false,
+        }
+    }
+}
+
+// Turns controller input into the keys that each button is mapped to, and the sticks and triggers
+// into camera movement. Without a controller, this does nothing.
+pub(crate) struct Gamepads {
+    #[cfg(feature = "gilrs")]
+    gilrs: Option<gilrs::Gilrs>,
+    // Buttons currently held down, and when they should repeat next
+    held: BTreeMap<Button, Instant>,
+    // Directions that the right stick is currently tilted towards
+    stick_directions: BTreeSet<Button>,
+    // Once any controller button is used, all of the mappings show up in the key bindings screen.
+    registered_actions: bool,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Gamepads {
+        Gamepads {
+            #[cfg(feature = "gilrs")]
+            gilrs: match gilrs::Gilrs::new() {
+                Ok(g) => Some(g),
+                Err(err) => {
+                    println!("No controller support: {}", err);
+                    None
+                }
+            },
+            held: BTreeMap::new(),
+            stick_directions: BTreeSet::new(),
+            registered_actions: false,
+        }
+    }
+
+    // Controllers don't wake up the event loop, so while one is plugged in, keep polling.
+    pub(crate) fn any_connected(&self) -> bool {
+        #[cfg(feature = "gilrs")]
+        {
+            if let Some(ref gilrs) = self.gilrs {
+                return gilrs.gamepads().any(|(_, pad)| pad.is_connected());
+            }
+        }
+        false
+    }
+
+    // Returns the keyboard events to send through the GUI, and updates the canvas's analog
+    // movement.
+    pub(crate) fn poll(&mut self, canvas: &mut Canvas) -> Vec<Event> {
+        let mut events = Vec::new();
+        #[cfg(feature = "gilrs")]
+        {
+            let mut pressed = Vec::new();
+            let mut released = Vec::new();
+            if let Some(ref mut gilrs) = self.gilrs {
+                while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+                    match event {
+                        gilrs::EventType::ButtonPressed(b, _) => {
+                            if let Some(b) = from_gilrs(b) {
+                                pressed.push(b);
+                            }
+                        }
+                        gilrs::EventType::ButtonReleased(b, _) => {
+                            if let Some(b) = from_gilrs(b) {
+                                released.push(b);
+                            }
+                        }
+                        gilrs::EventType::Connected => {
+                            println!("Controller connected: {}", gilrs.gamepad(id).name());
+                        }
+                        gilrs::EventType::Disconnected => {
+                            println!("Controller disconnected: {}", gilrs.gamepad(id).name());
+                            // Nothing can be held on a controller that's gone
+                            released.extend(self.held.keys().cloned());
+                        }
+                        _ => {}
+                    }
+                }
+
+                // Combine every connected controller
+                let (mut pan_x, mut pan_y, mut zoom) = (0.0, 0.0, 0.0);
+                let (mut right_x, mut right_y) = (0.0, 0.0);
+                for (_, pad) in gilrs.gamepads() {
+                    let value = |axis| deadzone(pad.value(axis) as f64);
+                    pan_x += value(gilrs::Axis::LeftStickX);
+                    pan_y -= value(gilrs::Axis::LeftStickY);
+                    right_x += value(gilrs::Axis::RightStickX);
+                    right_y -= value(gilrs::Axis::RightStickY);
+                    let trigger = |b| {
+                        pad.button_data(b)
+                            .map(|data| deadzone(data.value() as f64))
+                            .unwrap_or(0.0)
+                    };
+                    zoom += trigger(gilrs::Button::RightTrigger2)
+                        - trigger(gilrs::Button::LeftTrigger2);
+                }
+                // The camera only moves during updates, so make sure the event loop starts them
+                if !canvas.is_moving() && (pan_x != 0.0 || pan_y != 0.0 || zoom != 0.0) {
+                    events.push(Event::NoOp);
+                }
+                canvas.gamepad_pan = (pan_x, pan_y);
+                canvas.gamepad_zoom = zoom;
+
+                let mut directions = BTreeSet::new();
+                if right_x < -STICK_AS_BUTTON {
+                    directions.insert(Button::Left);
+                } else if right_x > STICK_AS_BUTTON {
+                    directions.insert(Button::Right);
+                }
+                if right_y < -STICK_AS_BUTTON {
+                    directions.insert(Button::Up);
+                } else if right_y > STICK_AS_BUTTON {
+                    directions.insert(Button::Down);
+                }
+                pressed.extend(directions.difference(&self.stick_directions));
+                released.extend(self.stick_directions.difference(&directions));
+                self.stick_directions = directions;
+            }
+
+            if !pressed.is_empty() && !self.registered_actions {
+                self.registered_actions = true;
+                for b in Button::ALL.iter() {
+                    let (action, default) = b.action();
+                    canvas.keybindings.resolve(action, default);
+                }
+            }
+            for b in released {
+                if self.held.remove(&b).is_some() {
+                    events.extend(key_events(b, &canvas.keybindings, false));
+                }
+            }
+            for b in pressed {
+                self.held.insert(
+                    b,
+                    Instant::now() + std::time::Duration::from_secs_f64(REPEAT_DELAY_SECONDS),
+                );
+                events.extend(key_events(b, &canvas.keybindings, true));
+            }
+        }
+
+        let now = Instant::now();
+        for (b, next) in self.held.iter_mut() {
+            if b.repeats() && now >= *next {
+                *next = now + std::time::Duration::from_secs_f64(REPEAT_EVERY_SECONDS);
+                events.extend(key_events(*b, &canvas.keybindings, true));
+            }
+        }
+        events
+    }
+}
+
+fn key_events(b: Button, keybindings: &KeyBindings, pressed: bool) -> Vec<Event> {
+    let (action, default) = b.action();
+    let (ctrl, keys) = match keybindings.resolve(action, default) {
+        Some(MultiKey::Normal(key)) => (false, vec![key]),
+        Some(MultiKey::LCtrl(key)) => (true, vec![key]),
+        // Any of these keys work, so just pick the first
+        Some(MultiKey::Any(keys)) => (false, keys.into_iter().take(1).collect()),
+        None => (false, Vec::new()),
+    };
+    let mut events = Vec::new();
+    if pressed {
+        if ctrl {
+            events.push(Event::KeyPress(Key::LeftControl));
+        }
+        events.extend(keys.into_iter().map(Event::KeyPress));
+    } else {
+        events.extend(keys.into_iter().map(Event::KeyRelease));
+        if ctrl {
+            events.push(Event::KeyRelease(Key::LeftControl));
+        }
+    }
+    events
+}
+
+#[cfg(feature = "gilrs")]
+fn from_gilrs(b: gilrs::Button) -> Option<Button> {
+    match b {
+        gilrs::Button::South => Some(Button::A),
+        gilrs::Button::East => Some(Button::B),
+        gilrs::Button::West => Some(Button::X),
+        gilrs::Button::North => Some(Button::Y),
+        gilrs::Button::DPadUp => Some(Button::Up),
+        gilrs::Button::DPadDown => Some(Button::Down),
+        gilrs::Button::DPadLeft => Some(Button::Left),
+        gilrs::Button::DPadRight => Some(Button::Right),
+        gilrs::Button::Start => Some(Button::Start),
+        gilrs::Button::Select => Some(Button::Select),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn deadzone(value: f64) -> f64 {
+    if value.abs() < STICK_DEADZONE {
+        0.0
+    } else {
+        value
+    }
+}
@@ -53,6 +53,26 @@ impl UserInput {
         None
     }
 
+    // Like any_key_pressed, but notices if Control is held. Pressing only Control isn't a key.
+    pub fn any_multikey_pressed(&mut self) -> Option<MultiKey> {
+        if self.event_consumed {
+            return None;
+        }
+
+        if let Event::KeyPress(key) = self.event {
+            if key == Key::LeftControl {
+                return None;
+            }
+            self.consume_event();
+            return Some(if self.lctrl_held {
+                MultiKey::LCtrl(key)
+            } else {
+                MultiKey::Normal(key)
+            });
+        }
+        None
+    }
+
     pub fn unimportant_key_pressed(&mut self, key: Key, action: &str) -> bool {
         self.reserve_key(key, action);
 
@@ -109,6 +129,12 @@ impl UserInput {
     pub fn left_mouse_button_released(&mut self) -> bool {
         self.event == Event::LeftMouseButtonUp
     }
+    pub(crate) fn middle_mouse_button_pressed(&self) -> bool {
+        self.event == Event::MiddleMouseButtonDown
+    }
+    pub(crate) fn middle_mouse_button_released(&self) -> bool {
+        self.event == Event::MiddleMouseButtonUp
+    }
 
     pub fn window_lost_cursor(&self) -> bool {
         self.event == Event::WindowLostCursor
@@ -0,0 +1,154 @@
+use crate::MultiKey;
+use abstutil::Timer;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+// Everywhere that asks for a hotkey also names the action and gives a default key. Buttons use
+// their action, and anything else should use EventCtx::action_pressed. The player can override
+// the key for any action by name.
+pub struct KeyBindings {
+    overrides: BTreeMap<String, Option<MultiKey>>,
+    // Every action requested so far this session, with its default key. Buttons are built with
+    // only &EventCtx, so this has to be a RefCell.
+    seen: RefCell<BTreeMap<String, Option<MultiKey>>>,
+}
+
+// This is what's stored on disk, so players can edit it by hand. A missing action uses its
+// default key; an action mapped to null is unbound.
+#[derive(Serialize, Deserialize)]
+struct SavedKeyBindings {
+    overrides: BTreeMap<String, Option<MultiKey>>,
+}
+
+impl KeyBindings {
+    pub fn new() -> KeyBindings {
+        KeyBindings {
+            overrides: BTreeMap::new(),
+            seen: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    // Problems (unbound actions, overrides that share the same key) are reported to the timer.
+    pub fn load(path: String, timer: &mut Timer) -> KeyBindings {
+        let mut bindings = KeyBindings::new();
+        if !abstutil::file_exists(path.clone()) {
+            return bindings;
+        }
+        match abstutil::maybe_read_json::<SavedKeyBindings>(path.clone(), timer) {
+            Ok(saved) => {
+                bindings.overrides = saved.overrides;
+            }
+            Err(err) => {
+                timer.error(format!("Ignoring key bindings in {}: {}", path, err));
+                return bindings;
+            }
+        }
+        for (action, key) in &bindings.overrides {
+            if key.is_none() {
+                timer.warn(format!(
+                    "Key binding for \"{}\" is unbound in {}",
+                    action, path
+                ));
+            }
+        }
+        for (key, actions) in bindings.conflicts() {
+            timer.warn(format!(
+                "{} is bound to multiple actions in {}: {}",
+                key.describe(),
+                path,
+                actions.join(", ")
+            ));
+        }
+        bindings
+    }
+
+    pub fn save(&self, path: String) {
+        abstutil::write_json(
+            path,
+            &SavedKeyBindings {
+                overrides: self.overrides.clone(),
+            },
+        );
+    }
+
+    pub fn resolve(&self, action: &str, default: Option<MultiKey>) -> Option<MultiKey> {
+        // Only remember actions that have some key by default. Otherwise every dynamically named
+        // button would wind up in the list.
+        if default.is_some() {
+            self.seen
+                .borrow_mut()
+                .entry(action.to_string())
+                .or_insert_with(|| default.clone());
+        }
+        match self.overrides.get(action) {
+            Some(key) => key.clone(),
+            None => default,
+        }
+    }
+
+    // Doesn't record anything. None if the action is unbound or hasn't been seen yet.
+    pub fn current(&self, action: &str) -> Option<MultiKey> {
+        match self.overrides.get(action) {
+            Some(key) => key.clone(),
+            None => self.seen.borrow().get(action).cloned().flatten(),
+        }
+    }
+
+    // None means unbind the action
+    pub fn rebind(&mut self, action: &str, key: Option<MultiKey>) {
+        self.overrides.insert(action.to_string(), key);
+    }
+
+    pub fn reset(&mut self, action: &str) {
+        self.overrides.remove(action);
+    }
+
+    pub fn is_overridden(&self, action: &str) -> bool {
+        self.overrides.contains_key(action)
+    }
+
+    // (action, default key, current key) for everything seen this session or overridden, sorted by
+    // action
+    pub fn all_actions(&self) -> Vec<(String, Option<MultiKey>, Option<MultiKey>)> {
+        let mut actions: BTreeMap<String, Option<MultiKey>> = self.seen.borrow().clone();
+        for action in self.overrides.keys() {
+            actions.entry(action.clone()).or_insert(None);
+        }
+        actions
+            .into_iter()
+            .map(|(action, default)| {
+                let current = self
+                    .overrides
+                    .get(&action)
+                    .cloned()
+                    .unwrap_or_else(|| default.clone());
+                (action, default, current)
+            })
+            .collect()
+    }
+
+    // Lots of actions share default keys -- Escape closes everything -- but they're used in
+    // different places. So a conflict only counts if the player overrode at least one of the
+    // actions. Controller buttons emulate keys on purpose, so they never conflict.
+    pub fn conflicts(&self) -> Vec<(MultiKey, Vec<String>)> {
+        let mut by_key: BTreeMap<MultiKey, Vec<(String, bool)>> = BTreeMap::new();
+        for (action, _, current) in self.all_actions() {
+            if action.starts_with("gamepad ") {
+                continue;
+            }
+            if let Some(key) = current {
+                let overridden = self.is_overridden(&action);
+                by_key
+                    .entry(key)
+                    .or_insert_with(Vec::new)
+                    .push((action, overridden));
+            }
+        }
+        by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1 && actions.iter().any(|(_, o)| *o))
+            .map(|(key, actions)| (key, actions.into_iter().map(|(a, _)| a).collect()))
+            .collect()
+    }
+}
@@ -33,9 +33,12 @@ mod color;
 mod drawing;
 mod event;
 mod event_ctx;
+mod gamepad;
 mod geom;
 mod input;
+mod keybindings;
 mod managed;
+mod recording;
 mod runner;
 mod screen_geom;
 mod style;
@@ -52,11 +55,14 @@ pub use crate::event::{hotkey, hotkeys, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::EventCtx;
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
+pub use crate::keybindings::KeyBindings;
 pub use crate::managed::{Composite, Widget};
-pub use crate::runner::{run, EventLoopMode, Settings, GUI};
+pub use crate::runner::{run, EventLoopMode, FramePacing, Settings, GUI};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::Style;
 pub use crate::text::{Line, Text, TextExt, TextSpan};
+pub use crate::tools::overlay::{OverlayPanel, PanelAnchor};
+pub use crate::tools::screenshot::screenshot_png;
 pub use crate::tools::warper::Warper;
 pub use crate::tools::wizard::{Choice, Wizard, WrappedWizard};
 pub use crate::widgets::autocomplete::Autocomplete;
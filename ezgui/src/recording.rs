@@ -0,0 +1,185 @@
+use crate::Event;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+// One JSON object per line, so a recording cut short by a crash is still readable.
+#[derive(Serialize, Deserialize)]
+enum Entry {
+    // The command line used to record, since replay needs the same flags and seed
+    Args(Vec<String>),
+    // Written before the event is handled, so the event that panics is captured too
+    Event(usize, Event),
+    // Whatever the GUI reports after handling a frame. Only written when it changes.
+    Checkpoint(usize, String),
+}
+
+pub(crate) struct Recorder {
+    path: String,
+    file: File,
+    last_checkpoint: Option<String>,
+}
+
+impl Recorder {
+    pub fn new(path: String) -> Recorder {
+        if let Some(dir) = std::path::Path::new(&path).parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let file = File::create(&path).unwrap();
+        let mut recorder = Recorder {
+            path,
+            file,
+            last_checkpoint: None,
+        };
+        recorder.write(Entry::Args(std::env::args().collect()));
+        println!("Recording input to {}", recorder.path);
+        recorder
+    }
+
+    pub fn event(&mut self, frame: usize, ev: Event) {
+        self.write(Entry::Event(frame, ev));
+    }
+
+    pub fn checkpoint(&mut self, frame: usize, checkpoint: Option<String>) {
+        if checkpoint.is_some() && checkpoint != self.last_checkpoint {
+            self.last_checkpoint = checkpoint.clone();
+            self.write(Entry::Checkpoint(frame, checkpoint.unwrap()));
+        }
+    }
+
+    // Every line goes straight to the file; a buffer would be lost in a panic.
+    fn write(&mut self, entry: Entry) {
+        let line = serde_json::to_string(&entry).unwrap();
+        if let Err(err) = writeln!(self.file, "{}", line) {
+            println!("Couldn't append to {}: {}", self.path, err);
+        }
+    }
+}
+
+pub(crate) struct Replayer {
+    path: String,
+    entries: VecDeque<Entry>,
+    expected: Option<String>,
+    diverged: bool,
+}
+
+impl Replayer {
+    pub fn load(path: String) -> Result<Replayer, String> {
+        let file = File::open(&path).map_err(|err| format!("Couldn't open {}: {}", path, err))?;
+        let mut entries = VecDeque::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push_back(entry),
+                // The recording process probably died mid-write. Everything before is usable.
+                Err(err) => {
+                    println!(
+                        "{} line {} is corrupt ({}), ignoring the rest",
+                        path,
+                        idx + 1,
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+        if let Some(Entry::Args(ref args)) = entries.front() {
+            println!("Replaying {}, recorded with: {}", path, args.join(" "));
+            println!("If the flags or seed differ, the replay will probably diverge.");
+        }
+        Ok(Replayer {
+            path,
+            entries,
+            expected: None,
+            diverged: false,
+        })
+    }
+
+    // None once the recording runs out
+    pub fn next_event(&mut self) -> Option<Event> {
+        while let Some(entry) = self.entries.pop_front() {
+            match entry {
+                Entry::Args(_) => {}
+                // The frame numbers line up, since the same events are fed in the same order
+                Entry::Event(_, ev) => {
+                    return Some(ev);
+                }
+                // Checkpoints are consumed by check, so this is one with no event after it
+                Entry::Checkpoint(_, checkpoint) => {
+                    self.expected = Some(checkpoint);
+                }
+            }
+        }
+        println!("Finished replaying {}", self.path);
+        None
+    }
+
+    // Compares against the recording after the frame is handled. Only the first divergence is
+    // reported; everything after it is suspect anyway.
+    pub fn check(&mut self, frame: usize, actual: Option<String>) {
+        if let Some(Entry::Checkpoint(f, _)) = self.entries.front() {
+            if *f == frame {
+                if let Some(Entry::Checkpoint(_, checkpoint)) = self.entries.pop_front() {
+                    self.expected = Some(checkpoint);
+                }
+            }
+        }
+        if self.diverged || actual.is_none() || self.expected.is_none() || actual == self.expected {
+            return;
+        }
+        self.diverged = true;
+        println!(
+            "Replay of {} diverged at frame {}! Expected {}, but got {}",
+            self.path,
+            frame,
+            self.expected.as_ref().unwrap(),
+            actual.unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Key, ScreenPt};
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir()
+            .join("ezgui_test_recording.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let events = vec![
+            Event::MouseMovedTo(ScreenPt::new(10.0, 20.5)),
+            Event::KeyPress(Key::Space),
+            Event::Update(geom::Duration::seconds(0.1)),
+        ];
+        {
+            let mut recorder = Recorder::new(path.clone());
+            for (frame, ev) in events.iter().enumerate() {
+                recorder.event(frame, *ev);
+                recorder.checkpoint(frame, Some(format!("selected {}", frame.min(1))));
+            }
+        }
+
+        let mut replayer = Replayer::load(path.clone()).unwrap();
+        for (frame, ev) in events.iter().enumerate() {
+            assert_eq!(replayer.next_event(), Some(*ev));
+            replayer.check(frame, Some(format!("selected {}", frame.min(1))));
+            assert!(!replayer.diverged);
+        }
+        assert_eq!(replayer.next_event(), None);
+
+        let mut replayer = Replayer::load(path.clone()).unwrap();
+        replayer.next_event();
+        replayer.check(0, Some("selected something else".to_string()));
+        assert!(replayer.diverged);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,6 +1,9 @@
 use crate::assets::Assets;
+use crate::gamepad::Gamepads;
+use crate::recording::{Recorder, Replayer};
+use crate::tools::overlay::draw_overlay_panels;
 use crate::tools::screenshot::screenshot_everything;
-use crate::{text, Canvas, Event, EventCtx, GfxCtx, Key, Prerender, Style, UserInput};
+use crate::{hotkey, text, Canvas, Event, EventCtx, GfxCtx, Key, Prerender, Style, UserInput};
 use geom::Duration;
 use image::{GenericImageView, Pixel};
 use instant::Instant;
@@ -8,7 +11,11 @@ use std::cell::Cell;
 use std::panic;
 use winit::window::Icon;
 
-const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
+// After this long without input, if nothing asked for animation frames, update much less often.
+const IDLE_AFTER_SECONDS: f64 = 1.0;
+const IDLE_UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(500);
+// While a controller is plugged in, check on it at least this often
+const GAMEPAD_POLL_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(33);
 
 pub trait GUI {
     fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode;
@@ -17,6 +24,11 @@ pub trait GUI {
     fn dump_before_abort(&self, _canvas: &Canvas) {}
     // Only before a normal exit, like window close
     fn before_quit(&self, _canvas: &Canvas) {}
+    // While recording or replaying input, describes something that should come out the same
+    // every time, like the current selection. A replay reports the first frame where it differs.
+    fn replay_checkpoint(&self) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -31,6 +43,26 @@ pub enum EventLoopMode {
     },
 }
 
+// How often to update and redraw while animating
+#[derive(Clone, Copy, PartialEq)]
+pub enum FramePacing {
+    // Only set at startup, through Settings
+    Vsync,
+    Cap60,
+    Cap30,
+}
+
+impl FramePacing {
+    fn update_frequency(self) -> std::time::Duration {
+        match self {
+            // Swapping buffers blocks until the next vblank
+            FramePacing::Vsync => std::time::Duration::from_millis(0),
+            FramePacing::Cap60 => std::time::Duration::from_millis(1000 / 60),
+            FramePacing::Cap30 => std::time::Duration::from_millis(1000 / 30),
+        }
+    }
+}
+
 pub(crate) struct State<G: GUI> {
     pub(crate) gui: G,
     pub(crate) canvas: Canvas,
@@ -52,7 +84,13 @@ impl<G: GUI> State<G> {
         } else {
             prerender
                 .inner
-                .set_cursor_icon(if self.canvas.drag_canvas_from.is_some() {
+                .set_cursor_icon(if self.canvas.middle_drag_from.is_some() {
+                    if ev == Event::MiddleMouseButtonUp {
+                        winit::window::CursorIcon::Default
+                    } else {
+                        winit::window::CursorIcon::Grabbing
+                    }
+                } else if self.canvas.drag_canvas_from.is_some() {
                     // We haven't run canvas_movement() yet, so we don't know if the button has been
                     // released. Bit of a hack to check this here, but better behavior.
                     if ev == Event::LeftMouseButtonUp {
@@ -65,6 +103,24 @@ impl<G: GUI> State<G> {
                 });
         }
 
+        // Smooth zooming and kinetic panning happen no matter what state the GUI is in.
+        match ev {
+            Event::Update(dt) => {
+                self.canvas.animate(dt);
+            }
+            Event::NoOp
+            | Event::MouseMovedTo(_)
+            | Event::WindowLostCursor
+            | Event::WindowGainedCursor
+            | Event::KeyRelease(_)
+            | Event::LeftMouseButtonUp
+            | Event::RightMouseButtonUp
+            | Event::MiddleMouseButtonUp => {}
+            _ => {
+                self.canvas.cancel_kinetic_panning();
+            }
+        }
+
         // It's impossible / very unlikey we'll grab the cursor in map space before the very first
         // start_drawing call.
         let input = UserInput::new(ev, &self.canvas);
@@ -84,9 +140,22 @@ impl<G: GUI> State<G> {
                     inner_size.1
                 );
                 let (width, height) = inner_size;
-                prerender.inner.window_resized(width, height);
-                self.canvas.window_width = width;
-                self.canvas.window_height = height;
+                // Minimizing the window on some platforms resizes it to nothing. Keep the old
+                // dimensions, so nothing divides by zero.
+                if width >= 1.0 && height >= 1.0 {
+                    prerender.inner.window_resized(width, height);
+                    self.canvas.window_resized(width, height);
+                }
+            }
+
+            if let Event::ScaleFactorChanged(scale_factor) = input.event {
+                if self.canvas.scale_factor_override.is_none() {
+                    prerender.assets.set_scale_factor(scale_factor);
+                }
+            }
+
+            if input.event == Event::LeftMouseButtonUp {
+                self.canvas.record_click();
             }
 
             if input.event == Event::KeyPress(Key::LeftControl) {
@@ -115,6 +184,10 @@ impl<G: GUI> State<G> {
             }
         }
 
+        self.canvas.needs_animation_frames = false;
+        self.canvas.triggered_action = self.canvas.next_triggered_action.take();
+        self.canvas.prev_actions_available = std::mem::take(&mut self.canvas.actions_available);
+
         match panic::catch_unwind(panic::AssertUnwindSafe(|| {
             let mut ctx = EventCtx {
                 fake_mouseover: false,
@@ -123,7 +196,23 @@ impl<G: GUI> State<G> {
                 prerender,
                 style: &mut self.style,
             };
-            let evloop = self.gui.event(&mut ctx);
+            // Works no matter what state the GUI is in. The window resize event follows.
+            if ctx.action_pressed("toggle fullscreen", hotkey(Key::F11)) {
+                ctx.prerender.inner.toggle_fullscreen();
+                return (EventLoopMode::InputOnly, true);
+            }
+            let mut evloop = self.gui.event(&mut ctx);
+            if ctx.canvas.is_moving() {
+                ctx.canvas.needs_animation_frames = true;
+            }
+            if evloop == EventLoopMode::InputOnly
+                && (ctx.canvas.is_moving() || ctx.canvas.next_triggered_action.is_some())
+            {
+                evloop = EventLoopMode::Animation;
+            }
+            ctx.canvas.camera_just_settled = false;
+            // Nothing claimed it
+            ctx.canvas.triggered_action = None;
             // TODO We should always do has_been_consumed, but various hacks prevent this from being
             // true. For now, just avoid the specific annoying redraw case when a KeyRelease event
             // is unused.
@@ -149,6 +238,8 @@ impl<G: GUI> State<G> {
 
         if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(|| {
             self.gui.draw(&mut g);
+            let panels = std::mem::replace(&mut g.overlay_panels, Vec::new());
+            draw_overlay_panels(&mut g, panels);
         })) {
             self.gui.dump_before_abort(&self.canvas);
             panic::resume_unwind(err);
@@ -177,6 +268,9 @@ pub struct Settings {
     dump_raw_events: bool,
     scale_factor: Option<f64>,
     window_icon: Option<String>,
+    vsync: bool,
+    record_input: Option<String>,
+    replay_input: Option<String>,
 }
 
 impl Settings {
@@ -189,6 +283,9 @@ impl Settings {
             dump_raw_events: false,
             scale_factor: None,
             window_icon: None,
+            vsync: false,
+            record_input: None,
+            replay_input: None,
         }
     }
 
@@ -210,14 +307,29 @@ impl Settings {
         self.scale_factor = Some(scale_factor);
     }
 
+    pub fn vsync(&mut self) {
+        self.vsync = true;
+    }
+
     pub fn window_icon(&mut self, path: &str) {
         self.window_icon = Some(path.to_string());
     }
+
+    // Appends every event to this file, to reproduce bugs later
+    pub fn record_input(&mut self, path: String) {
+        self.record_input = Some(path);
+    }
+
+    // Feeds the events from a recording in before accepting any real input. The GUI has to start
+    // the same way it did while recording.
+    pub fn replay_input(&mut self, path: String) {
+        self.replay_input = Some(path);
+    }
 }
 
 pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings, make_gui: F) -> ! {
     let (prerender_innards, event_loop, window_size) =
-        crate::backend::setup(&settings.window_title);
+        crate::backend::setup(&settings.window_title, settings.vsync);
 
     let mut canvas = Canvas::new(window_size.width, window_size.height);
     prerender_innards.window_resized(canvas.window_width, canvas.window_height);
@@ -231,6 +343,11 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
         let icon = Icon::from_rgba(rgba, width, height).unwrap();
         prerender_innards.set_window_icon(icon);
     }
+    canvas.scale_factor_override = settings.scale_factor;
+    canvas.input_recorded = settings.record_input.is_some() || settings.replay_input.is_some();
+    if settings.vsync {
+        canvas.frame_pacing = FramePacing::Vsync;
+    }
     let prerender = Prerender {
         assets: Assets::new(
             settings.default_font_size,
@@ -261,13 +378,33 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
     let profiling_enabled = settings.profiling_enabled;
     let dump_raw_events = settings.dump_raw_events;
 
+    let mut recorder = settings.record_input.map(|path| {
+        let prev_hook = panic::take_hook();
+        let note = path.clone();
+        panic::set_hook(Box::new(move |info| {
+            prev_hook(info);
+            println!(
+                "Input up to the crash was recorded in {}. Please attach it to the bug report.",
+                note
+            );
+        }));
+        Recorder::new(path)
+    });
+    let mut replayer = settings
+        .replay_input
+        .map(|path| Replayer::load(path).unwrap_or_else(|err| panic!("{}", err)));
+    let mut frame = 0;
+
+    let mut gamepads = Gamepads::new();
     let mut running = true;
     let mut last_update = Instant::now();
+    let mut last_input = Instant::now();
+    let mut idling = false;
     event_loop.run(move |event, _, control_flow| {
         if dump_raw_events {
             println!("Event: {:?}", event);
         }
-        let ev = match event {
+        let evs = match event {
             winit::event::Event::WindowEvent {
                 event: winit::event::WindowEvent::CloseRequested,
                 ..
@@ -282,8 +419,12 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
                 std::process::exit(0);
             }
             winit::event::Event::WindowEvent { event, .. } => {
+                // Real input would scramble the replay
+                if replayer.is_some() {
+                    return;
+                }
                 if let Some(ev) = Event::from_winit_event(event) {
-                    ev
+                    vec![ev]
                 } else {
                     // Don't touch control_flow if we got an irrelevant event
                     return;
@@ -295,11 +436,32 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
                 return;
             }
             winit::event::Event::MainEventsCleared => {
-                // We might've switched to InputOnly after the WaitUntil was requested.
-                if running {
-                    Event::Update(Duration::realtime_elapsed(last_update))
+                // One recorded event per loop, so everything gets drawn like it was originally
+                if let Some(ref mut r) = replayer {
+                    if let Some(ev) = r.next_event() {
+                        vec![ev]
+                    } else {
+                        // Back to real input
+                        replayer = None;
+                        last_update = Instant::now();
+                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(Instant::now());
+                        return;
+                    }
                 } else {
-                    return;
+                    let mut evs = gamepads.poll(&mut state.canvas);
+                    // We might've switched to InputOnly after the WaitUntil was requested. While
+                    // idling, checking on a controller doesn't count as an update.
+                    if running && (!idling || Instant::now() >= last_update + IDLE_UPDATE_FREQUENCY)
+                    {
+                        evs.push(Event::Update(Duration::realtime_elapsed(last_update)));
+                    }
+                    if evs.is_empty() {
+                        if gamepads.any_connected() {
+                            poll_gamepads_soon(control_flow);
+                        }
+                        return;
+                    }
+                    evs
                 }
             }
             _ => {
@@ -307,43 +469,104 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
             }
         };
 
-        // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
-        // doing the work (which takes time).
-        if let Event::Update(_) = ev {
-            last_update = Instant::now();
-            *control_flow =
-                winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
-        }
-
-        let (mode, input_used) = state.event(ev, &prerender);
-        if input_used {
-            prerender.request_redraw();
-        }
-
-        match mode {
-            EventLoopMode::InputOnly => {
-                running = false;
-                *control_flow = winit::event_loop::ControlFlow::Wait;
-            }
-            EventLoopMode::Animation => {
-                // If we just unpaused, then don't act as if lots of time has passed.
-                if !running {
-                    last_update = Instant::now();
+        for ev in evs {
+            // We want a max of the update frequency between updates, so measure the update time
+            // before doing the work (which takes time).
+            let update_frequency = state.canvas.frame_pacing.update_frequency();
+            if let Event::Update(_) = ev {
+                last_update = Instant::now();
+                *control_flow =
+                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + update_frequency);
+            } else {
+                last_input = Instant::now();
+                // Wake up right away, instead of waiting for the next slow update
+                if idling {
+                    idling = false;
                     *control_flow = winit::event_loop::ControlFlow::WaitUntil(
-                        Instant::now() + UPDATE_FREQUENCY,
+                        Instant::now() + update_frequency,
                     );
                 }
+            }
+            let is_update = if let Event::Update(_) = ev {
+                true
+            } else {
+                false
+            };
 
-                running = true;
+            if let Some(ref mut r) = recorder {
+                r.event(frame, ev);
             }
-            EventLoopMode::ScreenCaptureEverything {
-                dir,
-                zoom,
-                max_x,
-                max_y,
-            } => {
-                screenshot_everything(&mut state, &dir, &prerender, zoom, max_x, max_y);
+            let (mode, input_used) = state.event(ev, &prerender);
+            if let Some(ref mut r) = recorder {
+                r.checkpoint(frame, state.gui.replay_checkpoint());
             }
+            if let Some(ref mut r) = replayer {
+                r.check(frame, state.gui.replay_checkpoint());
+            }
+            frame += 1;
+            if input_used {
+                prerender.request_redraw();
+            }
+
+            match mode {
+                EventLoopMode::InputOnly => {
+                    running = false;
+                    *control_flow = winit::event_loop::ControlFlow::Wait;
+                }
+                EventLoopMode::Animation => {
+                    // If we just unpaused, then don't act as if lots of time has passed.
+                    if !running {
+                        last_update = Instant::now();
+                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                            Instant::now() + update_frequency,
+                        );
+                    }
+
+                    running = true;
+
+                    // Something like a timer might keep asking for updates, without anything
+                    // actually moving on the screen.
+                    if is_update
+                        && state.canvas.idle_when_inactive
+                        && !state.canvas.needs_animation_frames
+                        && abstutil::elapsed_seconds(last_input) > IDLE_AFTER_SECONDS
+                    {
+                        idling = true;
+                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                            last_update + IDLE_UPDATE_FREQUENCY,
+                        );
+                    }
+                }
+                EventLoopMode::ScreenCaptureEverything {
+                    dir,
+                    zoom,
+                    max_x,
+                    max_y,
+                } => {
+                    screenshot_everything(&mut state, &dir, &prerender, zoom, max_x, max_y);
+                }
+            }
+        }
+
+        if replayer.is_some() {
+            // Don't wait for the next update; feed in the next event right away
+            *control_flow = winit::event_loop::ControlFlow::Poll;
+        } else if gamepads.any_connected() {
+            poll_gamepads_soon(control_flow);
         }
     });
 }
+
+// Controllers don't wake up the event loop, so keep checking on them.
+fn poll_gamepads_soon(control_flow: &mut winit::event_loop::ControlFlow) {
+    let poll_by = Instant::now() + GAMEPAD_POLL_FREQUENCY;
+    match *control_flow {
+        winit::event_loop::ControlFlow::Wait => {
+            *control_flow = winit::event_loop::ControlFlow::WaitUntil(poll_by);
+        }
+        winit::event_loop::ControlFlow::WaitUntil(t) if t > poll_by => {
+            *control_flow = winit::event_loop::ControlFlow::WaitUntil(poll_by);
+        }
+        _ => {}
+    }
+}
@@ -2,7 +2,7 @@ use crate::Canvas;
 use geom::{trim_f64, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ScreenPt {
     pub x: f64,
     pub y: f64,
@@ -1,3 +1,4 @@
+pub mod overlay;
 pub mod screenshot;
 pub mod warper;
 pub mod wizard;
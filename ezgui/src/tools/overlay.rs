@@ -0,0 +1,164 @@
+use crate::{Color, GeomBatch, GfxCtx, ScreenDims, ScreenPt, ScreenRectangle, Text};
+use geom::Polygon;
+
+// Panels are wrapped to this percent of the window width.
+const MAX_WIDTH_PCT: f64 = 0.3;
+// These are scaled by the UI scale factor.
+const PADDING: f64 = 5.0;
+// Between stacked panels
+const SPACING: f64 = 5.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PanelAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    FollowCursor,
+}
+
+// A block of text drawn over everything else, for things that don't need to be interactive.
+// Declare these with GfxCtx::declare_panel while drawing; at the end of the frame, all panels
+// with the same anchor are stacked so they don't overlap each other or any Composite drawn that
+// frame.
+pub struct OverlayPanel {
+    anchor: PanelAnchor,
+    txt: Text,
+    priority: isize,
+}
+
+impl OverlayPanel {
+    pub fn new(anchor: PanelAnchor, txt: Text) -> OverlayPanel {
+        OverlayPanel {
+            anchor,
+            txt,
+            priority: 0,
+        }
+    }
+
+    // Higher priority panels are placed closest to the anchor.
+    pub fn priority(mut self, priority: isize) -> OverlayPanel {
+        self.priority = priority;
+        self
+    }
+}
+
+pub(crate) fn draw_overlay_panels(g: &mut GfxCtx, mut panels: Vec<OverlayPanel>) {
+    if panels.is_empty() {
+        return;
+    }
+    // Stable, so panels with the same priority keep the order they were declared in
+    panels.sort_by_key(|p| -p.priority);
+
+    let mut batch = GeomBatch::new();
+    for anchor in vec![
+        PanelAnchor::TopLeft,
+        PanelAnchor::TopRight,
+        PanelAnchor::BottomLeft,
+        PanelAnchor::BottomRight,
+        PanelAnchor::FollowCursor,
+    ] {
+        let rendered: Vec<GeomBatch> = panels
+            .iter()
+            .filter(|p| p.anchor == anchor)
+            .map(|p| render_panel(g, p.txt.clone()))
+            .collect();
+        if rendered.is_empty() {
+            continue;
+        }
+        let scale = *g.prerender.assets.scale_factor.borrow();
+        let spacing = SPACING * scale;
+        let total = ScreenDims::new(
+            rendered
+                .iter()
+                .map(|b| b.get_dims().width)
+                .fold(0.0, f64::max),
+            rendered.iter().map(|b| b.get_dims().height).sum::<f64>()
+                + spacing * ((rendered.len() - 1) as f64),
+        );
+
+        let (width, height) = (g.canvas.window_width, g.canvas.window_height);
+        // Leave room for the OSD at the bottom
+        let bottom = height - 60.0 * scale;
+        let mut top_left = match anchor {
+            PanelAnchor::TopLeft => ScreenPt::new(spacing, spacing),
+            PanelAnchor::TopRight => ScreenPt::new(width - total.width - spacing, spacing),
+            PanelAnchor::BottomLeft => ScreenPt::new(spacing, bottom - total.height),
+            PanelAnchor::BottomRight => {
+                ScreenPt::new(width - total.width - spacing, bottom - total.height)
+            }
+            PanelAnchor::FollowCursor => total.top_left_for_corner(
+                ScreenPt::new(g.canvas.cursor_x, g.canvas.cursor_y + 20.0),
+                g.canvas,
+            ),
+        };
+        if anchor != PanelAnchor::FollowCursor {
+            top_left = avoid_covered_areas(g, anchor, top_left, total, spacing);
+        }
+
+        // Bottom anchors grow upwards, so the highest priority panel is closest to the corner.
+        let mut ordered = rendered;
+        if anchor == PanelAnchor::BottomLeft || anchor == PanelAnchor::BottomRight {
+            ordered.reverse();
+        }
+        let mut y = top_left.y;
+        for panel in ordered {
+            let dims = panel.get_dims();
+            let x = match anchor {
+                PanelAnchor::TopRight | PanelAnchor::BottomRight => {
+                    top_left.x + total.width - dims.width
+                }
+                _ => top_left.x,
+            };
+            batch.append(panel.translate(x, y));
+            y += dims.height + spacing;
+        }
+    }
+    g.draw_overlay_batch(batch);
+}
+
+// Slide a stack of panels away from its corner until it doesn't overlap anything already drawn.
+fn avoid_covered_areas(
+    g: &GfxCtx,
+    anchor: PanelAnchor,
+    mut top_left: ScreenPt,
+    total: ScreenDims,
+    spacing: f64,
+) -> ScreenPt {
+    let covered = g.canvas.covered_areas.borrow();
+    // The stack only moves away from its corner, so each rectangle is hit at most once.
+    for _ in 0..covered.len() {
+        let stack = ScreenRectangle::top_left(top_left, total);
+        let hit = match covered.iter().find(|r| overlaps(r, &stack)) {
+            Some(r) => r,
+            None => break,
+        };
+        top_left.y = match anchor {
+            PanelAnchor::TopLeft | PanelAnchor::TopRight => hit.y2 + spacing,
+            _ => hit.y1 - spacing - total.height,
+        };
+    }
+    top_left
+}
+
+fn overlaps(r1: &ScreenRectangle, r2: &ScreenRectangle) -> bool {
+    r1.x1 < r2.x2 && r2.x1 < r1.x2 && r1.y1 < r2.y2 && r2.y1 < r1.y2
+}
+
+// Includes the background and padding
+fn render_panel(g: &GfxCtx, txt: Text) -> GeomBatch {
+    let txt_batch = txt
+        .inner_wrap_to_pct(MAX_WIDTH_PCT * g.canvas.window_width, &g.prerender.assets)
+        .render_g(g);
+    let padding = PADDING * *g.prerender.assets.scale_factor.borrow();
+    let raw_dims = txt_batch.get_dims();
+    let dims = ScreenDims::new(
+        raw_dims.width + 2.0 * padding,
+        raw_dims.height + 2.0 * padding,
+    );
+    let mut batch = GeomBatch::new();
+    // TODO Outline?
+    batch.push(Color::BLACK, Polygon::rectangle(dims.width, dims.height));
+    batch.append(txt_batch.translate(padding, padding));
+    batch
+}
@@ -52,7 +52,19 @@ pub(crate) fn screenshot_everything<G: GUI>(
     finish(dir_path, filenames, num_tiles_x, num_tiles_y);
 }
 
-fn screencap(filename: &str) -> bool {
+// Captures whatever the focused window shows right now, as a PNG. The caller has to make sure
+// the frame it wants has actually been drawn.
+pub fn screenshot_png() -> Option<Vec<u8>> {
+    if !scrot() {
+        return None;
+    }
+    let png = fs::read("screenshot.png").ok();
+    let _ = fs::remove_file("screenshot.png");
+    png
+}
+
+// Writes screenshot.png in the current directory
+fn scrot() -> bool {
     if !process::Command::new("scrot")
         .args(&[
             "--quality",
@@ -68,6 +80,13 @@ fn screencap(filename: &str) -> bool {
         println!("Screencapping failed; you probably don't have scrot (https://en.wikipedia.org/wiki/Scrot) installed");
         return false;
     }
+    true
+}
+
+fn screencap(filename: &str) -> bool {
+    if !scrot() {
+        return false;
+    }
     if !process::Command::new("convert")
         .arg("screenshot.png")
         .arg(filename)
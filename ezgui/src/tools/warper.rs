@@ -20,11 +20,16 @@ impl Warper {
 
     // None means done
     pub fn event(&self, ctx: &mut EventCtx) -> Option<EventLoopMode> {
+        // Don't fight with any zooming in progress
+        ctx.canvas.zoom_target = None;
+        ctx.canvas.kinetic_velocity = None;
+
         // Actually nothing for us to do
         if self.line.is_none() && self.cam_zoom.0 == self.cam_zoom.1 {
             return None;
         }
 
+        ctx.request_animation_frames();
         // Weird to do stuff for any event?
         if ctx.input.nonblocking_is_update_event().is_none() {
             return Some(EventLoopMode::Animation);
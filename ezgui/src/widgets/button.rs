@@ -37,6 +37,7 @@ impl Button {
         let bounds = hitbox.get_bounds();
         let dims = ScreenDims::new(bounds.width(), bounds.height());
         assert!(!tooltip.is_empty());
+        let hotkey = ctx.canvas.keybindings.resolve(tooltip, hotkey);
         Widget::new(Box::new(Button {
             action: tooltip.to_string(),
 
@@ -69,6 +70,12 @@ impl WidgetImpl for Button {
     }
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.check_triggered_action(&self.action) {
+            self.hovering = false;
+            output.outcome = Some(Outcome::Clicked(self.action.clone()));
+            return;
+        }
+
         if ctx.redo_mouseover() {
             if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
                 self.hovering = self
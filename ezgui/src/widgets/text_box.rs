@@ -7,8 +7,7 @@ use geom::Polygon;
 // TODO right now, only a single line
 
 pub struct TextBox {
-    line: String,
-    cursor_x: usize,
+    editor: LineEditor,
     has_focus: bool,
     hovering: bool,
     autofocus: bool,
@@ -20,8 +19,7 @@ pub struct TextBox {
 impl TextBox {
     pub fn new(ctx: &EventCtx, max_chars: usize, prefilled: String, autofocus: bool) -> TextBox {
         TextBox {
-            cursor_x: prefilled.len(),
-            line: prefilled,
+            editor: LineEditor::new(&prefilled),
             has_focus: false,
             hovering: false,
             autofocus,
@@ -34,23 +32,8 @@ impl TextBox {
         }
     }
 
-    fn calculate_text(&self) -> Text {
-        let mut txt = Text::from(Line(&self.line[0..self.cursor_x]));
-        if self.cursor_x < self.line.len() {
-            // TODO This "cursor" looks awful!
-            txt.append_all(vec![
-                Line("|").fg(text::SELECTED_COLOR),
-                Line(&self.line[self.cursor_x..=self.cursor_x]),
-                Line(&self.line[self.cursor_x + 1..]),
-            ]);
-        } else {
-            txt.append(Line("|").fg(text::SELECTED_COLOR));
-        }
-        txt
-    }
-
     pub fn get_line(&self) -> String {
-        self.line.clone()
+        self.editor.line()
     }
 }
 
@@ -83,30 +66,24 @@ impl WidgetImpl for TextBox {
             return;
         }
         if let Some(key) = ctx.input.any_key_pressed() {
-            match key {
-                Key::LeftArrow => {
-                    if self.cursor_x > 0 {
-                        self.cursor_x -= 1;
-                    }
-                }
-                Key::RightArrow => {
-                    self.cursor_x = (self.cursor_x + 1).min(self.line.len());
+            match self
+                .editor
+                .key(key, ctx.canvas.lctrl_held, ctx.canvas.lshift_held)
+            {
+                KeyOutcome::Used => {}
+                // Up, Down, Enter, and friends are for whoever owns this text box
+                KeyOutcome::Unused => {
+                    ctx.input.unconsume_event();
                 }
-                Key::Backspace => {
-                    if self.cursor_x > 0 {
-                        self.line.remove(self.cursor_x - 1);
-                        self.cursor_x -= 1;
-                    }
+                KeyOutcome::Copy(contents) => {
+                    set_clipboard(contents);
                 }
-                _ => {
-                    if let Some(c) = key.to_char(ctx.canvas.lshift_held) {
-                        self.line.insert(self.cursor_x, c);
-                        self.cursor_x += 1;
-                    } else {
-                        ctx.input.unconsume_event();
+                KeyOutcome::Paste => {
+                    if let Some(contents) = get_clipboard() {
+                        self.editor.paste(&contents);
                     }
                 }
-            };
+            }
         }
     }
 
@@ -116,8 +93,393 @@ impl WidgetImpl for TextBox {
             text::BG_COLOR,
             Polygon::rectangle(self.dims.width, self.dims.height),
         )]);
-        batch.append(self.calculate_text().render_to_batch(g.prerender));
+
+        let assets = &g.prerender.assets;
+        let width_until = |idx: usize| -> f64 {
+            if idx == 0 {
+                return 0.0;
+            }
+            let prefix: String = self.editor.chars[0..idx].iter().collect();
+            Text::from(Line(prefix)).dims(assets).width
+        };
+        if let Some((start, end)) = self.editor.selection() {
+            let x1 = width_until(start);
+            let x2 = width_until(end);
+            batch.push(
+                text::SELECTED_COLOR.alpha(0.5),
+                Polygon::rectangle(x2 - x1, self.dims.height).translate(x1, 0.0),
+            );
+        }
+        batch.append(Text::from(Line(self.editor.line())).render_to_batch(g.prerender));
+        if self.has_focus || self.autofocus {
+            batch.push(
+                text::SELECTED_COLOR,
+                Polygon::rectangle(2.0 * *assets.scale_factor.borrow(), self.dims.height)
+                    .translate(width_until(self.editor.cursor), 0.0),
+            );
+        }
+
         let draw = g.upload(batch);
         g.redraw_at(self.top_left, &draw);
     }
 }
+
+#[derive(Debug, PartialEq)]
+enum KeyOutcome {
+    Used,
+    Unused,
+    // The caller handles the clipboard
+    Copy(String),
+    Paste,
+}
+
+// All of the editing logic, separate from input and drawing. Indices are into chars, not bytes,
+// so pasting anything works.
+struct LineEditor {
+    chars: Vec<char>,
+    cursor: usize,
+    // Shift+movement selects everything between here and the cursor.
+    anchor: Option<usize>,
+}
+
+impl LineEditor {
+    fn new(line: &str) -> LineEditor {
+        let chars: Vec<char> = line.chars().collect();
+        LineEditor {
+            cursor: chars.len(),
+            chars,
+            anchor: None,
+        }
+    }
+
+    fn line(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    // Never empty
+    fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        if anchor == self.cursor {
+            None
+        } else {
+            Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+        }
+    }
+
+    fn key(&mut self, key: Key, ctrl: bool, shift: bool) -> KeyOutcome {
+        match key {
+            Key::LeftArrow => {
+                let to = if ctrl {
+                    self.prev_word()
+                } else {
+                    match self.selection() {
+                        Some((start, _)) if !shift => start,
+                        _ => self.cursor.saturating_sub(1),
+                    }
+                };
+                self.move_cursor(to, shift);
+            }
+            Key::RightArrow => {
+                let to = if ctrl {
+                    self.next_word()
+                } else {
+                    match self.selection() {
+                        Some((_, end)) if !shift => end,
+                        _ => (self.cursor + 1).min(self.chars.len()),
+                    }
+                };
+                self.move_cursor(to, shift);
+            }
+            Key::Home => {
+                self.move_cursor(0, shift);
+            }
+            Key::End => {
+                self.move_cursor(self.chars.len(), shift);
+            }
+            Key::Backspace => {
+                if !self.delete_selection() {
+                    let from = if ctrl {
+                        self.prev_word()
+                    } else {
+                        self.cursor.saturating_sub(1)
+                    };
+                    self.chars.drain(from..self.cursor);
+                    self.cursor = from;
+                }
+            }
+            Key::Delete => {
+                if !self.delete_selection() {
+                    let to = if ctrl {
+                        self.next_word()
+                    } else {
+                        (self.cursor + 1).min(self.chars.len())
+                    };
+                    self.chars.drain(self.cursor..to);
+                }
+            }
+            Key::A if ctrl => {
+                self.anchor = Some(0);
+                self.cursor = self.chars.len();
+            }
+            // With nothing selected, copy everything
+            Key::C if ctrl => {
+                return KeyOutcome::Copy(match self.selection() {
+                    Some((start, end)) => self.chars[start..end].iter().collect(),
+                    None => self.line(),
+                });
+            }
+            Key::V if ctrl => {
+                return KeyOutcome::Paste;
+            }
+            _ => {
+                if ctrl {
+                    return KeyOutcome::Unused;
+                }
+                if let Some(c) = key.to_char(shift) {
+                    self.delete_selection();
+                    self.chars.insert(self.cursor, c);
+                    self.cursor += 1;
+                } else {
+                    return KeyOutcome::Unused;
+                }
+            }
+        }
+        KeyOutcome::Used
+    }
+
+    // Replaces any selection. Only a single line is supported, so newlines become spaces.
+    fn paste(&mut self, contents: &str) {
+        self.delete_selection();
+        for c in contents
+            .trim_end_matches(|c| c == '\n' || c == '\r')
+            .chars()
+        {
+            let c = if c == '\n' || c == '\r' { ' ' } else { c };
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    fn move_cursor(&mut self, to: usize, shift: bool) {
+        if shift {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.cursor);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = to;
+    }
+
+    // True if there was something to delete
+    fn delete_selection(&mut self) -> bool {
+        let result = if let Some((start, end)) = self.selection() {
+            self.chars.drain(start..end);
+            self.cursor = start;
+            true
+        } else {
+            false
+        };
+        self.anchor = None;
+        result
+    }
+
+    // The start of the word before the cursor
+    fn prev_word(&self) -> usize {
+        let mut idx = self.cursor;
+        while idx > 0 && self.chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        while idx > 0 && !self.chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        idx
+    }
+
+    // The end of the word after the cursor
+    fn next_word(&self) -> usize {
+        let mut idx = self.cursor;
+        while idx < self.chars.len() && self.chars[idx].is_whitespace() {
+            idx += 1;
+        }
+        while idx < self.chars.len() && !self.chars[idx].is_whitespace() {
+            idx += 1;
+        }
+        idx
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn get_clipboard() -> Option<String> {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+
+    let mut cb: ClipboardContext = ClipboardProvider::new().ok()?;
+    cb.get_contents().ok()
+}
+
+#[cfg(feature = "clipboard")]
+fn set_clipboard(contents: String) {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+
+    if let Ok(mut cb) = ClipboardProvider::new() {
+        let cb: &mut ClipboardContext = &mut cb;
+        if let Err(err) = cb.set_contents(contents) {
+            println!("Couldn't copy to the clipboard: {}", err);
+        }
+    }
+}
+
+// Without the system clipboard, at least copying between text boxes works.
+#[cfg(not(feature = "clipboard"))]
+thread_local! {
+    static CLIPBOARD: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn get_clipboard() -> Option<String> {
+    CLIPBOARD.with(|cb| cb.borrow().clone())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn set_clipboard(contents: String) {
+    CLIPBOARD.with(|cb| *cb.borrow_mut() = Some(contents));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyOutcome, LineEditor};
+    use crate::Key;
+
+    // Each step is (key, ctrl, shift)
+    fn type_keys(editor: &mut LineEditor, keys: Vec<(Key, bool, bool)>) {
+        for (key, ctrl, shift) in keys {
+            editor.key(key, ctrl, shift);
+        }
+    }
+
+    fn plain(key: Key) -> (Key, bool, bool) {
+        (key, false, false)
+    }
+
+    #[test]
+    fn test_typing_and_moving() {
+        let mut e = LineEditor::new("");
+        type_keys(
+            &mut e,
+            vec![
+                plain(Key::A),
+                plain(Key::C),
+                plain(Key::LeftArrow),
+                plain(Key::B),
+                plain(Key::End),
+                (Key::D, false, true),
+                plain(Key::Home),
+                plain(Key::Delete),
+            ],
+        );
+        assert_eq!(e.line(), "bcD");
+        assert_eq!(e.cursor, 0);
+
+        type_keys(&mut e, vec![plain(Key::End), plain(Key::Backspace)]);
+        assert_eq!(e.line(), "bc");
+        assert_eq!(e.cursor, 2);
+
+        // Can't move past either end
+        type_keys(&mut e, vec![plain(Key::RightArrow), plain(Key::RightArrow)]);
+        assert_eq!(e.cursor, 2);
+        type_keys(
+            &mut e,
+            vec![
+                plain(Key::Home),
+                plain(Key::LeftArrow),
+                plain(Key::Backspace),
+            ],
+        );
+        assert_eq!(e.cursor, 0);
+        assert_eq!(e.line(), "bc");
+    }
+
+    #[test]
+    fn test_words() {
+        let mut e = LineEditor::new("47.6 -122.3  seattle");
+        e.key(Key::LeftArrow, true, false);
+        assert_eq!(e.cursor, 13);
+        e.key(Key::LeftArrow, true, false);
+        assert_eq!(e.cursor, 5);
+        e.key(Key::RightArrow, true, false);
+        assert_eq!(e.cursor, 11);
+
+        // Delete the word before the cursor, then the one after it
+        e.key(Key::Backspace, true, false);
+        assert_eq!(e.line(), "47.6   seattle");
+        e.key(Key::Delete, true, false);
+        assert_eq!(e.line(), "47.6 ");
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut e = LineEditor::new("hello world");
+        type_keys(
+            &mut e,
+            vec![(Key::LeftArrow, true, true), (Key::LeftArrow, false, true)],
+        );
+        assert_eq!(e.selection(), Some((5, 11)));
+        assert_eq!(
+            e.key(Key::C, true, false),
+            KeyOutcome::Copy(" world".to_string())
+        );
+
+        // Typing replaces the selection
+        e.key(Key::Num1, false, false);
+        assert_eq!(e.line(), "hello1");
+        assert_eq!(e.selection(), None);
+
+        // Moving without shift collapses the selection to one side
+        type_keys(
+            &mut e,
+            vec![
+                (Key::Home, false, true),
+                (Key::RightArrow, false, true),
+                plain(Key::RightArrow),
+            ],
+        );
+        assert_eq!(e.selection(), None);
+        assert_eq!(e.cursor, 6);
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let mut e = LineEditor::new("abc");
+        assert_eq!(
+            e.key(Key::C, true, false),
+            KeyOutcome::Copy("abc".to_string())
+        );
+        assert_eq!(e.key(Key::A, true, false), KeyOutcome::Used);
+        assert_eq!(e.selection(), Some((0, 3)));
+        assert_eq!(e.key(Key::V, true, false), KeyOutcome::Paste);
+        e.paste("/home/dabreegster/ünïcode\n");
+        assert_eq!(e.line(), "/home/dabreegster/ünïcode");
+        assert_eq!(e.cursor, 25);
+
+        e.paste("two\nlines");
+        assert_eq!(e.line(), "/home/dabreegster/ünïcodetwo lines");
+    }
+
+    #[test]
+    fn test_unused_keys() {
+        // Whoever owns the text box gets these
+        let mut e = LineEditor::new("x");
+        for key in vec![
+            Key::Enter,
+            Key::UpArrow,
+            Key::DownArrow,
+            Key::Escape,
+            Key::Tab,
+        ] {
+            assert_eq!(e.key(key, false, false), KeyOutcome::Unused);
+        }
+        // Control shouldn't type letters
+        assert_eq!(e.key(Key::Z, true, false), KeyOutcome::Unused);
+        assert_eq!(e.line(), "x");
+    }
+}
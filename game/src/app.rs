@@ -1,6 +1,9 @@
 use crate::challenges::HighScore;
 use crate::colors::ColorScheme;
+use crate::common::Toasts;
+use crate::debug::FrameTimings;
 use crate::helpers::ID;
+use crate::layer::neighborhoods::NeighborhoodCache;
 use crate::layer::Layer;
 use crate::options::Options;
 use crate::render::{AgentCache, AgentColorScheme, DrawMap, DrawOptions, Renderable};
@@ -8,10 +11,13 @@ use crate::sandbox::{GameplayMode, TutorialState};
 use abstutil::{MeasureMemory, Timer};
 use ezgui::{EventCtx, GfxCtx, Prerender};
 use geom::{Bounds, Circle, Distance, Duration, Pt2D, Time};
-use map_model::{IntersectionID, Map, Traversable};
+use instant::Instant;
+use map_model::{IntersectionID, Map, RoadID, Traversable};
 use rand::seq::SliceRandom;
-use sim::{Analytics, GetDrawAgents, Sim, SimCallback, SimFlags};
-use std::collections::BTreeMap;
+use sim::{
+    Analytics, GetDrawAgents, Intervention, Sim, SimCallback, SimEvent, SimFlags, Watchpoints,
+};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct App {
     // Naming is from older days when there was an A/B test, "side-by-side" mode. Keeping this
@@ -31,6 +37,9 @@ pub struct App {
 
     // Static data that lasts the entire session. Use sparingly.
     pub session: SessionState,
+    pub toasts: Toasts,
+    // Only recording when enabled from debug mode
+    pub frame_timings: Option<FrameTimings>,
 
     // Only filled out in edit mode. Stored here once to avoid lots of clones. Used for preview.
     pub suspended_sim: Option<Sim>,
@@ -80,6 +89,8 @@ impl App {
             per_obj: PerObjectActions::new(),
             layer: None,
             session: SessionState::empty(),
+            toasts: Toasts::new(),
+            frame_timings: None,
             suspended_sim: None,
         }
     }
@@ -145,8 +156,14 @@ impl App {
         let mut flags = self.primary.current_flags.clone();
         flags.sim_flags.load = load;
         let session = std::mem::replace(&mut self.session, SessionState::empty());
+        let toasts = std::mem::replace(&mut self.toasts, Toasts::new());
+        let frame_timings = self.frame_timings.take();
         *self = App::new(flags, self.opts.clone(), ctx, false);
         self.session = session;
+        // They refer to lanes and agents on the old map
+        self.session.watchpoints = Watchpoints::new();
+        self.toasts = toasts;
+        self.frame_timings = frame_timings;
     }
 
     pub fn draw(
@@ -223,6 +240,7 @@ impl App {
             );
         } else {
             let mut cache = self.primary.draw_map.agents.borrow_mut();
+            let culling_started = Instant::now();
             let objects = self.get_renderables_back_to_front(
                 g.get_screen_bounds(),
                 &g.prerender,
@@ -230,6 +248,9 @@ impl App {
                 source,
                 show_objs,
             );
+            if let Some(ref timings) = self.frame_timings {
+                timings.record_culling(abstutil::elapsed_seconds(culling_started));
+            }
 
             let mut drawn_all_buildings = false;
             let mut drawn_all_areas = false;
@@ -255,7 +276,9 @@ impl App {
                     _ => {}
                 };
 
-                if self.primary.current_selection == Some(obj.get_id()) {
+                if self.primary.current_selection == Some(obj.get_id())
+                    || self.primary.multi_selection.contains(&obj.get_id())
+                {
                     g.draw_polygon(self.cs.selected, &obj.get_outline(&self.primary.map));
                 }
 
@@ -497,6 +520,8 @@ pub struct Flags {
     // Number of agents to generate when requested. If unspecified, trips to/from borders will be
     // included.
     pub num_agents: Option<usize>,
+    // The raw map to compare against in debug mode. Defaults to the one the map was imported from.
+    pub raw_osm: Option<String>,
 }
 
 // All of the state that's bound to a specific map+edit has to live here.
@@ -506,11 +531,23 @@ pub struct PerMap {
     pub sim: Sim,
 
     pub current_selection: Option<ID>,
+    // Built up with ctrl+click or by double clicking a lane. Tools that work on many objects at
+    // once can use this.
+    pub multi_selection: BTreeSet<ID>,
     pub current_flags: Flags,
     pub last_warped_from: Option<(Pt2D, f64)>,
     pub sim_cb: Option<Box<dyn SimCallback>>,
+    // What happened in the sim since the previous event. Refreshed at the start of every event,
+    // so anything that wants to react to trips finishing and such should look here instead of
+    // polling the sim.
+    pub sim_events: Vec<(Time, SimEvent)>,
     // If we ever left edit mode and resumed without restarting from midnight, this is true.
     pub dirty_from_edits: bool,
+    // Filled out lazily
+    pub neighborhoods: NeighborhoodCache,
+    // Interventions the player scheduled partway through, like temporary lane closures. They
+    // survive resetting the sim, but unlike current_flags, they're never saved anywhere.
+    pub scheduled_interventions: Vec<(Time, Intervention)>,
 }
 
 impl PerMap {
@@ -529,23 +566,39 @@ impl PerMap {
             draw_map,
             sim,
             current_selection: None,
+            multi_selection: BTreeSet::new(),
             current_flags: flags.clone(),
             last_warped_from: None,
             sim_cb: None,
+            sim_events: Vec::new(),
             dirty_from_edits: false,
+            neighborhoods: NeighborhoodCache::new(),
+            scheduled_interventions: Vec::new(),
         }
     }
 
+    // Every road with some lane in the multi-selection
+    pub fn selected_roads(&self) -> BTreeSet<RoadID> {
+        self.multi_selection
+            .iter()
+            .filter_map(|id| match id {
+                ID::Lane(l) => Some(self.map.get_l(*l).parent),
+                ID::Road(r) => Some(*r),
+                _ => None,
+            })
+            .collect()
+    }
+
     // Returns whatever was there
     pub fn clear_sim(&mut self) -> Sim {
         self.dirty_from_edits = false;
+        self.sim_events.clear();
+        let mut opts = self.current_flags.sim_flags.opts.clone();
+        opts.interventions
+            .extend(self.scheduled_interventions.iter().cloned());
         std::mem::replace(
             &mut self.sim,
-            Sim::new(
-                &self.map,
-                self.current_flags.sim_flags.opts.clone(),
-                &mut Timer::new("reset simulation"),
-            ),
+            Sim::new(&self.map, opts, &mut Timer::new("reset simulation")),
         )
     }
 }
@@ -554,6 +607,9 @@ impl PerMap {
 pub struct SessionState {
     pub tutorial: Option<TutorialState>,
     pub high_scores: BTreeMap<GameplayMode, Vec<HighScore>>,
+    // Total trip time before the player's edits in the quick start tutorial
+    pub onboarding_baseline: Option<Duration>,
+    pub watchpoints: Watchpoints,
 }
 
 impl SessionState {
@@ -561,6 +617,8 @@ impl SessionState {
         SessionState {
             tutorial: None,
             high_scores: BTreeMap::new(),
+            onboarding_baseline: None,
+            watchpoints: Watchpoints::new(),
         }
     }
 }
@@ -147,6 +147,25 @@ impl Tab {
             "Introduction and tutorial".to_string(),
             Box::new(|ctx, app| Some(Tutorial::start(ctx, app))),
         ));
+        master_col.push(
+            Btn::text_bg2("Quick start on a tiny map")
+                .build_def(ctx, None)
+                .centered_horiz()
+                .bg(app.cs.panel_bg)
+                .padding(10)
+                .outline(10.0, Color::BLACK)
+                .margin_below(10),
+        );
+        cbs.push((
+            "Quick start on a tiny map".to_string(),
+            Box::new(|ctx, app| {
+                Some(Transition::Push(Box::new(SandboxMode::new(
+                    ctx,
+                    app,
+                    GameplayMode::Onboarding(0),
+                ))))
+            }),
+        ));
 
         // First list challenges
         let mut flex_row = Vec::new();
@@ -292,7 +311,7 @@ pub fn prebake_all() {
     {
         let map = map_model::Map::new(abstutil::path_map("montlake"), &mut timer);
         let scenario: Scenario =
-            abstutil::read_binary(abstutil::path_scenario("montlake", "weekday"), &mut timer);
+            abstutil::read_versioned(abstutil::path_scenario("montlake", "weekday"), &mut timer);
         prebake(&map, scenario, None, &mut timer);
 
         for generator in TutorialState::scenarios_to_prebake(&map) {
@@ -308,7 +327,7 @@ pub fn prebake_all() {
     for name in vec!["lakeslice"] {
         let map = map_model::Map::new(abstutil::path_map(name), &mut timer);
         let scenario: Scenario =
-            abstutil::read_binary(abstutil::path_scenario(name, "weekday"), &mut timer);
+            abstutil::read_versioned(abstutil::path_scenario(name, "weekday"), &mut timer);
         prebake(&map, scenario, None, &mut timer);
     }
 }
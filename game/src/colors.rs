@@ -58,6 +58,8 @@ pub struct ColorScheme {
     pub bus_lane: Color,
     pub parking_lane: Color,
     pub bike_lane: Color,
+    pub construction_hatching: Color,
+    pub closed_lane_hatching: Color,
     pub sidewalk: Color,
     pub sidewalk_lines: Color,
     pub general_road_marking: Color,
@@ -155,6 +157,8 @@ impl ColorScheme {
             bus_lane: Color::rgb(190, 74, 76),
             parking_lane: Color::grey(0.2),
             bike_lane: Color::rgb(15, 125, 75),
+            construction_hatching: Color::rgb(255, 109, 0),
+            closed_lane_hatching: Color::rgb(255, 220, 0),
             sidewalk: Color::grey(0.8),
             sidewalk_lines: Color::grey(0.7),
             general_road_marking: Color::WHITE,
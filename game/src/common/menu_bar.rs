@@ -0,0 +1,126 @@
+use crate::app::App;
+use ezgui::{
+    Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome, VerticalAlignment, Widget,
+};
+
+// Every entry names an action that some button or hotkey elsewhere already handles. Picking the
+// entry triggers that action, exactly like pressing its hotkey would.
+const MENUS: [(&str, &[&str]); 4] = [
+    (
+        "View",
+        &[
+            "change layers",
+            "search",
+            "more data",
+            "settings",
+            "toggle fullscreen",
+        ],
+    ),
+    ("Edit", &["edit map", "change map", "change traffic"]),
+    (
+        "Sim",
+        &[
+            "play",
+            "pause",
+            "jump to specific time",
+            "reset to midnight",
+        ],
+    ),
+    ("Debug", &["debug mode", "toggle developer mode", "warp"]),
+];
+
+// So new players can discover what's possible without knowing any keys. The bar itself has no
+// hotkeys, so it never steals keypresses.
+pub struct MenuBar {
+    bar: Composite,
+    // While a menu is open, nothing else gets input.
+    pulldown: Option<Composite>,
+}
+
+impl MenuBar {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> MenuBar {
+        MenuBar {
+            bar: Composite::new(
+                Widget::row(
+                    MENUS
+                        .iter()
+                        .map(|(name, _)| Btn::text_fg(*name).build_def(ctx, None).margin_right(5))
+                        .collect(),
+                )
+                .bg(app.cs.panel_bg)
+                .padding(5),
+            )
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx),
+            pulldown: None,
+        }
+    }
+
+    pub fn align_below(&mut self, ctx: &mut EventCtx, other: &Composite) {
+        self.bar.align_below(ctx, other, 5.0);
+    }
+
+    // True means the input was used by the menu, and the caller shouldn't handle anything else.
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &App) -> bool {
+        // The pulldown would be left floating somewhere strange
+        if ctx.input.is_window_resized() {
+            self.pulldown = None;
+        }
+        if let Some(ref mut pulldown) = self.pulldown {
+            match pulldown.event(ctx) {
+                Some(Outcome::Clicked(x)) => {
+                    self.pulldown = None;
+                    ctx.trigger_action(x["menu: ".len()..].to_string());
+                }
+                None => {
+                    if pulldown.clicked_outside(ctx)
+                        || ctx.input.key_pressed(Key::Escape, "close the menu")
+                    {
+                        self.pulldown = None;
+                    }
+                }
+            }
+            return true;
+        }
+
+        match self.bar.event(ctx) {
+            Some(Outcome::Clicked(x)) => {
+                let rect = self.bar.rect_of(&x).clone();
+                let entries = MENUS.iter().find(|(name, _)| *name == x).unwrap().1;
+                self.pulldown = Some(make_pulldown(ctx, app, entries, rect.x1, rect.y2));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        self.bar.draw(g);
+        if let Some(ref pulldown) = self.pulldown {
+            pulldown.draw(g);
+        }
+    }
+}
+
+fn make_pulldown(ctx: &mut EventCtx, app: &App, entries: &[&str], x: f64, y: f64) -> Composite {
+    let mut col = Vec::new();
+    for action in entries {
+        let label = match ctx.canvas.keybindings.current(action) {
+            Some(key) => format!("{} ({})", action, key.describe()),
+            None => action.to_string(),
+        };
+        // Greyed out if nothing handled this action during the last event, like editing in a
+        // challenge that doesn't allow it, or debug tools outside of developer mode.
+        col.push(if ctx.is_action_available(action) {
+            Btn::text_fg(label).build(ctx, format!("menu: {}", action), None)
+        } else {
+            Btn::text_fg(label).inactive(ctx)
+        });
+    }
+    Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(5))
+        .aligned(
+            HorizontalAlignment::Percent(x / ctx.canvas.window_width),
+            VerticalAlignment::Below(y),
+        )
+        .build(ctx)
+}
@@ -56,6 +56,10 @@ impl Minimap {
         (pct_x, pct_y)
     }
 
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.set_zoom(ctx, app, self.zoom_lvl);
+    }
+
     fn set_zoom(&mut self, ctx: &mut EventCtx, app: &App, zoom_lvl: usize) {
         // Make the frame wind up in the same relative position on the minimap
         let (pct_x, pct_y) = self.map_to_minimap_pct(ctx.canvas.center_to_map_pt());
@@ -1,17 +1,21 @@
 mod city_picker;
 mod colors;
 mod heatmap;
+mod menu_bar;
 mod minimap;
 mod navigate;
 mod panels;
+mod toasts;
 mod warp;
 
 pub use self::city_picker::CityPicker;
 pub use self::colors::{ColorDiscrete, ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 pub use self::heatmap::{make_heatmap, HeatmapOptions};
+pub use self::menu_bar::MenuBar;
 pub use self::minimap::Minimap;
 pub use self::panels::tool_panel;
-pub use self::warp::Warping;
+pub use self::toasts::Toasts;
+pub use self::warp::{find_warp_ids, warp_to_id, Warping};
 use crate::app::App;
 use crate::game::Transition;
 use crate::helpers::{list_names, ID};
@@ -46,22 +50,28 @@ impl CommonState {
         app: &mut App,
         ctx_actions: &mut dyn ContextualActions,
     ) -> Option<Transition> {
-        if ctx.input.new_was_pressed(&lctrl(Key::S).unwrap()) {
+        if ctx.action_pressed("toggle developer mode", lctrl(Key::S)) {
             app.opts.dev = !app.opts.dev;
         }
-        if app.opts.dev && ctx.input.new_was_pressed(&lctrl(Key::J).unwrap()) {
+        if app.opts.dev && ctx.action_pressed("warp", lctrl(Key::J)) {
             return Some(Transition::Push(warp::EnteringWarp::new()));
         }
 
-        if let Some(ref id) = app.primary.current_selection {
+        if let Some(id) = app.primary.current_selection.clone() {
+            if ctx.double_left_click() {
+                return self.double_click(ctx, app, id, ctx_actions);
+            }
             // TODO Also have a hotkey binding for this?
-            if app.per_obj.left_click(ctx, "show info") {
-                self.info_panel = Some(InfoPanel::new(
-                    ctx,
-                    app,
-                    Tab::from_id(app, id.clone()),
-                    ctx_actions,
-                ));
+            if ctx.is_ctrl_held() {
+                if app.per_obj.left_click(ctx, "add to the selection") {
+                    if !app.primary.multi_selection.remove(&id) {
+                        app.primary.multi_selection.insert(id);
+                    }
+                    return None;
+                }
+            } else if app.per_obj.left_click(ctx, "show info") {
+                self.info_panel =
+                    Some(InfoPanel::new(ctx, app, Tab::from_id(app, id), ctx_actions));
                 return None;
             }
         }
@@ -76,6 +86,15 @@ impl CommonState {
             }
         }
 
+        // If the info panel is open, Escape closes that first
+        if self.info_panel.is_none()
+            && !app.primary.multi_selection.is_empty()
+            && ctx.input.key_pressed(Key::Escape, "clear the selection")
+        {
+            app.primary.multi_selection.clear();
+            return None;
+        }
+
         if self.info_panel.is_none() {
             self.cached_actions.clear();
             if let Some(id) = app.primary.current_selection.clone() {
@@ -92,6 +111,41 @@ impl CommonState {
         None
     }
 
+    fn double_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        id: ID,
+        ctx_actions: &mut dyn ContextualActions,
+    ) -> Option<Transition> {
+        match id {
+            // Select the whole road
+            ID::Lane(l) => {
+                let r = app.primary.map.get_l(l).parent;
+                app.primary.multi_selection = app
+                    .primary
+                    .map
+                    .get_r(r)
+                    .all_lanes()
+                    .into_iter()
+                    .map(ID::Lane)
+                    .collect();
+                None
+            }
+            // Only if the current mode offers some way to edit it
+            ID::Intersection(_) => {
+                let action = ctx_actions
+                    .actions(app, id.clone())
+                    .into_iter()
+                    .map(|(_, action)| action)
+                    .find(|action| action == "edit traffic signal" || action == "edit stop sign")?;
+                self.info_panel = None;
+                Some(ctx_actions.execute(ctx, app, id, action, &mut false))
+            }
+            _ => None,
+        }
+    }
+
     pub fn draw(&self, g: &mut GfxCtx, app: &App) {
         let keys = if let Some(ref info) = self.info_panel {
             info.draw(g, app);
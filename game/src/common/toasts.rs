@@ -0,0 +1,160 @@
+use crate::colors::ColorScheme;
+use abstutil::Severity;
+use ezgui::{
+    lctrl, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Text,
+    VerticalAlignment, Widget,
+};
+use instant::Instant;
+use std::collections::BTreeSet;
+
+const SHOW_FOR_SECONDS: f64 = 5.0;
+const MAX_TOASTS: usize = 5;
+
+// Warnings and errors from the logs, plus anything explicitly passed to notify(), briefly appear
+// in the corner of the screen, no matter what state the game's in.
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    composite: Option<Composite>,
+    // The next LogLine ID that hasn't been considered yet
+    next_log_id: usize,
+    // Lines below Severity::Warn normally don't become toasts, unless they come from notify().
+    notified: BTreeSet<usize>,
+}
+
+struct Toast {
+    severity: Severity,
+    msg: String,
+    // Identical messages are grouped together
+    count: usize,
+    last_log_id: usize,
+    shown_at: Instant,
+}
+
+impl Toasts {
+    pub fn new() -> Toasts {
+        Toasts {
+            toasts: Vec::new(),
+            composite: None,
+            next_log_id: abstutil::num_logs_recorded(),
+            notified: BTreeSet::new(),
+        }
+    }
+
+    // Logs the message, and also shows it as a toast, even for Info or Debug.
+    #[track_caller]
+    pub fn notify(&mut self, severity: Severity, msg: String) {
+        self.notified.insert(abstutil::num_logs_recorded());
+        abstutil::log(severity, msg);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    // If the player asks to open the logs, returns the ID of the LogLine for the newest toast.
+    pub fn event(&mut self, ctx: &mut EventCtx, cs: &ColorScheme) -> Option<usize> {
+        let mut changed = false;
+
+        if self.next_log_id != abstutil::num_logs_recorded() {
+            let next_log_id = self.next_log_id;
+            let notified = &self.notified;
+            let (lines, _) = abstutil::filter_logs(|line| {
+                line.id >= next_log_id
+                    && (line.severity <= Severity::Warn || notified.contains(&line.id))
+            });
+            for line in lines {
+                self.add(line.severity, line.msg, line.id);
+            }
+            self.next_log_id = abstutil::num_logs_recorded();
+            self.notified.clear();
+            changed = true;
+        }
+
+        let len = self.toasts.len();
+        self.toasts
+            .retain(|t| abstutil::elapsed_seconds(t.shown_at) < SHOW_FOR_SECONDS);
+        if self.toasts.len() != len {
+            changed = true;
+        }
+
+        if !self.toasts.is_empty() {
+            if ctx.action_pressed("dismiss notifications", lctrl(Key::X)) {
+                self.toasts.clear();
+                changed = true;
+            } else if ctx.action_pressed("open notification in the logs", lctrl(Key::O)) {
+                let id = self.toasts.last().unwrap().last_log_id;
+                self.toasts.clear();
+                self.composite = None;
+                return Some(id);
+            }
+        }
+
+        if changed {
+            self.composite = if self.toasts.is_empty() {
+                None
+            } else {
+                Some(self.make_composite(ctx, cs))
+            };
+        }
+        if let Some(ref mut c) = self.composite {
+            c.event(ctx);
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref c) = self.composite {
+            c.draw(g);
+        }
+    }
+
+    fn add(&mut self, severity: Severity, msg: String, log_id: usize) {
+        if let Some(t) = self
+            .toasts
+            .iter_mut()
+            .find(|t| t.severity == severity && t.msg == msg)
+        {
+            t.count += 1;
+            t.last_log_id = log_id;
+            t.shown_at = Instant::now();
+            return;
+        }
+        self.toasts.push(Toast {
+            severity,
+            msg,
+            count: 1,
+            last_log_id: log_id,
+            shown_at: Instant::now(),
+        });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    fn make_composite(&self, ctx: &mut EventCtx, cs: &ColorScheme) -> Composite {
+        let mut col = Vec::new();
+        for t in &self.toasts {
+            let mut txt = Text::from(Line(&t.msg).fg(match t.severity {
+                Severity::Error => Color::RED,
+                Severity::Warn => Color::YELLOW,
+                Severity::Info | Severity::Debug => Color::WHITE,
+            }));
+            if t.count > 1 {
+                txt.append(Line(format!(" (x{})", t.count)));
+            }
+            col.push(
+                txt.wrap_to_pct(ctx, 25)
+                    .draw(ctx)
+                    .padding(10)
+                    .bg(cs.panel_bg)
+                    .margin_below(5),
+            );
+        }
+        col.push(
+            Text::from(Line("Ctrl+X to dismiss, Ctrl+O to open the logs").secondary()).draw(ctx),
+        );
+        Composite::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx)
+    }
+}
@@ -56,6 +56,8 @@ impl State for Warping {
                             Tab::from_id(app, id),
                             &mut actions,
                         );
+                    } else {
+                        app.primary.current_selection = Some(id);
                     }
                 }))
             } else {
@@ -98,59 +100,61 @@ fn inner_warp(ctx: &mut EventCtx, app: &mut App, line: &str) -> Option<Transitio
         return None;
     }
 
-    let id = match usize::from_str_radix(&line[1..line.len()], 10) {
-        Ok(idx) => match line.chars().next().unwrap() {
-            'r' => {
-                let r = app.primary.map.maybe_get_r(RoadID(idx))?;
-                ID::Lane(r.children_forwards[0].0)
-            }
-            'l' => ID::Lane(LaneID(idx)),
-            'i' => ID::Intersection(IntersectionID(idx)),
-            'b' => ID::Building(BuildingID(idx)),
-            'a' => ID::Area(AreaID(idx)),
-            'p' => ID::Pedestrian(PedestrianID(idx)),
-            'P' => {
-                let id = PersonID(idx);
-                app.primary.sim.lookup_person(id)?;
-                return Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
-                    // Other states pretty much don't use info panels.
-                    if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
-                        let mut actions = s.contextual_actions();
-                        s.controls.common.as_mut().unwrap().launch_info_panel(
-                            ctx,
-                            app,
-                            Tab::PersonTrips(id, BTreeMap::new()),
-                            &mut actions,
-                        );
-                    }
-                })));
-            }
-            'c' => {
-                // This one gets more complicated. :)
-                let c = app.primary.sim.lookup_car_id(idx)?;
-                ID::Car(c)
-            }
-            't' => {
-                let trip = TripID(idx);
-                let person = app.primary.sim.trip_to_person(trip);
-                return Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
-                    // Other states pretty much don't use info panels.
-                    if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
-                        let mut actions = s.contextual_actions();
-                        s.controls.common.as_mut().unwrap().launch_info_panel(
-                            ctx,
-                            app,
-                            Tab::PersonTrips(person, OpenTrip::single(trip)),
-                            &mut actions,
-                        );
-                    }
-                })));
-            }
-            _ => {
-                return None;
-            }
-        },
-        Err(_) => {
+    let (code, idx) = parse_warp_id(line)?;
+    warp_to_id(ctx, app, code, idx)
+}
+
+// The warp prompt and anything else that references objects by text (like the log viewer) share
+// this. Objects are named by a one-character code and a number, like "l45" or "c123".
+pub fn warp_to_id(ctx: &mut EventCtx, app: &mut App, code: char, idx: usize) -> Option<Transition> {
+    let id = match code {
+        'r' => {
+            let r = app.primary.map.maybe_get_r(RoadID(idx))?;
+            ID::Lane(r.children_forwards[0].0)
+        }
+        'l' => ID::Lane(LaneID(idx)),
+        'i' => ID::Intersection(IntersectionID(idx)),
+        'b' => ID::Building(BuildingID(idx)),
+        'a' => ID::Area(AreaID(idx)),
+        'p' => ID::Pedestrian(PedestrianID(idx)),
+        'P' => {
+            let id = PersonID(idx);
+            app.primary.sim.lookup_person(id)?;
+            return Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                // Other states pretty much don't use info panels.
+                if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
+                    let mut actions = s.contextual_actions();
+                    s.controls.common.as_mut().unwrap().launch_info_panel(
+                        ctx,
+                        app,
+                        Tab::PersonTrips(id, BTreeMap::new()),
+                        &mut actions,
+                    );
+                }
+            })));
+        }
+        'c' => {
+            // This one gets more complicated. :)
+            let c = app.primary.sim.lookup_car_id(idx)?;
+            ID::Car(c)
+        }
+        't' => {
+            let trip = TripID(idx);
+            let person = app.primary.sim.maybe_trip_to_person(trip)?;
+            return Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                // Other states pretty much don't use info panels.
+                if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
+                    let mut actions = s.contextual_actions();
+                    s.controls.common.as_mut().unwrap().launch_info_panel(
+                        ctx,
+                        app,
+                        Tab::PersonTrips(person, OpenTrip::single(trip)),
+                        &mut actions,
+                    );
+                }
+            })));
+        }
+        _ => {
             return None;
         }
     };
@@ -167,3 +171,56 @@ fn inner_warp(ctx: &mut EventCtx, app: &mut App, line: &str) -> Option<Transitio
         None
     }
 }
+
+// Parses something like "l45" or "P3".
+pub fn parse_warp_id(word: &str) -> Option<(char, usize)> {
+    let mut chars = word.chars();
+    let code = chars.next()?;
+    let idx = usize::from_str_radix(chars.as_str(), 10).ok()?;
+    Some((code, idx))
+}
+
+// Finds all object references in free-form text. Besides the short form the warp prompt takes,
+// this understands the Display and Debug forms of IDs, like "Lane #45", "car 123", or "CarID(7, Car)".
+pub fn find_warp_ids(line: &str) -> Vec<(char, usize)> {
+    let words: Vec<&str> = line
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut ids = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if let Some(code) = word_to_code(words[i]) {
+            if let Some(idx) = words.get(i + 1).and_then(|w| w.parse::<usize>().ok()) {
+                ids.push((code, idx));
+                i += 2;
+                continue;
+            }
+        }
+        if let Some((code, idx)) = parse_warp_id(words[i]) {
+            if WARP_CODES.contains(code) {
+                ids.push((code, idx));
+            }
+        }
+        i += 1;
+    }
+    ids
+}
+
+const WARP_CODES: &str = "rlibapPct";
+
+fn word_to_code(word: &str) -> Option<char> {
+    let word = word.trim_end_matches("ID").to_lowercase();
+    match word.as_ref() {
+        "road" => Some('r'),
+        "lane" => Some('l'),
+        "intersection" => Some('i'),
+        "building" | "bldg" => Some('b'),
+        "area" => Some('a'),
+        "pedestrian" | "ped" => Some('p'),
+        "person" => Some('P'),
+        "car" | "bus" | "bike" => Some('c'),
+        "trip" => Some('t'),
+        _ => None,
+    }
+}
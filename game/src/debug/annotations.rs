@@ -0,0 +1,346 @@
+use crate::app::App;
+use crate::common::Warping;
+use crate::debug::DebugMode;
+use crate::game::{DrawBaselayer, State, Transition, WizardState};
+use crate::helpers::ID;
+use abstutil::{deserialize_btreemap, serialize_btreemap, Timer};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Pt2D};
+use map_model::raw::{OriginalBuilding, OriginalIntersection, OriginalRoad};
+use map_model::Map;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Notes point at OSM IDs instead of map_model IDs, so they survive re-importing the map.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NoteTarget {
+    Intersection(OriginalIntersection),
+    Road(OriginalRoad),
+    Building(OriginalBuilding),
+}
+
+impl NoteTarget {
+    // Lanes are annotated through their road
+    pub fn from_id(id: &ID, map: &Map) -> Option<NoteTarget> {
+        match *id {
+            ID::Intersection(i) => Some(NoteTarget::Intersection(map.get_i(i).orig_id)),
+            ID::Road(r) => Some(NoteTarget::Road(map.get_r(r).orig_id)),
+            ID::Lane(l) => Some(NoteTarget::Road(map.get_r(map.get_l(l).parent).orig_id)),
+            ID::Building(b) => Some(NoteTarget::Building(OriginalBuilding {
+                osm_way_id: map.get_b(b).osm_way_id,
+            })),
+            _ => None,
+        }
+    }
+
+    // None if the object doesn't exist anymore
+    pub fn to_id(&self, map: &Map) -> Option<ID> {
+        match self {
+            NoteTarget::Intersection(i) => map
+                .find_i_by_osm_id(i.osm_node_id)
+                .ok()
+                .map(ID::Intersection),
+            NoteTarget::Road(r) => map
+                .find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
+                .ok()
+                .map(ID::Road),
+            NoteTarget::Building(b) => map.find_b_by_osm_id(b.osm_way_id).map(ID::Building),
+        }
+    }
+}
+
+impl fmt::Display for NoteTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoteTarget::Intersection(i) => write!(f, "node {}", i.osm_node_id),
+            NoteTarget::Road(r) => write!(
+                f,
+                "way {} between nodes {} and {}",
+                r.osm_way_id, r.i1.osm_node_id, r.i2.osm_node_id
+            ),
+            NoteTarget::Building(b) => write!(f, "building way {}", b.osm_way_id),
+        }
+    }
+}
+
+// Notes from review sessions, saved per map.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Annotations {
+    pub map_name: String,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub notes: BTreeMap<NoteTarget, String>,
+}
+
+impl Annotations {
+    pub fn load(map_name: &str) -> Annotations {
+        abstutil::maybe_read_json(
+            abstutil::path_annotations(map_name),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_else(|_| Annotations {
+            map_name: map_name.to_string(),
+            notes: BTreeMap::new(),
+        })
+    }
+
+    // An empty note deletes it. Saved immediately, so nothing's lost if the game crashes.
+    pub fn set(&mut self, target: NoteTarget, note: String) {
+        let note = note.trim().to_string();
+        if note.is_empty() {
+            self.notes.remove(&target);
+        } else {
+            self.notes.insert(target, note);
+        }
+        abstutil::write_json(abstutil::path_annotations(&self.map_name), self);
+    }
+
+    // Notes whose objects still exist, and the orphans
+    fn resolve(&self, map: &Map) -> (Vec<(ID, &NoteTarget)>, Vec<&NoteTarget>) {
+        let mut found = Vec::new();
+        let mut orphans = Vec::new();
+        for target in self.notes.keys() {
+            match target.to_id(map) {
+                Some(id) => found.push((id, target)),
+                None => orphans.push(target),
+            }
+        }
+        (found, orphans)
+    }
+}
+
+// Marks annotated objects on the map and shows the note when one is hovered.
+pub struct ShowAnnotations {
+    pub annotations: Annotations,
+    draw: Drawable,
+}
+
+impl ShowAnnotations {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> ShowAnnotations {
+        let annotations = Annotations::load(app.primary.map.get_name());
+        let draw = draw_markers(ctx, app, &annotations);
+        ShowAnnotations { annotations, draw }
+    }
+
+    pub fn set(&mut self, ctx: &mut EventCtx, app: &App, target: NoteTarget, note: String) {
+        self.annotations.set(target, note);
+        self.draw = draw_markers(ctx, app, &self.annotations);
+    }
+
+    pub fn replace(&mut self, ctx: &mut EventCtx, app: &App, annotations: Annotations) {
+        self.annotations = annotations;
+        self.draw = draw_markers(ctx, app, &self.annotations);
+    }
+
+    pub fn get(&self, app: &App, id: &ID) -> Option<&String> {
+        NoteTarget::from_id(id, &app.primary.map).and_then(|t| self.annotations.notes.get(&t))
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        if let Some(note) = app
+            .primary
+            .current_selection
+            .as_ref()
+            .and_then(|id| self.get(app, id))
+        {
+            g.draw_mouse_tooltip(Text::from(Line(note)));
+        }
+    }
+}
+
+fn marker_pt(id: &ID, map: &Map) -> Pt2D {
+    match id {
+        ID::Road(r) => map.get_r(*r).center_pts.middle(),
+        ID::Intersection(i) => map.get_i(*i).polygon.center(),
+        ID::Building(b) => map.get_b(*b).polygon.center(),
+        _ => unreachable!(),
+    }
+}
+
+fn draw_markers(ctx: &mut EventCtx, app: &App, annotations: &Annotations) -> Drawable {
+    let mut batch = GeomBatch::new();
+    let (found, _) = annotations.resolve(&app.primary.map);
+    for (id, _) in found {
+        let pt = marker_pt(&id, &app.primary.map);
+        batch.push(
+            Color::YELLOW,
+            Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+        );
+        batch.push(
+            Color::BLACK,
+            Circle::outline(pt, Distance::meters(3.0), Distance::meters(0.5)),
+        );
+    }
+    ctx.upload(batch)
+}
+
+// Prefilled with the existing note. Only pushed from DebugMode or AnnotationList.
+pub fn edit_note(target: NoteTarget, existing: Option<String>) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, _| {
+        let note = wiz.wrap(ctx).input_string_prefilled(
+            &format!("Note for {} (leave empty to delete)", target),
+            existing.clone().unwrap_or_else(String::new),
+        )?;
+        let target = target.clone();
+        Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+            if let Some(mode) = state.downcast_mut::<DebugMode>() {
+                mode.annotations.set(ctx, app, target, note);
+            } else {
+                let list = state.downcast_mut::<AnnotationList>().unwrap();
+                list.annotations.set(target, note);
+                list.composite = make_list(ctx, app, &list.annotations);
+            }
+        })))
+    }))
+}
+
+pub struct AnnotationList {
+    composite: Composite,
+    annotations: Annotations,
+}
+
+impl AnnotationList {
+    pub fn new(ctx: &mut EventCtx, app: &App, annotations: Annotations) -> Box<dyn State> {
+        Box::new(AnnotationList {
+            composite: make_list(ctx, app, &annotations),
+            annotations,
+        })
+    }
+}
+
+impl State for AnnotationList {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => {
+                if x == "close" {
+                    let annotations = self.annotations.clone();
+                    return Transition::PopWithData(Box::new(move |state, ctx, app| {
+                        let mode = state.downcast_mut::<DebugMode>().unwrap();
+                        mode.annotations.replace(ctx, app, annotations);
+                    }));
+                }
+                // Buttons are named "{action} {idx}", indexing into the notes
+                let (action, idx) = x.split_at(x.rfind(' ').unwrap());
+                let target = self
+                    .annotations
+                    .notes
+                    .keys()
+                    .nth(idx.trim().parse::<usize>().unwrap())
+                    .unwrap()
+                    .clone();
+                match action {
+                    "warp to" => {
+                        let id = match target.to_id(&app.primary.map).unwrap() {
+                            // Roads can't be selected
+                            ID::Road(r) => ID::Lane(app.primary.map.get_r(r).all_lanes()[0]),
+                            id => id,
+                        };
+                        return Transition::Push(Warping::new(
+                            ctx,
+                            id.canonical_point(&app.primary).unwrap(),
+                            Some(10.0),
+                            Some(id),
+                            &mut app.primary,
+                        ));
+                    }
+                    "edit" => {
+                        let existing = self.annotations.notes.get(&target).cloned();
+                        return Transition::Push(edit_note(target, existing));
+                    }
+                    "delete" => {
+                        self.annotations.set(target, String::new());
+                        self.composite = make_list(ctx, app, &self.annotations);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            None => {}
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+}
+
+fn make_list(ctx: &mut EventCtx, app: &App, annotations: &Annotations) -> Composite {
+    let map = &app.primary.map;
+    let (found, orphans) = annotations.resolve(map);
+    let idx_of = |target: &NoteTarget| annotations.notes.keys().position(|t| t == target).unwrap();
+    let note_row = |ctx: &mut EventCtx, target: &NoteTarget, label: String, warp: bool| {
+        let idx = idx_of(target);
+        let mut row = vec![Text::from_multiline(vec![
+            Line(label).secondary(),
+            Line(&annotations.notes[target]),
+        ])
+        .wrap_to_pct(ctx, 30)
+        .draw(ctx)
+        .margin_right(10)];
+        if warp {
+            row.push(
+                Btn::svg_def("../data/system/assets/tools/pin.svg")
+                    .build(ctx, format!("warp to {}", idx), None)
+                    .margin_right(5),
+            );
+        }
+        row.push(
+            Btn::text_fg("edit")
+                .build(ctx, format!("edit {}", idx), None)
+                .margin_right(5),
+        );
+        row.push(Btn::text_fg("delete").build(ctx, format!("delete {}", idx), None));
+        Widget::row(row).margin_below(10)
+    };
+
+    let mut col = vec![Widget::row(vec![
+        Line("Annotations").small_heading().draw(ctx),
+        Btn::text_fg("X")
+            .build(ctx, "close", hotkey(Key::Escape))
+            .align_right(),
+    ])
+    .margin_below(10)];
+    if annotations.notes.is_empty() {
+        col.push("No notes yet. Select something and press N to add one.".draw_text(ctx));
+    }
+    for (id, target) in found {
+        let label = match id {
+            ID::Road(r) => format!("{} ({})", r, map.get_r(r).get_name()),
+            ID::Intersection(i) => i.to_string(),
+            ID::Building(b) => b.to_string(),
+            _ => unreachable!(),
+        };
+        col.push(note_row(ctx, target, label, true));
+    }
+    if !orphans.is_empty() {
+        col.push(
+            Line(format!(
+                "Orphaned: {} notes refer to objects missing from this map",
+                orphans.len()
+            ))
+            .small_heading()
+            .draw(ctx)
+            .margin_above(10)
+            .margin_below(10),
+        );
+        for target in orphans {
+            col.push(note_row(ctx, target, target.to_string(), false));
+        }
+    }
+
+    Composite::new(Widget::col(col).padding(10).bg(app.cs.panel_bg))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .max_size_percent(60, 80)
+        .build(ctx)
+}
@@ -0,0 +1,211 @@
+use crate::app::App;
+use crate::helpers::ID;
+use ezgui::{
+    Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    VerticalAlignment, Widget,
+};
+use geom::Distance;
+use map_model::{BusRouteID, BusStopID};
+use sim::{AgentID, TripEndpoint};
+use std::collections::{BTreeSet, HashMap};
+
+const AGENTS: &str = "agents at an intersection, or blocking a car";
+const BUILDINGS: &str = "a building's sidewalk and parked cars";
+const BUS_STOPS: &str = "a bus stop's routes and neighboring stops";
+const LANES: &str = "the other lanes of a road";
+const DESTINATIONS: &str = "where an agent is going";
+
+// Highlights the things related to whatever's being hovered.
+pub struct ShowAssociated {
+    composite: Composite,
+    enabled: BTreeSet<&'static str>,
+    // Built once, since routes don't change while debugging
+    routes_per_stop: HashMap<BusStopID, Vec<BusRouteID>>,
+    adjacent_stops: HashMap<BusStopID, BTreeSet<BusStopID>>,
+    highlighted: Option<(ID, Drawable)>,
+}
+
+impl ShowAssociated {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> ShowAssociated {
+        let mut routes_per_stop: HashMap<BusStopID, Vec<BusRouteID>> = HashMap::new();
+        let mut adjacent_stops: HashMap<BusStopID, BTreeSet<BusStopID>> = HashMap::new();
+        for route in app.primary.map.get_all_bus_routes() {
+            for (idx, bs) in route.stops.iter().enumerate() {
+                routes_per_stop
+                    .entry(*bs)
+                    .or_insert_with(Vec::new)
+                    .push(route.id);
+                // Routes loop around
+                let next = route.stops[(idx + 1) % route.stops.len()];
+                if next != *bs {
+                    adjacent_stops
+                        .entry(*bs)
+                        .or_insert_with(BTreeSet::new)
+                        .insert(next);
+                    adjacent_stops
+                        .entry(next)
+                        .or_insert_with(BTreeSet::new)
+                        .insert(*bs);
+                }
+            }
+        }
+
+        let enabled: BTreeSet<&'static str> = vec![AGENTS].into_iter().collect();
+        ShowAssociated {
+            composite: make_composite(ctx, app, &enabled),
+            enabled,
+            routes_per_stop,
+            adjacent_stops,
+            highlighted: None,
+        }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.composite.event(ctx);
+        let enabled: BTreeSet<&'static str> =
+            vec![AGENTS, BUILDINGS, BUS_STOPS, LANES, DESTINATIONS]
+                .into_iter()
+                .filter(|name| self.composite.is_checked(name))
+                .collect();
+        let changed = enabled != self.enabled;
+        self.enabled = enabled;
+
+        match app.primary.current_selection {
+            Some(ref id) => {
+                if changed
+                    || self
+                        .highlighted
+                        .as_ref()
+                        .map(|(x, _)| x != id)
+                        .unwrap_or(true)
+                {
+                    let batch = self.highlight(ctx, app, id);
+                    self.highlighted = Some((id.clone(), ctx.upload(batch)));
+                }
+            }
+            None => {
+                self.highlighted = None;
+            }
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some((_, ref draw)) = self.highlighted {
+            g.redraw(draw);
+        }
+    }
+
+    pub fn draw_panel(&self, g: &mut GfxCtx) {
+        self.composite.draw(g);
+    }
+
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.composite = make_composite(ctx, app, &self.enabled);
+    }
+
+    fn highlight(&self, ctx: &EventCtx, app: &App, id: &ID) -> GeomBatch {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+        let mut batch = GeomBatch::new();
+        // Outlined at the end
+        let mut objects: Vec<(Color, ID)> = Vec::new();
+        let mut push = |color: Color, id: ID| objects.push((color, id));
+
+        match id {
+            ID::Intersection(i) if self.enabled.contains(AGENTS) => {
+                for a in sim.get_accepted_agents(*i) {
+                    push(Color::PURPLE, ID::from_agent(a));
+                }
+            }
+            ID::Building(b) if self.enabled.contains(BUILDINGS) => {
+                push(Color::CYAN, ID::Lane(map.get_b(*b).sidewalk()));
+                let people: BTreeSet<_> = sim
+                    .trips_from_bldg(*b)
+                    .iter()
+                    .map(|t| sim.trip_to_person(*t))
+                    .collect();
+                for p in people {
+                    for car in sim.get_parked_cars_by_owner(p) {
+                        push(Color::CYAN, ID::Car(car.vehicle.id));
+                    }
+                }
+            }
+            ID::BusStop(bs) if self.enabled.contains(BUS_STOPS) => {
+                for r in self
+                    .routes_per_stop
+                    .get(bs)
+                    .cloned()
+                    .unwrap_or_else(Vec::new)
+                {
+                    let stops = &map.get_br(r).stops;
+                    for (idx, stop) in stops.iter().enumerate() {
+                        push(Color::ORANGE, ID::BusStop(*stop));
+                        let pt1 = map.get_bs(*stop).driving_pos.pt(map);
+                        let pt2 = map
+                            .get_bs(stops[(idx + 1) % stops.len()])
+                            .driving_pos
+                            .pt(map);
+                        if let Some(line) = geom::Line::maybe_new(pt1, pt2) {
+                            batch.push(
+                                Color::ORANGE.alpha(0.5),
+                                line.make_polygons(Distance::meters(2.0)),
+                            );
+                        }
+                    }
+                }
+                for other in self.adjacent_stops.get(bs).into_iter().flatten() {
+                    push(Color::RED, ID::BusStop(*other));
+                }
+            }
+            ID::Lane(l) if self.enabled.contains(LANES) => {
+                for other in map.get_parent(*l).all_lanes() {
+                    if other != *l {
+                        push(Color::YELLOW, ID::Lane(other));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(agent) = id.agent_id() {
+            if let ID::Car(c) = id {
+                if self.enabled.contains(AGENTS) {
+                    for a in sim.get_blocked_by(AgentID::Car(*c)) {
+                        push(Color::PURPLE, ID::from_agent(a));
+                    }
+                }
+            }
+            if self.enabled.contains(DESTINATIONS) {
+                if let Some(trip) = sim.agent_to_trip(agent) {
+                    match sim.trip_info(trip).2 {
+                        TripEndpoint::Bldg(b) => push(Color::GREEN, ID::Building(b)),
+                        TripEndpoint::Border(i, _) => push(Color::GREEN, ID::Intersection(i)),
+                    }
+                }
+            }
+        }
+
+        for (color, id) in objects {
+            // Cars parked in garages aren't drawn
+            if let Some(obj) = app.primary.draw_map.get_obj(
+                id,
+                app,
+                &mut app.primary.draw_map.agents.borrow_mut(),
+                ctx.prerender,
+            ) {
+                batch.push(color, obj.get_outline(map));
+            }
+        }
+        batch
+    }
+}
+
+fn make_composite(ctx: &mut EventCtx, app: &App, enabled: &BTreeSet<&'static str>) -> Composite {
+    let mut col = vec![Line("Show associated").small_heading().draw(ctx)];
+    for name in vec![AGENTS, BUILDINGS, BUS_STOPS, LANES, DESTINATIONS] {
+        col.push(Checkbox::text(ctx, name, None, enabled.contains(name)).margin_above(5));
+    }
+    Composite::new(Widget::col(col).padding(10).bg(app.cs.panel_bg))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
+        .build(ctx)
+}
@@ -0,0 +1,555 @@
+use crate::app::App;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use abstutil::Timer;
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Slider, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Duration, Line as GeomLine, Polygon, Pt2D, Time};
+use map_model::{Map, Traversable, TurnID};
+use sim::{Divergence, Inactive, Sim, TripEndpoint, TripID};
+use std::collections::{BTreeMap, VecDeque};
+
+// How many of the furthest-apart trips can be cycled through
+const NUM_WORST: usize = 10;
+// Stepping forward happens in increments this small, so the tracked trip's history is detailed
+const SAMPLE_INTERVAL: Duration = Duration::const_seconds(5.0);
+// An hour of history
+const MAX_SAMPLES: usize = 720;
+const TIMELINE_WIDTH: f64 = 400.0;
+const TIMELINE_HEIGHT: f64 = 60.0;
+
+// Runs a second copy of the sim, loaded from a savestate, alongside the live one. Both are only
+// stepped from here, and the trips are compared after each step.
+pub struct DivergenceViewer {
+    composite: Composite,
+    other: Sim,
+    divergence: Divergence,
+    // Each displaced trip, from where it is in the live sim to where it is in the other
+    draw: Drawable,
+    // Index into the worst trips
+    current: Option<usize>,
+    write_report: bool,
+    // Where every trip was last seen in the live sim, then the other. Aborted trips are drawn
+    // here.
+    last_seen: (BTreeMap<TripID, Pt2D>, BTreeMap<TripID, Pt2D>),
+    // The last trip cycled to
+    tracked: Option<TrackedTrip>,
+}
+
+// Where one trip was in both sims over time
+struct TrackedTrip {
+    trip: TripID,
+    samples: VecDeque<Sample>,
+    first_different_turn: Option<Time>,
+    timeline: Composite,
+    // The sample picked with the slider, drawn as ghosts
+    scrubbed: Option<(usize, Drawable)>,
+}
+
+// If the trip isn't active in one sim but has finished or aborted, that position is anchored
+// where it ended up.
+struct Sample {
+    time: Time,
+    a: Option<Pt2D>,
+    b: Option<Pt2D>,
+    note: Option<String>,
+}
+
+impl DivergenceViewer {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, name: String, mut other: Sim) -> Box<dyn State> {
+        // Start both at the same time
+        ctx.loading_screen("line up the two sims", |_, timer| {
+            let now = app.primary.sim.time();
+            if other.time() < now {
+                other.timed_step(&app.primary.map, now - other.time(), &mut None, timer);
+            } else if other.time() > now {
+                app.primary.sim.timed_step(
+                    &app.primary.map,
+                    other.time() - now,
+                    &mut app.primary.sim_cb,
+                    timer,
+                );
+            }
+        });
+
+        let divergence = app.primary.sim.compare_trips(&mut other, &app.primary.map);
+        let mut viewer = DivergenceViewer {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Widget::row(vec![
+                        Line(format!("Comparing with {}", name))
+                            .small_heading()
+                            .draw(ctx),
+                        Btn::text_fg("X")
+                            .build(ctx, "close", hotkey(Key::Escape))
+                            .align_right(),
+                    ]),
+                    Text::new().draw(ctx).named("stats"),
+                    Btn::text_fg("step forward 1 minute")
+                        .build_def(ctx, hotkey(Key::M))
+                        .margin_below(5),
+                    Widget::row(vec![
+                        Btn::text_fg("previous divergent trip")
+                            .build_def(ctx, hotkey(Key::LeftBracket))
+                            .margin_right(5),
+                        Btn::text_fg("next divergent trip")
+                            .build_def(ctx, hotkey(Key::RightBracket)),
+                    ])
+                    .margin_below(5),
+                    Checkbox::text(ctx, "append to report", None, false),
+                ])
+                .padding(10)
+                .bg(app.cs.panel_bg),
+            )
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            other,
+            divergence,
+            draw: ctx.upload(GeomBatch::new()),
+            current: None,
+            write_report: false,
+            last_seen: (BTreeMap::new(), BTreeMap::new()),
+            tracked: None,
+        };
+        viewer.refresh(ctx, app);
+        Box::new(viewer)
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.divergence = app
+            .primary
+            .sim
+            .compare_trips(&mut self.other, &app.primary.map);
+        self.current = None;
+
+        let map = &app.primary.map;
+        let a = app
+            .primary
+            .sim
+            .get_trip_positions(map)
+            .canonical_pt_per_trip
+            .clone();
+        let b = self
+            .other
+            .get_trip_positions(map)
+            .canonical_pt_per_trip
+            .clone();
+        let mut batch = GeomBatch::new();
+        for (trip, _) in &self.divergence.displaced {
+            let pt1 = a[trip];
+            let pt2 = b[trip];
+            if let Some(line) = GeomLine::maybe_new(pt1, pt2) {
+                batch.push(Color::RED, line.make_polygons(Distance::meters(1.0)));
+            }
+            batch.push(
+                Color::RED.alpha(0.5),
+                Circle::new(pt2, Distance::meters(3.0)).to_polygon(),
+            );
+        }
+        for (only_in, active, inactive_sim, last_seen) in vec![
+            (
+                &self.divergence.only_in_a,
+                &a,
+                &self.other,
+                &self.last_seen.1,
+            ),
+            (
+                &self.divergence.only_in_b,
+                &b,
+                &app.primary.sim,
+                &self.last_seen.0,
+            ),
+        ] {
+            for (trip, why) in only_in {
+                let pt1 = active[trip];
+                let anchored = anchor(inactive_sim, *trip, *why, last_seen.get(trip).cloned(), map);
+                if let Some((pt2, color, label)) = anchored {
+                    if let Some(line) = GeomLine::maybe_new(pt1, pt2) {
+                        batch.push(color, line.make_polygons(Distance::meters(1.0)));
+                    }
+                    batch.append(
+                        Text::from(Line(label).fg(color))
+                            .render_to_batch(ctx.prerender)
+                            .scale(0.1)
+                            .centered_on(pt2),
+                    );
+                }
+            }
+        }
+        self.draw = ctx.upload(batch);
+        self.last_seen.0.extend(a);
+        self.last_seen.1.extend(b);
+
+        let mut txt = Text::new();
+        for line in self.divergence.describe() {
+            txt.add(Line(line));
+        }
+        self.composite
+            .replace(ctx, "stats", txt.draw(ctx).named("stats"));
+        self.append_report();
+    }
+
+    fn append_report(&self) {
+        if !self.write_report {
+            return;
+        }
+        let path = self.other.divergence_report_path();
+        if let Err(err) = self.divergence.append_to(&path) {
+            println!("Couldn't write {}: {}", path, err);
+        }
+    }
+
+    // Select and warp to one of the worst trips
+    fn cycle(&mut self, ctx: &mut EventCtx, app: &mut App, forwards: bool) {
+        let worst = self.divergence.worst(NUM_WORST);
+        if worst.is_empty() {
+            return;
+        }
+        let idx = match (self.current, forwards) {
+            (None, _) => 0,
+            (Some(idx), true) => (idx + 1) % worst.len(),
+            (Some(idx), false) => (idx + worst.len() - 1) % worst.len(),
+        };
+        self.current = Some(idx);
+
+        let trip = worst[idx];
+        if let Some(pt) = app
+            .primary
+            .sim
+            .get_canonical_pt_per_trip(trip, &app.primary.map)
+            .ok()
+        {
+            ctx.canvas.center_on_map_pt(pt);
+        }
+        app.primary.current_selection =
+            app.primary.sim.trip_to_agent(trip).ok().map(ID::from_agent);
+
+        if self.tracked.as_ref().map(|t| t.trip) != Some(trip) {
+            let mut tracked = TrackedTrip {
+                trip,
+                samples: VecDeque::new(),
+                first_different_turn: None,
+                timeline: Composite::new(Widget::nothing()).build(ctx),
+                scrubbed: None,
+            };
+            // Both sims record every turn from now on, so short turns between samples aren't missed
+            app.primary.sim.track_turns(Some(trip));
+            self.other.track_turns(Some(trip));
+            tracked.sample(&app.primary.sim, &self.other, &app.primary.map);
+            tracked.rebuild(ctx, app);
+            self.tracked = Some(tracked);
+        }
+    }
+}
+
+impl TrackedTrip {
+    fn sample(&mut self, a: &Sim, b: &Sim, map: &Map) {
+        let time = a.time();
+        let prev = self
+            .samples
+            .back()
+            .map(|s| (s.a, s.b))
+            .unwrap_or((None, None));
+        let mut note = None;
+        let mut locate = |sim: &Sim, last_seen: Option<Pt2D>, name: &str| {
+            if let Some(pt) = sim.get_canonical_pt_per_trip(self.trip, map).ok() {
+                return Some(pt);
+            }
+            let (pt, _, label) = anchor(
+                sim,
+                self.trip,
+                sim.why_trip_inactive(self.trip),
+                last_seen,
+                map,
+            )?;
+            note = Some(format!("{} run: {}", name, label));
+            Some(pt)
+        };
+        let pt_a = locate(a, prev.0, "first");
+        let pt_b = locate(b, prev.1, "second");
+        self.samples.push_back(Sample {
+            time,
+            a: pt_a,
+            b: pt_b,
+            note,
+        });
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        if self.first_different_turn.is_none() && turns_differ(a, b, self.trip) {
+            self.first_different_turn = Some(time);
+        }
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.scrubbed = None;
+        self.timeline = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Line(format!("How far apart {} is in the two sims", self.trip))
+                        .small_heading()
+                        .draw(ctx),
+                    Btn::text_fg("X")
+                        .build(ctx, "stop tracking", None)
+                        .align_right(),
+                ]),
+                Widget::draw_batch(ctx, self.render_strip(app)).margin_below(5),
+                Slider::horizontal(ctx, TIMELINE_WIDTH, 10.0, 1.0)
+                    .named("scrub")
+                    .margin_below(5),
+                self.describe_sample(self.samples.len() - 1)
+                    .draw_text(ctx)
+                    .named("scrubbed"),
+            ])
+            .padding(10)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(
+            HorizontalAlignment::Center,
+            VerticalAlignment::BottomAboveOSD,
+        )
+        .build(ctx);
+    }
+
+    // The gap over time. Times when the trip isn't active in one of the sims are left blank.
+    fn render_strip(&self, app: &App) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        batch.push(
+            app.cs.inner_panel,
+            Polygon::rectangle(TIMELINE_WIDTH, TIMELINE_HEIGHT),
+        );
+        let t0 = self.samples[0].time;
+        let total = self.samples.back().unwrap().time - t0;
+        let x = |t: Time| {
+            if total == Duration::ZERO {
+                TIMELINE_WIDTH
+            } else {
+                TIMELINE_WIDTH * ((t - t0) / total)
+            }
+        };
+        let gaps: Vec<Option<Distance>> = self
+            .samples
+            .iter()
+            .map(|s| match (s.a, s.b) {
+                (Some(a), Some(b)) => Some(a.dist_to(b)),
+                _ => None,
+            })
+            .collect();
+        let max_gap = gaps
+            .iter()
+            .filter_map(|g| *g)
+            .max()
+            .unwrap_or(Distance::ZERO)
+            .max(Distance::meters(1.0));
+        let y = |gap: Distance| TIMELINE_HEIGHT * (1.0 - gap / max_gap);
+
+        for idx in 1..self.samples.len() {
+            if let (Some(g1), Some(g2)) = (gaps[idx - 1], gaps[idx]) {
+                let pt1 = Pt2D::new(x(self.samples[idx - 1].time), y(g1));
+                let pt2 = Pt2D::new(x(self.samples[idx].time), y(g2));
+                if let Some(line) = GeomLine::maybe_new(pt1, pt2) {
+                    batch.push(Color::RED, line.make_polygons(Distance::meters(2.0)));
+                }
+            }
+        }
+
+        if let Some(t) = self.first_different_turn {
+            if t >= t0 {
+                batch.push(
+                    Color::ORANGE,
+                    Polygon::rectangle(2.0, TIMELINE_HEIGHT).translate(x(t) - 1.0, 0.0),
+                );
+            }
+        }
+        batch
+    }
+
+    fn describe_sample(&self, idx: usize) -> String {
+        let s = &self.samples[idx];
+        let mut txt = match (s.a, s.b) {
+            (Some(a), Some(b)) => format!("{}: {}", s.time, a.dist_to(b)),
+            _ => format!("{}: not active in both", s.time),
+        };
+        if let Some(ref note) = s.note {
+            txt = format!("{} ({})", txt, note);
+        }
+        if let Some(t) = self.first_different_turn {
+            txt = format!("{}. First different turn at {}", txt, t);
+        }
+        txt
+    }
+
+    // Returns false when the player stops tracking this trip
+    fn event(&mut self, ctx: &mut EventCtx) -> bool {
+        match self.timeline.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "stop tracking" => {
+                    return false;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let idx = (self.timeline.slider("scrub").get_percent() * (self.samples.len() - 1) as f64)
+            .round() as usize;
+        if self.scrubbed.as_ref().map(|(i, _)| *i) != Some(idx) {
+            let s = &self.samples[idx];
+            let mut batch = GeomBatch::new();
+            for (pt, color) in vec![(s.a, Color::BLUE), (s.b, Color::RED)] {
+                if let Some(pt) = pt {
+                    batch.push(
+                        color.alpha(0.5),
+                        Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+                    );
+                }
+            }
+            self.scrubbed = Some((idx, ctx.upload(batch)));
+            let label = self.describe_sample(idx).draw_text(ctx).named("scrubbed");
+            self.timeline.replace(ctx, "scrubbed", label);
+        }
+        true
+    }
+}
+
+// Where to draw a trip that's ended in one sim, with a color and label
+fn anchor(
+    sim: &Sim,
+    trip: TripID,
+    why: Inactive,
+    last_seen: Option<Pt2D>,
+    map: &Map,
+) -> Option<(Pt2D, Color, String)> {
+    match why {
+        Inactive::Finished(t) => {
+            let (_, _, end, _) = sim.trip_info(trip);
+            let pt = match end {
+                TripEndpoint::Bldg(b) => map.get_b(b).label_center,
+                TripEndpoint::Border(i, _) => map.get_i(i).polygon.center(),
+            };
+            Some((pt, Color::GREEN, format!("finished at {}", t)))
+        }
+        // The agent disappears from wherever it was
+        Inactive::Aborted => last_seen.map(|pt| (pt, Color::PURPLE, "aborted".to_string())),
+        Inactive::NotStarted | Inactive::Other => None,
+    }
+}
+
+// Tracking starts partway through the trip, so one run might've already made some turns that the
+// other hasn't reached yet. Line up the two histories on the first turn they share before
+// comparing.
+fn turns_differ(a: &Sim, b: &Sim, trip: TripID) -> bool {
+    let (turns_a, turns_b) = (a.get_tracked_turns(), b.get_tracked_turns());
+    if turns_a.is_empty() || turns_b.is_empty() {
+        return false;
+    }
+    if let Some(idx) = turns_a.iter().position(|t| *t == turns_b[0]) {
+        return turns_a[idx..].iter().zip(turns_b).any(|(x, y)| x != y);
+    }
+    if let Some(idx) = turns_b.iter().position(|t| *t == turns_a[0]) {
+        return turns_a.iter().zip(&turns_b[idx..]).any(|(x, y)| x != y);
+    }
+    // Nothing lines up yet. Unless one run is still on its way to the other's first turn, they've
+    // gone different ways.
+    !upcoming_turns(b, trip).contains(&turns_a[0]) && !upcoming_turns(a, trip).contains(&turns_b[0])
+}
+
+fn upcoming_turns(sim: &Sim, trip: TripID) -> Vec<TurnID> {
+    sim.trip_to_agent(trip)
+        .ok()
+        .and_then(|a| sim.get_path(a))
+        .map(|path| {
+            path.get_steps()
+                .iter()
+                .filter_map(|step| match step.as_traversable() {
+                    Traversable::Turn(t) => Some(t),
+                    Traversable::Lane(_) => None,
+                })
+                .collect()
+        })
+        .unwrap_or_else(Vec::new)
+}
+
+impl State for DivergenceViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    app.primary.sim.track_turns(None);
+                    return Transition::Pop;
+                }
+                "step forward 1 minute" => {
+                    let other = &mut self.other;
+                    let tracked = &mut self.tracked;
+                    ctx.loading_screen("step both sims", |_, timer| {
+                        let end = app.primary.sim.time() + Duration::minutes(1);
+                        while app.primary.sim.time() < end {
+                            let before = app.primary.sim.time();
+                            step_both(app, other, SAMPLE_INTERVAL, timer);
+                            // Something like a due intervention halts the sim until the player
+                            // deals with it
+                            if app.primary.sim.time() == before {
+                                break;
+                            }
+                            if let Some(ref mut t) = *tracked {
+                                t.sample(&app.primary.sim, other, &app.primary.map);
+                            }
+                        }
+                    });
+                    if let Some(ref mut t) = self.tracked {
+                        t.rebuild(ctx, app);
+                    }
+                    self.refresh(ctx, app);
+                    app.recalculate_current_selection(ctx);
+                }
+                "previous divergent trip" => {
+                    self.cycle(ctx, app, false);
+                }
+                "next divergent trip" => {
+                    self.cycle(ctx, app, true);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+        if let Some(ref mut t) = self.tracked {
+            if !t.event(ctx) {
+                self.tracked = None;
+                app.primary.sim.track_turns(None);
+                self.other.track_turns(None);
+            }
+        }
+        if self.composite.is_checked("append to report") != self.write_report {
+            self.write_report = !self.write_report;
+            self.append_report();
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+        if let Some(ref t) = self.tracked {
+            if let Some((_, ref draw)) = t.scrubbed {
+                g.redraw(draw);
+            }
+            t.timeline.draw(g);
+        }
+    }
+}
+
+fn step_both(app: &mut App, other: &mut Sim, dt: Duration, timer: &mut Timer) {
+    app.primary
+        .sim
+        .timed_step(&app.primary.map, dt, &mut app.primary.sim_cb, timer);
+    other.timed_step(&app.primary.map, dt, &mut None, timer);
+}
@@ -0,0 +1,301 @@
+use crate::app::App;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use abstutil::Severity;
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text,
+    VerticalAlignment, Widget,
+};
+use geom::Pt2D;
+use map_model::Map;
+use serde_json::{json, Value};
+use sim::{AgentID, Sim};
+use std::collections::BTreeSet;
+
+// Everything known about one object, for debugging importer problems: the full struct, raw OSM
+// tags, geometry, referenced IDs, and the sim state of agents.
+pub struct ObjectDump {
+    composite: Composite,
+    id: ID,
+    dump: Value,
+    // Paths of the expanded nodes
+    expanded: BTreeSet<String>,
+}
+
+impl ObjectDump {
+    pub fn new(ctx: &mut EventCtx, app: &App, id: ID) -> Box<dyn State> {
+        let dump = dump_object(&id, &app.primary.map, &app.primary.sim);
+        let mut state = ObjectDump {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            id,
+            dump,
+            // The struct itself is usually what's interesting
+            expanded: vec!["object".to_string()].into_iter().collect(),
+        };
+        state.composite = state.make_composite(ctx, app);
+        Box::new(state)
+    }
+
+    fn make_composite(&self, ctx: &mut EventCtx, app: &App) -> Composite {
+        let mut col = vec![
+            Widget::row(vec![
+                Line(format!("{:?}", self.id)).small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                Btn::text_fg("write to log")
+                    .build_def(ctx, hotkey(Key::W))
+                    .margin_right(10),
+                Btn::text_fg("copy to clipboard").build_def(ctx, hotkey(Key::C)),
+            ])
+            .margin_below(10),
+        ];
+        if let Value::Object(ref fields) = self.dump {
+            for (key, value) in fields {
+                tree_rows(ctx, key.clone(), key, value, 0, &self.expanded, &mut col);
+            }
+        }
+        Composite::new(Widget::col(col).padding(10).bg(app.cs.panel_bg))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .max_size_percent(40, 85)
+            .build(ctx)
+    }
+}
+
+impl State for ObjectDump {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "write to log" => {
+                    abstutil::log(
+                        Severity::Debug,
+                        serde_json::to_string_pretty(&self.dump).unwrap(),
+                    );
+                    app.toasts
+                        .notify(Severity::Info, format!("Wrote {:?} to the log", self.id));
+                }
+                "copy to clipboard" => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        use clipboard::{ClipboardContext, ClipboardProvider};
+
+                        let mut cb: ClipboardContext = ClipboardProvider::new().unwrap();
+                        cb.set_contents(serde_json::to_string_pretty(&self.dump).unwrap())
+                            .unwrap();
+                    }
+                }
+                path => {
+                    if !self.expanded.remove(path) {
+                        self.expanded.insert(path.to_string());
+                    }
+                    let mut new = self.make_composite(ctx, app);
+                    new.restore(ctx, &self.composite);
+                    self.composite = new;
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+}
+
+// Objects and arrays are buttons that toggle their children; the button's action is the node's
+// path from the root.
+fn tree_rows(
+    ctx: &EventCtx,
+    path: String,
+    key: &str,
+    value: &Value,
+    depth: usize,
+    expanded: &BTreeSet<String>,
+    col: &mut Vec<Widget>,
+) {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(ref fields) => fields.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(ref list) => list
+            .iter()
+            .enumerate()
+            .map(|(idx, v)| (idx.to_string(), v))
+            .collect(),
+        _ => {
+            col.push(
+                Text::from(Line(format!("{}: {}", key, value)))
+                    .draw(ctx)
+                    .margin_left(20 * depth),
+            );
+            return;
+        }
+    };
+    let open = expanded.contains(&path);
+    col.push(
+        Btn::plaintext(format!(
+            "{} {} ({})",
+            if open { "▼" } else { "▶" },
+            key,
+            children.len()
+        ))
+        .build(ctx, path.clone(), None)
+        .margin_left(20 * depth),
+    );
+    if open {
+        for (k, v) in children {
+            tree_rows(
+                ctx,
+                format!("{}/{}", path, k),
+                &k,
+                v,
+                depth + 1,
+                expanded,
+                col,
+            );
+        }
+    }
+}
+
+fn dump_object(id: &ID, map: &Map, sim: &Sim) -> Value {
+    let mut dump = serde_json::Map::new();
+    let mut refs: Vec<String> = Vec::new();
+    let mut geometry: Vec<Pt2D> = Vec::new();
+    match id {
+        ID::Road(r) => {
+            let road = map.get_r(*r);
+            dump.insert("object".to_string(), to_value(road));
+            dump.insert("osm tags".to_string(), to_value(&road.osm_tags));
+            geometry.extend(road.center_pts.points());
+            refs.push(road.src_i.to_string());
+            refs.push(road.dst_i.to_string());
+            refs.extend(road.all_lanes().into_iter().map(|l| l.to_string()));
+            refs.extend(road.turn_restrictions.iter().map(|(_, r)| r.to_string()));
+        }
+        ID::Lane(l) => {
+            let lane = map.get_l(*l);
+            dump.insert("object".to_string(), to_value(lane));
+            dump.insert(
+                "osm tags".to_string(),
+                to_value(&map.get_parent(*l).osm_tags),
+            );
+            geometry.extend(lane.lane_center_pts.points());
+            refs.push(lane.parent.to_string());
+            refs.push(lane.src_i.to_string());
+            refs.push(lane.dst_i.to_string());
+            refs.extend(map.get_turns_from_lane(*l).iter().map(|t| t.id.to_string()));
+            refs.extend(lane.building_paths.iter().map(|b| b.to_string()));
+            refs.extend(lane.bus_stops.iter().map(|bs| bs.to_string()));
+        }
+        ID::Intersection(i) => {
+            let i = map.get_i(*i);
+            dump.insert("object".to_string(), to_value(i));
+            if let Some(signal) = map.maybe_get_traffic_signal(i.id) {
+                dump.insert("traffic signal".to_string(), to_value(signal));
+            }
+            if let Some(ss) = map.maybe_get_stop_sign(i.id) {
+                dump.insert("stop sign".to_string(), to_value(ss));
+            }
+            geometry.extend(i.polygon.points());
+            refs.extend(i.roads.iter().map(|r| r.to_string()));
+            refs.extend(i.incoming_lanes.iter().map(|l| l.to_string()));
+            refs.extend(i.outgoing_lanes.iter().map(|l| l.to_string()));
+        }
+        ID::Building(b) => {
+            let b = map.get_b(*b);
+            dump.insert("object".to_string(), to_value(b));
+            geometry.extend(b.polygon.points());
+            refs.push(b.sidewalk().to_string());
+        }
+        ID::ParkingLot(pl) => {
+            let pl = map.get_pl(*pl);
+            dump.insert("object".to_string(), to_value(pl));
+            geometry.extend(pl.polygon.points());
+            refs.push(pl.driving_pos.lane().to_string());
+            refs.push(pl.sidewalk_pos.lane().to_string());
+        }
+        ID::BusStop(bs) => {
+            let bs = map.get_bs(*bs);
+            dump.insert("object".to_string(), to_value(bs));
+            geometry.push(bs.driving_pos.pt(map));
+            geometry.push(bs.sidewalk_pos.pt(map));
+            refs.push(bs.driving_pos.lane().to_string());
+            refs.push(bs.sidewalk_pos.lane().to_string());
+            refs.extend(
+                map.get_routes_serving_stop(bs.id)
+                    .into_iter()
+                    .map(|r| format!("{} ({})", r.id, r.name)),
+            );
+        }
+        ID::Area(a) => {
+            let a = map.get_a(*a);
+            dump.insert("object".to_string(), to_value(a));
+            dump.insert("osm tags".to_string(), to_value(&a.osm_tags));
+            geometry.extend(a.polygon.points());
+        }
+        ID::Car(_) | ID::Pedestrian(_) => {
+            let agent = id.agent_id().unwrap();
+            dump.insert("sim state".to_string(), agent_state(agent, sim));
+            if let Some(pt) = sim.canonical_pt_for_agent(agent, map) {
+                geometry.push(pt);
+            }
+            if let Some(t) = sim.agent_to_trip(agent) {
+                refs.push(t.to_string());
+                refs.push(sim.trip_to_person(t).to_string());
+                dump.insert(
+                    "trip phases".to_string(),
+                    Value::Array(
+                        sim.get_analytics()
+                            .get_trip_phases(t, map)
+                            .into_iter()
+                            .map(|p| Value::String(format!("{:?}", p)))
+                            .collect(),
+                    ),
+                );
+            }
+        }
+        ID::PedCrowd(members) => {
+            dump.insert(
+                "sim state".to_string(),
+                Value::Array(
+                    members
+                        .iter()
+                        .map(|p| agent_state(AgentID::Pedestrian(*p), sim))
+                        .collect(),
+                ),
+            );
+            refs.extend(members.iter().map(|p| p.to_string()));
+        }
+    }
+    dump.insert(
+        "geometry".to_string(),
+        Value::Array(
+            geometry
+                .into_iter()
+                .map(|pt| json!([pt.x(), pt.y()]))
+                .collect(),
+        ),
+    );
+    dump.insert(
+        "references".to_string(),
+        Value::Array(refs.into_iter().map(Value::String).collect()),
+    );
+    Value::Object(dump)
+}
+
+fn agent_state(agent: AgentID, sim: &Sim) -> Value {
+    sim.agent_state_json(agent)
+        .map(|json| serde_json::from_str(&json).unwrap())
+        .unwrap_or_else(|| Value::String(format!("{} has no state; maybe it's parked", agent)))
+}
+
+fn to_value<T: serde::Serialize>(obj: &T) -> Value {
+    serde_json::to_value(obj).unwrap()
+}
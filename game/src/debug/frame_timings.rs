@@ -0,0 +1,104 @@
+use sim::Sim;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Stop recording after this many frames (about 10 minutes at 30 FPS) or bytes, whichever comes
+// first.
+const MAX_FRAMES: usize = 18_000;
+const MAX_BYTES: usize = 10 * 1024 * 1024;
+
+// When players report slowness, ask them to turn this on from debug mode. Each frame (all of the
+// events since the last draw, then the draw) becomes one row in a CSV file that can be plotted
+// offline. Drawing only has &App, hence the RefCell.
+pub struct FrameTimings {
+    path: String,
+    inner: RefCell<Inner>,
+}
+
+struct Inner {
+    file: BufWriter<File>,
+    frames: usize,
+    bytes: usize,
+    // Set when the caps are hit or writing fails. Whoever owns this should notice and drop it.
+    done: bool,
+
+    // Seconds, accumulated since the last draw
+    event: f64,
+    sim: f64,
+    culling: f64,
+}
+
+impl FrameTimings {
+    pub fn start(path: String) -> std::io::Result<FrameTimings> {
+        let mut file = BufWriter::new(File::create(&path)?);
+        let header = "frame,sim_time_seconds,active_agents,event_ms,sim_ms,culling_ms,draw_ms\n";
+        file.write_all(header.as_bytes())?;
+        Ok(FrameTimings {
+            path,
+            inner: RefCell::new(Inner {
+                file,
+                frames: 0,
+                bytes: header.len(),
+                done: false,
+                event: 0.0,
+                sim: 0.0,
+                culling: 0.0,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.inner.borrow().done
+    }
+
+    // Includes any time spent stepping the sim; that's subtracted later.
+    pub fn record_event(&self, seconds: f64) {
+        self.inner.borrow_mut().event += seconds;
+    }
+
+    pub fn record_sim_step(&self, seconds: f64) {
+        self.inner.borrow_mut().sim += seconds;
+    }
+
+    // Only happens when zoomed in; unzoomed, agents are culled and drawn in one step.
+    pub fn record_culling(&self, seconds: f64) {
+        self.inner.borrow_mut().culling += seconds;
+    }
+
+    // Culling is part of drawing, so it's also subtracted.
+    pub fn finish_frame(&self, draw_seconds: f64, sim: &Sim) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.done {
+            return;
+        }
+        let active_agents: usize = sim.num_trips().2.values().sum();
+        let row = format!(
+            "{},{},{},{:.3},{:.3},{:.3},{:.3}\n",
+            inner.frames,
+            sim.time().inner_seconds(),
+            active_agents,
+            1000.0 * (inner.event - inner.sim).max(0.0),
+            1000.0 * inner.sim,
+            1000.0 * inner.culling,
+            1000.0 * (draw_seconds - inner.culling).max(0.0),
+        );
+        inner.frames += 1;
+        inner.bytes += row.len();
+        inner.event = 0.0;
+        inner.sim = 0.0;
+        inner.culling = 0.0;
+
+        if let Err(err) = inner.file.write_all(row.as_bytes()) {
+            println!("Couldn't write to {}: {}", self.path, err);
+            inner.done = true;
+        }
+        if inner.frames >= MAX_FRAMES || inner.bytes >= MAX_BYTES {
+            inner.done = true;
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::app::App;
+use crate::common::{find_warp_ids, warp_to_id};
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use abstutil::{prettyprint_usize, LogLine, Severity};
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, TextSpan, VerticalAlignment, Widget,
+};
+use std::collections::BTreeSet;
+
+// Rendering thousands of lines of text is slow, so only show the most recent matches.
+const MAX_DISPLAYED_LINES: usize = 200;
+
+// Each crate producing logs gets one of these, in order of first appearance.
+const TARGET_COLORS: [Color; 6] = [
+    Color::CYAN,
+    Color::ORANGE,
+    Color::PINK,
+    Color::GREEN,
+    Color::rgb_f(0.6, 0.6, 1.0),
+    Color::rgb_f(0.8, 0.8, 0.4),
+];
+
+pub struct LogViewer {
+    composite: Composite,
+    view: View,
+}
+
+struct View {
+    show: BTreeSet<Severity>,
+    filter: String,
+    // Crates that've been unchecked. Everything else, including crates that haven't logged
+    // anything yet, is shown.
+    hide_targets: BTreeSet<String>,
+    // Every crate with retained logs, in order of first appearance
+    targets: Vec<String>,
+    // Compared against abstutil::num_logs_recorded() to notice new lines
+    num_recorded: usize,
+
+    // The LogLine IDs currently displayed, and which one the cursor is on
+    displayed: Vec<usize>,
+    selected: Option<usize>,
+}
+
+impl LogViewer {
+    // If a LogLine ID is specified, start with it selected.
+    pub fn new(ctx: &mut EventCtx, app: &App, selected: Option<usize>) -> Box<dyn State> {
+        let mut view = View {
+            show: Severity::all().into_iter().collect(),
+            filter: String::new(),
+            hide_targets: BTreeSet::new(),
+            targets: Vec::new(),
+            num_recorded: 0,
+            displayed: Vec::new(),
+            selected,
+        };
+        let mut composite = view.make_composite(ctx, app);
+        if view.selected.is_some() {
+            composite.scroll_to_member(ctx, "selected line".to_string());
+        }
+        Box::new(LogViewer { composite, view })
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut new = self.view.make_composite(ctx, app);
+        new.restore(ctx, &self.composite);
+        self.composite = new;
+    }
+}
+
+impl View {
+    fn matches(&self, line: &LogLine) -> bool {
+        self.show.contains(&line.severity)
+            && !self.hide_targets.contains(line.crate_name())
+            && (self.filter.is_empty() || line.msg.contains(&self.filter))
+    }
+
+    fn make_composite(&mut self, ctx: &mut EventCtx, app: &App) -> Composite {
+        self.num_recorded = abstutil::num_logs_recorded();
+        let (all, _) = abstutil::filter_logs(|_| true);
+        self.targets.clear();
+        for line in &all {
+            if !self.targets.iter().any(|t| t == line.crate_name()) {
+                self.targets.push(line.crate_name().to_string());
+            }
+        }
+        let targets = &self.targets;
+
+        let matches: Vec<&LogLine> = all.iter().filter(|line| self.matches(line)).collect();
+        let suppressed = all.len() - matches.len();
+
+        let skip = matches.len().saturating_sub(MAX_DISPLAYED_LINES);
+        self.displayed = matches.iter().skip(skip).map(|line| line.id).collect();
+        if let Some(id) = self.selected {
+            if !self.displayed.contains(&id) {
+                self.selected = None;
+            }
+        }
+
+        // The selected line is its own widget, so the panel can scroll to it.
+        let mut lines = Vec::new();
+        let mut txt = Text::new();
+        for line in matches.iter().skip(skip) {
+            let color = target_color(targets, line.crate_name());
+            let spans = styled_line(line, color);
+            if self.selected == Some(line.id) {
+                if !txt.is_empty() {
+                    lines.push(txt.draw(ctx));
+                }
+                let mut selected = Text::new();
+                let mut iter = spans.into_iter();
+                selected.add_highlighted(iter.next().unwrap(), app.cs.hovering);
+                selected.append_all(iter.collect());
+                lines.push(selected.draw(ctx).named("selected line"));
+                txt = Text::new();
+            } else {
+                txt.add_appended(spans);
+            }
+        }
+        if matches.is_empty() {
+            txt.add(Line("No matching log lines").secondary());
+        }
+        if !txt.is_empty() {
+            lines.push(txt.draw(ctx));
+        }
+
+        let mut summary = format!(
+            "{} lines hidden by the filters",
+            prettyprint_usize(suppressed)
+        );
+        if skip > 0 {
+            summary = format!(
+                "{}; only showing the last {} of {} matches",
+                summary,
+                prettyprint_usize(MAX_DISPLAYED_LINES),
+                prettyprint_usize(matches.len())
+            );
+        }
+
+        Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Line("Logs").small_heading().draw(ctx).margin_right(10),
+                    Btn::text_fg("save these lines to a file").build_def(ctx, None),
+                    Btn::text_fg("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ])
+                .margin_below(10),
+                Widget::row(
+                    Severity::all()
+                        .into_iter()
+                        .map(|severity| {
+                            Checkbox::text(
+                                ctx,
+                                severity_label(severity),
+                                None,
+                                self.show.contains(&severity),
+                            )
+                            .margin_right(10)
+                        })
+                        .collect(),
+                )
+                .margin_below(10),
+                Widget::row(
+                    targets
+                        .iter()
+                        .map(|target| {
+                            Checkbox::colored(
+                                ctx,
+                                target,
+                                target_color(targets, target),
+                                !self.hide_targets.contains(target),
+                            )
+                            .margin_right(10)
+                        })
+                        .collect(),
+                )
+                .margin_below(10),
+                Widget::row(vec![
+                    "Only show lines containing:"
+                        .draw_text(ctx)
+                        .centered_vert()
+                        .margin_right(10),
+                    Widget::text_entry(ctx, self.filter.clone(), true).named("filter"),
+                ])
+                .margin_below(10),
+                summary.draw_text(ctx).margin_below(10),
+                Text::from(
+                    Line(
+                        "Use the up/down arrow keys to pick a line, then Enter to warp to the \
+                         first object it mentions",
+                    )
+                    .secondary(),
+                )
+                .draw(ctx)
+                .margin_below(10),
+                Widget::col(lines),
+            ])
+            .padding(10)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .max_size_percent(80, 80)
+        .build(ctx)
+    }
+
+    // Unlike the panel, this includes every matching line, not just the most recent ones.
+    fn save(&self) -> Box<dyn State> {
+        let (lines, _) = abstutil::filter_logs(|line| self.matches(line));
+        let path = abstutil::path_log_file(&format!(
+            "filtered_{}",
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ));
+        let mut contents = String::new();
+        for line in &lines {
+            contents.push_str(&format!("{}\n", line));
+        }
+        match std::fs::write(&path, contents) {
+            Ok(()) => msg(
+                "Saved logs",
+                vec![format!(
+                    "Wrote {} lines to {}",
+                    prettyprint_usize(lines.len()),
+                    path
+                )],
+            ),
+            Err(err) => msg("Error", vec![format!("Couldn't write {}: {}", path, err)]),
+        }
+    }
+
+    fn move_cursor(&mut self, up: bool) {
+        if self.displayed.is_empty() {
+            return;
+        }
+        let idx = match self
+            .selected
+            .and_then(|id| self.displayed.iter().position(|x| *x == id))
+        {
+            // Start from the newest line, since that's what's usually interesting
+            None => self.displayed.len() - 1,
+            Some(idx) if up => idx.saturating_sub(1),
+            Some(idx) => (idx + 1).min(self.displayed.len() - 1),
+        };
+        self.selected = Some(self.displayed[idx]);
+    }
+}
+
+impl State for LogViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save these lines to a file" => {
+                    return Transition::Push(self.view.save());
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let mut changed = false;
+        for severity in Severity::all() {
+            let enabled = self.composite.is_checked(severity_label(severity));
+            if enabled != self.view.show.contains(&severity) {
+                if enabled {
+                    self.view.show.insert(severity);
+                } else {
+                    self.view.show.remove(&severity);
+                }
+                changed = true;
+            }
+        }
+        for target in self.view.targets.clone() {
+            let enabled = self.composite.is_checked(&target);
+            if enabled == self.view.hide_targets.contains(&target) {
+                if enabled {
+                    self.view.hide_targets.remove(&target);
+                } else {
+                    self.view.hide_targets.insert(target);
+                }
+                changed = true;
+            }
+        }
+        let filter = self.composite.text_box("filter");
+        if filter != self.view.filter {
+            self.view.filter = filter;
+            changed = true;
+        }
+
+        if ctx
+            .input
+            .key_pressed(Key::UpArrow, "select the previous line")
+        {
+            self.view.move_cursor(true);
+            changed = true;
+        }
+        if ctx
+            .input
+            .key_pressed(Key::DownArrow, "select the next line")
+        {
+            self.view.move_cursor(false);
+            changed = true;
+        }
+        if let Some(id) = self.view.selected {
+            if ctx
+                .input
+                .key_pressed(Key::Enter, "warp to the object in this line")
+            {
+                let (lines, _) = abstutil::filter_logs(|line| line.id == id);
+                if let Some(line) = lines.into_iter().next() {
+                    for (code, idx) in find_warp_ids(&line.msg) {
+                        if let Some(t) = warp_to_id(ctx, app, code, idx) {
+                            return t;
+                        }
+                    }
+                }
+            }
+        }
+
+        if changed || self.view.num_recorded != abstutil::num_logs_recorded() {
+            self.refresh(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "errors",
+        Severity::Warn => "warnings",
+        Severity::Info => "info",
+        Severity::Debug => "debug",
+    }
+}
+
+fn target_color(targets: &[String], target: &str) -> Color {
+    let idx = targets.iter().position(|t| t == target).unwrap_or(0);
+    TARGET_COLORS[idx % TARGET_COLORS.len()]
+}
+
+fn styled_line(line: &LogLine, target_color: Color) -> Vec<TextSpan> {
+    let severity_color = match line.severity {
+        Severity::Error => Color::RED,
+        Severity::Warn => Color::YELLOW,
+        Severity::Info => Color::WHITE,
+        Severity::Debug => Color::grey(0.6),
+    };
+    let mut msg = Line(format!(" {}", line.msg));
+    if line.severity == Severity::Debug {
+        msg = msg.fg(severity_color);
+    }
+    vec![
+        Line(format!("[{}] ", line.severity.describe())).fg(severity_color),
+        Line(&line.target).fg(target_color),
+        msg,
+    ]
+}
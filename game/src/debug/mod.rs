@@ -1,21 +1,32 @@
+mod annotations;
+mod associated;
+mod divergence;
+mod dump;
 mod floodfill;
+mod frame_timings;
+mod logs;
 mod objects;
 mod polygons;
+mod raw_osm;
+
+pub use self::frame_timings::FrameTimings;
+pub use self::logs::LogViewer;
 
 use crate::app::{App, ShowLayers, ShowObject};
 use crate::common::{tool_panel, CommonState, ContextualActions};
 use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
-use crate::helpers::ID;
+use crate::helpers::{saved_file_choice, ID};
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::render::{calculate_corners, DrawOptions};
-use abstutil::Timer;
+use crate::sandbox::{GameplayMode, SandboxMode};
+use abstutil::{Severity, Timer};
 use ezgui::{
     hotkey, lctrl, Btn, Checkbox, Color, Composite, Drawable, EventCtx, EventLoopMode, GeomBatch,
     GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text, VerticalAlignment, Widget, Wizard,
 };
 use geom::Pt2D;
-use map_model::{ControlTrafficSignal, NORMAL_LANE_THICKNESS};
-use sim::{AgentID, Sim, TripID};
+use map_model::{ControlTrafficSignal, CANNED_SYNTHETIC_MAPS, NORMAL_LANE_THICKNESS};
+use sim::{Sim, TripID};
 use std::collections::HashSet;
 
 pub struct DebugMode {
@@ -27,54 +38,20 @@ pub struct DebugMode {
     layers: ShowLayers,
     search_results: Option<SearchResults>,
     all_routes: Option<(usize, Drawable)>,
+    // The recording might stop on its own, so track the checkbox separately
+    record_frame_timings: bool,
+    // Only loaded the first time it's shown, then kept around
+    raw_osm: Option<Result<raw_osm::RawOsmOverlay, String>>,
+    show_raw_osm: bool,
 
-    highlighted_agents: Option<(ID, Drawable)>,
+    associated: associated::ShowAssociated,
+    annotations: annotations::ShowAnnotations,
 }
 
 impl DebugMode {
     pub fn new(ctx: &mut EventCtx, app: &App) -> DebugMode {
         DebugMode {
-            composite: Composite::new(
-                Widget::col(vec![
-                    Widget::row(vec![
-                        Line("Debug Mode").small_heading().draw(ctx),
-                        Btn::text_fg("X")
-                            .build(ctx, "close", hotkey(Key::Escape))
-                            .align_right(),
-                    ]),
-                    Text::new().draw(ctx).named("current info"),
-                    Checkbox::text(ctx, "show buildings", hotkey(Key::Num1), true).margin_below(5),
-                    Checkbox::text(ctx, "show intersections", hotkey(Key::Num2), true)
-                        .margin_below(5),
-                    Checkbox::text(ctx, "show lanes", hotkey(Key::Num3), true).margin_below(5),
-                    Checkbox::text(ctx, "show areas", hotkey(Key::Num4), true).margin_below(5),
-                    Checkbox::text(ctx, "show labels", hotkey(Key::Num5), false).margin_below(5),
-                    Checkbox::text(ctx, "show route for all agents", hotkey(Key::R), false)
-                        .margin_below(5),
-                    Widget::col(
-                        vec![
-                            (lctrl(Key::H), "unhide everything"),
-                            (None, "screenshot everything"),
-                            (hotkey(Key::Slash), "search OSM metadata"),
-                            (lctrl(Key::Slash), "clear OSM search results"),
-                            (hotkey(Key::O), "save sim state"),
-                            (hotkey(Key::Y), "load previous sim state"),
-                            (hotkey(Key::U), "load next sim state"),
-                            (None, "pick a savestate to load"),
-                            (None, "find bad traffic signals"),
-                        ]
-                        .into_iter()
-                        .map(|(key, action)| {
-                            Btn::text_fg(action).build_def(ctx, key).margin_below(5)
-                        })
-                        .collect(),
-                    ),
-                ])
-                .padding(10)
-                .bg(app.cs.panel_bg),
-            )
-            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
-            .build(ctx),
+            composite: make_composite(ctx, app, None),
             common: CommonState::new(),
             tool_panel: tool_panel(ctx, app),
             objects: objects::ObjectDebugger::new(),
@@ -82,7 +59,11 @@ impl DebugMode {
             layers: ShowLayers::new(),
             search_results: None,
             all_routes: None,
-            highlighted_agents: None,
+            record_frame_timings: app.frame_timings.is_some(),
+            raw_osm: None,
+            show_raw_osm: false,
+            associated: associated::ShowAssociated::new(ctx, app),
+            annotations: annotations::ShowAnnotations::new(ctx, app),
         }
     }
 
@@ -103,6 +84,11 @@ impl DebugMode {
                 abstutil::prettyprint_usize(n)
             )));
         }
+        if self.show_raw_osm {
+            if let Some(Ok(ref overlay)) = self.raw_osm {
+                txt.add(Line(overlay.describe()));
+            }
+        }
         self.composite
             .replace(ctx, "current info", txt.draw(ctx).named("current info"));
     }
@@ -181,6 +167,9 @@ impl State for DebugMode {
                 "pick a savestate to load" => {
                     return Transition::Push(WizardState::new(Box::new(load_savestate)));
                 }
+                "compare with a savestate" => {
+                    return Transition::Push(WizardState::new(Box::new(compare_with_savestate)));
+                }
                 "unhide everything" => {
                     self.hidden.clear();
                     app.primary.current_selection = app.calculate_current_selection(
@@ -213,6 +202,19 @@ impl State for DebugMode {
                 "find bad traffic signals" => {
                     find_bad_signals(app);
                 }
+                "load a synthetic map" => {
+                    return Transition::Push(WizardState::new(Box::new(load_synthetic_map)));
+                }
+                "list annotations" => {
+                    return Transition::Push(annotations::AnnotationList::new(
+                        ctx,
+                        app,
+                        self.annotations.annotations.clone(),
+                    ));
+                }
+                "show logs" => {
+                    return Transition::Push(logs::LogViewer::new(ctx, app, None));
+                }
                 _ => unreachable!(),
             },
             None => {}
@@ -234,48 +236,40 @@ impl State for DebugMode {
                 self.reset_info(ctx);
             }
         }
-
-        match app.primary.current_selection {
-            Some(ID::Intersection(_)) | Some(ID::Car(_)) => {
-                let id = app.primary.current_selection.clone().unwrap();
-                if self
-                    .highlighted_agents
-                    .as_ref()
-                    .map(|(x, _)| *x != id)
-                    .unwrap_or(true)
-                {
-                    let mut batch = GeomBatch::new();
-                    let agents = match id {
-                        ID::Intersection(i) => app.primary.sim.get_accepted_agents(i),
-                        ID::Car(c) => app.primary.sim.get_blocked_by(AgentID::Car(c)),
-                        _ => unreachable!(),
-                    };
-                    for a in agents {
-                        batch.push(
-                            Color::PURPLE,
-                            app.primary
-                                .draw_map
-                                .get_obj(
-                                    ID::from_agent(a),
-                                    app,
-                                    &mut app.primary.draw_map.agents.borrow_mut(),
-                                    ctx.prerender,
-                                )
-                                .unwrap()
-                                .get_outline(&app.primary.map),
-                        );
-                    }
-                    self.highlighted_agents = Some((id, ctx.upload(batch)));
+        if self.composite.is_checked("show raw OSM geometry") != self.show_raw_osm {
+            self.show_raw_osm = !self.show_raw_osm;
+            if self.show_raw_osm && self.raw_osm.is_none() {
+                let path = app
+                    .primary
+                    .current_flags
+                    .raw_osm
+                    .clone()
+                    .unwrap_or_else(|| abstutil::path_raw_map(app.primary.map.get_name()));
+                let result = ctx.loading_screen("load raw OSM geometry", |ctx, timer| {
+                    raw_osm::RawOsmOverlay::load(ctx, app, path, timer)
+                });
+                if let Err(ref err) = result {
+                    app.toasts.notify(Severity::Error, err.clone());
                 }
+                self.raw_osm = Some(result);
             }
-            _ => {
-                self.highlighted_agents = None;
-            }
+            self.reset_info(ctx);
+        }
+        if self.composite.is_checked("record frame timings") != self.record_frame_timings {
+            self.record_frame_timings = !self.record_frame_timings;
+            toggle_frame_timings(app, self.record_frame_timings);
         }
 
+        self.associated.event(ctx, app);
         self.objects.event(ctx);
 
-        if let Some(t) = self.common.event(ctx, app, &mut Actions {}) {
+        if let Some(t) = self.common.event(
+            ctx,
+            app,
+            &mut Actions {
+                annotations: &self.annotations,
+            },
+        ) {
             return t;
         }
         match self.tool_panel.event(ctx, app) {
@@ -300,8 +294,12 @@ impl State for DebugMode {
         if let Some(ref results) = self.search_results {
             g.redraw(&results.draw);
         }
-        if let Some((_, ref draw)) = self.highlighted_agents {
-            g.redraw(draw);
+        self.associated.draw(g);
+        self.annotations.draw(g, app);
+        if self.show_raw_osm {
+            if let Some(Ok(ref overlay)) = self.raw_osm {
+                overlay.draw(g);
+            }
         }
 
         self.objects.draw(g, app);
@@ -311,10 +309,18 @@ impl State for DebugMode {
 
         if !g.is_screencap() {
             self.composite.draw(g);
+            self.associated.draw_panel(g);
             self.common.draw(g, app);
             self.tool_panel.draw(g);
         }
     }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.composite = make_composite(ctx, app, Some(&self.composite));
+        self.reset_info(ctx);
+        self.tool_panel = tool_panel(ctx, app);
+        self.associated.recreate_panel(ctx, app);
+    }
 }
 
 impl ShowObject for DebugMode {
@@ -384,18 +390,66 @@ struct SearchResults {
 }
 
 fn load_savestate(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
-    let ss = wiz.wrap(ctx).choose_string("Load which savestate?", || {
-        abstutil::list_all_objects(app.primary.sim.save_dir())
+    let (_, path) = wiz.wrap(ctx).choose("Load which savestate?", || {
+        abstutil::list_objects(app.primary.sim.save_dir())
+            .into_iter()
+            .map(|m| saved_file_choice::<Sim, _>(&m, m.path.clone()))
+            .collect()
     })?;
-    // TODO Oh no, we have to do path construction here :(
-    let ss_path = format!("{}/{}.bin", app.primary.sim.save_dir(), ss);
 
-    ctx.loading_screen("load savestate", |ctx, mut timer| {
-        app.primary.sim = Sim::load_savestate(ss_path, &app.primary.map, &mut timer)
-            .expect("Can't load savestate");
+    let result = ctx.loading_screen("load savestate", |ctx, mut timer| {
+        let sim = Sim::load_savestate(path, &app.primary.map, &mut timer)?;
+        app.primary.sim = sim;
         app.recalculate_current_selection(ctx);
+        Ok(())
+    });
+    match result {
+        Ok(()) => Some(Transition::Pop),
+        Err(err) => Some(Transition::Replace(msg("Error", vec![err]))),
+    }
+}
+
+// The savestate should come from the same scenario, usually from an earlier run
+fn compare_with_savestate(
+    wiz: &mut Wizard,
+    ctx: &mut EventCtx,
+    app: &mut App,
+) -> Option<Transition> {
+    let (_, (ss, ss_path)) = wiz.wrap(ctx).choose("Compare with which savestate?", || {
+        abstutil::list_objects(app.primary.sim.save_dir())
+            .into_iter()
+            .map(|m| saved_file_choice::<Sim, _>(&m, (m.name.clone(), m.path.clone())))
+            .collect()
+    })?;
+
+    let other = ctx.loading_screen("load savestate", |_, timer| {
+        Sim::load_savestate(ss_path.clone(), &app.primary.map, timer)
     });
-    Some(Transition::Pop)
+    match other {
+        Ok(other) => Some(Transition::Replace(divergence::DivergenceViewer::new(
+            ctx, app, ss, other,
+        ))),
+        Err(err) => Some(Transition::Replace(msg("Error", vec![err]))),
+    }
+}
+
+// Map::new builds canned maps straight from the builder, so there's no file to write first
+fn load_synthetic_map(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+    let name = wiz
+        .wrap(ctx)
+        .choose_string("Load which synthetic map?", || {
+            CANNED_SYNTHETIC_MAPS.to_vec()
+        })?;
+    let path = abstutil::path_synthetic_map(&name);
+
+    Some(ctx.loading_screen("switch map", |ctx, _| {
+        app.switch_map(ctx, path.clone());
+        Transition::Clear(vec![Box::new(SandboxMode::new(
+            ctx,
+            app,
+            GameplayMode::Freeform(path),
+        ))])
+    }))
 }
 
 fn calc_all_routes(ctx: &EventCtx, app: &mut App) -> (usize, Drawable) {
@@ -427,10 +481,23 @@ fn calc_all_routes(ctx: &EventCtx, app: &mut App) -> (usize, Drawable) {
     (cnt, ctx.upload(batch))
 }
 
-struct Actions;
-impl ContextualActions for Actions {
+struct Actions<'a> {
+    annotations: &'a annotations::ShowAnnotations,
+}
+
+impl<'a> ContextualActions for Actions<'a> {
     fn actions(&self, app: &App, id: ID) -> Vec<(Key, String)> {
-        let mut actions = vec![(Key::D, "debug".to_string())];
+        let mut actions = vec![
+            (Key::D, "debug".to_string()),
+            (Key::J, "show everything about this".to_string()),
+        ];
+        if annotations::NoteTarget::from_id(&id, &app.primary.map).is_some() {
+            if self.annotations.get(app, &id).is_some() {
+                actions.push((Key::N, "edit note".to_string()));
+            } else {
+                actions.push((Key::N, "annotate this".to_string()));
+            }
+        }
         match id {
             ID::Lane(l) => {
                 actions.push((Key::H, "hide this".to_string()));
@@ -472,14 +539,29 @@ impl ContextualActions for Actions {
                 let mode = state.downcast_mut::<DebugMode>().unwrap();
                 println!("Hiding {:?}", id);
                 app.primary.current_selection = None;
+                // If this is part of a multi-selection, hide everything selected
+                if app.primary.multi_selection.contains(&id) {
+                    mode.hidden
+                        .extend(std::mem::take(&mut app.primary.multi_selection));
+                }
                 mode.hidden.insert(id);
                 mode.reset_info(ctx);
             })),
+            (id, "annotate this") | (id, "edit note") => {
+                *close_info = false;
+                let target = annotations::NoteTarget::from_id(&id, &app.primary.map).unwrap();
+                let existing = self.annotations.get(app, &id).cloned();
+                Transition::Push(annotations::edit_note(target, existing))
+            }
             (id, "debug") => {
                 *close_info = false;
                 objects::ObjectDebugger::dump_debug(id, &app.primary.map, &app.primary.sim);
                 Transition::Keep
             }
+            (id, "show everything about this") => {
+                *close_info = false;
+                Transition::Push(dump::ObjectDump::new(ctx, app, id))
+            }
             (ID::Car(c), "forcibly kill this car") => {
                 app.primary.sim.kill_stuck_car(c, &app.primary.map);
                 app.primary
@@ -621,3 +703,87 @@ fn find_bad_signals(app: &App) {
         }
     }
 }
+
+fn toggle_frame_timings(app: &mut App, enabled: bool) {
+    if enabled {
+        let path = abstutil::path_frame_timings(
+            &chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string(),
+        );
+        match FrameTimings::start(path.clone()) {
+            Ok(timings) => {
+                app.frame_timings = Some(timings);
+                app.toasts.notify(
+                    Severity::Info,
+                    format!("Recording frame timings to {}", path),
+                );
+            }
+            Err(err) => {
+                app.toasts.notify(
+                    Severity::Error,
+                    format!("Couldn't create {}: {}", path, err),
+                );
+            }
+        }
+    } else if let Some(timings) = app.frame_timings.take() {
+        app.toasts.notify(
+            Severity::Info,
+            format!("Wrote frame timings to {}", timings.path()),
+        );
+    }
+}
+
+// When the panel is rebuilt, the checkboxes keep their values from prev.
+fn make_composite(ctx: &mut EventCtx, app: &App, prev: Option<&Composite>) -> Composite {
+    let checkbox = |ctx: &mut EventCtx, name: &str, key, default: bool| {
+        let enabled = prev.map(|c| c.is_checked(name)).unwrap_or(default);
+        Checkbox::text(ctx, name, key, enabled).margin_below(5)
+    };
+    Composite::new(
+        Widget::col(vec![
+            Widget::row(vec![
+                Line("Debug Mode").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::new().draw(ctx).named("current info"),
+            checkbox(ctx, "show buildings", hotkey(Key::Num1), true),
+            checkbox(ctx, "show intersections", hotkey(Key::Num2), true),
+            checkbox(ctx, "show lanes", hotkey(Key::Num3), true),
+            checkbox(ctx, "show areas", hotkey(Key::Num4), true),
+            checkbox(ctx, "show labels", hotkey(Key::Num5), false),
+            checkbox(ctx, "show route for all agents", hotkey(Key::R), false),
+            checkbox(ctx, "show raw OSM geometry", hotkey(Key::Num6), false),
+            checkbox(
+                ctx,
+                "record frame timings",
+                None,
+                app.frame_timings.is_some(),
+            ),
+            Widget::col(
+                vec![
+                    (lctrl(Key::H), "unhide everything"),
+                    (None, "screenshot everything"),
+                    (hotkey(Key::Slash), "search OSM metadata"),
+                    (lctrl(Key::Slash), "clear OSM search results"),
+                    (hotkey(Key::O), "save sim state"),
+                    (hotkey(Key::Y), "load previous sim state"),
+                    (hotkey(Key::U), "load next sim state"),
+                    (None, "pick a savestate to load"),
+                    (None, "compare with a savestate"),
+                    (None, "find bad traffic signals"),
+                    (None, "load a synthetic map"),
+                    (None, "list annotations"),
+                    (hotkey(Key::L), "show logs"),
+                ]
+                .into_iter()
+                .map(|(key, action)| Btn::text_fg(action).build_def(ctx, key).margin_below(5))
+                .collect(),
+            ),
+        ])
+        .padding(10)
+        .bg(app.cs.panel_bg),
+    )
+    .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+    .build(ctx)
+}
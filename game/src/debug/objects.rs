@@ -1,6 +1,6 @@
 use crate::app::App;
 use crate::helpers::ID;
-use ezgui::{EventCtx, GfxCtx, Key, Line, Text};
+use ezgui::{EventCtx, GfxCtx, Key, Line, OverlayPanel, PanelAnchor, Text};
 use map_model::{Map, PathConstraints};
 use sim::{AgentID, Sim};
 
@@ -34,7 +34,8 @@ impl ObjectDebugger {
                     txt.add(Line(gps.to_string()));
                     txt.add(Line(format!("{:?}", g.canvas.get_cursor())));
                     txt.add(Line(format!("zoom: {}", g.canvas.cam_zoom)));
-                    g.draw_mouse_tooltip(txt);
+                    // Below any tooltip for whatever's hovered
+                    g.declare_panel(OverlayPanel::new(PanelAnchor::FollowCursor, txt));
                 }
             }
         }
@@ -0,0 +1,108 @@
+use crate::app::App;
+use abstutil::Timer;
+use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx};
+use geom::{Circle, Distance, Line, Pt2D};
+use map_model::raw::RawMap;
+
+// Imported centerlines straying further than this from the raw way get flagged
+const DEVIATION_THRESHOLD: Distance = Distance::const_meters(3.0);
+
+// The roads straight from the raw map, before map_model trims, merges, and smooths them. Drawn
+// thin over the map, to spot where import went wrong.
+pub struct RawOsmOverlay {
+    pub num_raw_roads: usize,
+    // Imported roads whose centerline is far from their raw way
+    pub num_deviations: usize,
+    // Imported roads that aren't in the raw map at all
+    pub num_missing: usize,
+    draw: Drawable,
+}
+
+impl RawOsmOverlay {
+    pub fn load(
+        ctx: &mut EventCtx,
+        app: &App,
+        path: String,
+        timer: &mut Timer,
+    ) -> Result<RawOsmOverlay, String> {
+        let raw: RawMap = abstutil::maybe_read_binary(path.clone(), timer)
+            .map_err(|err| format!("Couldn't load {}: {}", path, err))?;
+        let map = &app.primary.map;
+        // The raw map and the final map might not share bounds, so go through GPS
+        let to_map = |pts: &Vec<Pt2D>| {
+            map.get_gps_bounds()
+                .forcibly_convert(&raw.gps_bounds.must_convert_back(pts))
+        };
+
+        let mut batch = GeomBatch::new();
+        for road in raw.roads.values() {
+            for pair in to_map(&road.center_points).windows(2) {
+                if let Some(line) = Line::maybe_new(pair[0], pair[1]) {
+                    batch.push(Color::CYAN, line.make_polygons(Distance::meters(0.5)));
+                }
+            }
+        }
+
+        let mut num_deviations = 0;
+        let mut num_missing = 0;
+        timer.start_iter("compare with raw roads", map.all_roads().len());
+        for r in map.all_roads() {
+            timer.next();
+            let raw_pts = match raw.roads.get(&r.orig_id) {
+                Some(road) => to_map(&road.center_points),
+                None => {
+                    num_missing += 1;
+                    continue;
+                }
+            };
+            let (worst_pt, worst_dist) = r
+                .center_pts
+                .points()
+                .iter()
+                .map(|pt| (*pt, dist_to_pts(*pt, &raw_pts)))
+                .max_by_key(|(_, dist)| *dist)
+                .unwrap();
+            if worst_dist > DEVIATION_THRESHOLD {
+                num_deviations += 1;
+                batch.push(
+                    Color::RED.alpha(0.5),
+                    r.center_pts.make_polygons(Distance::meters(2.0)),
+                );
+                batch.push(
+                    Color::RED,
+                    Circle::outline(worst_pt, worst_dist, Distance::meters(0.5)),
+                );
+            }
+        }
+
+        Ok(RawOsmOverlay {
+            num_raw_roads: raw.roads.len(),
+            num_deviations,
+            num_missing,
+            draw: ctx.upload(batch),
+        })
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "Raw OSM: {} roads, {} imported roads deviate by more than {}, {} are missing",
+            abstutil::prettyprint_usize(self.num_raw_roads),
+            abstutil::prettyprint_usize(self.num_deviations),
+            DEVIATION_THRESHOLD,
+            abstutil::prettyprint_usize(self.num_missing)
+        )
+    }
+}
+
+// To the closest segment
+fn dist_to_pts(pt: Pt2D, pts: &Vec<Pt2D>) -> Distance {
+    pts.windows(2)
+        .filter_map(|pair| Line::maybe_new(pair[0], pair[1]))
+        .map(|line| line.project_pt(pt).dist_to(pt))
+        .min()
+        .unwrap_or_else(|| pts.iter().map(|p| p.dist_to(pt)).min().unwrap())
+}
@@ -8,14 +8,15 @@ mod story;
 
 use crate::app::App;
 use crate::common::CityPicker;
-use crate::game::{DrawBaselayer, State, Transition, WizardState};
-use crate::helpers::nice_map_name;
+use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
+use crate::helpers::{nice_map_name, saved_file_choice};
 use abstutil::Timer;
 use ezgui::{
     hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, TextExt,
     VerticalAlignment, Widget, Wizard,
 };
 use geom::LonLat;
+use sim::Scenario;
 
 pub struct DevToolsMode {
     composite: Composite,
@@ -116,24 +117,30 @@ impl State for DevToolsMode {
 
 fn load_scenario(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
     let map_name = app.primary.map.get_name().to_string();
-    let s = wiz.wrap(ctx).choose_string("Load which scenario?", || {
-        abstutil::list_all_objects(abstutil::path_all_scenarios(&map_name))
+    let (_, s) = wiz.wrap(ctx).choose("Load which scenario?", || {
+        abstutil::list_objects(abstutil::path_all_scenarios(&map_name))
+            .into_iter()
+            .map(|m| saved_file_choice::<Scenario, _>(&m, m.name.clone()))
+            .collect()
     })?;
-    let scenario = abstutil::read_binary(
-        abstutil::path_scenario(&map_name, &s),
+    match abstutil::load_versioned_object(
+        abstutil::path_all_scenarios(&map_name),
+        &s,
         &mut Timer::throwaway(),
-    );
-    Some(Transition::Replace(Box::new(
-        scenario::ScenarioManager::new(scenario, ctx, app),
-    )))
+    ) {
+        Ok(scenario) => Some(Transition::Replace(Box::new(
+            scenario::ScenarioManager::new(scenario, ctx, app),
+        ))),
+        Err(err) => Some(Transition::Replace(msg("Error", vec![err]))),
+    }
 }
 
 fn choose_polygon(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
     // TODO Sorry, Seattle only right now
     let name = wiz.wrap(ctx).choose_string("Edit which polygon?", || {
-        abstutil::list_all_objects("../data/input/seattle/polygons/".to_string())
+        abstutil::list_all_objects(abstutil::path_all_neighborhoods("seattle"))
     })?;
-    match LonLat::read_osmosis_polygon(format!("../data/input/seattle/polygons/{}.poly", name)) {
+    match LonLat::read_osmosis_polygon(abstutil::path_neighborhood("seattle", &name)) {
         Ok(pts) => Some(Transition::Replace(polygon::PolygonEditor::new(
             ctx, app, name, pts,
         ))),
@@ -147,7 +154,7 @@ fn choose_polygon(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option
 fn choose_kml(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
     // TODO Sorry, Seattle only right now
     let path = wiz.wrap(ctx).choose_string("View what KML dataset?", || {
-        abstutil::list_dir(std::path::Path::new("../data/input/seattle/"))
+        abstutil::list_dir(std::path::Path::new(&abstutil::path("input/seattle/")))
             .into_iter()
             .filter(|x| x.ends_with(".bin") && !x.ends_with("popdat.bin"))
             .collect()
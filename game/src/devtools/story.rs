@@ -215,7 +215,7 @@ impl State for StoryMapEditor {
                             || {
                                 let mut list = Vec::new();
                                 for (name, story) in abstutil::load_all_objects::<RecordedStoryMap>(
-                                    "../data/player/stories".to_string(),
+                                    abstutil::path_all_stories(app.primary.map.get_name()),
                                 ) {
                                     if story.name == current {
                                         continue;
@@ -460,7 +460,7 @@ impl StoryMap {
                 .collect(),
         };
         abstutil::write_json(
-            format!("../data/player/stories/{}.json", story.name),
+            abstutil::path_story(app.primary.map.get_name(), &story.name),
             &story,
         );
     }
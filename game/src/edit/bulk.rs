@@ -302,12 +302,20 @@ pub struct PaintSelect {
 }
 
 impl PaintSelect {
+    // Starts with any roads in the multi-selection
     pub fn new(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State> {
         app.primary.current_selection = None;
+        let roads = app.primary.selected_roads();
+        app.primary.multi_selection.clear();
+        let preview = if roads.is_empty() {
+            None
+        } else {
+            Some(preview_roads(ctx, app, &roads))
+        };
         Box::new(PaintSelect {
-            composite: make_paint_composite(ctx, app, Mode::Paint, &BTreeSet::new()),
-            roads: BTreeSet::new(),
-            preview: None,
+            composite: make_paint_composite(ctx, app, Mode::Paint, &roads),
+            roads,
+            preview,
             mode: Mode::Paint,
             dragging: false,
         })
@@ -365,24 +373,7 @@ impl State for PaintSelect {
                     Mode::Pan => unreachable!(),
                 };
                 if change {
-                    let mut batch = GeomBatch::new();
-                    for r in &self.roads {
-                        batch.push(
-                            Color::BLUE.alpha(0.5),
-                            app.primary
-                                .map
-                                .get_r(*r)
-                                .get_thick_polygon(&app.primary.map)
-                                .unwrap(),
-                        );
-                    }
-                    for i in intersections_from_roads(&self.roads, &app.primary.map) {
-                        batch.push(
-                            Color::BLUE.alpha(0.5),
-                            app.primary.map.get_i(i).polygon.clone(),
-                        );
-                    }
-                    self.preview = Some(ctx.upload(batch));
+                    self.preview = Some(preview_roads(ctx, app, &self.roads));
                     self.composite = make_paint_composite(ctx, app, self.mode, &self.roads);
                 }
             }
@@ -498,6 +489,27 @@ fn change_lane_types(
     errors
 }
 
+fn preview_roads(ctx: &EventCtx, app: &App, roads: &BTreeSet<RoadID>) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for r in roads {
+        batch.push(
+            Color::BLUE.alpha(0.5),
+            app.primary
+                .map
+                .get_r(*r)
+                .get_thick_polygon(&app.primary.map)
+                .unwrap(),
+        );
+    }
+    for i in intersections_from_roads(roads, &app.primary.map) {
+        batch.push(
+            Color::BLUE.alpha(0.5),
+            app.primary.map.get_i(i).polygon.clone(),
+        );
+    }
+    ctx.upload(batch)
+}
+
 fn make_paint_composite(
     ctx: &mut EventCtx,
     app: &App,
@@ -0,0 +1,94 @@
+use crate::app::App;
+use crate::edit::apply_map_edits;
+use crate::game::{State, Transition, WizardState};
+use abstutil::Severity;
+use ezgui::EventCtx;
+use geom::{Duration, Time};
+use map_model::{connectivity, BuildingID, EditCmd, LaneID, Map, MapEdits, RoadID};
+use sim::Intervention;
+use std::collections::HashSet;
+
+// Only lanes that vehicles route through. Sidewalks stay open, so buildings can still be reached on
+// foot.
+pub fn can_close(map: &Map, l: LaneID) -> bool {
+    let lane = map.get_l(l);
+    lane.is_driving() || lane.is_biking() || lane.is_bus()
+}
+
+// Closes every lane on the road that can be closed and isn't already.
+pub fn close_road(map: &Map, r: RoadID) -> Result<Vec<EditCmd>, String> {
+    let road = map.get_r(r);
+    if !road.all_bus_stops(map).is_empty() {
+        return Err(format!(
+            "{} has a bus stop, so it can't be closed",
+            road.get_name()
+        ));
+    }
+    let cmds: Vec<EditCmd> = road
+        .all_lanes()
+        .into_iter()
+        .filter(|l| can_close(map, *l) && !map.get_edits().closed_lanes.contains(l))
+        .map(|id| EditCmd::CloseLane { id })
+        .collect();
+    if cmds.is_empty() {
+        return Err(format!("{} has nothing left to close", road.get_name()));
+    }
+    Ok(cmds)
+}
+
+// Like apply_map_edits, but warns if the closures cut off buildings from driving.
+pub fn apply_closures(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
+    let before = connectivity::find_unreachable_buildings(&app.primary.map);
+    apply_map_edits(ctx, app, edits);
+    warn_about_unreachable(app, before);
+}
+
+pub fn warn_about_unreachable(app: &mut App, before: HashSet<BuildingID>) {
+    let newly_unreachable = connectivity::find_unreachable_buildings(&app.primary.map)
+        .difference(&before)
+        .count();
+    if newly_unreachable > 0 {
+        app.toasts.notify(
+            Severity::Warn,
+            format!(
+                "This closure cuts off {} buildings from driving",
+                abstutil::prettyprint_usize(newly_unreachable)
+            ),
+        );
+    }
+}
+
+// Closes lanes between two times using sim interventions, without touching the map edits.
+pub fn close_temporarily(lanes: Vec<LaneID>) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let now = app.primary.sim.time();
+        let mut wizard = wiz.wrap(ctx);
+        let start = wizard.input_something(
+            "Close at what time? (HH:MM:SS)",
+            Some(now.to_string()),
+            Box::new(move |s| Time::parse(&s).ok().filter(|t| *t >= now)),
+        )?;
+        let end = wizard.input_something(
+            "Reopen at what time? (HH:MM:SS)",
+            Some((start + Duration::hours(1)).to_string()),
+            Box::new(move |s| Time::parse(&s).ok().filter(|t| *t > start)),
+        )?;
+        for l in &lanes {
+            for (t, intervention) in vec![
+                (start, Intervention::CloseLane(*l)),
+                (end, Intervention::ReopenLane(*l)),
+            ] {
+                app.primary
+                    .sim
+                    .schedule_intervention(t, intervention.clone());
+                // So the closure survives resetting the sim
+                app.primary.scheduled_interventions.push((t, intervention));
+            }
+        }
+        app.toasts.notify(
+            Severity::Info,
+            format!("{} lanes will close from {} to {}", lanes.len(), start, end),
+        );
+        Some(Transition::Pop)
+    }))
+}
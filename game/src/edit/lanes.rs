@@ -1,10 +1,12 @@
 use crate::app::App;
 use crate::common::CommonState;
+use crate::edit::closures::{apply_closures, can_close, close_road};
 use crate::edit::{apply_map_edits, can_edit_lane, change_speed_limit, maybe_edit_intersection};
-use crate::game::{msg, State, Transition};
+use crate::game::{State, Transition};
 use crate::helpers::ID;
 use crate::render::Renderable;
 use crate::sandbox::GameplayMode;
+use abstutil::Severity;
 use ezgui::{
     hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
     RewriteColor, TextExt, VerticalAlignment, Widget,
@@ -74,12 +76,38 @@ impl LaneEditor {
         }
 
         let parent = app.primary.map.get_parent(l);
-        let col = vec![
+        let mut col = vec![
             format!("Convert this lane of {} to what type?", parent.get_name())
                 .draw_text(ctx)
                 .centered_horiz(),
+        ];
+        if let Some((filled, capacity)) = app
+            .suspended_sim
+            .as_ref()
+            .and_then(|sim| sim.get_parking_lane_occupancy(l))
+        {
+            col.push(
+                format!("{} / {} parking spots filled", filled, capacity)
+                    .draw_text(ctx)
+                    .centered_horiz(),
+            );
+        }
+        col.extend(vec![
             Widget::row(row).centered().margin_below(5),
             change_speed_limit(ctx, parent.speed_limit).margin_below(5),
+            Widget::row(vec![
+                if app.primary.map.get_edits().closed_lanes.contains(&l) {
+                    Btn::text_fg("reopen lane").build_def(ctx, hotkey(Key::O))
+                } else if can_close(&app.primary.map, l) {
+                    Btn::text_fg("close lane").build_def(ctx, hotkey(Key::O))
+                } else {
+                    Btn::text_fg("close lane").inactive(ctx)
+                }
+                .margin_right(5),
+                Btn::text_fg("close entire road").build_def(ctx, None),
+            ])
+            .centered()
+            .margin_below(5),
             Widget::row(vec![
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
                 // TODO Handle reverting speed limit too...
@@ -92,7 +120,7 @@ impl LaneEditor {
                 },
             ])
             .centered(),
-        ];
+        ]);
 
         let composite = Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(10))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -158,6 +186,45 @@ impl State for LaneEditor {
                         try_change_lane_type(self.l, LaneType::Construction, map)
                     }
                     "reverse lane direction" => try_reverse(self.l, map),
+                    "close entire road" => match close_road(map, map.get_l(self.l).parent) {
+                        Ok(cmds) => {
+                            let mut edits = map.get_edits().clone();
+                            edits.commands.extend(cmds);
+                            apply_closures(ctx, app, edits);
+                            return Transition::Replace(Box::new(LaneEditor::new(
+                                ctx,
+                                app,
+                                self.l,
+                                self.mode.clone(),
+                            )));
+                        }
+                        Err(err) => {
+                            app.toasts.notify(Severity::Error, err);
+                            return Transition::Keep;
+                        }
+                    },
+                    "close lane" => {
+                        let mut edits = map.get_edits().clone();
+                        edits.commands.push(EditCmd::CloseLane { id: self.l });
+                        apply_closures(ctx, app, edits);
+                        return Transition::Replace(Box::new(LaneEditor::new(
+                            ctx,
+                            app,
+                            self.l,
+                            self.mode.clone(),
+                        )));
+                    }
+                    "reopen lane" => {
+                        let mut edits = map.get_edits().clone();
+                        assert!(edits.reopen_lane(self.l));
+                        apply_map_edits(ctx, app, edits);
+                        return Transition::Replace(Box::new(LaneEditor::new(
+                            ctx,
+                            app,
+                            self.l,
+                            self.mode.clone(),
+                        )));
+                    }
                     "Finish" => {
                         return Transition::Pop;
                     }
@@ -173,9 +240,31 @@ impl State for LaneEditor {
                 };
                 match result {
                     Ok(cmd) => {
+                        let was_parking = map.get_l(self.l).lane_type == LaneType::Parking;
                         let mut edits = app.primary.map.get_edits().clone();
                         edits.commands.push(cmd);
-                        apply_map_edits(ctx, app, edits);
+                        if x == "close for construction" {
+                            apply_closures(ctx, app, edits);
+                        } else {
+                            apply_map_edits(ctx, app, edits);
+                        }
+                        if was_parking
+                            && app.primary.map.get_l(self.l).lane_type != LaneType::Parking
+                        {
+                            if let Some(ref sim) = app.suspended_sim {
+                                let displaced = sim.get_parked_cars_on_lane(self.l).len();
+                                if displaced > 0 {
+                                    app.toasts.notify(
+                                        Severity::Warn,
+                                        format!(
+                                            "{} parked cars will be displaced when the \
+                                             simulation resets",
+                                            displaced
+                                        ),
+                                    );
+                                }
+                            }
+                        }
                         return Transition::Replace(Box::new(LaneEditor::new(
                             ctx,
                             app,
@@ -184,7 +273,7 @@ impl State for LaneEditor {
                         )));
                     }
                     Err(err) => {
-                        return Transition::Push(msg("Error", vec![err]));
+                        app.toasts.notify(Severity::Error, err);
                     }
                 }
             }
@@ -224,6 +313,10 @@ impl State for LaneEditor {
         self.composite.draw(g);
         CommonState::draw_osd(g, app);
     }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.composite = LaneEditor::new(ctx, app, self.l, self.mode.clone()).composite;
+    }
 }
 
 fn can_change_lane_type(l: LaneID, new_lt: LaneType, map: &Map) -> Option<String> {
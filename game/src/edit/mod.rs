@@ -1,22 +1,29 @@
 mod bulk;
+mod closures;
 mod cluster_traffic_signals;
 mod lanes;
+mod route_diff;
+mod routes;
 mod stop_signs;
 mod traffic_signals;
+mod turn_restrictions;
 
+pub use self::closures::{can_close, close_temporarily};
 pub use self::cluster_traffic_signals::ClusterTrafficSignalEditor;
 pub use self::lanes::LaneEditor;
+pub use self::routes::RouteEditor;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
+pub use self::turn_restrictions::TurnRestrictionEditor;
 use crate::app::{App, ShowEverything};
 use crate::common::{tool_panel, ColorDiscrete, CommonState, Warping};
 use crate::debug::DebugMode;
 use crate::game::{msg, State, Transition, WizardState};
-use crate::helpers::ID;
+use crate::helpers::{describe_saved_file, ID};
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::render::{DrawIntersection, DrawLane, DrawRoad};
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
-use abstutil::Timer;
+use abstutil::{Severity, Timer};
 use ezgui::{
     hotkey, lctrl, Btn, Choice, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment,
     Key, Line, Outcome, PersistentSplit, RewriteColor, ScreenRectangle, Text, TextExt,
@@ -24,10 +31,10 @@ use ezgui::{
 };
 use geom::Speed;
 use map_model::{
-    connectivity, EditCmd, EditIntersection, IntersectionID, LaneID, LaneType, MapEdits,
+    connectivity, EditCmd, EditIntersection, IntersectionID, LaneID, LaneType, Map, MapEdits,
     PathConstraints, PermanentMapEdits,
 };
-use sim::DontDrawAgents;
+use sim::{DontDrawAgents, Sim};
 use std::collections::BTreeSet;
 
 pub struct EditMode {
@@ -52,11 +59,13 @@ impl EditMode {
         let orig_dirty = app.primary.dirty_from_edits;
         assert!(app.suspended_sim.is_none());
         app.suspended_sim = Some(app.primary.clear_sim());
+        // The player shouldn't see or save edits made by interventions
+        sync_intervention_edits(ctx, app);
         let edits = app.primary.map.get_edits();
         let layer = crate::layer::map::Static::edits(ctx, app);
         EditMode {
             tool_panel: tool_panel(ctx, app),
-            top_center: make_topcenter(ctx, app, &mode),
+            top_center: make_topcenter(ctx, app, &mode, &edits),
             changelist: make_changelist(ctx, app),
             orig_edits: edits.clone(),
             orig_dirty,
@@ -115,6 +124,7 @@ impl State for EditMode {
             if self.changelist_key != changelist_key {
                 self.changelist_key = changelist_key;
                 self.changelist = make_changelist(ctx, app);
+                self.top_center = make_topcenter(ctx, app, &self.mode, &self.orig_edits);
                 let layer = crate::layer::map::Static::edits(ctx, app);
                 self.unzoomed = layer.unzoomed;
                 self.zoomed = layer.zoomed;
@@ -143,23 +153,52 @@ impl State for EditMode {
                     app.primary.current_selection = None;
                 }
             } else if let Some(ID::Road(_)) = app.primary.current_selection {
+            } else if let Some(ID::BusStop(bs)) = app.primary.current_selection {
+                if !self.mode.can_edit_lanes()
+                    || app.primary.map.get_routes_serving_stop(bs).is_empty()
+                {
+                    app.primary.current_selection = None;
+                }
             } else {
                 app.primary.current_selection = None;
             }
         }
 
-        if app.opts.dev && ctx.input.new_was_pressed(&lctrl(Key::D).unwrap()) {
+        if app.opts.dev && ctx.action_pressed("debug mode", lctrl(Key::D)) {
             return Transition::Push(Box::new(DebugMode::new(ctx, app)));
         }
 
+        if !app.primary.multi_selection.is_empty()
+            && ctx.input.key_pressed(Key::Escape, "clear the selection")
+        {
+            app.primary.multi_selection.clear();
+        }
+
         match self.top_center.event(ctx) {
             Some(Outcome::Clicked(x)) => match x.as_ref() {
                 "bulk edit" => {
                     return Transition::Push(bulk::PaintSelect::new(ctx, app));
                 }
+                "compare routes" => {
+                    return Transition::Push(route_diff::RouteDiffViewer::pick_edits());
+                }
                 "finish editing" => {
                     return self.quit(ctx, app);
                 }
+                "apply live" => {
+                    let cmds =
+                        pending_edits(&self.orig_edits, app.primary.map.get_edits()).unwrap();
+                    let mut sim = app.suspended_sim.take().unwrap();
+                    ctx.loading_screen("apply edits live", |_, mut timer| {
+                        app.primary
+                            .map
+                            .recalculate_pathfinding_after_edits(&mut timer);
+                    });
+                    sim.handle_live_edits(&app.primary.map, &cmds).unwrap();
+                    app.primary.sim = sim;
+                    app.primary.dirty_from_edits = true;
+                    return Transition::Pop;
+                }
                 _ => unreachable!(),
             },
             None => {}
@@ -181,7 +220,7 @@ impl State for EditMode {
                 }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
-                    let id = cmd_to_id(&edits.commands.pop().unwrap());
+                    let id = cmd_to_id(&edits.commands.pop().unwrap(), &app.primary.map);
                     apply_map_edits(ctx, app, edits);
                     return Transition::Push(Warping::new(
                         ctx,
@@ -196,6 +235,7 @@ impl State for EditMode {
                     let id = cmd_to_id(
                         &app.primary.map.get_edits().commands
                             [app.primary.map.get_edits().commands.len() - idx],
+                        &app.primary.map,
                     );
                     return Transition::Push(Warping::new(
                         ctx,
@@ -211,7 +251,19 @@ impl State for EditMode {
         // Just kind of constantly scrape this
         app.opts.resume_after_edit = self.top_center.persistent_split_value("finish editing");
 
-        if ctx.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+        if ctx.is_ctrl_held() {
+            // Build up a selection for bulk editing
+            match app.primary.current_selection.clone() {
+                Some(id @ ID::Lane(_)) | Some(id @ ID::Road(_)) => {
+                    if app.per_obj.left_click(ctx, "add to the selection") {
+                        if !app.primary.multi_selection.remove(&id) {
+                            app.primary.multi_selection.insert(id);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        } else if ctx.canvas.cam_zoom < app.opts.min_zoom_for_detail {
             if let Some(id) = &app.primary.current_selection {
                 if app.per_obj.left_click(ctx, "edit this") {
                     return Transition::Push(Warping::new(
@@ -239,6 +291,13 @@ impl State for EditMode {
                     )));
                 }
             }
+            if let Some(ID::BusStop(bs)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "edit bus route") {
+                    // Arbitrarily pick the first route serving the stop
+                    let route = app.primary.map.get_routes_serving_stop(bs)[0].id;
+                    return Transition::Push(RouteEditor::new(ctx, app, route, self.mode.clone()));
+                }
+            }
         }
 
         match self.tool_panel.event(ctx, app) {
@@ -262,6 +321,12 @@ impl State for EditMode {
         }
         CommonState::draw_osd(g, app);
     }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.tool_panel = tool_panel(ctx, app);
+        self.top_center = make_topcenter(ctx, app, &self.mode, &self.orig_edits);
+        self.changelist = make_changelist(ctx, app);
+    }
 }
 
 pub fn save_edits_as(wizard: &mut WrappedWizard, app: &mut App) -> Option<()> {
@@ -343,24 +408,34 @@ fn make_load_edits(app: &App, btn: ScreenRectangle, mode: GameplayMode) -> Box<d
             ),
             None,
             || {
-                let mut list = Choice::from(
-                    abstutil::load_all_objects(abstutil::path_all_edits(
-                        app.primary.map.get_name(),
-                    ))
-                    .into_iter()
-                    .chain(abstutil::load_all_objects::<PermanentMapEdits>(
-                        "../data/system/proposals".to_string(),
-                    ))
-                    .filter_map(|(path, perma)| {
+                let map_name = app.primary.map.get_name();
+                let mut list = Vec::new();
+                for (meta, result) in abstutil::load_all_versioned_objects::<PermanentMapEdits>(
+                    abstutil::path_all_edits(map_name),
+                )
+                .into_iter()
+                .chain(abstutil::load_all_versioned_objects::<PermanentMapEdits>(
+                    abstutil::path_all_proposals(),
+                )) {
+                    // Every file has to be loaded anyway, to check if this mode allows it
+                    match result.and_then(|perma| {
                         PermanentMapEdits::from_permanent(perma, &app.primary.map)
-                            .map(|edits| (path, edits))
-                            .ok()
-                    })
-                    .filter(|(_, edits)| {
-                        mode.allows(edits) && edits.edits_name != current_edits_name
-                    })
-                    .collect(),
-                );
+                    }) {
+                        Ok(edits) => {
+                            if mode.allows(&edits) && edits.edits_name != current_edits_name {
+                                list.push(Choice::new(describe_saved_file(&meta), edits));
+                            }
+                        }
+                        // Flag broken files, instead of silently hiding them
+                        Err(err) => {
+                            list.push(
+                                Choice::new(format!("{} (can't load)", meta.name), MapEdits::new())
+                                    .active(false)
+                                    .tooltip(err),
+                            );
+                        }
+                    }
+                }
                 list.push(Choice::new("start over with blank edits", MapEdits::new()));
                 list
             },
@@ -370,7 +445,52 @@ fn make_load_edits(app: &App, btn: ScreenRectangle, mode: GameplayMode) -> Box<d
     }))
 }
 
-fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composite {
+// The commands separating the suspended sim's edits from the current ones. Undone commands count
+// too, since reverting them touches the same things. None if a different set of edits was loaded.
+fn pending_edits(orig: &MapEdits, current: &MapEdits) -> Option<Vec<EditCmd>> {
+    if orig.commands.len() <= current.commands.len()
+        && current.commands[..orig.commands.len()] == orig.commands[..]
+    {
+        Some(current.commands[orig.commands.len()..].to_vec())
+    } else if current.commands.len() < orig.commands.len()
+        && orig.commands[..current.commands.len()] == current.commands[..]
+    {
+        Some(orig.commands[current.commands.len()..].to_vec())
+    } else {
+        None
+    }
+}
+
+fn make_topcenter(
+    ctx: &mut EventCtx,
+    app: &App,
+    mode: &GameplayMode,
+    orig_edits: &MapEdits,
+) -> Composite {
+    // Only a few kinds of edits can be applied without resetting the simulation.
+    let live = match pending_edits(orig_edits, app.primary.map.get_edits()) {
+        Some(cmds) if cmds.is_empty() => None,
+        Some(cmds) => {
+            let blockers = Sim::live_edit_blockers(&cmds);
+            if blockers.is_empty() {
+                Some(Btn::text_fg("apply live").build_def(ctx, hotkey(Key::L)))
+            } else {
+                Some(Widget::col(vec![
+                    Btn::text_fg("apply live").inactive(ctx),
+                    format!("Can't apply live: {}", blockers.join(", "))
+                        .draw_text(ctx)
+                        .margin_above(5),
+                ]))
+            }
+        }
+        None => Some(Widget::col(vec![
+            Btn::text_fg("apply live").inactive(ctx),
+            "Can't apply live: a different set of edits was loaded"
+                .draw_text(ctx)
+                .margin_above(5),
+        ])),
+    };
+
     Composite::new(
         Widget::col(vec![
             Widget::row(vec![Line("Editing map").small_heading().draw(ctx)])
@@ -383,6 +503,9 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composi
                     Btn::text_fg("bulk edit").inactive(ctx)
                 }
                 .margin_right(15),
+                Btn::text_fg("compare routes")
+                    .build_def(ctx, None)
+                    .margin_right(15),
                 PersistentSplit::new(
                     ctx,
                     "finish editing",
@@ -401,6 +524,8 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composi
                 )
                 .bg(app.cs.section_bg),
             ]),
+            live.map(|w| w.margin_above(10))
+                .unwrap_or_else(Widget::nothing),
         ])
         .padding(16)
         .bg(app.cs.panel_bg),
@@ -411,9 +536,73 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Composi
 
 pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
     let mut timer = Timer::new("apply map edits");
+    apply_edits_and_redraw(ctx, app, edits, &mut timer);
+
+    // Autosave
+    if app.primary.map.get_edits().edits_name != "untitled edits" {
+        app.primary.map.save_edits();
+        app.toasts.notify(
+            Severity::Info,
+            format!("Saved {}", app.primary.map.get_edits().edits_name),
+        );
+    }
+}
+
+// The sim stops when a scheduled intervention comes due, until the map is changed to match.
+pub fn apply_interventions(ctx: &mut EventCtx, app: &mut App) {
+    let mut timer = Timer::new("apply interventions");
+    let edits = app.primary.sim.start_interventions(&app.primary.map);
+    let unreachable = connectivity::find_unreachable_buildings(&app.primary.map);
+    // These are temporary edits, so there's nothing to autosave
+    apply_edits_and_redraw(ctx, app, edits, &mut timer);
+    closures::warn_about_unreachable(app, unreachable);
+    app.primary
+        .map
+        .recalculate_pathfinding_after_edits(&mut timer);
+    let mut rng = app.primary.current_flags.sim_flags.make_rng();
+    app.primary
+        .sim
+        .finish_interventions(&app.primary.map, &mut rng, &mut timer);
+}
 
+// The map keeps the edits the current sim's interventions made. After resetting the sim or loading
+// a savestate, they have to catch up.
+pub fn sync_intervention_edits(ctx: &mut EventCtx, app: &mut App) {
+    if &app.primary.map.get_edits().temporary == app.primary.sim.get_intervention_edits() {
+        return;
+    }
+    let mut timer = Timer::new("sync intervention edits");
+    let mut edits = app.primary.map.get_edits().clone();
+    edits.temporary = app.primary.sim.get_intervention_edits().clone();
+    let signals = app
+        .primary
+        .map
+        .get_edits()
+        .temporary
+        .iter()
+        .chain(&edits.temporary)
+        .filter_map(|cmd| match cmd {
+            EditCmd::ChangeIntersection { i, .. } => Some(*i),
+            _ => None,
+        })
+        .collect();
+    apply_edits_and_redraw(ctx, app, edits, &mut timer);
+    app.primary
+        .map
+        .recalculate_pathfinding_after_edits(&mut timer);
+    app.primary
+        .sim
+        .restart_traffic_signals(&app.primary.map, signals);
+}
+
+fn apply_edits_and_redraw(ctx: &mut EventCtx, app: &mut App, edits: MapEdits, timer: &mut Timer) {
     let (roads_changed, turns_deleted, turns_added, mut modified_intersections) =
-        app.primary.map.apply_edits(edits, &mut timer);
+        app.primary.map.apply_edits(edits, timer);
+    app.primary.neighborhoods.edits_applied(
+        &app.primary.map,
+        &roads_changed,
+        &modified_intersections,
+    );
 
     for r in roads_changed {
         let road = app.primary.map.get_r(r);
@@ -429,7 +618,7 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
                 &app.primary.map,
                 app.primary.current_flags.draw_lane_markings,
                 &app.cs,
-                &mut timer,
+                timer,
             )
             .finish(ctx.prerender, &app.cs, lane);
         }
@@ -451,18 +640,13 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             &app.primary.map,
             &app.cs,
             ctx.prerender,
-            &mut timer,
+            timer,
         );
     }
 
     if app.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
         app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
     }
-
-    // Autosave
-    if app.primary.map.get_edits().edits_name != "untitled edits" {
-        app.primary.map.save_edits();
-    }
 }
 
 pub fn can_edit_lane(mode: &GameplayMode, l: LaneID, app: &App) -> bool {
@@ -701,11 +885,17 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         .build(ctx)
 }
 
-fn cmd_to_id(cmd: &EditCmd) -> ID {
+fn cmd_to_id(cmd: &EditCmd, map: &Map) -> ID {
     match cmd {
         EditCmd::ChangeLaneType { id, .. } => ID::Lane(*id),
         EditCmd::ReverseLane { l, .. } => ID::Lane(*l),
+        EditCmd::CloseLane { id } => ID::Lane(*id),
         EditCmd::ChangeSpeedLimit { id, .. } => ID::Road(*id),
         EditCmd::ChangeIntersection { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeTurnRestriction { t, .. } => ID::Intersection(t.parent),
+        // Warp to the first stop, since routes themselves can't be selected
+        EditCmd::ChangeRouteStops { id, .. } | EditCmd::ChangeRouteSchedule { id, .. } => {
+            ID::BusStop(map.get_br(*id).stops[0])
+        }
     }
 }
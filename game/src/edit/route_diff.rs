@@ -0,0 +1,284 @@
+use crate::app::App;
+use crate::common::{ColorNetwork, DivergingScale};
+use crate::game::{msg, State, Transition, WizardState};
+use abstutil::{prettyprint_usize, Counter, Timer};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration};
+use map_model::{Map, MapEdits, Path, PathConstraints, PathRequest, PathStep, Position, RoadID};
+use rand::seq::SliceRandom;
+
+// Without running a sim, how would driving routes change under a different set of edits? The
+// same random trips are routed under the current edits and the candidate ones.
+pub struct RouteDiff {
+    candidate: String,
+    num_requests: usize,
+    // Only routable before or after
+    num_lost: usize,
+    num_gained: usize,
+    num_changed: usize,
+    // For changed routes, after minus before
+    length_deltas: Vec<Distance>,
+    time_deltas: Vec<Duration>,
+    // How many routes cross each road
+    before: Counter<RoadID>,
+    after: Counter<RoadID>,
+}
+
+impl RouteDiff {
+    // Temporarily switches the map to the candidate edits, then back.
+    pub fn new(
+        app: &mut App,
+        candidate: String,
+        num_requests: usize,
+        timer: &mut Timer,
+    ) -> Result<RouteDiff, String> {
+        let mut rng = app.primary.current_flags.sim_flags.make_rng();
+        let map = &mut app.primary.map;
+        let requests: Vec<PathRequest> = (0..num_requests)
+            .map(|_| {
+                let mut pos = || {
+                    let b = map.all_buildings().choose(&mut rng).unwrap().id;
+                    let l = map.find_driving_lane_near_building(b);
+                    Position::new(l, map.get_l(l).length() / 2.0)
+                };
+                PathRequest {
+                    start: pos(),
+                    end: pos(),
+                    constraints: PathConstraints::Car,
+                }
+            })
+            .collect();
+
+        map.recalculate_pathfinding_after_edits(timer);
+        let before = calculate_routes(map, requests.clone(), timer);
+
+        let orig_edits = map.get_edits().clone();
+        // Loading edits needs a map without any
+        map.apply_edits(MapEdits::new(), timer);
+        let after = match MapEdits::load(map, &candidate, timer) {
+            Ok(edits) => {
+                map.apply_edits(edits, timer);
+                map.recalculate_pathfinding_after_edits(timer);
+                Ok(calculate_routes(map, requests, timer))
+            }
+            Err(err) => Err(err),
+        };
+        map.apply_edits(orig_edits, timer);
+        map.recalculate_pathfinding_after_edits(timer);
+        let after = after?;
+
+        let mut diff = RouteDiff {
+            candidate,
+            num_requests,
+            num_lost: 0,
+            num_gained: 0,
+            num_changed: 0,
+            length_deltas: Vec::new(),
+            time_deltas: Vec::new(),
+            before: Counter::new(),
+            after: Counter::new(),
+        };
+        for (route1, route2) in before.into_iter().zip(after.into_iter()) {
+            match (route1, route2) {
+                (Some(r1), Some(r2)) => {
+                    if r1.steps != r2.steps {
+                        diff.num_changed += 1;
+                        diff.length_deltas.push(r2.length - r1.length);
+                        diff.time_deltas.push(r2.time - r1.time);
+                    }
+                    for r in r1.roads {
+                        diff.before.inc(r);
+                    }
+                    for r in r2.roads {
+                        diff.after.inc(r);
+                    }
+                }
+                (Some(_), None) => {
+                    diff.num_lost += 1;
+                }
+                (None, Some(_)) => {
+                    diff.num_gained += 1;
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(diff)
+    }
+
+    fn describe(&self) -> Text {
+        let mut txt = Text::new();
+        txt.add(Line(format!(
+            "{} of {} routes change",
+            prettyprint_usize(self.num_changed),
+            prettyprint_usize(self.num_requests)
+        )));
+        txt.add(Line(format!(
+            "{} are only possible before, {} only after",
+            prettyprint_usize(self.num_lost),
+            prettyprint_usize(self.num_gained)
+        )));
+        if let Some(x) = describe_deltas(&self.length_deltas) {
+            txt.add(Line(format!("Change in length: {}", x)));
+        }
+        if let Some(x) = describe_deltas(&self.time_deltas) {
+            txt.add(Line(format!("Change in free-flow time: {}", x)));
+        }
+        txt
+    }
+}
+
+struct Route {
+    steps: Vec<PathStep>,
+    roads: Vec<RoadID>,
+    length: Distance,
+    // Ignoring turns and traffic
+    time: Duration,
+}
+
+fn calculate_routes(
+    map: &Map,
+    requests: Vec<PathRequest>,
+    timer: &mut Timer,
+) -> Vec<Option<Route>> {
+    timer.parallelize("calculate routes", requests, |req| {
+        map.pathfind(req).map(|path| summarize(path, map))
+    })
+}
+
+fn summarize(path: Path, map: &Map) -> Route {
+    let mut roads = Vec::new();
+    let mut time = Duration::ZERO;
+    for step in path.get_steps() {
+        if let PathStep::Lane(l) = step {
+            let lane = map.get_l(*l);
+            let road = map.get_r(lane.parent);
+            time += lane.length() / road.speed_limit;
+            if roads.last() != Some(&road.id) {
+                roads.push(road.id);
+            }
+        }
+    }
+    Route {
+        steps: path.get_steps().iter().cloned().collect(),
+        roads,
+        length: path.total_length(),
+        time,
+    }
+}
+
+// min, median, 90th percentile, max
+fn describe_deltas<T: Copy + Ord + std::fmt::Display>(deltas: &[T]) -> Option<String> {
+    if deltas.is_empty() {
+        return None;
+    }
+    let mut sorted = deltas.to_vec();
+    sorted.sort();
+    let pct = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+    Some(format!(
+        "min {}, median {}, 90%ile {}, max {}",
+        pct(0),
+        pct(50),
+        pct(90),
+        pct(100)
+    ))
+}
+
+pub struct RouteDiffViewer {
+    composite: Composite,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+}
+
+impl RouteDiffViewer {
+    pub fn pick_edits() -> Box<dyn State> {
+        WizardState::new(Box::new(|wiz, ctx, app| {
+            let mut wizard = wiz.wrap(ctx);
+            let candidate = wizard.choose_string("Compare routes with which edits?", || {
+                abstutil::list_all_objects(abstutil::path_all_edits(app.primary.map.get_name()))
+            })?;
+            let num_requests = wizard
+                .choose_string("How many random trips?", || vec!["100", "1,000", "10,000"])?;
+            let num_requests = num_requests.replace(",", "").parse::<usize>().unwrap();
+
+            let result = wizard.ctx.loading_screen("compare routes", |_, timer| {
+                RouteDiff::new(app, candidate.clone(), num_requests, timer)
+            });
+            Some(Transition::Replace(match result {
+                Ok(diff) => RouteDiffViewer::new(wizard.ctx, app, diff),
+                Err(err) => msg(
+                    "Error",
+                    vec![format!("Couldn't load {}: {}", candidate, err)],
+                ),
+            }))
+        }))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &App, diff: RouteDiff) -> Box<dyn State> {
+        let mut colorer = ColorNetwork::new(app);
+        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+            .range(0.0, 2.0)
+            .ignore(0.7, 1.3);
+        for (r, before, after) in diff.before.clone().compare(diff.after.clone()) {
+            if let Some(c) = scale.eval((after as f64) / (before as f64)) {
+                colorer.add_r(r, c);
+            }
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        Box::new(RouteDiffViewer {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Widget::row(vec![
+                        Line(format!("Routes with {}", diff.candidate))
+                            .small_heading()
+                            .draw(ctx),
+                        Btn::text_fg("X")
+                            .build(ctx, "close", hotkey(Key::Escape))
+                            .align_right(),
+                    ]),
+                    diff.describe().draw(ctx).margin_below(5),
+                    scale.make_legend(ctx, vec!["fewer routes", "same", "more"]),
+                ])
+                .padding(10)
+                .bg(app.cs.panel_bg),
+            )
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx),
+            unzoomed,
+            zoomed,
+        })
+    }
+}
+
+impl State for RouteDiffViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.composite.draw(g);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::game::{State, Transition};
+use crate::helpers::ID;
+use crate::layer::bus::StopLabels;
+use crate::sandbox::GameplayMode;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Spinner, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, Time};
+use map_model::{BusRoute, BusRouteID, BusStopID, EditCmd, PathConstraints, PathRequest, PathStep};
+
+// Buses start running at this interval for the whole day. 0 means just one bus.
+const MAX_HEADWAY_MINUTES: usize = 240;
+
+// Changes are made to a copy of the route, and only become map edits when applied.
+pub struct RouteEditor {
+    composite: Composite,
+    id: BusRouteID,
+    mode: GameplayMode,
+    stops: Vec<BusStopID>,
+    headway_mins: usize,
+    // Problems with the current stops, if any
+    error: Option<String>,
+
+    draw: Drawable,
+    labels: StopLabels,
+}
+
+impl RouteEditor {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        id: BusRouteID,
+        mode: GameplayMode,
+    ) -> Box<dyn State> {
+        // Validating the stops needs pathfinding to reflect any other edits
+        ctx.loading_screen("update pathfinding", |_, timer| {
+            app.primary.map.recalculate_pathfinding_after_edits(timer);
+        });
+        let route = app.primary.map.get_br(id);
+        let stops = route.stops.clone();
+        let headway_mins = headway_mins(&route.spawn_times);
+        Box::new(RouteEditor::make(ctx, app, id, mode, stops, headway_mins))
+    }
+
+    fn make(
+        ctx: &mut EventCtx,
+        app: &App,
+        id: BusRouteID,
+        mode: GameplayMode,
+        stops: Vec<BusStopID>,
+        headway_mins: usize,
+    ) -> RouteEditor {
+        let map = &app.primary.map;
+        let error = BusRoute::validate_stops(&stops, map).err();
+
+        let mut batch = GeomBatch::new();
+        for idx1 in 0..stops.len() {
+            let bs1 = map.get_bs(stops[idx1]);
+            let bs2 = map.get_bs(stops[(idx1 + 1) % stops.len()]);
+            match map.pathfind(PathRequest {
+                start: bs1.driving_pos,
+                end: bs2.driving_pos,
+                constraints: PathConstraints::Bus,
+            }) {
+                Some(path) => {
+                    for step in path.get_steps() {
+                        if let PathStep::Lane(l) = step {
+                            batch.push(
+                                app.cs.unzoomed_bus.alpha(0.8),
+                                app.primary.draw_map.get_l(*l).polygon.clone(),
+                            );
+                        }
+                    }
+                }
+                // Show the gap
+                None => {
+                    if let Some(line) =
+                        geom::Line::maybe_new(bs1.sidewalk_pos.pt(map), bs2.sidewalk_pos.pt(map))
+                    {
+                        batch.push(Color::RED, line.make_polygons(Distance::meters(3.0)));
+                    }
+                }
+            }
+        }
+
+        let route = map.get_br(id);
+        let mut col = vec![
+            Line(format!("Editing bus route {}", route.name))
+                .small_heading()
+                .draw(ctx)
+                .margin_below(10),
+            "Click a bus stop on the map to add or remove it"
+                .draw_text(ctx)
+                .margin_below(10),
+        ];
+        for (idx, bs) in stops.iter().enumerate() {
+            let road = map.get_parent(map.get_bs(*bs).sidewalk_pos.lane());
+            col.push(
+                Widget::row(vec![
+                    format!("Stop {}: {}", idx + 1, road.get_name())
+                        .draw_text(ctx)
+                        .margin_right(10),
+                    if stops.len() > 2 {
+                        Btn::text_fg("remove").build(ctx, format!("remove stop {}", idx), None)
+                    } else {
+                        Btn::text_fg("remove").inactive(ctx)
+                    }
+                    .align_right(),
+                ])
+                .margin_below(5),
+            );
+        }
+        col.push(
+            Widget::row(vec![
+                "Minutes between buses (0 for one bus):"
+                    .draw_text(ctx)
+                    .margin_right(10),
+                Spinner::new(ctx, (0, MAX_HEADWAY_MINUTES), headway_mins).named("headway"),
+            ])
+            .margin_above(10),
+        );
+        col.push(
+            format!("{} buses will run per day", spawn_times(headway_mins).len()).draw_text(ctx),
+        );
+        if let Some(ref err) = error {
+            col.push(Line(err).fg(Color::RED).draw(ctx).margin_above(10));
+        }
+        col.push(
+            Widget::row(vec![
+                if error.is_none() {
+                    Btn::text_bg2("Apply").build_def(ctx, hotkey(Key::Enter))
+                } else {
+                    Btn::text_bg2("Apply").inactive(ctx)
+                }
+                .margin_right(10),
+                Btn::text_bg2("Cancel").build_def(ctx, hotkey(Key::Escape)),
+            ])
+            .margin_above(10),
+        );
+
+        RouteEditor {
+            composite: Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(16))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .max_size_percent(30, 80)
+                .build(ctx),
+            id,
+            mode,
+            labels: StopLabels::new(ctx, app, &stops),
+            stops,
+            headway_mins,
+            error,
+            draw: ctx.upload(batch),
+        }
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App) {
+        let stops = std::mem::replace(&mut self.stops, Vec::new());
+        *self = RouteEditor::make(
+            ctx,
+            app,
+            self.id,
+            self.mode.clone(),
+            stops,
+            self.headway_mins,
+        );
+    }
+}
+
+impl State for RouteEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+            match app.primary.current_selection {
+                Some(ID::BusStop(_)) => {}
+                _ => {
+                    app.primary.current_selection = None;
+                }
+            }
+        }
+        if let Some(ID::BusStop(bs)) = app.primary.current_selection {
+            if let Some(idx) = self.stops.iter().position(|x| *x == bs) {
+                if self.stops.len() > 2 && app.per_obj.left_click(ctx, "remove this stop") {
+                    self.stops.remove(idx);
+                    self.rebuild(ctx, app);
+                }
+            } else if app.per_obj.left_click(ctx, "add this stop to the route") {
+                let idx = best_insertion(&self.stops, bs, app);
+                self.stops.insert(idx, bs);
+                self.rebuild(ctx, app);
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let map = &app.primary.map;
+                    let route = map.get_br(self.id);
+                    let mut edits = map.get_edits().clone();
+                    if self.stops != route.stops {
+                        edits.commands.push(EditCmd::ChangeRouteStops {
+                            id: self.id,
+                            new: self.stops.clone(),
+                            old: route.stops.clone(),
+                        });
+                    }
+                    let new_times = spawn_times(self.headway_mins);
+                    if new_times != route.spawn_times {
+                        edits.commands.push(EditCmd::ChangeRouteSchedule {
+                            id: self.id,
+                            new: new_times,
+                            old: route.spawn_times.clone(),
+                        });
+                    }
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Pop;
+                }
+                x => {
+                    let idx = x["remove stop ".len()..].parse::<usize>().unwrap();
+                    self.stops.remove(idx);
+                    self.rebuild(ctx, app);
+                }
+            },
+            None => {}
+        }
+
+        let headway_mins = self.composite.spinner("headway");
+        if headway_mins != self.headway_mins {
+            self.headway_mins = headway_mins;
+            self.rebuild(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.labels.draw(g);
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.rebuild(ctx, app);
+    }
+}
+
+// Just one bus, or a bus every so often for the whole day
+fn spawn_times(headway_mins: usize) -> Vec<Time> {
+    if headway_mins == 0 {
+        return vec![Time::START_OF_DAY];
+    }
+    let headway = Duration::minutes(headway_mins);
+    let mut times = Vec::new();
+    let mut t = Time::START_OF_DAY;
+    while t < Time::START_OF_DAY + Duration::hours(24) {
+        times.push(t);
+        t = t + headway;
+    }
+    times
+}
+
+// Irregular schedules get summarized by the first gap
+fn headway_mins(times: &Vec<Time>) -> usize {
+    if times.len() < 2 {
+        return 0;
+    }
+    let mins = ((times[1] - times[0]).inner_seconds() / 60.0).round() as usize;
+    mins.min(MAX_HEADWAY_MINUTES)
+}
+
+// Where to put a new stop so the loop gets the least bit longer, going as the crow flies
+fn best_insertion(stops: &Vec<BusStopID>, new: BusStopID, app: &App) -> usize {
+    let map = &app.primary.map;
+    let pt = |bs: BusStopID| map.get_bs(bs).sidewalk_pos.pt(map);
+    let new_pt = pt(new);
+    (0..stops.len())
+        .min_by_key(|idx| {
+            let pt1 = pt(stops[*idx]);
+            let pt2 = pt(stops[(*idx + 1) % stops.len()]);
+            pt1.dist_to(new_pt) + new_pt.dist_to(pt2) - pt1.dist_to(pt2)
+        })
+        .map(|idx| idx + 1)
+        .unwrap_or(0)
+}
@@ -1,6 +1,8 @@
 use crate::app::App;
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, close_intersection, TrafficSignalEditor};
+use crate::edit::{
+    apply_map_edits, close_intersection, TrafficSignalEditor, TurnRestrictionEditor,
+};
 use crate::game::{State, Transition};
 use crate::render::DrawIntersection;
 use crate::sandbox::GameplayMode;
@@ -15,8 +17,7 @@ use map_model::{
 };
 use std::collections::HashMap;
 
-// TODO For now, individual turns can't be manipulated. Banning turns could be useful, but I'm not
-// sure what to do about the player orphaning a section of the map.
+// Individual turns are banned in the TurnRestrictionEditor.
 pub struct StopSignEditor {
     composite: Composite,
     id: IntersectionID,
@@ -59,6 +60,11 @@ impl StopSignEditor {
                 },
                 Btn::text_fg("close intersection for construction").build_def(ctx, hotkey(Key::C)),
                 Btn::text_fg("convert to traffic signal").build_def(ctx, None),
+                if mode.can_edit_lanes() {
+                    Btn::text_fg("edit turn restrictions").build_def(ctx, hotkey(Key::T))
+                } else {
+                    Btn::text_fg("edit turn restrictions").inactive(ctx)
+                },
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
             ])
             .bg(app.cs.panel_bg)
@@ -145,6 +151,9 @@ impl State for StopSignEditor {
                 "close intersection for construction" => {
                     return close_intersection(ctx, app, self.id, true);
                 }
+                "edit turn restrictions" => {
+                    return Transition::Replace(TurnRestrictionEditor::new(ctx, app, self.id));
+                }
                 "convert to traffic signal" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     edits.commands.push(EditCmd::ChangeIntersection {
@@ -204,4 +213,8 @@ impl State for StopSignEditor {
             CommonState::draw_osd(g, app);
         }
     }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.composite = StopSignEditor::new(ctx, app, self.id, self.mode.clone()).composite;
+    }
 }
@@ -1,6 +1,6 @@
 use crate::app::{App, ShowEverything};
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, close_intersection, StopSignEditor};
+use crate::edit::{apply_map_edits, close_intersection, StopSignEditor, TurnRestrictionEditor};
 use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
 use crate::render::{
     draw_signal_phase, make_signal_diagram, DrawOptions, DrawTurnGroup, BIG_ARROW_THICKNESS,
@@ -14,8 +14,8 @@ use ezgui::{
 };
 use geom::{ArrowCap, Distance, Duration};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, Phase,
-    TurnGroupID, TurnPriority,
+    ControlStopSign, ControlTrafficSignal, CrosswalkTiming, EditCmd, EditIntersection,
+    IntersectionID, Phase, TurnGroupID, TurnPriority,
 };
 use std::collections::BTreeSet;
 
@@ -79,12 +79,12 @@ impl State for TrafficSignalEditor {
         ctx.canvas_movement();
 
         // TODO Buttons for these...
-        if self.current_phase != 0 && ctx.input.new_was_pressed(&hotkey(Key::UpArrow).unwrap()) {
+        if self.current_phase != 0 && ctx.action_pressed("previous phase", hotkey(Key::UpArrow)) {
             self.change_phase(self.current_phase - 1, ctx, app);
         }
 
         if self.current_phase != app.primary.map.get_traffic_signal(self.i).phases.len() - 1
-            && ctx.input.new_was_pressed(&hotkey(Key::DownArrow).unwrap())
+            && ctx.action_pressed("next phase", hotkey(Key::DownArrow))
         {
             self.change_phase(self.current_phase + 1, ctx, app);
         }
@@ -101,6 +101,25 @@ impl State for TrafficSignalEditor {
                         - 1;
                     return Transition::Push(change_duration(app, self.i, idx));
                 }
+                x if x.starts_with("change walk timing of phase ") => {
+                    let idx = x["change walk timing of phase ".len()..]
+                        .parse::<usize>()
+                        .unwrap()
+                        - 1;
+
+                    let mut new_signal = orig_signal.clone();
+                    new_signal.phases[idx].crosswalk_timing =
+                        match new_signal.phases[idx].crosswalk_timing {
+                            CrosswalkTiming::Fixed => CrosswalkTiming::Actuated,
+                            CrosswalkTiming::Actuated => CrosswalkTiming::Fixed,
+                        };
+                    self.command_stack.push(orig_signal.clone());
+                    self.redo_stack.clear();
+                    self.top_panel = make_top_panel(ctx, app, true, false);
+                    change_traffic_signal(new_signal, ctx, app);
+                    self.change_phase(idx, ctx, app);
+                    return Transition::Keep;
+                }
                 x if x.starts_with("delete phase ") => {
                     let idx = x["delete phase ".len()..].parse::<usize>().unwrap() - 1;
 
@@ -231,7 +250,7 @@ impl State for TrafficSignalEditor {
                     abstutil::write_json(
                         format!(
                             "../traffic_signal_data/{}.json",
-                            ts.intersection_osm_node_id
+                            ts.signal.intersection_osm_node_id
                         ),
                         &ts,
                     );
@@ -292,6 +311,7 @@ impl State for TrafficSignalEditor {
             &phase,
             self.i,
             None,
+            None,
             &mut batch,
             app,
             app.opts.traffic_signal_style.clone(),
@@ -409,6 +429,16 @@ impl State for TrafficSignalEditor {
             CommonState::draw_osd(g, app);
         }
     }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.change_phase(self.current_phase, ctx, app);
+        self.top_panel = make_top_panel(
+            ctx,
+            app,
+            !self.command_stack.is_empty(),
+            !self.redo_stack.is_empty(),
+        );
+    }
 }
 
 pub fn make_top_panel(ctx: &mut EventCtx, app: &App, can_undo: bool, can_redo: bool) -> Composite {
@@ -511,6 +541,7 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
         let stop_sign = "convert to stop signs";
         let close = "close intersection for construction";
         let offset = "edit signal offset";
+        let turns = "edit turn restrictions";
         let reset = "reset to default";
 
         let mut choices = vec![use_template];
@@ -523,6 +554,9 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
             choices.push(close);
         }
         choices.push(offset);
+        if mode.can_edit_lanes() {
+            choices.push(turns);
+        }
         choices.push(reset);
 
         let mut wizard = wiz.wrap(ctx);
@@ -577,6 +611,10 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
                 ))))
             }
             x if x == close => Some(close_intersection(ctx, app, i, false)),
+            // Banning turns regenerates the signal, so don't return to the signal editor
+            x if x == turns => Some(Transition::PopThenReplace(TurnRestrictionEditor::new(
+                ctx, app, i,
+            ))),
             x if x == offset => {
                 let new_duration = wizard.input_usize_prefilled(
                     "What should the offset of this traffic signal be (seconds)?",
@@ -788,13 +826,14 @@ impl State for PreviewTrafficSignal {
         if self.speed.is_paused() {
             Transition::Keep
         } else {
+            ctx.request_animation_frames();
             Transition::KeepWithMode(EventLoopMode::Animation)
         }
     }
 
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.composite.draw(g);
-        self.speed.draw(g);
+        self.speed.draw(g, app);
         self.time_panel.draw(g);
     }
 }
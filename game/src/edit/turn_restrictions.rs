@@ -0,0 +1,218 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::game::{State, Transition};
+use crate::render::BIG_ARROW_THICKNESS;
+use crate::sandbox::CURRENT_TURN;
+use abstutil::Severity;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, VerticalAlignment, Widget,
+};
+use geom::{Angle, ArrowCap, Circle, Distance, PolyLine};
+use map_model::{connectivity, EditCmd, IntersectionID, LaneID, Turn};
+use std::collections::HashSet;
+
+// Cycles through the vehicle turns at one intersection, banning or allowing each one.
+pub struct TurnRestrictionEditor {
+    composite: Composite,
+    i: IntersectionID,
+    // Every vehicle turn the lanes here could support, including the banned ones and the ones OSM
+    // restrictions leave out
+    turns: Vec<Turn>,
+    current: usize,
+    // Lanes leading into this intersection that can't go anywhere
+    stranded: HashSet<LaneID>,
+    draw_banned: Drawable,
+}
+
+impl TurnRestrictionEditor {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, i: IntersectionID) -> Box<dyn State> {
+        app.primary.current_selection = None;
+        let mut editor = TurnRestrictionEditor {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            i,
+            turns: app.primary.map.get_possible_vehicle_turns(i),
+            current: 0,
+            stranded: HashSet::new(),
+            draw_banned: ctx.upload(GeomBatch::new()),
+        };
+        editor.refresh(ctx, app);
+        Box::new(editor)
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        self.stranded = connectivity::find_stranded_lanes(map)
+            .into_iter()
+            .filter(|l| map.get_l(*l).dst_i == self.i)
+            .collect();
+
+        // Banned by the player or by OSM
+        let mut batch = GeomBatch::new();
+        for t in &self.turns {
+            if map.maybe_get_t(t.id).is_none() {
+                batch.extend(Color::RED, slash_icon(&t.geom));
+            }
+        }
+        for l in &self.stranded {
+            let lane = map.get_l(*l);
+            batch.push(
+                Color::RED.alpha(0.5),
+                lane.lane_center_pts.make_polygons(lane.width),
+            );
+        }
+        self.draw_banned = ctx.upload(batch);
+
+        self.composite = self.make_panel(ctx, app);
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App) -> Composite {
+        let map = &app.primary.map;
+        let mut col = vec![Widget::row(vec![
+            Line(format!(
+                "Turn restrictions at {}",
+                map.get_i(self.i).name(map)
+            ))
+            .small_heading()
+            .draw(ctx),
+            Btn::text_fg("Finish")
+                .build_def(ctx, hotkey(Key::Escape))
+                .align_right(),
+        ])];
+
+        if self.turns.is_empty() {
+            col.push(Text::from(Line("No vehicles turn here")).draw(ctx));
+        } else {
+            let t = self.turns[self.current].id;
+            let exists = map.maybe_get_t(t).is_some();
+            col.push(Widget::row(vec![
+                Btn::text_fg("<")
+                    .build(ctx, "previous turn", hotkey(Key::LeftArrow))
+                    .margin(5),
+                Text::from(
+                    Line(format!("turn {} of {}", self.current + 1, self.turns.len())).secondary(),
+                )
+                .draw(ctx)
+                .margin(5)
+                .centered_vert(),
+                Btn::text_fg(">")
+                    .build(ctx, "next turn", hotkey(Key::RightArrow))
+                    .margin(5),
+            ]));
+
+            let mut txt = Text::new();
+            txt.add(Line(format!("From {}", map.get_parent(t.src).get_name())));
+            txt.add(Line(format!("To {}", map.get_parent(t.dst).get_name())));
+            if map.get_edits().banned_turns.contains(&t) {
+                txt.add(Line("Banned").fg(Color::RED));
+            } else if !exists {
+                txt.add(Line("Not allowed by OpenStreetMap").fg(Color::RED));
+            } else if map.get_edits().allowed_turns.contains(&t) {
+                txt.add(Line("Allowed, overriding OpenStreetMap").secondary());
+            }
+            col.push(txt.draw(ctx));
+            col.push(
+                Btn::text_fg(if exists {
+                    "ban this turn"
+                } else {
+                    "allow this turn"
+                })
+                .build(ctx, "toggle turn", hotkey(Key::Space)),
+            );
+        }
+
+        if !self.stranded.is_empty() {
+            col.push(
+                Text::from(
+                    Line(format!(
+                        "{} lanes here can't turn anywhere",
+                        self.stranded.len()
+                    ))
+                    .fg(Color::RED),
+                )
+                .draw(ctx),
+            );
+        }
+
+        Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(16))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx)
+    }
+}
+
+impl State for TurnRestrictionEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Finish" => {
+                    return Transition::Pop;
+                }
+                "previous turn" => {
+                    self.current = (self.current + self.turns.len() - 1) % self.turns.len();
+                    self.composite = self.make_panel(ctx, app);
+                }
+                "next turn" => {
+                    self.current = (self.current + 1) % self.turns.len();
+                    self.composite = self.make_panel(ctx, app);
+                }
+                "toggle turn" => {
+                    let t = self.turns[self.current].id;
+                    let banned = app.primary.map.maybe_get_t(t).is_some();
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(EditCmd::ChangeTurnRestriction { t, banned });
+                    let num_stranded = self.stranded.len();
+                    apply_map_edits(ctx, app, edits);
+                    self.refresh(ctx, app);
+                    if self.stranded.len() > num_stranded {
+                        app.toasts.notify(
+                            Severity::Warn,
+                            format!(
+                                "Banning this turn leaves {} lanes with no way out",
+                                self.stranded.len() - num_stranded
+                            ),
+                        );
+                    }
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_banned);
+        if let Some(t) = self.turns.get(self.current) {
+            g.draw_polygon(
+                CURRENT_TURN.alpha(0.5),
+                &t.geom
+                    .make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle)
+                    .unwrap(),
+            );
+        }
+
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// A circle with a slash through it, halfway along the turn
+fn slash_icon(pl: &PolyLine) -> Vec<geom::Polygon> {
+    let center = pl.middle();
+    let radius = Distance::meters(1.5);
+    let thickness = Distance::meters(0.3);
+    let slash = PolyLine::new(vec![
+        center.project_away(radius, Angle::new_degs(135.0)),
+        center.project_away(radius, Angle::new_degs(-45.0)),
+    ]);
+    vec![
+        Circle::outline(center, radius, thickness),
+        slash.make_polygons(thickness),
+    ]
+}
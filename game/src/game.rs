@@ -1,10 +1,13 @@
 use crate::app::{App, Flags, ShowEverything};
+use crate::debug::LogViewer;
 use crate::options::Options;
 use crate::pregame::TitleScreen;
 use crate::render::DrawOptions;
 use crate::sandbox::{GameplayMode, SandboxMode};
+use abstutil::Severity;
 use ezgui::{Canvas, Drawable, EventCtx, EventLoopMode, GfxCtx, Wizard, GUI};
 use geom::Polygon;
+use instant::Instant;
 
 // This is the top-level of the GUI logic. This module should just manage interactions between the
 // top-level game states.
@@ -12,6 +15,8 @@ pub struct Game {
     // A stack of states
     states: Vec<Box<dyn State>>,
     app: App,
+    // The UI scale factor that the current panels were rendered at
+    scale_factor: f64,
 }
 
 impl Game {
@@ -22,11 +27,17 @@ impl Game {
         maybe_mode: Option<GameplayMode>,
         ctx: &mut EventCtx,
     ) -> Game {
+        let load = &flags.sim_flags.load;
         let title = !opts.dev
-            && !flags.sim_flags.load.contains("data/player/save")
-            && !flags.sim_flags.load.contains("data/system/scenarios")
+            && !load.starts_with(&abstutil::path_all_savestates())
+            && !load.starts_with(&abstutil::path("system/scenarios"))
             && maybe_mode.is_none();
         let mut app = App::new(flags, opts, ctx, title);
+        // After App::new, so any problems show up as toasts
+        ctx.canvas.keybindings = ezgui::KeyBindings::load(
+            abstutil::path_keybindings(),
+            &mut abstutil::Timer::new("load key bindings"),
+        );
 
         // Handle savestates
         let savestate = if app
@@ -34,7 +45,7 @@ impl Game {
             .current_flags
             .sim_flags
             .load
-            .starts_with("../data/player/saves/")
+            .starts_with(&abstutil::path_all_savestates())
         {
             assert!(maybe_mode.is_none());
             Some(app.primary.clear_sim())
@@ -70,15 +81,44 @@ impl Game {
             // PlayScenario without clobbering.
             app.primary.sim = ss;
         }
-        Game { states, app }
+        Game {
+            states,
+            app,
+            scale_factor: ctx.get_scale_factor(),
+        }
     }
 }
 
-impl GUI for Game {
-    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+impl Game {
+    fn handle_event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
         self.app.per_obj.reset();
+        // Whatever stepped the sim last frame, it's waiting on these now
+        if self.app.primary.sim.has_due_interventions() {
+            crate::edit::apply_interventions(ctx, &mut self.app);
+        } else {
+            crate::edit::sync_intervention_edits(ctx, &mut self.app);
+        }
+        self.app.primary.sim_events = self.app.primary.sim.collect_events();
+
+        // Either the player changed it in the settings, or the window moved to another monitor
+        if ctx.get_scale_factor() != self.scale_factor {
+            self.scale_factor = ctx.get_scale_factor();
+            for state in self.states.iter_mut() {
+                state.recreate_panels(ctx, &mut self.app);
+            }
+        }
 
         let transition = self.states.last_mut().unwrap().event(ctx, &mut self.app);
+        // Whatever state starts or ends, a multi-selection made before doesn't apply to it
+        match transition {
+            Transition::Keep
+            | Transition::KeepWithMode(_)
+            | Transition::KeepWithMouseover
+            | Transition::KeepWithData(_) => {}
+            _ => {
+                self.app.primary.multi_selection.clear();
+            }
+        }
         // If we fall through, there's a new state that we need to wakeup.
         match transition {
             Transition::Keep => {
@@ -151,10 +191,42 @@ impl GUI for Game {
         // Let the new state initialize with a fake event. Usually these just return
         // Transition::Keep, but nothing stops them from doing whatever. (For example, entering
         // tutorial mode immediately pushes on a Warper.) So just recurse.
-        ctx.no_op_event(true, |ctx| self.event(ctx))
+        ctx.no_op_event(true, |ctx| self.handle_event(ctx))
+    }
+}
+
+impl GUI for Game {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        if let Some(id) = self.app.toasts.event(ctx, &self.app.cs) {
+            self.states.push(LogViewer::new(ctx, &self.app, Some(id)));
+        }
+
+        let started = Instant::now();
+        let mode = self.handle_event(ctx);
+        if let Some(ref timings) = self.app.frame_timings {
+            timings.record_event(abstutil::elapsed_seconds(started));
+            if timings.is_done() {
+                let path = timings.path().to_string();
+                self.app.frame_timings = None;
+                self.app
+                    .toasts
+                    .notify(Severity::Info, format!("Wrote frame timings to {}", path));
+            }
+        }
+
+        // Toasts need to disappear on their own, on time, even if the player isn't touching
+        // anything. Otherwise the runner idles and only updates a few times a second.
+        if !self.app.toasts.is_empty() {
+            ctx.request_animation_frames();
+            if mode == EventLoopMode::InputOnly {
+                return EventLoopMode::Animation;
+            }
+        }
+        mode
     }
 
     fn draw(&self, g: &mut GfxCtx) {
+        let started = Instant::now();
         let state = self.states.last().unwrap();
 
         match state.draw_baselayer() {
@@ -186,6 +258,11 @@ impl GUI for Game {
             }
         }
         state.draw(g, &self.app);
+        self.app.toasts.draw(g);
+
+        if let Some(ref timings) = self.app.frame_timings {
+            timings.finish_frame(abstutil::elapsed_seconds(started), &self.app.primary.sim);
+        }
     }
 
     fn dump_before_abort(&self, canvas: &Canvas) {
@@ -199,6 +276,16 @@ impl GUI for Game {
 
     fn before_quit(&self, canvas: &Canvas) {
         canvas.save_camera_state(self.app.primary.map.get_name());
+        abstutil::flush_log_file();
+    }
+
+    fn replay_checkpoint(&self) -> Option<String> {
+        Some(format!(
+            "{} at {}, selecting {:?}",
+            self.app.primary.map.get_name(),
+            self.app.primary.sim.time(),
+            self.app.primary.current_selection
+        ))
     }
 }
 
@@ -220,6 +307,8 @@ pub trait State: downcast_rs::Downcast {
 
     // Before this state is popped or replaced, call this.
     fn on_destroy(&mut self, _: &mut EventCtx, _: &mut App) {}
+    // When the UI scale factor changes, panels rendered at the old scale should be rebuilt.
+    fn recreate_panels(&mut self, _: &mut EventCtx, _: &mut App) {}
     // We don't need an on_enter -- the constructor for the state can just do it.
 }
 
@@ -1,5 +1,5 @@
 use crate::app::{App, PerMap};
-use ezgui::{hotkey, Btn, Color, EventCtx, Key, Line, Text, TextSpan, Widget};
+use ezgui::{hotkey, Btn, Choice, Color, EventCtx, Key, Line, Text, TextSpan, Widget};
 use geom::{Duration, Pt2D};
 use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, ParkingLotID, RoadID};
 use sim::{AgentID, CarID, PedestrianID, TripMode, TripPhaseType};
@@ -101,6 +101,29 @@ pub fn nice_map_name(name: &str) -> &str {
     }
 }
 
+// For menus of saved files, so old ones stand out before they're upgraded on load. Like
+// "name (version 1, 12 KB, modified 3 days ago)"
+pub fn describe_saved_file(meta: &abstutil::ObjectMetadata) -> String {
+    match abstutil::file_version(&meta.path) {
+        Ok(version) => format!("{} (version {}, {})", meta.name, version, meta.describe()),
+        Err(_) => format!("{} ({})", meta.name, meta.describe()),
+    }
+}
+
+// Files that obviously can't be loaded are still listed, so they don't silently disappear, but
+// can't be picked.
+pub fn saved_file_choice<T: abstutil::Versioned, D>(
+    meta: &abstutil::ObjectMetadata,
+    data: D,
+) -> Choice<D> {
+    match abstutil::check_versioned_file::<T>(&meta.path) {
+        Ok(()) => Choice::new(describe_saved_file(meta), data),
+        Err(err) => Choice::new(format!("{} (can't load)", meta.name), data)
+            .active(false)
+            .tooltip(err),
+    }
+}
+
 // Shorter is better
 pub fn cmp_duration_shorter(after: Duration, before: Duration) -> Vec<TextSpan> {
     if after.epsilon_eq(before) {
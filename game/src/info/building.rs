@@ -1,10 +1,14 @@
 use crate::app::App;
-use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
+use crate::info::{header_btns, make_table, make_tabs, Details, OpenTrip, Tab};
 use crate::render::DrawPedestrian;
+use abstutil::Counter;
 use ezgui::{Btn, Color, EventCtx, Line, Text, TextExt, Widget};
 use geom::{Angle, Circle, Distance, Speed, Time};
 use map_model::{BuildingID, LaneID, Traversable, SIDEWALK_THICKNESS};
-use sim::{DrawPedestrianInput, PedestrianID, PersonID, TripMode, TripResult};
+use sim::{
+    AgentID, DrawPedestrianInput, ParkingSpot, PedestrianID, PersonID, TripEndpoint, TripMode,
+    TripResult, TripState,
+};
 use std::collections::BTreeMap;
 
 pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID) -> Vec<Widget> {
@@ -85,6 +89,59 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID
         txt.add(Line("No nearby parking available"))
     }
 
+    // There's no notion of home; guess it's wherever somebody spends the night.
+    let mut residents_cars = Vec::new();
+    for person in app.primary.sim.get_all_people() {
+        if let Some(t) = person.trips.last() {
+            if app.primary.sim.trip_info(*t).2 == TripEndpoint::Bldg(id) {
+                residents_cars.extend(app.primary.sim.get_parked_cars_by_owner(person.id));
+            }
+        }
+    }
+    if !residents_cars.is_empty() {
+        let color = app.cs.parking_trip;
+        let here = b.label_center;
+        let mut far_away = 0;
+        for car in &residents_cars {
+            if let Some(pt) = app
+                .primary
+                .sim
+                .canonical_pt_for_agent(AgentID::Car(car.vehicle.id), &app.primary.map)
+            {
+                if let Some(line) = geom::Line::maybe_new(here, pt) {
+                    details
+                        .unzoomed
+                        .push(color, line.make_polygons(Distance::meters(5.0)));
+                    details.zoomed.extend(
+                        color,
+                        line.to_polyline().dashed_lines(
+                            Distance::meters(0.75),
+                            Distance::meters(1.0),
+                            Distance::meters(0.4),
+                        ),
+                    );
+                }
+                details
+                    .unzoomed
+                    .push(color, Circle::new(pt, Distance::meters(10.0)).to_polygon());
+                details
+                    .zoomed
+                    .push(color, Circle::new(pt, Distance::meters(2.0)).to_polygon());
+            }
+            if let ParkingSpot::Offstreet(b, _) = car.spot {
+                if b == id {
+                    continue;
+                }
+            }
+            far_away += 1;
+        }
+        txt.add(Line(format!(
+            "{} residents' cars are parked, {} of them elsewhere",
+            residents_cars.len(),
+            far_away
+        )));
+    }
+
     if !txt.is_empty() {
         rows.push(txt.draw(ctx))
     }
@@ -152,6 +209,53 @@ pub fn people(ctx: &mut EventCtx, app: &App, details: &mut Details, id: Building
     rows
 }
 
+pub fn trips(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID) -> Vec<Widget> {
+    let mut rows = header(ctx, app, details, id, Tab::BldgTrips(id));
+    let sim = &app.primary.sim;
+
+    for (label, trips) in vec![
+        ("starting", sim.trips_from_bldg(id)),
+        ("ending", sim.trips_to_bldg(id)),
+    ] {
+        let mut per_mode = Counter::new();
+        let mut ongoing = Vec::new();
+        for t in trips {
+            let trip = sim.trip_detail(*t);
+            per_mode.inc(trip.mode);
+            if let TripState::Ongoing(_) = trip.state {
+                ongoing.push(trip);
+            }
+        }
+
+        let mut txt = Text::from(Line(format!("{} trips {} here", trips.len(), label)));
+        for mode in TripMode::all() {
+            if per_mode.get(mode) > 0 {
+                txt.add(Line(format!("- {} {}", per_mode.get(mode), mode.verb())).secondary());
+            }
+        }
+        rows.push(txt.draw(ctx).margin_above(10));
+
+        if ongoing.is_empty() {
+            rows.push("None happening right now".draw_text(ctx));
+        }
+        for trip in ongoing {
+            details.hyperlinks.insert(
+                trip.id.to_string(),
+                Tab::PersonTrips(trip.person, OpenTrip::single(trip.id)),
+            );
+            rows.push(Widget::row(vec![
+                Btn::text_bg1(trip.id.to_string()).build_def(ctx, None),
+                format!("{} {}", trip.person, trip.mode.ongoing_verb())
+                    .draw_text(ctx)
+                    .centered_vert()
+                    .margin_left(10),
+            ]));
+        }
+    }
+
+    rows
+}
+
 fn header(
     ctx: &EventCtx,
     app: &App,
@@ -170,7 +274,11 @@ fn header(
         ctx,
         &mut details.hyperlinks,
         tab,
-        vec![("Info", Tab::BldgInfo(id)), ("People", Tab::BldgPeople(id))],
+        vec![
+            ("Info", Tab::BldgInfo(id)),
+            ("People", Tab::BldgPeople(id)),
+            ("Trips", Tab::BldgTrips(id)),
+        ],
     ));
 
     draw_occupants(details, app, id, None);
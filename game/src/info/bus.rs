@@ -58,8 +58,8 @@ pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusStopID)
     rows
 }
 
-// TODO For now, this conflates a single bus with the whole route, but that's fine, since the sim
-// only spawns one per route anyway.
+// TODO For now, this conflates a single bus with the whole route, even though edited schedules
+// can run several buses per route.
 pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID) -> Vec<Widget> {
     let mut rows = bus_header(ctx, app, details, id, Tab::BusStatus(id));
 
@@ -12,6 +12,7 @@ use crate::common::Warping;
 use crate::game::Transition;
 use crate::helpers::{color_for_mode, hotkey_btn, ID};
 use crate::sandbox::{SandboxMode, TimeWarpScreen};
+use abstutil::Severity;
 use ezgui::{
     hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, LinePlot, Outcome, PlotOptions, Series, TextExt,
@@ -20,8 +21,8 @@ use ezgui::{
 use geom::{Circle, Distance, Time};
 use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, ParkingLotID};
 use sim::{
-    AgentID, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState, TripID, TripMode,
-    VehicleType,
+    AgentID, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState, SimEvent, TripID,
+    TripMode, VehicleType,
 };
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 pub use trip::OpenTrip;
@@ -61,6 +62,7 @@ pub enum Tab {
 
     BldgInfo(BuildingID),
     BldgPeople(BuildingID),
+    BldgTrips(BuildingID),
 
     ParkingLot(ParkingLotID),
 
@@ -140,7 +142,9 @@ impl Tab {
                 ParkingSpot::Offstreet(b, _) => Some(ID::Building(b)),
                 ParkingSpot::Lot(_, _) => Some(ID::Car(*c)),
             },
-            Tab::BldgInfo(b) | Tab::BldgPeople(b) => Some(ID::Building(*b)),
+            Tab::BldgInfo(b) | Tab::BldgPeople(b) | Tab::BldgTrips(b) => {
+                Some(ID::Building(*b))
+            }
             Tab::ParkingLot(pl) => Some(ID::ParkingLot(*pl)),
             Tab::Crowd(members) => Some(ID::PedCrowd(members.clone())),
             Tab::Area(a) => Some(ID::Area(*a)),
@@ -226,6 +230,7 @@ impl InfoPanel {
             ),
             Tab::BldgInfo(b) => (building::info(ctx, app, &mut details, b), true),
             Tab::BldgPeople(b) => (building::people(ctx, app, &mut details, b), false),
+            Tab::BldgTrips(b) => (building::trips(ctx, app, &mut details, b), false),
             Tab::ParkingLot(pl) => (parking_lot::info(ctx, app, &mut details, pl), true),
             Tab::Crowd(ref members) => (person::crowd(ctx, app, &mut details, members), true),
             Tab::Area(a) => (debug::area(ctx, app, &mut details, a), true),
@@ -350,6 +355,26 @@ impl InfoPanel {
             return (true, None);
         }
 
+        // Summarize the trip of the person we're following when they arrive
+        if let Tab::PersonTrips(p, _) | Tab::PersonBio(p) | Tab::PersonSchedule(p) = self.tab {
+            for (_, ev) in &app.primary.sim_events {
+                if let SimEvent::TripFinished {
+                    trip,
+                    person,
+                    total_time,
+                    ..
+                } = ev
+                {
+                    if *person == p {
+                        app.toasts.notify(
+                            Severity::Info,
+                            format!("{} finished {} after {}", p, trip, total_time),
+                        );
+                    }
+                }
+            }
+        }
+
         // Live update?
         if app.primary.sim.time() != self.time || ctx_actions.is_paused() != self.is_paused {
             let mut new = InfoPanel::new(ctx, app, self.tab.clone(), ctx_actions);
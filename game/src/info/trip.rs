@@ -5,7 +5,7 @@ use ezgui::{
     Btn, Color, EventCtx, GeomBatch, Line, LinePlot, PlotOptions, RewriteColor, Series, Text,
     TextExt, Widget,
 };
-use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Pt2D, Time};
+use geom::{ArrowCap, Circle, Distance, Duration, PolyLine, Polygon, Pt2D, Time};
 use map_model::{Map, Path, PathStep};
 use maplit::btreemap;
 use sim::{AgentID, PersonID, TripEndpoint, TripID, TripPhase, TripPhaseType, VehicleType};
@@ -14,8 +14,8 @@ use std::collections::BTreeMap;
 #[derive(Clone)]
 pub struct OpenTrip {
     pub show_after: bool,
-    // (unzoomed, zoomed). Indexed by order of TripPhase.
-    cached_routes: Vec<Option<(Polygon, Vec<Polygon>)>>,
+    // (unzoomed, zoomed, start). Indexed by order of TripPhase.
+    cached_routes: Vec<Option<(Polygon, Vec<Polygon>, Pt2D)>>,
 }
 // Ignore cached_routes
 impl std::cmp::PartialEq for OpenTrip {
@@ -53,7 +53,7 @@ pub fn ongoing(
     let (start_time, _, _, _) = app.primary.sim.trip_info(trip);
 
     let col_width = 7;
-    let props = app.primary.sim.agent_properties(agent);
+    let props = app.primary.sim.agent_properties(agent, &app.primary.map);
     // This is different than the entire TripMode, and also not the current TripPhaseType.
     // Sigh.
     let activity = match agent {
@@ -551,14 +551,28 @@ fn make_timeline(
                             Distance::meters(1.0),
                             Distance::meters(0.4),
                         ),
+                        trace.first_pt(),
                     )));
                 } else {
                     open_trip.cached_routes.push(None);
                 }
             }
-            if let Some((ref unzoomed, ref zoomed)) = open_trip.cached_routes[idx] {
+            if let Some((ref unzoomed, ref zoomed, start)) = open_trip.cached_routes[idx] {
                 details.unzoomed.push(color, unzoomed.clone());
                 details.zoomed.extend(color, zoomed.clone());
+                // Mark where the trip switches modes
+                if idx > 0 {
+                    for (batch, radius) in vec![
+                        (&mut details.unzoomed, Distance::meters(15.0)),
+                        (&mut details.zoomed, Distance::meters(3.0)),
+                    ] {
+                        batch.push(Color::WHITE, Circle::new(start, radius).to_polygon());
+                        batch.push(
+                            color.alpha(1.0),
+                            Circle::outline(start, radius, radius / 3.0),
+                        );
+                    }
+                }
             }
         } else if p.has_path_req {
             path_impossible = true;
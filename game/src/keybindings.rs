@@ -0,0 +1,160 @@
+use crate::app::App;
+use crate::game::{DrawBaselayer, State, Transition};
+use ezgui::{
+    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, MultiKey,
+    Outcome, Text, VerticalAlignment, Widget,
+};
+use std::collections::BTreeSet;
+
+// Only actions that've been used at least once this session (or that the player already
+// overrode) are listed, since hotkeys are only known once something asks for them.
+pub struct KeyBindingsScreen {
+    composite: Composite,
+    // The action waiting for the player to press a new key
+    capturing: Option<String>,
+}
+
+impl KeyBindingsScreen {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        Box::new(KeyBindingsScreen {
+            composite: make_composite(ctx, app, None),
+            capturing: None,
+        })
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut new = make_composite(ctx, app, self.capturing.as_ref());
+        new.restore(ctx, &self.composite);
+        self.composite = new;
+    }
+}
+
+impl State for KeyBindingsScreen {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(action) = self.capturing.clone() {
+            if let Some(key) = ctx.input.any_multikey_pressed() {
+                match key {
+                    MultiKey::Normal(Key::Escape) => {}
+                    MultiKey::Normal(Key::Backspace) => {
+                        ctx.canvas.keybindings.rebind(&action, None);
+                    }
+                    key => {
+                        ctx.canvas.keybindings.rebind(&action, Some(key));
+                    }
+                }
+                self.capturing = None;
+                self.refresh(ctx, app);
+            }
+            return Transition::Keep;
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save" => {
+                    ctx.canvas.keybindings.save(abstutil::path_keybindings());
+                    return Transition::Pop;
+                }
+                x => {
+                    if x.starts_with("rebind ") {
+                        self.capturing = Some(x["rebind ".len()..].to_string());
+                    } else if x.starts_with("reset ") {
+                        ctx.canvas.keybindings.reset(&x["reset ".len()..]);
+                    } else {
+                        unreachable!()
+                    }
+                    self.refresh(ctx, app);
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn make_composite(ctx: &mut EventCtx, app: &App, capturing: Option<&String>) -> Composite {
+    let conflicting: BTreeSet<String> = ctx
+        .canvas
+        .keybindings
+        .conflicts()
+        .into_iter()
+        .flat_map(|(_, actions)| actions)
+        .collect();
+
+    let mut rows = Vec::new();
+    for (action, default, current) in ctx.canvas.keybindings.all_actions() {
+        let mut txt = Text::from(Line(&action));
+        if capturing == Some(&action) {
+            txt.append(
+                Line(": press a new key (Escape to cancel, Backspace to unbind)")
+                    .fg(app.cs.hovering),
+            );
+        } else {
+            let describe = |key: &Option<MultiKey>| match key {
+                Some(key) => key.describe(),
+                None => "unbound".to_string(),
+            };
+            let line = Line(format!(": {}", describe(&current)));
+            txt.append(if conflicting.contains(&action) {
+                line.fg(Color::RED)
+            } else {
+                line
+            });
+            if ctx.canvas.keybindings.is_overridden(&action) {
+                txt.append(Line(format!(" (default {})", describe(&default))).secondary());
+            }
+        }
+
+        let mut row = vec![
+            txt.draw(ctx).centered_vert().margin_right(10),
+            Btn::text_fg("rebind").build(ctx, format!("rebind {}", action), None),
+        ];
+        if ctx.canvas.keybindings.is_overridden(&action) {
+            row.push(
+                Btn::text_fg("reset")
+                    .build(ctx, format!("reset {}", action), None)
+                    .margin_left(5),
+            );
+        }
+        rows.push(Widget::row(row).margin_below(5));
+    }
+
+    let mut col = vec![
+        Widget::row(vec![
+            Line("Key bindings").small_heading().draw(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])
+        .margin_below(10),
+        Text::from(
+            Line(
+                "Only actions used so far this session are listed. Keys bound to more than one \
+                 action are red.",
+            )
+            .secondary(),
+        )
+        .wrap_to_pct(ctx, 50)
+        .draw(ctx)
+        .margin_below(10),
+    ];
+    col.push(Widget::col(rows).margin_below(10));
+    col.push(Btn::text_bg2("save").build_def(ctx, None).centered_horiz());
+
+    Composite::new(Widget::col(col).padding(16).bg(app.cs.panel_bg))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .max_size_percent(60, 80)
+        .build(ctx)
+}
@@ -1,19 +1,21 @@
 use crate::app::App;
-use crate::common::ColorDiscrete;
+use crate::common::{ColorDiscrete, ColorLegend};
 use crate::layer::{Layer, LayerOutcome};
 use ezgui::{
-    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
-    Line, Text, TextExt, VerticalAlignment, Widget,
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, ScreenDims, ScreenPt, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Circle, Distance, Pt2D, Time};
-use map_model::{BusRouteID, PathConstraints, PathRequest, PathStep};
+use geom::{Circle, Distance, Duration, Pt2D, Time};
+use map_model::{BusRouteID, BusStopID, PathStep};
 
 // TODO This maybe shouldn't be a layer
 pub struct ShowBusRoute {
     time: Time,
     route: BusRouteID,
-    labels: Vec<(Text, Pt2D)>,
+    labels: StopLabels,
     bus_locations: Vec<Pt2D>,
+    // Length and round trip time, calculated once
+    estimate: (Distance, Duration),
 
     composite: Composite,
     unzoomed: Drawable,
@@ -30,11 +32,25 @@ impl Layer for ShowBusRoute {
         app: &mut App,
         minimap: &Composite,
     ) -> Option<LayerOutcome> {
+        // Only the buses move; the route itself doesn't change in the sandbox
         if app.primary.sim.time() != self.time {
-            *self = ShowBusRoute::new(ctx, app, self.route);
+            self.time = app.primary.sim.time();
+            self.bus_locations = bus_locations(app, self.route);
+            self.composite = make_panel(
+                ctx,
+                app,
+                self.route,
+                self.estimate,
+                self.bus_locations.len(),
+            );
         }
 
-        Layer::simple_event(ctx, minimap, &mut self.composite)
+        let outcome = Layer::simple_event(ctx, minimap, &mut self.composite);
+        let route = self.composite.dropdown_value("route");
+        if route != self.route {
+            *self = ShowBusRoute::new(ctx, app, route);
+        }
+        outcome
     }
     fn draw(&self, g: &mut GfxCtx, app: &App) {
         if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
@@ -43,21 +59,7 @@ impl Layer for ShowBusRoute {
             g.redraw(&self.zoomed);
         }
         self.composite.draw(g);
-
-        // TODO Do this once
-        let mut screen_batch = GeomBatch::new();
-        for (label, pt) in &self.labels {
-            screen_batch.append(
-                label
-                    .clone()
-                    .render_g(g)
-                    .centered_on(g.canvas.map_to_screen(*pt).to_pt()),
-            );
-        }
-        let draw = g.upload(screen_batch);
-        g.fork_screenspace();
-        g.redraw(&draw);
-        g.unfork();
+        self.labels.draw(g);
 
         let mut batch = GeomBatch::new();
         let radius = Distance::meters(20.0) / g.canvas.cam_zoom;
@@ -76,73 +78,118 @@ impl ShowBusRoute {
         let map = &app.primary.map;
         let route = app.primary.map.get_br(id);
 
-        let mut bus_locations = Vec::new();
-        for (_, pt) in app.primary.sim.location_of_buses(id, map) {
-            bus_locations.push(pt);
-        }
-
         let mut colorer = ColorDiscrete::new(app, vec![("route", app.cs.unzoomed_bus)]);
-        for (stop1, stop2) in
-            route
-                .stops
-                .iter()
-                .zip(route.stops.iter().skip(1))
-                .chain(std::iter::once((
-                    route.stops.last().unwrap(),
-                    &route.stops[0],
-                )))
-        {
-            let bs1 = map.get_bs(*stop1);
-            let bs2 = map.get_bs(*stop2);
-            for step in map
-                .pathfind(PathRequest {
-                    start: bs1.driving_pos,
-                    end: bs2.driving_pos,
-                    constraints: PathConstraints::Bus,
-                })
-                .unwrap()
-                .get_steps()
-            {
+        for path in route.all_paths(map) {
+            for step in path.get_steps() {
                 if let PathStep::Lane(l) = step {
                     colorer.add_l(*l, "route");
                 }
             }
         }
+        let (unzoomed, zoomed, _) = colorer.build(ctx);
 
-        let mut labels = Vec::new();
-        for (idx, bs) in route.stops.iter().enumerate() {
-            labels.push((
-                Text::from(Line(format!("{}", idx + 1))).with_bg(),
-                map.get_bs(*bs).sidewalk_pos.pt(map),
-            ));
-        }
-
-        let (unzoomed, zoomed, legend) = colorer.build(ctx);
+        let estimate = route.estimate_loop(map);
+        let bus_locations = bus_locations(app, id);
         ShowBusRoute {
             time: app.primary.sim.time(),
             route: id,
-            labels,
+            labels: StopLabels::new(ctx, app, &route.stops),
+            estimate,
             unzoomed,
             zoomed,
-            composite: Composite::new(
-                Widget::col(vec![
-                    Widget::row(vec![
-                        Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
-                            .margin_right(10),
-                        Line(&route.name).draw(ctx),
-                        Btn::plaintext("X")
-                            .build(ctx, "close", hotkey(Key::Escape))
-                            .align_right(),
-                    ]),
-                    format!("{} buses", bus_locations.len()).draw_text(ctx),
-                    legend,
-                ])
-                .padding(5)
-                .bg(app.cs.panel_bg),
-            )
-            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
-            .build(ctx),
+            composite: make_panel(ctx, app, id, estimate, bus_locations.len()),
             bus_locations,
         }
     }
 }
+
+fn bus_locations(app: &App, id: BusRouteID) -> Vec<Pt2D> {
+    app.primary
+        .sim
+        .location_of_buses(id, &app.primary.map)
+        .into_iter()
+        .map(|(_, pt)| pt)
+        .collect()
+}
+
+fn make_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: BusRouteID,
+    (length, driving_time): (Distance, Duration),
+    num_buses: usize,
+) -> Composite {
+    let map = &app.primary.map;
+    let route = map.get_br(id);
+    let mut choices: Vec<Choice<BusRouteID>> = map
+        .get_all_bus_routes()
+        .iter()
+        .map(|r| Choice::new(r.name.clone(), r.id))
+        .collect();
+    choices.sort_by_key(|c| c.label.clone());
+
+    let round_trip = driving_time + (route.stops.len() as f64) * sim::TIME_TO_WAIT_AT_STOP;
+    Composite::new(
+        Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg").margin_right(10),
+                Widget::dropdown(ctx, "route", id, choices),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            format!("{} stops, {} long", route.stops.len(), length).draw_text(ctx),
+            format!("Round trip takes at least {}", round_trip).draw_text(ctx),
+            format!(
+                "{} buses running, {} scheduled per day",
+                num_buses,
+                route.spawn_times.len()
+            )
+            .draw_text(ctx),
+            ColorLegend::row(ctx, app.cs.unzoomed_bus, "route"),
+        ])
+        .padding(5)
+        .bg(app.cs.panel_bg),
+    )
+    .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+    .build(ctx)
+}
+
+// Numbers each stop, in order. Labels stay the same size at any zoom, so they're drawn in
+// screen-space; each one is uploaded once and just moved around as the camera changes.
+pub struct StopLabels {
+    labels: Vec<(Drawable, ScreenDims, Pt2D)>,
+}
+
+impl StopLabels {
+    pub fn new(ctx: &EventCtx, app: &App, stops: &Vec<BusStopID>) -> StopLabels {
+        let map = &app.primary.map;
+        StopLabels {
+            labels: stops
+                .iter()
+                .enumerate()
+                .map(|(idx, bs)| {
+                    let batch = Text::from(Line(format!("{}", idx + 1)))
+                        .with_bg()
+                        .render_ctx(ctx);
+                    let dims = batch.get_dims();
+                    (
+                        ctx.upload(batch),
+                        dims,
+                        map.get_bs(*bs).sidewalk_pos.pt(map),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        for (draw, dims, pt) in &self.labels {
+            let center = g.canvas.map_to_screen(*pt);
+            g.redraw_at(
+                ScreenPt::new(center.x - dims.width / 2.0, center.y - dims.height / 2.0),
+                draw,
+            );
+        }
+    }
+}
@@ -7,6 +7,9 @@ use ezgui::{
 };
 use geom::{ArrowCap, Distance, PolyLine};
 
+// Anything steeper gets the darkest color
+const STEEPEST_GRADE: f64 = 0.15;
+
 pub struct Elevation {
     unzoomed: Drawable,
     zoomed: Drawable,
@@ -47,10 +50,7 @@ impl Elevation {
             let pct = r.percent_grade(&app.primary.map).abs();
             max = max.max(pct);
 
-            let color = app.cs.good_to_bad_red.eval(
-                // TODO Rescale based on a reasonable steepest grade, once the data doesn't suck
-                pct.max(0.0).min(1.0),
-            );
+            let color = app.cs.good_to_bad_red.eval((pct / STEEPEST_GRADE).min(1.0));
             colorer.add_r(r.id, color);
         }
 
@@ -60,13 +60,12 @@ impl Elevation {
         // TODO Or try gradient colors.
         for r in app.primary.map.all_roads() {
             let (mut pl, _) = r.get_thick_polyline(&app.primary.map).unwrap();
-            let e1 = app.primary.map.get_i(r.src_i).elevation;
-            let e2 = app.primary.map.get_i(r.dst_i).elevation;
-            if (e1 - e2).abs() / pl.length() < 0.01 {
+            let grade = r.percent_grade(&app.primary.map);
+            if grade.abs() < 0.01 {
                 // Don't bother with ~flat roads
                 continue;
             }
-            if e1 > e2 {
+            if grade < 0.0 {
                 pl = pl.reversed();
             }
 
@@ -102,12 +101,16 @@ impl Elevation {
                         .build(ctx, "close", hotkey(Key::Escape))
                         .align_right(),
                 ]),
-                Text::from_multiline(vec![
-                    Line(format!("Steepest road: {:.0}% grade", max * 100.0)),
-                    Line("Note: elevation data is currently wrong!").secondary(),
-                ])
-                .draw(ctx),
-                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["flat", "steep"]),
+                if max == 0.0 {
+                    Text::from(Line("This map has no elevation data").secondary()).draw(ctx)
+                } else {
+                    Text::from(Line(format!("Steepest road: {:.0}% grade", max * 100.0))).draw(ctx)
+                },
+                ColorLegend::gradient(
+                    ctx,
+                    &app.cs.good_to_bad_red,
+                    vec!["flat", "5%", "10%", "15%+"],
+                ),
             ])
             .padding(5)
             .bg(app.cs.panel_bg),
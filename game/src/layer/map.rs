@@ -254,7 +254,12 @@ impl Static {
         );
 
         let edits = app.primary.map.get_edits();
-        for l in edits.original_lts.keys().chain(&edits.reversed_lanes) {
+        for l in edits
+            .original_lts
+            .keys()
+            .chain(&edits.reversed_lanes)
+            .chain(&edits.closed_lanes)
+        {
             colorer.add_l(*l, "modified lane/intersection");
         }
         for i in edits.original_intersections.keys() {
@@ -263,6 +268,9 @@ impl Static {
         for r in &edits.changed_speed_limits {
             colorer.add_r(*r, "modified lane/intersection");
         }
+        for t in edits.banned_turns.iter().chain(&edits.allowed_turns) {
+            colorer.add_i(t.parent, "modified lane/intersection");
+        }
 
         Static::new(
             ctx,
@@ -273,6 +281,7 @@ impl Static {
             Text::from_multiline(vec![
                 Line(format!("{} lane types changed", edits.original_lts.len())),
                 Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
+                Line(format!("{} lanes closed", edits.closed_lanes.len())),
                 Line(format!(
                     "{} speed limits changed",
                     edits.changed_speed_limits.len()
@@ -281,6 +290,11 @@ impl Static {
                     "{} intersections changed",
                     edits.original_intersections.len()
                 )),
+                Line(format!(
+                    "{} turns banned, {} allowed",
+                    edits.banned_turns.len(),
+                    edits.allowed_turns.len()
+                )),
             ])
             .draw(ctx),
         )
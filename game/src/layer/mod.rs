@@ -1,9 +1,11 @@
 pub mod bus;
 mod elevation;
 pub mod map;
+pub mod neighborhoods;
 mod pandemic;
 mod parking;
 mod population;
+mod road_classes;
 pub mod traffic;
 
 use crate::app::App;
@@ -49,6 +51,7 @@ impl dyn Layer {
 // TODO Just return a bool for closed? Less readable...
 pub enum LayerOutcome {
     Close,
+    Transition(Transition),
 }
 
 // TODO Maybe overkill, but could embed a minimap and preview the layer on hover
@@ -69,6 +72,10 @@ impl PickLayer {
                 app.layer = None;
                 return None;
             }
+            Some(LayerOutcome::Transition(t)) => {
+                app.layer = Some(layer);
+                return Some(t);
+            }
             None => {}
         }
         app.layer = Some(layer);
@@ -106,6 +113,7 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("gridlock", Key::G),
                 ]),
                 Widget::col(vec![
                     "Map".draw_text(ctx).margin_below(10),
@@ -114,6 +122,7 @@ impl PickLayer {
                     btn("bike network", Key::B),
                     btn("bus network", Key::U),
                     btn("population map", Key::X),
+                    btn("neighborhoods", Key::H),
                 ]),
             ])
             .evenly_spaced(),
@@ -124,6 +133,7 @@ impl PickLayer {
             btn("amenities", Key::A),
             btn("backpressure", Key::Z),
             btn("elevation", Key::S),
+            btn("OSM road classes", Key::O),
         ]);
         if app.primary.sim.get_pandemic_model().is_some() {
             col.push(btn("pandemic model", Key::Y));
@@ -156,6 +166,9 @@ impl State for PickLayer {
                 "traffic jams" => {
                     app.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
                 }
+                "gridlock" => {
+                    app.layer = Some(Box::new(traffic::Gridlock::new(ctx, app)));
+                }
                 "throughput" => {
                     app.layer = Some(Box::new(traffic::Throughput::new(ctx, app, false)));
                 }
@@ -168,9 +181,15 @@ impl State for PickLayer {
                 "bus network" => {
                     app.layer = Some(Box::new(map::Static::bus_network(ctx, app)));
                 }
+                "neighborhoods" => {
+                    app.layer = Some(Box::new(neighborhoods::Neighborhoods::new(ctx, app, None)));
+                }
                 "elevation" => {
                     app.layer = Some(Box::new(elevation::Elevation::new(ctx, app)));
                 }
+                "OSM road classes" => {
+                    app.layer = Some(Box::new(road_classes::RoadClasses::new(ctx, app)));
+                }
                 "map edits" => {
                     app.layer = Some(Box::new(map::Static::edits(ctx, app)));
                 }
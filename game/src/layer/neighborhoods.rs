@@ -0,0 +1,958 @@
+use crate::app::App;
+use crate::common::Warping;
+use crate::game::{State, Transition, WizardState};
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::{prettyprint_usize, Severity, Timer};
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, LonLat, Polygon, Pt2D, Speed, Time};
+use instant::Instant;
+use map_model::{BuildingID, EditCmd, IntersectionID, LaneType, Map, RoadID, Traversable};
+use sim::{AgentID, CarID, CarStatus, GetDrawAgents, Sim, TripEndpoint, VehicleType};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{mpsc, Arc};
+
+// How often live metrics are recalculated, in sim time
+const LIVE_REFRESH: Duration = Duration::const_seconds(5.0);
+
+// One of the named regions in data/input/{city}/polygons that overlaps the current map
+pub struct Neighborhood {
+    pub name: String,
+    pub polygon: Polygon,
+}
+
+// Everything about a neighborhood that only depends on the map
+#[derive(Clone)]
+pub struct StaticSummary {
+    // In square meters
+    pub area: f64,
+    pub buildings: BTreeSet<BuildingID>,
+    pub roads: BTreeSet<RoadID>,
+    pub intersections: BTreeSet<IntersectionID>,
+    pub num_amenities: usize,
+    pub num_signals: usize,
+    pub onstreet_parking: usize,
+    pub lane_length: BTreeMap<LaneType, Distance>,
+}
+
+impl StaticSummary {
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{:.2} km²", self.area / 1_000_000.0),
+            format!(
+                "{} buildings, {} amenities",
+                prettyprint_usize(self.buildings.len()),
+                prettyprint_usize(self.num_amenities)
+            ),
+            format!(
+                "{} roads, {} intersections ({} signalized)",
+                prettyprint_usize(self.roads.len()),
+                prettyprint_usize(self.intersections.len()),
+                prettyprint_usize(self.num_signals)
+            ),
+            format!(
+                "{} on-street parking spots",
+                prettyprint_usize(self.onstreet_parking)
+            ),
+        ];
+        for (lt, dist) in &self.lane_length {
+            lines.push(format!(
+                "{:.1} km of {}s",
+                dist.inner_meters() / 1000.0,
+                lt.short_name()
+            ));
+        }
+        lines
+    }
+
+    // Every stat as a number, for comparing. Area comes first, then the number of buildings.
+    pub fn rows(&self) -> Vec<(String, f64)> {
+        let mut rows = vec![
+            ("km²".to_string(), self.area / 1_000_000.0),
+            ("buildings".to_string(), self.buildings.len() as f64),
+            ("amenities".to_string(), self.num_amenities as f64),
+            ("roads".to_string(), self.roads.len() as f64),
+            ("intersections".to_string(), self.intersections.len() as f64),
+            ("traffic signals".to_string(), self.num_signals as f64),
+            (
+                "on-street parking spots".to_string(),
+                self.onstreet_parking as f64,
+            ),
+        ];
+        // Always list every type, so rows from different neighborhoods line up
+        for lt in vec![
+            LaneType::Driving,
+            LaneType::Parking,
+            LaneType::Sidewalk,
+            LaneType::Biking,
+            LaneType::Bus,
+            LaneType::SharedLeftTurn,
+            LaneType::Construction,
+        ] {
+            let dist = self.lane_length.get(&lt).cloned().unwrap_or(Distance::ZERO);
+            rows.push((
+                format!("km of {}s", lt.short_name()),
+                dist.inner_meters() / 1000.0,
+            ));
+        }
+        rows
+    }
+}
+
+// What's happening in a neighborhood right now, using the static summary to figure out what's
+// inside
+pub struct LiveSummary {
+    pub time: Time,
+    pub num_peds: usize,
+    pub num_cars: usize,
+    pub num_bikes: usize,
+    pub num_buses: usize,
+    // Since midnight
+    pub trips_started: usize,
+    pub trips_finished: usize,
+    // Of vehicles moving on roads inside since the previous refresh
+    pub avg_speed: Option<Speed>,
+    // How far each moving vehicle inside had gotten, to measure speed next time
+    dist_crossed: BTreeMap<CarID, Distance>,
+}
+
+impl LiveSummary {
+    pub fn new(
+        summary: &StaticSummary,
+        map: &Map,
+        sim: &Sim,
+        prev: Option<&LiveSummary>,
+    ) -> LiveSummary {
+        let inside = |on: Traversable| match on {
+            Traversable::Lane(l) => summary.roads.contains(&map.get_l(l).parent),
+            Traversable::Turn(t) => summary.intersections.contains(&t.parent),
+        };
+        let endpoint_inside = |endpt: TripEndpoint| match endpt {
+            TripEndpoint::Bldg(b) => summary.buildings.contains(&b),
+            TripEndpoint::Border(i, _) => summary.intersections.contains(&i),
+        };
+
+        let mut live = LiveSummary {
+            time: sim.time(),
+            num_peds: 0,
+            num_cars: 0,
+            num_bikes: 0,
+            num_buses: 0,
+            trips_started: 0,
+            trips_finished: 0,
+            avg_speed: None,
+            dist_crossed: BTreeMap::new(),
+        };
+        live.num_peds = sim
+            .get_all_draw_peds(map)
+            .into_iter()
+            .filter(|p| inside(p.on))
+            .count();
+
+        let mut total_dist = Distance::ZERO;
+        let mut num_measured = 0;
+        for car in sim.get_all_draw_cars(map) {
+            if !inside(car.on) {
+                continue;
+            }
+            match car.id.1 {
+                VehicleType::Car => {
+                    live.num_cars += 1;
+                }
+                VehicleType::Bike => {
+                    live.num_bikes += 1;
+                }
+                VehicleType::Bus => {
+                    live.num_buses += 1;
+                }
+            }
+            // Stuck or queued vehicles still count as inside, but only moving ones are sampled for
+            // speed
+            if car.status != CarStatus::Moving {
+                continue;
+            }
+            let dist = sim.agent_properties(AgentID::Car(car.id), map).dist_crossed;
+            // Only if it was here last time too, and hasn't started a new leg
+            if let Some(before) = prev.and_then(|p| p.dist_crossed.get(&car.id)) {
+                if dist >= *before {
+                    total_dist += dist - *before;
+                    num_measured += 1;
+                }
+            }
+            live.dist_crossed.insert(car.id, dist);
+        }
+        if let Some(prev) = prev {
+            if num_measured > 0 && live.time > prev.time {
+                live.avg_speed = Some(Speed::meters_per_second(
+                    total_dist.inner_meters()
+                        / (num_measured as f64)
+                        / (live.time - prev.time).inner_seconds(),
+                ));
+            }
+        }
+
+        let analytics = sim.get_analytics();
+        for trip in analytics.started_trips.keys() {
+            if endpoint_inside(sim.trip_info(*trip).1) {
+                live.trips_started += 1;
+            }
+        }
+        for (_, trip, mode, _) in &analytics.finished_trips {
+            // Aborted trips have no mode
+            if mode.is_some() && endpoint_inside(sim.trip_info(*trip).2) {
+                live.trips_finished += 1;
+            }
+        }
+        live
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        vec![
+            format!("At {}", self.time.ampm_tostring()),
+            format!(
+                "{} pedestrians, {} cars, {} bikes, {} buses inside",
+                prettyprint_usize(self.num_peds),
+                prettyprint_usize(self.num_cars),
+                prettyprint_usize(self.num_bikes),
+                prettyprint_usize(self.num_buses)
+            ),
+            format!(
+                "{} trips started here, {} finished here",
+                prettyprint_usize(self.trips_started),
+                prettyprint_usize(self.trips_finished)
+            ),
+            match self.avg_speed {
+                Some(speed) => format!("Vehicles moving at {} on average", speed),
+                None => "Not enough vehicles moving to measure speed".to_string(),
+            },
+        ]
+    }
+}
+
+// Lives as long as the map. Summarizing everything inside a polygon is slow on big maps, so it
+// only happens the first time somebody asks about a neighborhood, off the UI thread. Results are
+// kept until an edit touches something inside the neighborhood.
+pub struct NeighborhoodCache {
+    regions: Option<Vec<Neighborhood>>,
+    // Bumped every time the map is edited
+    revision: usize,
+    // The map's edits as of this revision, to notice changes made without telling the cache
+    edits: (Vec<EditCmd>, Vec<EditCmd>),
+    // Shared by all of the background work, until the next edit
+    points: Option<Arc<MapPoints>>,
+    summaries: BTreeMap<String, (usize, StaticSummary)>,
+    pending: BTreeMap<String, (usize, mpsc::Receiver<(StaticSummary, Duration)>)>,
+    // Summaries that never came back, for this revision
+    failed: BTreeMap<String, usize>,
+    // Spent on the UI thread, and in the background, for the load time comparison
+    time_loading: Duration,
+    time_summarizing: Duration,
+    num_summarized: usize,
+}
+
+impl NeighborhoodCache {
+    pub fn new() -> NeighborhoodCache {
+        NeighborhoodCache {
+            regions: None,
+            revision: 0,
+            edits: (Vec::new(), Vec::new()),
+            points: None,
+            summaries: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            failed: BTreeMap::new(),
+            time_loading: Duration::ZERO,
+            time_summarizing: Duration::ZERO,
+            num_summarized: 0,
+        }
+    }
+
+    pub fn regions(&mut self, map: &Map) -> &Vec<Neighborhood> {
+        if self.regions.is_none() {
+            let started = Instant::now();
+            self.regions = Some(load_regions(map));
+            self.time_loading += Duration::realtime_elapsed(started);
+        }
+        self.regions.as_ref().unwrap()
+    }
+
+    // None while the summary is still being computed; keep asking. Also None if it failed.
+    pub fn get(&mut self, name: &str, map: &Map) -> Option<&StaticSummary> {
+        self.check_edits(map);
+        if let Some((rev, rx)) = self.pending.get(name) {
+            match rx.try_recv() {
+                Ok((summary, elapsed)) => {
+                    // If the map was edited in the meantime, start over
+                    if *rev == self.revision {
+                        self.summaries
+                            .insert(name.to_string(), (self.revision, summary));
+                    }
+                    self.pending.remove(name);
+                    self.summarized(map, elapsed);
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    return None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    println!("Summarizing neighborhood {} failed", name);
+                    self.pending.remove(name);
+                    self.failed.insert(name.to_string(), self.revision);
+                }
+            }
+        }
+        if self.has_failed(name) {
+            return None;
+        }
+
+        if self.summaries.get(name).map(|(rev, _)| *rev) != Some(self.revision) {
+            self.start(name, map);
+        }
+        self.summaries.get(name).map(|(_, summary)| summary)
+    }
+
+    // Doesn't retry until the map is edited
+    pub fn has_failed(&self, name: &str) -> bool {
+        self.failed.get(name) == Some(&self.revision)
+    }
+
+    // Only neighborhoods containing something that changed have to be recomputed.
+    pub fn edits_applied(
+        &mut self,
+        map: &Map,
+        roads: &BTreeSet<RoadID>,
+        intersections: &BTreeSet<IntersectionID>,
+    ) {
+        self.revision += 1;
+        self.points = None;
+        self.edits = snapshot_edits(map);
+        let revision = self.revision;
+        self.summaries.retain(|_, (rev, summary)| {
+            if summary.roads.is_disjoint(roads) && summary.intersections.is_disjoint(intersections)
+            {
+                *rev = revision;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    // Some things edit the map without going through edits_applied, like previewing a route diff
+    // or loading a savestate. Without knowing what changed, everything has to be redone.
+    fn check_edits(&mut self, map: &Map) {
+        let edits = map.get_edits();
+        if edits.commands == self.edits.0 && edits.temporary == self.edits.1 {
+            return;
+        }
+        self.revision += 1;
+        self.points = None;
+        self.edits = snapshot_edits(map);
+        self.summaries.clear();
+    }
+
+    fn summarized(&mut self, map: &Map, elapsed: Duration) {
+        self.time_summarizing += elapsed;
+        self.num_summarized += 1;
+        let num_regions = self.regions(map).len();
+        // Summarizing everything while loading the map used to cost this much
+        let eager = self.time_loading
+            + self.time_summarizing * (num_regions as f64 / self.num_summarized as f64);
+        println!(
+            "Neighborhoods: {} on the UI thread so far, instead of about {} while loading the \
+             map ({} of {} summarized in the background, taking {})",
+            self.time_loading, eager, self.num_summarized, num_regions, self.time_summarizing
+        );
+    }
+
+    fn start(&mut self, name: &str, map: &Map) {
+        let polygon = self
+            .regions(map)
+            .iter()
+            .find(|n| n.name == name)
+            .unwrap()
+            .polygon
+            .clone();
+        if self.points.is_none() {
+            let started = Instant::now();
+            self.points = Some(Arc::new(MapPoints::new(map)));
+            self.time_loading += Duration::realtime_elapsed(started);
+        }
+        let points = self.points.clone().unwrap();
+
+        // No threads on the web
+        if cfg!(target_arch = "wasm32") {
+            let (summary, elapsed) = summarize(&polygon, &points);
+            self.summaries
+                .insert(name.to_string(), (self.revision, summary));
+            self.summarized(map, elapsed);
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The cache might be gone by the time this finishes
+            let _ = tx.send(summarize(&polygon, &points));
+        });
+        self.pending.insert(name.to_string(), (self.revision, rx));
+    }
+}
+
+// Just what summarizing needs, copied out of the map so it can be sent to another thread
+struct MapPoints {
+    // With the number of amenities
+    buildings: Vec<(BuildingID, Pt2D, usize)>,
+    // Each lane's type, length, and number of parking spots
+    roads: Vec<(RoadID, Pt2D, Vec<(LaneType, Distance, usize)>)>,
+    // And if it's a traffic signal
+    intersections: Vec<(IntersectionID, Pt2D, bool)>,
+}
+
+impl MapPoints {
+    fn new(map: &Map) -> MapPoints {
+        MapPoints {
+            buildings: map
+                .all_buildings()
+                .iter()
+                .map(|b| (b.id, b.label_center, b.amenities.len()))
+                .collect(),
+            roads: map
+                .all_roads()
+                .iter()
+                .map(|r| {
+                    let lanes = r
+                        .all_lanes()
+                        .into_iter()
+                        .map(|l| {
+                            let lane = map.get_l(l);
+                            (lane.lane_type, lane.length(), lane.number_parking_spots())
+                        })
+                        .collect();
+                    (r.id, r.center_pts.middle(), lanes)
+                })
+                .collect(),
+            intersections: map
+                .all_intersections()
+                .iter()
+                .map(|i| (i.id, i.polygon.center(), i.is_traffic_signal()))
+                .collect(),
+        }
+    }
+}
+
+fn snapshot_edits(map: &Map) -> (Vec<EditCmd>, Vec<EditCmd>) {
+    let edits = map.get_edits();
+    (edits.commands.clone(), edits.temporary.clone())
+}
+
+// Also returns how long it took
+fn summarize(polygon: &Polygon, points: &MapPoints) -> (StaticSummary, Duration) {
+    let started = Instant::now();
+    let bounds = polygon.get_bounds();
+    let inside = |pt: Pt2D| bounds.contains(pt) && polygon.contains_pt(pt);
+
+    let mut summary = StaticSummary {
+        area: polygon.area(),
+        buildings: BTreeSet::new(),
+        roads: BTreeSet::new(),
+        intersections: BTreeSet::new(),
+        num_amenities: 0,
+        num_signals: 0,
+        onstreet_parking: 0,
+        lane_length: BTreeMap::new(),
+    };
+    for (b, pt, amenities) in &points.buildings {
+        if inside(*pt) {
+            summary.buildings.insert(*b);
+            summary.num_amenities += amenities;
+        }
+    }
+    for (r, pt, lanes) in &points.roads {
+        if inside(*pt) {
+            summary.roads.insert(*r);
+            for (lt, len, spots) in lanes {
+                *summary.lane_length.entry(*lt).or_insert(Distance::ZERO) += *len;
+                summary.onstreet_parking += spots;
+            }
+        }
+    }
+    for (i, pt, signal) in &points.intersections {
+        if inside(*pt) {
+            summary.intersections.insert(*i);
+            if *signal {
+                summary.num_signals += 1;
+            }
+        }
+    }
+    (summary, Duration::realtime_elapsed(started))
+}
+
+// Smallest first, so the UI doesn't lead with the region covering everything
+fn load_regions(map: &Map) -> Vec<Neighborhood> {
+    let mut timer = Timer::new("load neighborhoods");
+    let mut regions = Vec::new();
+    // The raw polygons aren't bundled on the web
+    if cfg!(target_arch = "wasm32") {
+        return regions;
+    }
+
+    let map_bounds = map.get_bounds();
+    for name in abstutil::list_all_objects(abstutil::path_all_neighborhoods(map.get_city_name())) {
+        let pts = match LonLat::read_osmosis_polygon(abstutil::path_neighborhood(
+            map.get_city_name(),
+            &name,
+        )) {
+            Ok(pts) => pts,
+            Err(err) => {
+                timer.warn(format!("Couldn't load neighborhood {}: {}", name, err));
+                continue;
+            }
+        };
+        let polygon = Polygon::new(&map.get_gps_bounds().forcibly_convert(&pts));
+        let b = polygon.get_bounds();
+        if b.max_x < map_bounds.min_x
+            || b.min_x > map_bounds.max_x
+            || b.max_y < map_bounds.min_y
+            || b.min_y > map_bounds.max_y
+        {
+            continue;
+        }
+        regions.push(Neighborhood { name, polygon });
+    }
+    regions.sort_by_key(|n| n.polygon.get_bounds().width() as usize);
+    timer.note(format!("{} neighborhoods overlap this map", regions.len()));
+    regions
+}
+
+pub struct Neighborhoods {
+    composite: Composite,
+    draw: Drawable,
+    selected: Option<String>,
+    // Still waiting on the summary of the selected neighborhood
+    computing: bool,
+    show_live: bool,
+    live: Option<LiveSummary>,
+}
+
+impl Layer for Neighborhoods {
+    fn name(&self) -> Option<&'static str> {
+        Some("neighborhoods")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if self.computing {
+            let name = self.selected.as_ref().unwrap();
+            if app
+                .primary
+                .neighborhoods
+                .get(name, &app.primary.map)
+                .is_some()
+            {
+                self.rebuild(ctx, app, minimap);
+            } else {
+                ctx.request_animation_frames();
+            }
+        } else if let Some(ref live) = self.live {
+            let now = app.primary.sim.time();
+            if now < live.time || now - live.time >= LIVE_REFRESH {
+                self.rebuild(ctx, app, minimap);
+            }
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                "compare neighborhoods" => {
+                    return Some(LayerOutcome::Transition(Transition::Push(
+                        CompareNeighborhoods::pick(),
+                    )));
+                }
+                "show live metrics" | "show map summary" => {
+                    self.show_live = !self.show_live;
+                    self.rebuild(ctx, app, minimap);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let selected: Option<String> = self.composite.dropdown_value("neighborhood");
+                if selected != self.selected {
+                    self.selected = selected;
+                    self.live = None;
+                    self.rebuild(ctx, app, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Neighborhoods {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, selected: Option<String>) -> Neighborhoods {
+        let mut n = Neighborhoods {
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            draw: ctx.upload(GeomBatch::new()),
+            selected,
+            computing: false,
+            show_live: false,
+            live: None,
+        };
+        n.build(ctx, app);
+        n
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &mut App, minimap: &Composite) {
+        self.build(ctx, app);
+        self.composite.align_above(ctx, minimap);
+    }
+
+    fn build(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        let mut batch = GeomBatch::new();
+        let mut choices = vec![Choice::new("none", None)];
+        for n in app.primary.neighborhoods.regions(&app.primary.map) {
+            if Some(&n.name) == self.selected.as_ref() {
+                batch.push(Color::BLUE.alpha(0.3), n.polygon.clone());
+            }
+            batch.push(Color::BLACK, n.polygon.to_outline(Distance::meters(5.0)));
+            choices.push(Choice::new(n.name.clone(), Some(n.name.clone())));
+        }
+        self.draw = ctx.upload(batch);
+
+        let mut col = vec![Widget::row(vec![
+            Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg").margin_right(10),
+            "Neighborhoods".draw_text(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        self.computing = false;
+        if choices.len() == 1 {
+            col.push("No neighborhoods cover this map".draw_text(ctx));
+        } else {
+            if choices.len() > 2 {
+                col.push(Btn::text_fg("compare neighborhoods").build_def(ctx, None));
+            }
+            col.push(Widget::dropdown(
+                ctx,
+                "neighborhood",
+                self.selected.clone(),
+                choices,
+            ));
+            if let Some(ref name) = self.selected {
+                let mut txt = Text::new();
+                match app.primary.neighborhoods.get(name, &app.primary.map) {
+                    Some(summary) => {
+                        let lines = if self.show_live {
+                            let live = LiveSummary::new(
+                                summary,
+                                &app.primary.map,
+                                &app.primary.sim,
+                                self.live.as_ref(),
+                            );
+                            let lines = live.describe();
+                            self.live = Some(live);
+                            lines
+                        } else {
+                            self.live = None;
+                            summary.describe()
+                        };
+                        for line in lines {
+                            txt.add(Line(line));
+                        }
+                    }
+                    None => {
+                        if app.primary.neighborhoods.has_failed(name) {
+                            txt.add(Line("Couldn't summarize this neighborhood").secondary());
+                        } else {
+                            self.computing = true;
+                            txt.add(Line("computing…").secondary());
+                        }
+                    }
+                }
+                col.push(txt.draw(ctx).margin_above(5));
+                col.push(
+                    if self.show_live {
+                        Btn::text_fg("show map summary")
+                    } else {
+                        Btn::text_fg("show live metrics")
+                    }
+                    .build_def(ctx, hotkey(Key::L))
+                    .margin_above(5),
+                );
+            }
+        }
+
+        self.composite = Composite::new(Widget::col(col).padding(5).bg(app.cs.panel_bg))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Normalize {
+    Absolute,
+    PerArea,
+    PerBuilding,
+}
+
+// Two neighborhoods' static summaries next to each other
+pub struct CompareNeighborhoods {
+    names: [String; 2],
+    // Filled in once they're computed
+    rows: Option<[Vec<(String, f64)>; 2]>,
+    normalize: Normalize,
+    composite: Composite,
+    draw: Drawable,
+}
+
+impl CompareNeighborhoods {
+    fn pick() -> Box<dyn State> {
+        WizardState::new(Box::new(|wiz, ctx, app| {
+            let names: Vec<String> = app
+                .primary
+                .neighborhoods
+                .regions(&app.primary.map)
+                .iter()
+                .map(|n| n.name.clone())
+                .collect();
+            let mut wizard = wiz.wrap(ctx);
+            let name1 = wizard.choose_string("Compare which neighborhood?", || {
+                names.iter().map(|n| n.as_str()).collect()
+            })?;
+            let name2 = wizard.choose_string("With which?", || {
+                names
+                    .iter()
+                    .filter(|n| **n != name1)
+                    .map(|n| n.as_str())
+                    .collect()
+            })?;
+            Some(Transition::Replace(Box::new(CompareNeighborhoods::new(
+                wizard.ctx,
+                app,
+                [name1, name2],
+            ))))
+        }))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &mut App, names: [String; 2]) -> CompareNeighborhoods {
+        let mut batch = GeomBatch::new();
+        for n in app.primary.neighborhoods.regions(&app.primary.map) {
+            if n.name == names[0] {
+                batch.push(Color::BLUE.alpha(0.3), n.polygon.clone());
+            } else if n.name == names[1] {
+                batch.push(Color::RED.alpha(0.3), n.polygon.clone());
+            }
+        }
+        let mut c = CompareNeighborhoods {
+            names,
+            rows: None,
+            normalize: Normalize::Absolute,
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            draw: ctx.upload(batch),
+        };
+        c.update(ctx, app);
+        c
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        if self.rows.is_none() {
+            let map = &app.primary.map;
+            let cache = &mut app.primary.neighborhoods;
+            // Ask for both, so they're computed at the same time
+            let rows1 = cache.get(&self.names[0], map).map(|s| s.rows());
+            let rows2 = cache.get(&self.names[1], map).map(|s| s.rows());
+            if let (Some(rows1), Some(rows2)) = (rows1, rows2) {
+                self.rows = Some([rows1, rows2]);
+            }
+        }
+
+        let mut col = vec![Widget::row(vec![
+            Line("Compare neighborhoods").small_heading().draw(ctx),
+            Btn::text_fg("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        if let Some(table) = self.table() {
+            col.push(
+                Widget::row(vec![
+                    "Normalize:".draw_text(ctx).margin_right(5),
+                    Widget::dropdown(
+                        ctx,
+                        "normalize",
+                        self.normalize,
+                        vec![
+                            Choice::new("no", Normalize::Absolute),
+                            Choice::new("per km²", Normalize::PerArea),
+                            Choice::new("per building", Normalize::PerBuilding),
+                        ],
+                    ),
+                ])
+                .margin_below(5),
+            );
+
+            let mut labels = vec![Widget::nothing()];
+            let mut values1 = vec![Btn::text_fg(&self.names[0]).build_def(ctx, None)];
+            let mut values2 = vec![Btn::text_fg(&self.names[1]).build_def(ctx, None)];
+            let mut deltas = vec!["difference".draw_text(ctx)];
+            for (label, x1, x2) in table {
+                labels.push(label.draw_text(ctx));
+                values1.push(fmt_value(x1).draw_text(ctx));
+                values2.push(fmt_value(x2).draw_text(ctx));
+                let delta = x2 - x1;
+                let mut line = Line(if delta > 0.0 {
+                    format!("+{}", fmt_value(delta))
+                } else {
+                    fmt_value(delta)
+                });
+                // Only call out big differences
+                if (delta.abs() / x1.abs().max(x2.abs()).max(1e-9)) > 0.1 {
+                    line = line.fg(if delta > 0.0 {
+                        Color::hex("#5D9630")
+                    } else {
+                        Color::hex("#A32015")
+                    });
+                }
+                deltas.push(line.draw(ctx));
+            }
+            col.push(
+                Widget::row(vec![
+                    Widget::col(labels).margin_right(10),
+                    Widget::col(values1).margin_right(10),
+                    Widget::col(values2).margin_right(10),
+                    Widget::col(deltas),
+                ])
+                .margin_below(5),
+            );
+            col.push(Btn::text_fg("write to log").build_def(ctx, None));
+        } else if self
+            .names
+            .iter()
+            .any(|n| app.primary.neighborhoods.has_failed(n))
+        {
+            col.push(
+                Line("Couldn't summarize these neighborhoods")
+                    .secondary()
+                    .draw(ctx),
+            );
+        } else {
+            col.push(Line("computing…").secondary().draw(ctx));
+        }
+
+        self.composite = Composite::new(Widget::col(col).padding(10).bg(app.cs.panel_bg))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx);
+    }
+
+    // Each row's label and value for both neighborhoods, with the current normalization
+    fn table(&self) -> Option<Vec<(String, f64, f64)>> {
+        let [rows1, rows2] = self.rows.as_ref()?;
+        // Area and buildings are the first two rows; normalizing a row by itself is useless
+        let divisor = match self.normalize {
+            Normalize::Absolute => None,
+            Normalize::PerArea => Some(0),
+            Normalize::PerBuilding => Some(1),
+        };
+        let mut table = Vec::new();
+        for (idx, ((label, x1), (_, x2))) in rows1.iter().zip(rows2.iter()).enumerate() {
+            match divisor {
+                Some(d) if d != idx => {
+                    let per = if d == 0 { "km²" } else { "building" };
+                    table.push((
+                        format!("{} per {}", label, per),
+                        x1 / rows1[d].1.max(1e-9),
+                        x2 / rows2[d].1.max(1e-9),
+                    ));
+                }
+                _ => {
+                    table.push((label.clone(), *x1, *x2));
+                }
+            }
+        }
+        Some(table)
+    }
+}
+
+impl State for CompareNeighborhoods {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if self.rows.is_none() {
+            self.update(ctx, app);
+            if self.rows.is_none() {
+                ctx.request_animation_frames();
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "write to log" => {
+                    println!("{} vs {}", self.names[0], self.names[1]);
+                    for (label, x1, x2) in self.table().unwrap() {
+                        println!(
+                            "{}: {}, {} ({})",
+                            label,
+                            fmt_value(x1),
+                            fmt_value(x2),
+                            fmt_value(x2 - x1)
+                        );
+                    }
+                    app.toasts.notify(
+                        Severity::Info,
+                        "Wrote the comparison to the log".to_string(),
+                    );
+                }
+                name => {
+                    let pt = app
+                        .primary
+                        .neighborhoods
+                        .regions(&app.primary.map)
+                        .iter()
+                        .find(|n| n.name == name)
+                        .unwrap()
+                        .polygon
+                        .center();
+                    return Transition::Push(Warping::new(ctx, pt, None, None, &mut app.primary));
+                }
+            },
+            None => {
+                if self.rows.is_some() {
+                    let normalize = self.composite.dropdown_value("normalize");
+                    if normalize != self.normalize {
+                        self.normalize = normalize;
+                        self.update(ctx, app);
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+    }
+}
+
+fn fmt_value(x: f64) -> String {
+    if x == x.round() {
+        format!("{}", x)
+    } else {
+        format!("{:.2}", x)
+    }
+}
@@ -7,7 +7,7 @@ use ezgui::{
     Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::Time;
-use map_model::{BuildingID, Map, ParkingLotID, RoadID};
+use map_model::{BuildingID, LaneID, ParkingLotID};
 use sim::{ParkingSpot, VehicleType};
 use std::collections::HashSet;
 
@@ -211,12 +211,12 @@ impl Occupancy {
         let mut avail = Counter::new();
         let mut keys = HashSet::new();
         for spot in filled_spots {
-            let loc = Loc::new(spot, &app.primary.map);
+            let loc = Loc::new(spot);
             keys.insert(loc);
             filled.inc(loc);
         }
         for spot in avail_spots {
-            let loc = Loc::new(spot, &app.primary.map);
+            let loc = Loc::new(spot);
             keys.insert(loc);
             avail.inc(loc);
         }
@@ -228,7 +228,7 @@ impl Occupancy {
             let percent = (closed as f64) / ((open + closed) as f64);
             let color = app.cs.good_to_bad_red.eval(percent);
             match loc {
-                Loc::Road(r) => colorer.add_r(r, color),
+                Loc::Lane(l) => colorer.add_l(l, color),
                 Loc::Bldg(b) => colorer.add_b(b, color),
                 Loc::Lot(pl) => colorer.add_pl(pl, color),
             }
@@ -250,15 +250,15 @@ impl Occupancy {
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 enum Loc {
-    Road(RoadID),
+    Lane(LaneID),
     Bldg(BuildingID),
     Lot(ParkingLotID),
 }
 
 impl Loc {
-    fn new(spot: ParkingSpot, map: &Map) -> Loc {
+    fn new(spot: ParkingSpot) -> Loc {
         match spot {
-            ParkingSpot::Onstreet(l, _) => Loc::Road(map.get_l(l).parent),
+            ParkingSpot::Onstreet(l, _) => Loc::Lane(l),
             ParkingSpot::Offstreet(b, _) => Loc::Bldg(b),
             ParkingSpot::Lot(pl, _) => Loc::Lot(pl),
         }
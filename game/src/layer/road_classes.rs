@@ -0,0 +1,233 @@
+use crate::app::App;
+use crate::common::ColorNetwork;
+use crate::helpers::ID;
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::{prettyprint_usize, Counter};
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Polygon;
+use map_model::{osm, Road, RoadID};
+use std::collections::BTreeSet;
+
+// Anything else, including a missing tag
+const UNKNOWN: &str = "unknown";
+
+// The highway tags grouped into each class
+const CLASSES: [(&str, &[&str]); 7] = [
+    (
+        "motorway",
+        &["motorway", "motorway_link", "trunk", "trunk_link"],
+    ),
+    ("primary", &["primary", "primary_link"]),
+    (
+        "secondary",
+        &["secondary", "secondary_link", "tertiary", "tertiary_link"],
+    ),
+    (
+        "residential",
+        &["residential", "unclassified", "living_street"],
+    ),
+    ("service", &["service"]),
+    ("cycleway", &["cycleway"]),
+    ("footway", &["footway", "path", "pedestrian", "steps"]),
+];
+
+const KEYS: [Key; 8] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+];
+
+fn all_classes() -> Vec<&'static str> {
+    CLASSES
+        .iter()
+        .map(|(class, _)| *class)
+        .chain(std::iter::once(UNKNOWN))
+        .collect()
+}
+
+fn classify(road: &Road) -> &'static str {
+    if let Some(highway) = road.osm_tags.get(osm::HIGHWAY) {
+        for (class, tags) in &CLASSES {
+            if tags.contains(&highway.as_str()) {
+                return *class;
+            }
+        }
+    }
+    UNKNOWN
+}
+
+fn class_color(class: &str) -> Color {
+    match class {
+        "motorway" => Color::hex("#E892A2"),
+        "primary" => Color::hex("#FCD6A4"),
+        "secondary" => Color::hex("#F7FABF"),
+        "residential" => Color::WHITE,
+        "service" => Color::hex("#8C8C8C"),
+        "cycleway" => Color::hex("#0F7D4B"),
+        "footway" => Color::hex("#FA8072"),
+        // Loud, so gaps in the importer stand out
+        _ => Color::hex("#FF00FF"),
+    }
+}
+
+// Colors roads by their OSM highway tag, to spot import mistakes.
+pub struct RoadClasses {
+    composite: Composite,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    hidden: BTreeSet<&'static str>,
+    selected: Option<RoadID>,
+}
+
+impl Layer for RoadClasses {
+    fn name(&self) -> Option<&'static str> {
+        Some("OSM road classes")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let hidden: BTreeSet<&'static str> = all_classes()
+            .into_iter()
+            .filter(|class| !self.composite.is_checked(class))
+            .collect();
+        if hidden != self.hidden {
+            let (unzoomed, zoomed) = draw_roads(ctx, app, &hidden);
+            self.unzoomed = unzoomed;
+            self.zoomed = zoomed;
+            self.hidden = hidden;
+        }
+
+        let selected = match app.primary.current_selection {
+            Some(ID::Road(r)) => Some(r),
+            Some(ID::Lane(l)) => Some(app.primary.map.get_l(l).parent),
+            _ => None,
+        };
+        if selected != self.selected {
+            self.selected = selected;
+            self.composite
+                .replace(ctx, "selected", describe_selected(ctx, app, selected));
+        }
+
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl RoadClasses {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> RoadClasses {
+        let mut counts: Counter<&'static str> = Counter::new();
+        for r in app.primary.map.all_roads() {
+            counts.inc(classify(r));
+        }
+
+        let mut col = vec![Widget::row(vec![
+            Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg").margin_right(10),
+            "OSM road classes".draw_text(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])];
+        for (class, key) in all_classes().into_iter().zip(KEYS.iter()) {
+            let color = class_color(class);
+            let mut count = Line(format!("{} roads", prettyprint_usize(counts.get(class))));
+            if class == UNKNOWN && counts.get(class) > 0 {
+                count = count.fg(color);
+            } else {
+                count = count.secondary();
+            }
+            col.push(
+                Widget::row(vec![
+                    Widget::draw_batch(
+                        ctx,
+                        GeomBatch::from(vec![(color, Polygon::rectangle(20.0, 20.0))]),
+                    )
+                    .margin_right(10)
+                    .centered_vert(),
+                    Checkbox::text(ctx, class, hotkey(*key), true).margin_right(10),
+                    count.draw(ctx).centered_vert(),
+                ])
+                .margin_above(5),
+            );
+        }
+        col.push(describe_selected(ctx, app, None));
+
+        let (unzoomed, zoomed) = draw_roads(ctx, app, &BTreeSet::new());
+        RoadClasses {
+            composite: Composite::new(Widget::col(col).padding(5).bg(app.cs.panel_bg))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+            unzoomed,
+            zoomed,
+            hidden: BTreeSet::new(),
+            selected: None,
+        }
+    }
+}
+
+fn draw_roads(
+    ctx: &mut EventCtx,
+    app: &App,
+    hidden: &BTreeSet<&'static str>,
+) -> (Drawable, Drawable) {
+    let mut colorer = ColorNetwork::new(app);
+    for r in app.primary.map.all_roads() {
+        let class = classify(r);
+        if !hidden.contains(class) {
+            colorer.add_r(r.id, class_color(class));
+        }
+    }
+    colorer.build(ctx)
+}
+
+fn describe_selected(ctx: &EventCtx, app: &App, selected: Option<RoadID>) -> Widget {
+    let line = match selected {
+        Some(r) => Line(format!(
+            "{}: highway={}",
+            r,
+            app.primary
+                .map
+                .get_r(r)
+                .osm_tags
+                .get(osm::HIGHWAY)
+                .map(|x| x.as_str())
+                .unwrap_or("(missing)")
+        )),
+        None => Line("Select a road to see its raw highway tag").secondary(),
+    };
+    Text::from(line)
+        .draw(ctx)
+        .margin_above(10)
+        .named("selected")
+}
@@ -1,12 +1,13 @@
 use crate::app::App;
 use crate::common::{ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 use crate::layer::{Layer, LayerOutcome};
+use crate::render::BIG_ARROW_THICKNESS;
 use abstutil::Counter;
 use ezgui::{
     hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Distance, Duration, Polygon, Time};
+use geom::{ArrowCap, Circle, Distance, Duration, Polygon, Time};
 use map_model::{IntersectionID, Map, Traversable};
 use maplit::btreeset;
 use std::collections::BTreeSet;
@@ -617,3 +618,113 @@ impl Jam {
         polygons
     }
 }
+
+// The most recent cycle of agents waiting on each other, and the turns they're stuck waiting for.
+pub struct Gridlock {
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Gridlock {
+    fn name(&self) -> Option<&'static str> {
+        Some("gridlock")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Gridlock::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Gridlock {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Gridlock {
+        let map = &app.primary.map;
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        let summary = if let Some(gridlock) = app.primary.sim.get_gridlock() {
+            for i in &gridlock.intersections {
+                let polygon = map.get_i(*i).polygon.clone();
+                unzoomed.push(Color::RED, polygon.to_outline(Distance::meters(5.0)));
+                zoomed.push(
+                    Color::RED.alpha(0.4),
+                    polygon.to_outline(Distance::meters(1.0)),
+                );
+            }
+            for a in &gridlock.agents {
+                if let Some(pt) = app.primary.sim.canonical_pt_for_agent(*a, map) {
+                    unzoomed.push(
+                        Color::RED,
+                        Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+                    );
+                    zoomed.push(
+                        Color::RED.alpha(0.5),
+                        Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+                    );
+                }
+            }
+            for t in &gridlock.turns {
+                zoomed.push(
+                    Color::RED,
+                    map.get_t(*t)
+                        .geom
+                        .make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle)
+                        .unwrap(),
+                );
+            }
+            format!(
+                "{} agents are stuck waiting on each other near {} intersections",
+                gridlock.agents.len(),
+                gridlock.intersections.len()
+            )
+        } else {
+            "No gridlock right now".to_string()
+        };
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Gridlock".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from(Line(summary).secondary())
+                    .wrap_to_pct(ctx, 15)
+                    .draw(ctx),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Gridlock {
+            time: app.primary.sim.time(),
+            unzoomed: ctx.upload(unzoomed),
+            zoomed: ctx.upload(zoomed),
+            composite,
+        }
+    }
+}
@@ -9,6 +9,7 @@ mod edit;
 mod game;
 mod helpers;
 mod info;
+mod keybindings;
 mod layer;
 mod managed;
 mod options;
@@ -32,6 +33,7 @@ fn main() {
         sim_flags: SimFlags::from_args(&mut args),
         draw_lane_markings: !args.enabled("--dont_draw_lane_markings"),
         num_agents: args.optional_parse("--num_agents", |s| s.parse()),
+        raw_osm: args.optional("--raw_osm"),
     };
     let mut opts = options::Options::default();
     opts.dev = args.enabled("--dev");
@@ -66,12 +68,18 @@ fn main() {
     if args.enabled("--dump_raw_events") {
         settings.dump_raw_events();
     }
+    if let Some(n) = args.optional_parse("--max_log_lines", |s| s.parse::<usize>()) {
+        abstutil::set_max_log_lines(n);
+    }
     if let Some(n) = args.optional_parse("--font_size", |s| s.parse::<usize>()) {
         settings.default_font_size(n);
     }
     if let Some(s) = args.optional_parse("--scale_factor", |s| s.parse::<f64>()) {
         settings.scale_factor(s);
     }
+    if args.enabled("--vsync") {
+        settings.vsync();
+    }
 
     let mut mode = None;
     if let Some(x) = args.optional("--challenge") {
@@ -96,18 +104,37 @@ fn main() {
             sandbox::TutorialPointer::new(n - 1, 0),
         ));
     }
-    if mode.is_none() && flags.sim_flags.load.contains("scenarios/") {
-        // TODO regex
-        let parts = flags.sim_flags.load.split("/").collect::<Vec<_>>();
-        let map_path = abstutil::path_map(parts[4]);
-        let scenario = abstutil::basename(parts[5]);
+    let scenarios_dir = abstutil::path("system/scenarios/");
+    if mode.is_none() && flags.sim_flags.load.starts_with(&scenarios_dir) {
+        // Like montlake/weekday.bin
+        let parts = flags.sim_flags.load[scenarios_dir.len()..]
+            .split("/")
+            .collect::<Vec<_>>();
+        let map_path = abstutil::path_map(parts[0]);
+        let scenario = abstutil::basename(parts[1]);
         flags.sim_flags.load = map_path.clone();
         mode = Some(sandbox::GameplayMode::PlayScenario(map_path, scenario));
     }
     let start_with_edits = args.optional("--edits");
+    let record_input = args.enabled("--record_input");
+    if let Some(path) = args.optional("--replay_input") {
+        settings.replay_input(path);
+    }
 
     args.done();
 
+    let session = format!(
+        "session_{}",
+        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+    );
+    let log_path = abstutil::path_log_file(&session);
+    println!("Logs will be written to {}", log_path);
+    abstutil::tee_logs_to_file(log_path);
+    // Replay with the same flags, swapping this for --replay_input=path
+    if record_input {
+        settings.record_input(abstutil::path_input_recording(&session));
+    }
+
     ezgui::run(settings, |ctx| {
         game::Game::new(flags, opts, start_with_edits, mode, ctx)
     });
@@ -1,9 +1,10 @@
 use crate::app::App;
 use crate::colors::ColorSchemeChoice;
 use crate::game::{State, Transition};
+use crate::keybindings::KeyBindingsScreen;
 use ezgui::{
-    hotkey, Btn, Checkbox, Choice, Composite, EventCtx, GfxCtx, Key, Line, Outcome, Spinner,
-    TextExt, Widget,
+    hotkey, Btn, Checkbox, Choice, Composite, EventCtx, FramePacing, GfxCtx, Key, Line, Outcome,
+    Spinner, TextExt, Widget,
 };
 use geom::Duration;
 
@@ -17,6 +18,7 @@ pub struct Options {
     pub color_scheme: ColorSchemeChoice,
     pub min_zoom_for_detail: f64,
     pub large_unzoomed_agents: bool,
+    pub show_menu_bar: bool,
 
     pub time_increment: Duration,
     pub resume_after_edit: bool,
@@ -32,6 +34,7 @@ impl Options {
             color_scheme: ColorSchemeChoice::Standard,
             min_zoom_for_detail: 4.0,
             large_unzoomed_agents: false,
+            show_menu_bar: true,
 
             time_increment: Duration::minutes(10),
             resume_after_edit: true,
@@ -39,6 +42,34 @@ impl Options {
     }
 }
 
+fn scale_factor_choices(ctx: &EventCtx) -> Vec<Choice<Option<f64>>> {
+    let mut choices = vec![
+        Choice::new("same as monitor", None),
+        Choice::new("0.5", Some(0.5)),
+        Choice::new("1.0", Some(1.0)),
+        Choice::new("1.5", Some(1.5)),
+        Choice::new("2.0", Some(2.0)),
+    ];
+    // From --scale_factor
+    if let Some(x) = ctx.get_scale_factor_override() {
+        if !choices.iter().any(|c| c.data == Some(x)) {
+            choices.push(Choice::new(x.to_string(), Some(x)));
+        }
+    }
+    choices
+}
+
+fn frame_pacing_choices(ctx: &EventCtx) -> Vec<Choice<FramePacing>> {
+    // Vsync can only be enabled at startup, with --vsync
+    if ctx.canvas.frame_pacing == FramePacing::Vsync {
+        return vec![Choice::new("vsync", FramePacing::Vsync)];
+    }
+    vec![
+        Choice::new("at most 60 FPS", FramePacing::Cap60),
+        Choice::new("at most 30 FPS", FramePacing::Cap30),
+    ]
+}
+
 #[derive(Clone, PartialEq)]
 pub enum TrafficSignalStyle {
     BAP,
@@ -90,6 +121,15 @@ impl OptionsPanel {
                             ctx.canvas.touchpad_to_move,
                         )
                         .margin_below(10),
+                        Checkbox::text(ctx, "Animate zooming", None, ctx.canvas.smooth_zoom)
+                            .margin_below(10),
+                        Checkbox::text(
+                            ctx,
+                            "Keep panning after releasing a drag",
+                            None,
+                            ctx.canvas.kinetic_panning,
+                        )
+                        .margin_below(10),
                         Checkbox::text(
                             ctx,
                             "Use arrow keys to pan and Q/W to zoom",
@@ -104,7 +144,18 @@ impl OptionsPanel {
                                 .margin_right(10),
                             Spinner::new(ctx, (1, 50), ctx.canvas.gui_scroll_speed)
                                 .named("gui_scroll_speed"),
-                        ]),
+                        ])
+                        .margin_below(10),
+                        Widget::row(vec![
+                            "Arrow key panning speed (hold Shift to go faster)"
+                                .draw_text(ctx)
+                                .centered_vert()
+                                .margin_right(10),
+                            Spinner::new(ctx, (1, 100), ctx.canvas.keys_pan_speed)
+                                .named("keys_pan_speed"),
+                        ])
+                        .margin_below(10),
+                        Btn::text_fg("Change key bindings").build_def(ctx, None),
                     ])
                     .bg(app.cs.section_bg)
                     .padding(8)
@@ -113,6 +164,8 @@ impl OptionsPanel {
                     Widget::col(vec![
                         Checkbox::text(ctx, "Draw road names", None, app.opts.label_roads)
                             .margin_below(10),
+                        Checkbox::text(ctx, "Show the menu bar", None, app.opts.show_menu_bar)
+                            .margin_below(10),
                         Widget::row(vec![
                             "Traffic signal rendering:".draw_text(ctx).margin_right(15),
                             Widget::dropdown(
@@ -166,13 +219,8 @@ impl OptionsPanel {
                             Widget::dropdown(
                                 ctx,
                                 "Scale factor",
-                                ctx.get_scale_factor(),
-                                vec![
-                                    Choice::new("0.5", 0.5),
-                                    Choice::new("1.0", 1.0),
-                                    Choice::new("1.5", 1.5),
-                                    Choice::new("2.0", 2.0),
-                                ],
+                                ctx.get_scale_factor_override(),
+                                scale_factor_choices(ctx),
                             ),
                         ])
                         .margin_below(10),
@@ -200,6 +248,23 @@ impl OptionsPanel {
                             "Draw enlarged unzoomed agents",
                             None,
                             app.opts.large_unzoomed_agents,
+                        )
+                        .margin_below(10),
+                        Widget::row(vec![
+                            "Frame rate:".draw_text(ctx).margin_right(15),
+                            Widget::dropdown(
+                                ctx,
+                                "Frame rate",
+                                ctx.canvas.frame_pacing,
+                                frame_pacing_choices(ctx),
+                            ),
+                        ])
+                        .margin_below(10),
+                        Checkbox::text(
+                            ctx,
+                            "Lower the frame rate when nothing's happening",
+                            None,
+                            ctx.canvas.idle_when_inactive,
                         ),
                     ])
                     .bg(app.cs.section_bg)
@@ -224,6 +289,9 @@ impl State for OptionsPanel {
                 "close" => {
                     return Transition::Pop;
                 }
+                "Change key bindings" => {
+                    return Transition::Push(KeyBindingsScreen::new(ctx, app));
+                }
                 "Apply" => {
                     app.opts.dev = self.composite.is_checked("Enable developer mode");
 
@@ -237,9 +305,15 @@ impl State for OptionsPanel {
                         .composite
                         .is_checked("Use arrow keys to pan and Q/W to zoom");
                     ctx.canvas.edge_auto_panning = self.composite.is_checked("autopan");
+                    ctx.canvas.smooth_zoom = self.composite.is_checked("Animate zooming");
+                    ctx.canvas.kinetic_panning = self
+                        .composite
+                        .is_checked("Keep panning after releasing a drag");
+                    ctx.canvas.keys_pan_speed = self.composite.spinner("keys_pan_speed");
                     ctx.canvas.gui_scroll_speed = self.composite.spinner("gui_scroll_speed");
 
                     app.opts.label_roads = self.composite.is_checked("Draw road names");
+                    app.opts.show_menu_bar = self.composite.is_checked("Show the menu bar");
                     let style = self.composite.dropdown_value("Traffic signal rendering");
                     if app.opts.traffic_signal_style != style {
                         app.opts.traffic_signal_style = style;
@@ -256,13 +330,17 @@ impl State for OptionsPanel {
                     }
 
                     let factor = self.composite.dropdown_value("Scale factor");
-                    if ctx.get_scale_factor() != factor {
-                        ctx.set_scale_factor(factor);
+                    if ctx.get_scale_factor_override() != factor {
+                        ctx.override_scale_factor(factor);
                     }
 
                     app.opts.min_zoom_for_detail = self.composite.dropdown_value("min zoom");
                     app.opts.large_unzoomed_agents =
                         self.composite.is_checked("Draw enlarged unzoomed agents");
+                    ctx.canvas.frame_pacing = self.composite.dropdown_value("Frame rate");
+                    ctx.canvas.idle_when_inactive = self
+                        .composite
+                        .is_checked("Lower the frame rate when nothing's happening");
 
                     return Transition::Pop;
                 }
@@ -60,6 +60,7 @@ impl State for TitleScreen {
 
         self.screensaver
             .update(&mut self.rng, ctx, &app.primary.map);
+        ctx.request_animation_frames();
         Transition::KeepWithMode(EventLoopMode::Animation)
     }
 
@@ -323,8 +324,11 @@ impl Proposals {
         let mut proposals = HashMap::new();
         let mut buttons = Vec::new();
         let mut current_tab = Vec::new();
-        for (name, edits) in
-            abstutil::load_all_objects::<PermanentMapEdits>("../data/system/proposals".to_string())
+        for (name, edits) in abstutil::load_all_versioned_objects::<PermanentMapEdits>(
+            abstutil::path_all_proposals(),
+        )
+        .into_iter()
+        .filter_map(|(meta, result)| result.ok().map(|edits| (meta.name, edits)))
         {
             if current == Some(name.clone()) {
                 let mut txt = Text::new();
@@ -149,6 +149,7 @@ impl Renderable for DrawIntersection {
                     phase,
                     self.id,
                     Some(t),
+                    Some(app.primary.sim.get_walk_signals(self.id)),
                     &mut batch,
                     app,
                     app.opts.traffic_signal_style.clone(),
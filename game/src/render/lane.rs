@@ -147,9 +147,21 @@ impl DrawLane {
                             .make_polygons(Distance::meters(0.25)),
                     );
                 }
-                LaneType::Construction => {}
+                LaneType::Construction => {
+                    draw.extend(
+                        cs.construction_hatching,
+                        calculate_construction_hatching(lane, timer),
+                    );
+                }
             };
         }
+        // Closed lanes keep their type, so hatch over whatever's there
+        if map.get_edits().is_lane_closed(lane.id) {
+            draw.extend(
+                cs.closed_lane_hatching,
+                calculate_construction_hatching(lane, timer),
+            );
+        }
 
         AlmostDrawLane {
             id: lane.id,
@@ -185,6 +197,29 @@ impl Renderable for DrawLane {
     }
 }
 
+// Diagonal stripes across the whole lane, so closures stand out even without the icons
+fn calculate_construction_hatching(lane: &Lane, timer: &mut Timer) -> Vec<Polygon> {
+    let left = lane.lane_center_pts.shift_left(lane.width / 2.0).get(timer);
+    let right = lane
+        .lane_center_pts
+        .shift_right(lane.width / 2.0)
+        .get(timer);
+    let len = left.length().min(right.length());
+
+    let mut result = Vec::new();
+    let mut dist = Distance::ZERO;
+    while dist + lane.width <= len {
+        if let Some(line) = Line::maybe_new(
+            left.dist_along(dist).0,
+            right.dist_along(dist + lane.width).0,
+        ) {
+            result.push(line.make_polygons(Distance::meters(0.5)));
+        }
+        dist += Distance::meters(2.0);
+    }
+    result
+}
+
 // TODO this always does it at pt1
 fn perp_line(l: Line, length: Distance) -> Line {
     let pt1 = l.shift_right(length / 2.0).pt1();
@@ -7,20 +7,40 @@ use ezgui::{
     RewriteColor, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Angle, ArrowCap, Circle, Distance, Duration, Line, PolyLine, Polygon, Pt2D};
-use map_model::{IntersectionID, Phase, TurnPriority, SIDEWALK_THICKNESS};
+use map_model::{
+    CrosswalkTiming, IntersectionID, Phase, TurnGroupID, TurnPriority, SIDEWALK_THICKNESS,
+};
 use std::collections::BTreeSet;
 
-// Only draws a box when time_left is present
+// Only draws a box when time_left is present. Pass in the crosswalks showing walk from the live
+// sim; otherwise, crosswalks that only show walk on demand are drawn faded.
 pub fn draw_signal_phase(
     prerender: &Prerender,
     phase: &Phase,
     i: IntersectionID,
     time_left: Option<Duration>,
+    walk: Option<&BTreeSet<TurnGroupID>>,
     batch: &mut GeomBatch,
     app: &App,
     signal_style: TrafficSignalStyle,
 ) {
     let signal = app.primary.map.get_traffic_signal(i);
+    // None means don't walk
+    let walk_alpha = |g: &TurnGroupID| -> Option<f32> {
+        match walk {
+            Some(set) => {
+                if set.contains(g) {
+                    Some(1.0)
+                } else {
+                    None
+                }
+            }
+            None => match phase.crosswalk_timing {
+                CrosswalkTiming::Fixed => Some(1.0),
+                CrosswalkTiming::Actuated => Some(0.5),
+            },
+        }
+    };
 
     match signal_style {
         TrafficSignalStyle::BAP => {
@@ -66,14 +86,14 @@ pub fn draw_signal_phase(
                             .make_arrow(BIG_ARROW_THICKNESS, ArrowCap::Triangle)
                             .unwrap(),
                     );
-                } else {
+                } else if let Some(alpha) = walk_alpha(g) {
                     let (center, angle) = crosswalk_icon(&signal.turn_groups[g].geom);
                     batch.append(
                         GeomBatch::mapspace_svg(prerender, "../data/system/assets/map/walk.svg")
                             .scale(0.07)
                             .centered_on(center)
                             .rotate(angle)
-                            .color(RewriteColor::ChangeAlpha(percent)),
+                            .color(RewriteColor::ChangeAlpha(percent * alpha)),
                     );
                     dont_walk.remove(g);
                 }
@@ -155,13 +175,14 @@ pub fn draw_signal_phase(
                             .make_arrow(BIG_ARROW_THICKNESS * 2.0, ArrowCap::Triangle)
                             .unwrap(),
                     );
-                } else {
+                } else if let Some(alpha) = walk_alpha(g) {
                     let (center, angle) = crosswalk_icon(&signal.turn_groups[g].geom);
                     batch.append(
                         GeomBatch::mapspace_svg(prerender, "../data/system/assets/map/walk.svg")
                             .scale(0.07)
                             .centered_on(center)
-                            .rotate(angle),
+                            .rotate(angle)
+                            .color(RewriteColor::ChangeAlpha(alpha)),
                     );
                     dont_walk.remove(g);
                 }
@@ -196,6 +217,9 @@ pub fn draw_signal_phase(
             }
             for g in &phase.protected_groups {
                 if g.crosswalk {
+                    if walk_alpha(g).is_none() {
+                        continue;
+                    }
                     make_crosswalk(
                         batch,
                         app.primary.map.get_t(signal.turn_groups[g].members[0]),
@@ -354,6 +378,7 @@ pub fn make_signal_diagram(
                 phase,
                 i,
                 None,
+                None,
                 &mut orig_batch,
                 app,
                 TrafficSignalStyle::Sidewalks,
@@ -401,6 +426,20 @@ pub fn make_signal_diagram(
                     },
                 ])
                 .margin_below(10),
+                if phase.crosswalk_groups().next().is_some() {
+                    Btn::text_fg(match phase.crosswalk_timing {
+                        CrosswalkTiming::Fixed => "Walk: every cycle",
+                        CrosswalkTiming::Actuated => "Walk: when the button is pressed",
+                    })
+                    .build(
+                        ctx,
+                        format!("change walk timing of phase {}", idx + 1),
+                        None,
+                    )
+                    .margin_below(10)
+                } else {
+                    Widget::nothing()
+                },
                 Widget::row(vec![
                     phase_btn,
                     Widget::col(vec![
@@ -1,8 +1,7 @@
 use crate::app::App;
-use crate::common::Tab;
-use crate::game::{msg, DrawBaselayer, State, Transition};
+use crate::game::{DrawBaselayer, State, Transition};
+use crate::layer::bus::ShowBusRoute;
 use crate::sandbox::dashboards::DashTab;
-use crate::sandbox::SandboxMode;
 use ezgui::{
     Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series, Widget,
 };
@@ -102,25 +101,11 @@ impl State for BusRoutes {
         match self.composite.event(ctx) {
             Some(Outcome::Clicked(x)) => {
                 if let Some(r) = app.primary.map.get_bus_route(&x) {
-                    let buses = app.primary.sim.status_of_buses(r.id);
-                    if buses.is_empty() {
-                        Transition::Push(msg(
-                            "No buses running",
-                            vec![format!("Sorry, no buses for route {} running", r.name)],
-                        ))
-                    } else {
-                        Transition::PopWithData(Box::new(move |state, ctx, app| {
-                            let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
-                            let mut actions = sandbox.contextual_actions();
-                            sandbox.controls.common.as_mut().unwrap().launch_info_panel(
-                                ctx,
-                                app,
-                                // Arbitrarily use the first one
-                                Tab::BusStatus(buses[0].0),
-                                &mut actions,
-                            );
-                        }))
-                    }
+                    // Show the route even when no buses are running
+                    let id = r.id;
+                    Transition::PopWithData(Box::new(move |_, ctx, app| {
+                        app.layer = Some(Box::new(ShowBusRoute::new(ctx, app, id)));
+                    }))
                 } else {
                     DashTab::BusRoutes.transition(ctx, app, &x)
                 }
@@ -4,8 +4,11 @@ mod summaries;
 mod trip_table;
 
 use crate::app::App;
-use crate::game::Transition;
-use ezgui::{hotkey, Btn, Color, EventCtx, Key, Widget};
+use crate::game::{State, Transition};
+use crate::layer::map::Static;
+use abstutil::Severity;
+use ezgui::{hotkey, Btn, Color, EventCtx, EventLoopMode, GfxCtx, Key, Widget};
+use std::{thread, time};
 pub use trip_table::TripTable;
 
 // Oh the dashboards melted, but we still had the radio
@@ -41,15 +44,19 @@ impl DashTab {
             // TODO Centered, but actually, we need to set the padding of each button to divide the
             // available space evenly. Fancy fill rules... hmmm.
             Widget::row(row).bg(Color::WHITE).margin_vert(16),
+            Btn::text_fg("generate report")
+                .build_def(ctx, None)
+                .align_right(),
             Btn::plaintext("X")
                 .build(ctx, "close", hotkey(Key::Escape))
-                .align_right(),
+                .margin_left(16),
         ])
     }
 
-    pub fn transition(self, ctx: &mut EventCtx, app: &App, action: &str) -> Transition {
+    pub fn transition(self, ctx: &mut EventCtx, app: &mut App, action: &str) -> Transition {
         match action {
             "close" => Transition::Pop,
+            "generate report" => Transition::Push(GenerateReport::new(ctx, app)),
             "trip table" => Transition::Replace(TripTable::new(ctx, app)),
             "trip summaries" => Transition::Replace(summaries::TripSummaries::new(
                 ctx,
@@ -65,3 +72,81 @@ impl DashTab {
         }
     }
 }
+
+// Writes an HTML page summarizing the edits and the run so far, to share with people who don't
+// have the game. The overview is a screenshot of the whole map with the edits layer, so this
+// state has to show that for a frame first.
+struct GenerateReport {
+    edits: Static,
+    // (cam_x, cam_y, cam_zoom) to restore afterwards
+    orig_camera: (f64, f64, f64),
+    drawn: bool,
+}
+
+impl GenerateReport {
+    fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let orig_camera = (ctx.canvas.cam_x, ctx.canvas.cam_y, ctx.canvas.cam_zoom);
+        ctx.canvas.cam_zoom = ctx.canvas.min_zoom();
+        ctx.canvas
+            .center_on_map_pt(app.primary.map.get_bounds().center());
+        Box::new(GenerateReport {
+            edits: Static::edits(ctx, app),
+            orig_camera,
+            drawn: false,
+        })
+    }
+}
+
+impl State for GenerateReport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if !self.drawn {
+            self.drawn = true;
+            ctx.request_animation_frames();
+            return Transition::KeepWithMode(EventLoopMode::Animation);
+        }
+
+        // Same as screenshot_everything; give the frame time to actually show up
+        thread::sleep(time::Duration::from_millis(100));
+        let png = ezgui::screenshot_png();
+        if png.is_none() {
+            app.toasts.notify(
+                Severity::Warn,
+                "Couldn't capture the map; the report won't have an overview".to_string(),
+            );
+        }
+
+        let (cam_x, cam_y, cam_zoom) = self.orig_camera;
+        ctx.canvas.cam_x = cam_x;
+        ctx.canvas.cam_y = cam_y;
+        ctx.canvas.cam_zoom = cam_zoom;
+
+        let baseline = if app.has_prebaked().is_some() {
+            Some(app.prebaked())
+        } else {
+            None
+        };
+        let html = sim::make_report(&app.primary.map, &app.primary.sim, baseline, png.as_deref());
+        let path = abstutil::path_report(
+            app.primary.map.get_name(),
+            &format!(
+                "{}_{}",
+                app.primary.map.get_edits().edits_name,
+                app.primary.sim.time().as_filename()
+            ),
+        );
+        match std::fs::write(&path, html) {
+            Ok(()) => app
+                .toasts
+                .notify(Severity::Info, format!("Report saved to {}", path)),
+            Err(err) => app.toasts.notify(
+                Severity::Error,
+                format!("Couldn't write report to {}: {}", path, err),
+            ),
+        }
+        Transition::Pop
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.edits.unzoomed);
+    }
+}
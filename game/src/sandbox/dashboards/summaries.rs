@@ -8,7 +8,7 @@ use ezgui::{
     GfxCtx, Line, Outcome, Text, TextExt, Widget,
 };
 use geom::{Distance, Duration, Polygon, Pt2D};
-use sim::TripMode;
+use sim::{pct_diff, TimeSpent, TripMode};
 use std::collections::BTreeSet;
 
 pub struct TripSummaries {
@@ -49,6 +49,7 @@ impl TripSummaries {
                     DashTab::TripSummaries.picker(ctx, app),
                     Widget::row(filters).centered_horiz().margin_below(10),
                     summary(ctx, app, &filter).margin_below(10),
+                    time_spent(ctx, app, &filter).margin_below(10),
                     Widget::row(vec![
                         contingency_table(ctx, app, &filter)
                             .centered_vert()
@@ -105,70 +106,41 @@ fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
         return Widget::nothing();
     }
 
-    let mut num_same = 0;
-    let mut num_faster = 0;
-    let mut num_slower = 0;
-    let mut sum_faster = Duration::ZERO;
-    let mut sum_slower = Duration::ZERO;
-    for (b, a, mode) in app
-        .primary
-        .sim
-        .get_analytics()
-        .both_finished_trips(app.primary.sim.time(), app.prebaked())
-    {
-        if !filter.modes.contains(&mode) {
-            continue;
-        }
-        let same = if let Some(pct) = filter.changes_pct {
-            pct_diff(a, b) <= pct
-        } else {
-            a == b
-        };
-
-        if same {
-            num_same += 1;
-        } else if a < b {
-            num_faster += 1;
-            sum_faster += b - a;
-        } else {
-            num_slower += 1;
-            sum_slower += a - b;
-        }
-    }
+    let cmp = app.primary.sim.get_analytics().compare_trips(
+        app.primary.sim.time(),
+        app.prebaked(),
+        &filter.modes,
+        filter.changes_pct,
+    );
 
     Widget::col(vec![Widget::row(vec![
         Widget::col(vec![Text::from_multiline(vec![
-            Line(format!("{} trips faster", prettyprint_usize(num_faster))),
-            Line(format!("{} total time saved", sum_faster)),
             Line(format!(
-                "Average {} per faster trip",
-                if num_faster == 0 {
-                    Duration::ZERO
-                } else {
-                    sum_faster / (num_faster as f64)
-                }
+                "{} trips faster",
+                prettyprint_usize(cmp.num_faster)
             )),
+            Line(format!("{} total time saved", cmp.sum_faster)),
+            Line(format!("Average {} per faster trip", cmp.avg_faster())),
         ])
         .draw(ctx)])
         .outline(2.0, Color::GREEN)
         .padding(10),
-        Line(format!("{} trips unchanged", prettyprint_usize(num_same)))
-            .draw(ctx)
-            .centered_vert()
-            .margin_horiz(5)
-            .outline(2.0, Color::YELLOW)
-            .padding(10),
+        Line(format!(
+            "{} trips unchanged",
+            prettyprint_usize(cmp.num_same)
+        ))
+        .draw(ctx)
+        .centered_vert()
+        .margin_horiz(5)
+        .outline(2.0, Color::YELLOW)
+        .padding(10),
         Widget::col(vec![Text::from_multiline(vec![
-            Line(format!("{} trips slower", prettyprint_usize(num_slower))),
-            Line(format!("{} total time lost", sum_slower)),
             Line(format!(
-                "Average {} per slower trip",
-                if num_slower == 0 {
-                    Duration::ZERO
-                } else {
-                    sum_slower / (num_slower as f64)
-                }
+                "{} trips slower",
+                prettyprint_usize(cmp.num_slower)
             )),
+            Line(format!("{} total time lost", cmp.sum_slower)),
+            Line(format!("Average {} per slower trip", cmp.avg_slower())),
         ])
         .draw(ctx)])
         .outline(2.0, Color::RED)
@@ -177,6 +149,33 @@ fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     .evenly_spaced()])
 }
 
+// Where the time in finished trips goes
+fn time_spent(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+    let now = app.primary.sim.time();
+    let after = app
+        .primary
+        .sim
+        .get_analytics()
+        .total_time_spent(now, &filter.modes);
+    let before = if app.has_prebaked().is_some() {
+        Some(app.prebaked().total_time_spent(now, &filter.modes))
+    } else {
+        None
+    };
+
+    let mut txt = Text::from(Line("Time spent in finished trips"));
+    for category in TimeSpent::all() {
+        let dt = after.get(&category).cloned().unwrap_or(Duration::ZERO);
+        let mut line = format!("{} {}", dt, category.describe());
+        if let Some(ref before) = before {
+            let dt_before = before.get(&category).cloned().unwrap_or(Duration::ZERO);
+            line = format!("{} (before: {})", line, dt_before);
+        }
+        txt.add(Line(line).secondary());
+    }
+    txt.draw(ctx).centered_horiz()
+}
+
 fn scatter_plot(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
@@ -365,11 +364,3 @@ impl Filter {
         points
     }
 }
-
-fn pct_diff(a: Duration, b: Duration) -> f64 {
-    if a >= b {
-        (a / b) - 1.0
-    } else {
-        (b / a) - 1.0
-    }
-}
@@ -2,7 +2,7 @@ use crate::app::{App, ShowEverything};
 use crate::common::{CityPicker, CommonState};
 use crate::edit::EditMode;
 use crate::game::{State, Transition, WizardState};
-use crate::helpers::{nice_map_name, ID};
+use crate::helpers::{nice_map_name, saved_file_choice, ID};
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::SandboxControls;
 use crate::sandbox::SandboxMode;
@@ -138,10 +138,14 @@ pub fn make_change_traffic(btn: ScreenRectangle, current: String) -> Box<dyn Sta
             None,
             || {
                 let mut list = Vec::new();
-                for name in abstutil::list_all_objects(abstutil::path_all_scenarios(
-                    app.primary.map.get_name(),
-                )) {
-                    if name == "weekday" {
+                for meta in
+                    abstutil::list_objects(abstutil::path_all_scenarios(app.primary.map.get_name()))
+                {
+                    let name = meta.name.clone();
+                    // A broken weekday scenario is flagged like any other file
+                    if name == "weekday"
+                        && abstutil::check_versioned_file::<Scenario>(&meta.path).is_ok()
+                    {
                         list.push(Choice::new("realistic weekday traffic", name).tooltip(
                             "Trips will begin throughout the entire day. Midnight is usually \
                              quiet, so you may need to fast-forward to morning rush hour. Data \
@@ -156,7 +160,7 @@ pub fn make_change_traffic(btn: ScreenRectangle, current: String) -> Box<dyn Sta
                                 ),
                         );
                     } else {
-                        list.push(Choice::new(name.clone(), name));
+                        list.push(saved_file_choice::<Scenario, _>(&meta, name));
                     }
                 }
                 list.push(
@@ -2,6 +2,7 @@
 pub mod commute;
 pub mod fix_traffic_signals;
 mod freeform;
+mod onboarding;
 mod play_scenario;
 mod tutorial;
 
@@ -37,6 +38,8 @@ pub enum GameplayMode {
 
     // current
     Tutorial(TutorialPointer),
+    // current step
+    Onboarding(usize),
 }
 
 pub trait GameplayState: downcast_rs::Downcast {
@@ -84,6 +87,7 @@ impl GameplayMode {
             GameplayMode::FixTrafficSignals => abstutil::path_map("downtown"),
             GameplayMode::OptimizeCommute(_, _) => abstutil::path_map("montlake"),
             GameplayMode::Tutorial(_) => abstutil::path_map("montlake"),
+            GameplayMode::Onboarding(_) => abstutil::path_synthetic_map(onboarding::ONBOARDING_MAP),
         }
     }
 
@@ -105,6 +109,9 @@ impl GameplayMode {
             GameplayMode::Tutorial(_) => {
                 return None;
             }
+            GameplayMode::Onboarding(_) => {
+                return Some(onboarding::scenario(map).generate(map, &mut rng, timer));
+            }
             _ => "weekday".to_string(),
         };
         Some(if name == "random" {
@@ -116,11 +123,11 @@ impl GameplayMode {
             .generate(map, &mut rng, &mut Timer::new("generate scenario"))
         } else if name == "5 weekdays repeated" {
             let s: Scenario =
-                abstutil::read_binary(abstutil::path_scenario(map.get_name(), "weekday"), timer);
+                abstutil::read_versioned(abstutil::path_scenario(map.get_name(), "weekday"), timer);
             s.repeat_days(5)
         } else {
             let path = abstutil::path_scenario(map.get_name(), &name);
-            match abstutil::maybe_read_binary(path.clone(), timer) {
+            match abstutil::maybe_read_versioned(path.clone(), timer) {
                 Ok(s) => s,
                 Err(err) => {
                     println!("\n\n{} is missing or corrupt. Check https://github.com/dabreegster/abstreet/blob/master/docs/dev.md and file an issue if you have trouble.", path);
@@ -157,7 +164,11 @@ impl GameplayMode {
             match cmd {
                 EditCmd::ChangeLaneType { .. }
                 | EditCmd::ReverseLane { .. }
-                | EditCmd::ChangeSpeedLimit { .. } => {
+                | EditCmd::ChangeSpeedLimit { .. }
+                | EditCmd::ChangeRouteStops { .. }
+                | EditCmd::ChangeRouteSchedule { .. }
+                | EditCmd::ChangeTurnRestriction { .. }
+                | EditCmd::CloseLane { .. } => {
                     if !self.can_edit_lanes() {
                         return false;
                     }
@@ -240,6 +251,7 @@ impl GameplayMode {
                 commute::OptimizeCommute::new(ctx, app, *p, *goal)
             }
             GameplayMode::Tutorial(current) => Tutorial::new(ctx, app, *current),
+            GameplayMode::Onboarding(current) => onboarding::Onboarding::new(ctx, app, *current),
         }
     }
 }
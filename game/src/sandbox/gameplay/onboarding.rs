@@ -0,0 +1,323 @@
+use crate::app::App;
+use crate::edit::EditMode;
+use crate::game::{msg, Transition};
+use crate::helpers::ID;
+use crate::sandbox::gameplay::{GameplayMode, GameplayState};
+use crate::sandbox::{maybe_exit_sandbox, SandboxControls};
+use ezgui::{
+    lctrl, Btn, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, OverlayPanel, PanelAnchor, Text, VerticalAlignment, Widget,
+};
+use geom::{Duration, Polygon, Pt2D, Time};
+use map_model::Map;
+use sim::{BorderSpawnOverTime, OriginDestination, ScenarioGenerator, SpawnOverTime};
+
+// Small enough to understand at a glance
+pub const ONBOARDING_MAP: &str = "grid_2x2";
+
+// Something the player has to do before moving on. New tutorials should be able to reuse these.
+#[derive(Clone, Copy, PartialEq)]
+enum Goal {
+    MoveCamera,
+    SelectLane,
+    StartSim,
+    // Remembers the total trip time, to compare against later
+    FinishTrips,
+    EditMap,
+    CompareScores,
+    Done,
+}
+
+// What to highlight on the map during a step
+#[derive(Clone, Copy)]
+enum Target {
+    // A driving lane near the middle of the map
+    CenterLane,
+}
+
+struct Step {
+    title: &'static str,
+    instructions: Vec<&'static str>,
+    goal: Goal,
+    target: Option<Target>,
+}
+
+fn steps() -> Vec<Step> {
+    vec![
+        Step {
+            title: "Look around",
+            instructions: vec![
+                "Click and drag to pan the map.",
+                "Scroll to zoom in and out.",
+            ],
+            goal: Goal::MoveCamera,
+            target: None,
+        },
+        Step {
+            title: "Select a lane",
+            instructions: vec![
+                "Zoom in and hover over the highlighted lane.",
+                "Lanes, intersections, and buildings can all be selected.",
+            ],
+            goal: Goal::SelectLane,
+            target: Some(Target::CenterLane),
+        },
+        Step {
+            title: "Run the simulation",
+            instructions: vec!["Press the play button at the bottom to start time."],
+            goal: Goal::StartSim,
+            target: None,
+        },
+        Step {
+            title: "Let everyone arrive",
+            instructions: vec![
+                "Speed up time until every trip finishes.",
+                "The total time spent traveling is your baseline score.",
+            ],
+            goal: Goal::FinishTrips,
+            target: None,
+        },
+        Step {
+            title: "Edit a lane",
+            instructions: vec![
+                "Click the edit button, then click the highlighted lane.",
+                "Try turning it into a bike lane, then finish editing.",
+            ],
+            goal: Goal::EditMap,
+            target: Some(Target::CenterLane),
+        },
+        Step {
+            title: "Compare scores",
+            instructions: vec![
+                "The simulation restarted with your edit.",
+                "Run it until everyone arrives again to see how your change did.",
+            ],
+            goal: Goal::CompareScores,
+            target: None,
+        },
+        Step {
+            title: "All done",
+            instructions: vec![
+                "That's the basic loop: watch traffic, edit the map, compare.",
+                "Try the full tutorial or a challenge next.",
+            ],
+            goal: Goal::Done,
+            target: None,
+        },
+    ]
+}
+
+impl Target {
+    fn find(self, map: &Map) -> Option<ID> {
+        match self {
+            Target::CenterLane => {
+                let center = map.get_bounds().center();
+                map.all_lanes()
+                    .iter()
+                    .filter(|l| l.is_driving())
+                    .min_by_key(|l| l.lane_center_pts.middle().dist_to(center))
+                    .map(|l| ID::Lane(l.id))
+            }
+        }
+    }
+}
+
+// Total time spent in finished trips
+fn score(app: &App) -> Duration {
+    app.primary
+        .sim
+        .get_analytics()
+        .finished_trips
+        .iter()
+        .filter(|(_, _, mode, _)| mode.is_some())
+        .fold(Duration::ZERO, |sum, (_, _, _, dt)| sum + *dt)
+}
+
+pub struct Onboarding {
+    top_center: Composite,
+    steps: Vec<Step>,
+    current: usize,
+    target: Option<ID>,
+    // Where the camera was when the step started
+    start_camera: (Pt2D, f64),
+}
+
+impl Onboarding {
+    pub fn new(ctx: &mut EventCtx, app: &App, current: usize) -> Box<dyn GameplayState> {
+        Box::new(Onboarding::make(ctx, app, current))
+    }
+
+    fn make(ctx: &mut EventCtx, app: &App, current: usize) -> Onboarding {
+        let steps = steps();
+        let current = current.min(steps.len() - 1);
+        let target = steps[current].target.and_then(|t| t.find(&app.primary.map));
+        Onboarding {
+            top_center: make_top_center(ctx, app, &steps, current),
+            steps,
+            current,
+            target,
+            start_camera: (ctx.canvas.center_to_map_pt(), ctx.canvas.cam_zoom),
+        }
+    }
+
+    fn is_done(&self, ctx: &EventCtx, app: &App) -> bool {
+        let sim = &app.primary.sim;
+        let edited = !app.primary.map.get_edits().commands.is_empty();
+        match self.steps[self.current].goal {
+            Goal::MoveCamera => {
+                (ctx.canvas.center_to_map_pt(), ctx.canvas.cam_zoom) != self.start_camera
+            }
+            Goal::SelectLane => match app.primary.current_selection {
+                Some(ID::Lane(_)) => true,
+                _ => false,
+            },
+            Goal::StartSim => sim.time() > Time::START_OF_DAY,
+            Goal::FinishTrips => sim.time() > Time::START_OF_DAY && sim.is_done(),
+            Goal::EditMap => edited,
+            Goal::CompareScores => edited && sim.time() > Time::START_OF_DAY && sim.is_done(),
+            Goal::Done => false,
+        }
+    }
+}
+
+impl GameplayState for Onboarding {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        _: &mut SandboxControls,
+    ) -> Option<Transition> {
+        match self.top_center.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Quit" => {
+                    return Some(maybe_exit_sandbox());
+                }
+                "edit map" => {
+                    return Some(Transition::Push(Box::new(EditMode::new(
+                        ctx,
+                        app,
+                        GameplayMode::Onboarding(self.current),
+                    ))));
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        if !self.is_done(ctx, app) {
+            return None;
+        }
+        let goal = self.steps[self.current].goal;
+        *self = Onboarding::make(ctx, app, self.current + 1);
+        match goal {
+            Goal::FinishTrips => {
+                app.session.onboarding_baseline = Some(score(app));
+            }
+            Goal::CompareScores => {
+                let after = score(app);
+                let lines = match app.session.onboarding_baseline {
+                    Some(before) if after < before => vec![format!(
+                        "Trips took {} in total, down from {}. Nice!",
+                        after, before
+                    )],
+                    Some(before) if after > before => vec![
+                        format!("Trips took {} in total, up from {}.", after, before),
+                        "Not every edit helps! Try something else.".to_string(),
+                    ],
+                    Some(_) => vec![format!("Trips took {} in total, same as before.", after)],
+                    None => vec![format!("Trips took {} in total.", after)],
+                };
+                return Some(Transition::Push(msg("Scores", lines)));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ID::Lane(l)) = self.target {
+            g.draw_polygon(
+                Color::hex("#e25822").alpha(0.8),
+                &app.primary.draw_map.get_l(l).polygon,
+            );
+        }
+        self.top_center.draw(g);
+
+        if app.primary.sim.time() > Time::START_OF_DAY {
+            let mut txt = Text::from(Line(format!("Trips so far took {} in total", score(app))));
+            if let Some(before) = app.session.onboarding_baseline {
+                txt.add(Line(format!("Baseline: {}", before)));
+            }
+            g.declare_panel(OverlayPanel::new(PanelAnchor::TopRight, txt));
+        }
+    }
+}
+
+fn make_top_center(ctx: &mut EventCtx, app: &App, steps: &[Step], current: usize) -> Composite {
+    let step = &steps[current];
+    let mut txt = Text::from(
+        Line(format!(
+            "Step {}/{}: {}",
+            current + 1,
+            steps.len(),
+            step.title
+        ))
+        .small_heading(),
+    );
+    for line in &step.instructions {
+        txt.add(Line(*line));
+    }
+
+    let mut row = vec![
+        Line("Quick start").small_heading().draw(ctx).margin(5),
+        Widget::draw_batch(
+            ctx,
+            GeomBatch::from(vec![(Color::WHITE, Polygon::rectangle(2.0, 50.0))]),
+        )
+        .margin(5),
+    ];
+    // Editing before the baseline is recorded would spoil the comparison
+    if steps[..current].iter().any(|s| s.goal == Goal::FinishTrips) {
+        row.push(
+            Btn::svg_def("../data/system/assets/tools/edit_map.svg")
+                .build(ctx, "edit map", lctrl(Key::E))
+                .margin(5),
+        );
+    }
+    row.push(Btn::text_fg("Quit").build_def(ctx, None).margin(5));
+
+    Composite::new(
+        Widget::col(vec![Widget::row(row).centered(), txt.draw(ctx).margin(5)])
+            .bg(app.cs.panel_bg)
+            .padding(16),
+    )
+    .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+    .build(ctx)
+}
+
+pub fn scenario(map: &Map) -> ScenarioGenerator {
+    let mut s = ScenarioGenerator::empty("onboarding");
+    s.only_seed_buses = None;
+    s.spawn_over_time.push(SpawnOverTime {
+        num_agents: 20,
+        start_time: Time::START_OF_DAY,
+        stop_time: Time::START_OF_DAY + Duration::minutes(1),
+        goal: OriginDestination::Anywhere,
+        percent_driving: 0.5,
+        percent_biking: 0.2,
+        percent_use_transit: 0.0,
+    });
+    for i in map.all_incoming_borders() {
+        s.border_spawn_over_time.push(BorderSpawnOverTime {
+            num_peds: 2,
+            num_cars: 5,
+            num_bikes: 2,
+            start_time: Time::START_OF_DAY,
+            stop_time: Time::START_OF_DAY + Duration::minutes(1),
+            start_from_border: i.some_outgoing_road(map).unwrap(),
+            goal: OriginDestination::Anywhere,
+            percent_use_transit: 0.0,
+        });
+    }
+    s
+}
@@ -0,0 +1,245 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::game::{State, Transition};
+use ezgui::{
+    hotkey, Btn, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Speed, Time};
+use map_model::{Map, Traversable};
+use sim::{AgentID, AgentProperties, TripEndpoint, VehicleType};
+
+// Shows everything the sim knows about one agent, updating as time passes. Following who the
+// agent is waiting behind walks down the queue; backspace walks back up.
+pub struct AgentInspector {
+    composite: Composite,
+    // The agent being inspected is last
+    chain: Vec<AgentID>,
+    time: Time,
+    // As of the last refresh, so drawing doesn't have to ask the sim. None once the agent is gone.
+    props: Option<AgentProperties>,
+    // The speed at the last refresh, to estimate acceleration from
+    prev_speed: Option<(Time, Speed)>,
+}
+
+impl AgentInspector {
+    pub fn new(ctx: &mut EventCtx, app: &App, id: AgentID) -> Box<dyn State> {
+        let props = get_props(app, id);
+        Box::new(AgentInspector {
+            composite: make_panel(ctx, app, &vec![id], props.as_ref(), None),
+            chain: vec![id],
+            time: app.primary.sim.time(),
+            prev_speed: props.as_ref().map(|p| (app.primary.sim.time(), p.speed)),
+            props,
+        })
+    }
+
+    fn current(&self) -> AgentID {
+        *self.chain.last().unwrap()
+    }
+
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &App) {
+        let now = app.primary.sim.time();
+        self.props = get_props(app, self.current());
+        let accel = match (&self.props, self.prev_speed) {
+            (Some(props), Some((t, speed))) if now > t => {
+                Some((props.speed - speed) * (1.0 / (now - t).inner_seconds()))
+            }
+            _ => None,
+        };
+        self.prev_speed = self.props.as_ref().map(|p| (now, p.speed));
+        self.time = now;
+        self.composite = make_panel(ctx, app, &self.chain, self.props.as_ref(), accel);
+    }
+
+    // Switching to a different agent; the old speed says nothing about the new one
+    fn change_agent(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.prev_speed = None;
+        self.refresh(ctx, app);
+    }
+}
+
+impl State for AgentInspector {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "back" => {
+                    self.chain.pop();
+                    self.change_agent(ctx, app);
+                }
+                "step forwards 0.1s" => {
+                    app.primary
+                        .sim
+                        .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
+                }
+                "waiting behind" => {
+                    let blocker = self.props.as_ref().unwrap().waiting_for.unwrap();
+                    if let Some(pt) = app
+                        .primary
+                        .sim
+                        .canonical_pt_for_agent(blocker, &app.primary.map)
+                    {
+                        ctx.canvas.center_on_map_pt(pt);
+                    }
+                    self.chain.push(blocker);
+                    self.change_agent(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        if app.primary.sim.time() != self.time {
+            self.refresh(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let sim = &app.primary.sim;
+        let map = &app.primary.map;
+        let radius = Distance::meters(10.0) / g.canvas.cam_zoom;
+        let mut batch = GeomBatch::new();
+        let pt = sim.canonical_pt_for_agent(self.current(), map);
+        if let Some(pt) = pt {
+            batch.push(Color::CYAN.alpha(0.5), Circle::new(pt, radius).to_polygon());
+        }
+        if let Some(ref props) = self.props {
+            let blocker = props
+                .waiting_for
+                .and_then(|a| sim.canonical_pt_for_agent(a, map));
+            if let (Some(pt1), Some(pt2)) = (pt, blocker) {
+                batch.push(Color::RED.alpha(0.5), Circle::new(pt2, radius).to_polygon());
+                if let Some(line) = geom::Line::maybe_new(pt1, pt2) {
+                    batch.push(Color::RED, line.make_polygons(Distance::meters(1.0)));
+                }
+            }
+        }
+        batch.draw(g);
+
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn get_props(app: &App, id: AgentID) -> Option<AgentProperties> {
+    if app.primary.sim.does_agent_exist(id) {
+        Some(app.primary.sim.agent_properties(id, &app.primary.map))
+    } else {
+        None
+    }
+}
+
+// accel is how fast the speed changed since the last refresh, if the agent was around then
+fn make_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    chain: &Vec<AgentID>,
+    props: Option<&AgentProperties>,
+    accel: Option<Speed>,
+) -> Composite {
+    let id = *chain.last().unwrap();
+    let sim = &app.primary.sim;
+    let map = &app.primary.map;
+
+    let mut col = vec![Widget::row(vec![
+        Line(format!("Inspecting {}", id)).small_heading().draw(ctx),
+        Btn::plaintext("X")
+            .build(ctx, "close", hotkey(Key::Escape))
+            .align_right(),
+    ])];
+    if chain.len() > 1 {
+        col.push(
+            Btn::text_fg(format!("back to {}", chain[chain.len() - 2])).build(
+                ctx,
+                "back",
+                hotkey(Key::Backspace),
+            ),
+        );
+    }
+
+    let mode = match id {
+        AgentID::Pedestrian(_) => "walking",
+        AgentID::Car(c) => match c.1 {
+            VehicleType::Car => "driving",
+            VehicleType::Bike => "biking",
+            VehicleType::Bus => "bus",
+        },
+        AgentID::BusPassenger(_, _) => "riding the bus",
+    };
+    let mut txt = Text::new();
+    txt.add(Line(format!("Mode: {}", mode)));
+    let props = if let Some(props) = props {
+        props
+    } else {
+        txt.add(Line(format!("{} isn't on the map anymore", id)).fg(Color::RED));
+        col.push(txt.draw(ctx));
+        return build(ctx, app, col);
+    };
+
+    txt.add(Line(format!("Speed: {}", props.speed)));
+    match accel {
+        Some(accel) => txt.add(Line(format!("Acceleration: {} per second", accel))),
+        None => txt.add(Line("Acceleration: known after the next update").secondary()),
+    }
+    txt.add(Line(format!("On: {}", describe(props.on, map))));
+    txt.add(Line(format!(
+        "  {} along, out of {}",
+        props.dist_along,
+        props.on.length(map)
+    )));
+    if let Some(trip) = sim.agent_to_trip(id) {
+        let (start, from, to, _) = sim.trip_info(trip);
+        txt.add(Line(format!("{}, departed at {}", trip, start)));
+        txt.add(Line(format!("  From: {}", name_endpoint(&from, map))));
+        txt.add(Line(format!("  To: {}", name_endpoint(&to, map))));
+    }
+    txt.add(Line(format!("Waiting here for {}", props.waiting_here)));
+    txt.add(Line(format!(
+        "Waiting for {} in total, this leg",
+        props.total_waiting
+    )));
+    col.push(txt.draw(ctx));
+
+    col.push(match props.waiting_for {
+        Some(blocker) => Btn::text_fg(format!("waiting behind {}", blocker)).build(
+            ctx,
+            "waiting behind",
+            hotkey(Key::B),
+        ),
+        None => "Not waiting behind anybody".draw_text(ctx),
+    });
+    col.push(Btn::text_fg("step forwards 0.1s").build_def(ctx, hotkey(Key::N)));
+    build(ctx, app, col)
+}
+
+fn build(ctx: &mut EventCtx, app: &App, col: Vec<Widget>) -> Composite {
+    Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(16))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx)
+}
+
+fn describe(on: Traversable, map: &Map) -> String {
+    match on {
+        Traversable::Lane(l) => format!("{} of {}", l, map.get_parent(l).get_name()),
+        Traversable::Turn(t) => format!(
+            "{}, from {} to {}",
+            t,
+            map.get_parent(t.src).get_name(),
+            map.get_parent(t.dst).get_name()
+        ),
+    }
+}
+
+fn name_endpoint(endpt: &TripEndpoint, map: &Map) -> String {
+    match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).address.clone(),
+        TripEndpoint::Border(i, _) => format!("off map, via {}", map.get_i(*i).name(map)),
+    }
+}
@@ -7,7 +7,7 @@ use ezgui::{
     Line, Outcome, Text, VerticalAlignment, Widget,
 };
 use geom::{ArrowCap, Distance, Polygon, Time};
-use map_model::{IntersectionID, LaneID, TurnType};
+use map_model::{IntersectionID, LaneID, MovementType, TurnID, TurnPriority, TurnType};
 use sim::{AgentID, DontDrawAgents};
 
 pub struct RoutePreview {
@@ -97,12 +97,12 @@ impl State for ShowTrafficSignal {
         ctx.canvas_movement();
 
         // TODO Buttons for these...
-        if self.current_phase != 0 && ctx.input.new_was_pressed(&hotkey(Key::UpArrow).unwrap()) {
+        if self.current_phase != 0 && ctx.action_pressed("previous phase", hotkey(Key::UpArrow)) {
             self.change_phase(self.current_phase - 1, ctx, app);
         }
 
         if self.current_phase != app.primary.map.get_traffic_signal(self.i).phases.len() - 1
-            && ctx.input.new_was_pressed(&hotkey(Key::DownArrow).unwrap())
+            && ctx.action_pressed("next phase", hotkey(Key::DownArrow))
         {
             self.change_phase(self.current_phase + 1, ctx, app);
         }
@@ -136,6 +136,7 @@ impl State for ShowTrafficSignal {
             &app.primary.map.get_traffic_signal(self.i).phases[self.current_phase],
             self.i,
             None,
+            None,
             &mut batch,
             app,
             app.opts.traffic_signal_style.clone(),
@@ -148,18 +149,53 @@ impl State for ShowTrafficSignal {
 
 pub struct TurnExplorer {
     l: LaneID,
-    // 0 means all turns, otherwise one particular turn
+    // 0 means all matching turns, otherwise one particular turn
     idx: usize,
     composite: Composite,
+    filter: Option<MovementType>,
+    // Indices of the turns matching the filter
+    matching: Vec<usize>,
+
+    // Per turn from the lane, cached while exploring
+    movements: Vec<Option<MovementType>>,
+    conflicts: Vec<Vec<TurnID>>,
+    // None if the intersection doesn't have a signal
+    phases: Vec<Option<Vec<(usize, TurnPriority)>>>,
 }
 
 impl TurnExplorer {
     pub fn new(ctx: &mut EventCtx, app: &App, l: LaneID) -> Box<dyn State> {
-        Box::new(TurnExplorer {
+        let map = &app.primary.map;
+        let turns = map.get_turns_from_lane(l);
+        let mut explorer = TurnExplorer {
             l,
             idx: 0,
-            composite: TurnExplorer::make_panel(ctx, app, l, 0),
-        })
+            composite: Composite::new(Widget::nothing()).build(ctx),
+            filter: None,
+            matching: (0..turns.len()).collect(),
+            movements: turns.iter().map(|t| t.movement_type(map)).collect(),
+            conflicts: turns
+                .iter()
+                .map(|t| map.get_conflicting_turns(t.id))
+                .collect(),
+            phases: turns
+                .iter()
+                .map(|t| {
+                    map.maybe_get_traffic_signal(t.id.parent)
+                        .map(|signal| signal.phases_with_turn(t.id))
+                })
+                .collect(),
+        };
+        explorer.composite = explorer.make_panel(ctx, app);
+        Box::new(explorer)
+    }
+
+    fn set_filter(&mut self, filter: Option<MovementType>) {
+        self.filter = filter;
+        self.idx = 0;
+        self.matching = (0..self.movements.len())
+            .filter(|idx| filter.is_none() || self.movements[*idx] == filter)
+            .collect();
     }
 }
 
@@ -174,13 +210,21 @@ impl State for TurnExplorer {
                 }
                 "previous turn" => {
                     self.idx -= 1;
-                    self.composite = TurnExplorer::make_panel(ctx, app, self.l, self.idx);
+                    self.composite = self.make_panel(ctx, app);
                 }
                 "next turn" => {
                     self.idx += 1;
-                    self.composite = TurnExplorer::make_panel(ctx, app, self.l, self.idx);
+                    self.composite = self.make_panel(ctx, app);
+                }
+                "all movements" => {
+                    self.set_filter(None);
+                    self.composite = self.make_panel(ctx, app);
+                }
+                x => {
+                    let filter = *MOVEMENTS.iter().find(|m| m.describe() == x).unwrap();
+                    self.set_filter(Some(filter));
+                    self.composite = self.make_panel(ctx, app);
                 }
-                _ => unreachable!(),
             },
             None => {}
         }
@@ -201,8 +245,9 @@ impl State for TurnExplorer {
         }
         app.draw(g, opts, &DontDrawAgents {}, &ShowEverything::new());
 
+        let turns = app.primary.map.get_turns_from_lane(self.l);
         if self.idx == 0 {
-            for turn in &app.primary.map.get_turns_from_lane(self.l) {
+            for turn in self.matching.iter().map(|idx| turns[*idx]) {
                 g.draw_polygon(
                     color_turn_type(turn.turn_type).alpha(0.5),
                     &turn
@@ -212,21 +257,20 @@ impl State for TurnExplorer {
                 );
             }
         } else {
-            let current = &app.primary.map.get_turns_from_lane(self.l)[self.idx - 1];
+            let idx = self.matching[self.idx - 1];
+            let current = turns[idx];
 
             let mut batch = GeomBatch::new();
-            for t in app.primary.map.get_turns_in_intersection(current.id.parent) {
-                if current.conflicts_with(t) {
-                    batch.extend(
-                        CONFLICTING_TURN,
-                        t.geom.dashed_arrow(
-                            BIG_ARROW_THICKNESS,
-                            Distance::meters(1.0),
-                            Distance::meters(0.5),
-                            ArrowCap::Triangle,
-                        ),
-                    );
-                }
+            for t in &self.conflicts[idx] {
+                batch.extend(
+                    CONFLICTING_TURN,
+                    app.primary.map.get_t(*t).geom.dashed_arrow(
+                        BIG_ARROW_THICKNESS,
+                        Distance::meters(1.0),
+                        Distance::meters(0.5),
+                        ArrowCap::Triangle,
+                    ),
+                );
             }
             batch.push(
                 CURRENT_TURN,
@@ -243,8 +287,10 @@ impl State for TurnExplorer {
 }
 
 impl TurnExplorer {
-    fn make_panel(ctx: &mut EventCtx, app: &App, l: LaneID, idx: usize) -> Composite {
-        let num_turns = app.primary.map.get_turns_from_lane(l).len();
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App) -> Composite {
+        let l = self.l;
+        let idx = self.idx;
+        let num_turns = self.matching.len();
 
         let mut col = vec![Widget::row(vec![
             Text::from(
@@ -267,10 +313,17 @@ impl TurnExplorer {
                 Btn::text_fg("<").build(ctx, "previous turn", hotkey(Key::LeftArrow))
             }
             .margin(5),
-            Text::from(Line(format!("{}/{}", idx, num_turns)).secondary())
-                .draw(ctx)
-                .margin(5)
-                .centered_vert(),
+            Text::from(
+                Line(if idx == 0 {
+                    format!("all {} turns", num_turns)
+                } else {
+                    format!("turn {} of {}", idx, num_turns)
+                })
+                .secondary(),
+            )
+            .draw(ctx)
+            .margin(5)
+            .centered_vert(),
             if idx == num_turns {
                 Btn::text_fg(">").inactive(ctx)
             } else {
@@ -279,6 +332,27 @@ impl TurnExplorer {
             .margin(5),
             Btn::text_fg("X").build(ctx, "close", hotkey(Key::Escape)),
         ])];
+
+        let mut filters = vec![if self.filter.is_none() {
+            Btn::text_bg2("all movements").inactive(ctx)
+        } else {
+            Btn::text_fg("all movements").build_def(ctx, hotkey(Key::A))
+        }
+        .margin(5)];
+        for (m, key) in MOVEMENTS
+            .iter()
+            .zip(vec![Key::S, Key::L, Key::R, Key::U, Key::C])
+        {
+            filters.push(
+                if self.filter == Some(*m) {
+                    Btn::text_bg2(m.describe()).inactive(ctx)
+                } else {
+                    Btn::text_fg(m.describe()).build_def(ctx, hotkey(key))
+                }
+                .margin(5),
+            );
+        }
+        col.push(Widget::row(filters));
         if idx == 0 {
             if app.primary.map.get_l(l).is_sidewalk() {
                 col.push(ColorLegend::row(
@@ -321,6 +395,24 @@ impl TurnExplorer {
         } else {
             col.push(ColorLegend::row(ctx, CURRENT_TURN, "current turn"));
             col.push(ColorLegend::row(ctx, CONFLICTING_TURN, "conflicting turn"));
+            if let Some(ref phases) = self.phases[self.matching[idx - 1]] {
+                let mut txt = Text::new();
+                if phases.is_empty() {
+                    txt.add(Line("Not allowed by the traffic signal"));
+                }
+                for (phase, pri) in phases {
+                    txt.add(Line(format!(
+                        "Phase {}: {}",
+                        phase + 1,
+                        if *pri == TurnPriority::Protected {
+                            "protected"
+                        } else {
+                            "yielding"
+                        }
+                    )));
+                }
+                col.push(txt.draw(ctx).margin(5));
+            }
         }
 
         Composite::new(Widget::col(col).bg(app.cs.panel_bg))
@@ -329,6 +421,14 @@ impl TurnExplorer {
     }
 }
 
+const MOVEMENTS: [MovementType; 5] = [
+    MovementType::Straight,
+    MovementType::Left,
+    MovementType::Right,
+    MovementType::UTurn,
+    MovementType::Crosswalk,
+];
+
 // Since this is extremely localized and probably changing, not going to put this in ColorScheme.
 fn color_turn_type(t: TurnType) -> Color {
     match t {
@@ -342,5 +442,5 @@ fn color_turn_type(t: TurnType) -> Color {
     }
 }
 
-const CURRENT_TURN: Color = Color::GREEN;
+pub const CURRENT_TURN: Color = Color::GREEN;
 const CONFLICTING_TURN: Color = Color::RED.alpha(0.8);
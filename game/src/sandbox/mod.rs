@@ -1,19 +1,24 @@
 mod dashboards;
 pub mod gameplay;
+mod inspector;
 mod misc_tools;
 mod speed;
 mod uber_turns;
+mod watchpoints;
 
+use self::inspector::AgentInspector;
+pub use self::misc_tools::CURRENT_TURN;
 use self::misc_tools::{RoutePreview, ShowTrafficSignal, TurnExplorer};
 use crate::app::App;
-use crate::common::{tool_panel, CommonState, ContextualActions, Minimap};
+use crate::common::{tool_panel, CommonState, ContextualActions, MenuBar, Minimap};
 use crate::debug::DebugMode;
 use crate::edit::{
-    apply_map_edits, can_edit_lane, save_edits_as, EditMode, LaneEditor, StopSignEditor,
-    TrafficSignalEditor,
+    apply_map_edits, can_close, can_edit_lane, close_temporarily, save_edits_as, EditMode,
+    LaneEditor, RouteEditor, StopSignEditor, TrafficSignalEditor,
 };
 use crate::game::{State, Transition, WizardState};
 use crate::helpers::ID;
+use crate::info::Tab;
 use crate::layer::PickLayer;
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::pregame::MainMenu;
@@ -25,7 +30,7 @@ use ezgui::{
 pub use gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
 use geom::{Polygon, Time};
 use map_model::MapEdits;
-use sim::{TripMode, VehicleType};
+use sim::{AgentID, TripMode, VehicleType};
 pub use speed::TimeWarpScreen;
 pub use speed::{SpeedControls, TimePanel};
 
@@ -44,6 +49,7 @@ pub struct SandboxControls {
     speed: Option<SpeedControls>,
     pub agent_meter: Option<AgentMeter>,
     minimap: Option<Minimap>,
+    menu_bar: Option<MenuBar>,
 }
 
 impl SandboxMode {
@@ -51,6 +57,21 @@ impl SandboxMode {
         app.primary.clear_sim();
         let gameplay = mode.initialize(ctx, app);
 
+        let time_panel = if gameplay.has_time_panel() {
+            Some(TimePanel::new(ctx, app))
+        } else {
+            None
+        };
+        let menu_bar = if gameplay.has_tool_panel() {
+            let mut m = MenuBar::new(ctx, app);
+            if let Some(ref tp) = time_panel {
+                m.align_below(ctx, &tp.composite);
+            }
+            Some(m)
+        } else {
+            None
+        };
+
         SandboxMode {
             controls: SandboxControls {
                 common: if gameplay.has_common() {
@@ -68,11 +89,7 @@ impl SandboxMode {
                 } else {
                     None
                 },
-                time_panel: if gameplay.has_time_panel() {
-                    Some(TimePanel::new(ctx, app))
-                } else {
-                    None
-                },
+                time_panel,
                 speed: if gameplay.has_speed() {
                     Some(SpeedControls::new(ctx, app))
                 } else {
@@ -88,6 +105,7 @@ impl SandboxMode {
                 } else {
                     None
                 },
+                menu_bar,
             },
             gameplay,
             gameplay_mode: mode,
@@ -111,6 +129,14 @@ impl SandboxMode {
 
 impl State for SandboxMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if app.opts.show_menu_bar {
+            if let Some(ref mut m) = self.controls.menu_bar {
+                if m.event(ctx, app) {
+                    return Transition::Keep;
+                }
+            }
+        }
+
         // Do this before gameplay
         if self.gameplay.can_move_canvas() {
             ctx.canvas_movement();
@@ -125,7 +151,7 @@ impl State for SandboxMode {
         }
 
         // Order here is pretty arbitrary
-        if app.opts.dev && ctx.input.new_was_pressed(&lctrl(Key::D).unwrap()) {
+        if app.opts.dev && ctx.action_pressed("debug mode", lctrl(Key::D)) {
             return Transition::Push(Box::new(DebugMode::new(ctx, app)));
         }
 
@@ -161,6 +187,12 @@ impl State for SandboxMode {
 
         if let Some(ref mut tp) = self.controls.time_panel {
             tp.event(ctx, app);
+            // The time panel only just moved to fit the new window size
+            if ctx.input.is_window_resized() {
+                if let Some(ref mut m) = self.controls.menu_bar {
+                    m.align_below(ctx, &tp.composite);
+                }
+            }
         }
 
         if let Some(ref mut tp) = self.controls.tool_panel {
@@ -192,6 +224,7 @@ impl State for SandboxMode {
         {
             Transition::Keep
         } else {
+            ctx.request_animation_frames();
             Transition::KeepWithMode(EventLoopMode::Animation)
         }
     }
@@ -210,7 +243,7 @@ impl State for SandboxMode {
             tp.draw(g);
         }
         if let Some(ref s) = self.controls.speed {
-            s.draw(g);
+            s.draw(g, app);
         }
         if let Some(ref tp) = self.controls.time_panel {
             tp.draw(g);
@@ -226,6 +259,38 @@ impl State for SandboxMode {
         }
 
         self.gameplay.draw(g, app);
+
+        if app.opts.show_menu_bar {
+            if let Some(ref m) = self.controls.menu_bar {
+                m.draw(g);
+            }
+        }
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        let c = &mut self.controls;
+        if let Some(ref mut tp) = c.tool_panel {
+            *tp = tool_panel(ctx, app);
+        }
+        if let Some(ref mut tp) = c.time_panel {
+            *tp = TimePanel::new(ctx, app);
+        }
+        if let Some(ref mut s) = c.speed {
+            s.recreate_panel(ctx, app);
+        }
+        if let Some(ref mut am) = c.agent_meter {
+            *am = AgentMeter::new(ctx, app);
+        }
+        if let Some(ref mut m) = c.minimap {
+            m.recreate_panel(ctx, app);
+        }
+        if let Some(ref mut m) = c.menu_bar {
+            *m = MenuBar::new(ctx, app);
+            if let Some(ref tp) = c.time_panel {
+                m.align_below(ctx, &tp.composite);
+            }
+        }
+        // TODO Panels belonging to the gameplay mode keep the old scale
     }
 
     fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
@@ -392,6 +457,7 @@ impl ContextualActions for Actions {
                     if app.opts.dev {
                         actions.push((Key::U, "explore uber-turns".to_string()));
                     }
+                    actions.push((Key::W, "add watchpoint".to_string()));
                 }
                 ID::Lane(l) => {
                     if !app.primary.map.get_turns_from_lane(l).is_empty() {
@@ -400,11 +466,44 @@ impl ContextualActions for Actions {
                     if can_edit_lane(&self.gameplay, l, app) {
                         actions.push((Key::E, "edit lane".to_string()));
                     }
+                    if self.gameplay.can_edit_lanes() && can_close(&app.primary.map, l) {
+                        actions.push((Key::C, "close lane temporarily".to_string()));
+                        actions.push((Key::X, "close road temporarily".to_string()));
+                    }
+                    if !app.primary.map.get_l(l).is_sidewalk() {
+                        actions.push((Key::W, "add watchpoint".to_string()));
+                    }
+                }
+                ID::Building(b) => {
+                    if !app.primary.sim.trips_from_bldg(b).is_empty()
+                        || !app.primary.sim.trips_to_bldg(b).is_empty()
+                    {
+                        actions.push((Key::T, "show trips to and from here".to_string()));
+                    }
                 }
                 ID::Car(c) => {
+                    if app.primary.sim.does_agent_exist(AgentID::Car(c)) {
+                        actions.push((Key::I, "inspect".to_string()));
+                        actions.push((Key::W, "add watchpoint".to_string()));
+                    }
                     if c.1 == VehicleType::Bus {
                         // TODO Hide the button if the layer is open
                         actions.push((Key::R, "show route".to_string()));
+                        if self.gameplay.can_edit_lanes() {
+                            actions.push((Key::E, "edit route".to_string()));
+                        }
+                    }
+                }
+                ID::Pedestrian(_) => {
+                    actions.push((Key::I, "inspect".to_string()));
+                    actions.push((Key::W, "add watchpoint".to_string()));
+                }
+                ID::BusStop(bs) => {
+                    if !app.primary.map.get_routes_serving_stop(bs).is_empty() {
+                        actions.push((Key::R, "show route".to_string()));
+                        if self.gameplay.can_edit_lanes() {
+                            actions.push((Key::E, "edit route".to_string()));
+                        }
                     }
                 }
                 _ => {}
@@ -443,6 +542,17 @@ impl ContextualActions for Actions {
                 Box::new(EditMode::new(ctx, app, self.gameplay.clone())),
                 Box::new(LaneEditor::new(ctx, app, l, self.gameplay.clone())),
             ),
+            (ID::Lane(l), "close lane temporarily") => Transition::Push(close_temporarily(vec![l])),
+            (ID::Lane(l), "close road temporarily") => {
+                let map = &app.primary.map;
+                let lanes = map
+                    .get_parent(l)
+                    .all_lanes()
+                    .into_iter()
+                    .filter(|l| can_close(map, *l))
+                    .collect();
+                Transition::Push(close_temporarily(lanes))
+            }
             (ID::Car(c), "show route") => {
                 *close_panel = false;
                 app.layer = Some(Box::new(crate::layer::bus::ShowBusRoute::new(
@@ -452,6 +562,47 @@ impl ContextualActions for Actions {
                 )));
                 Transition::Keep
             }
+            (ID::BusStop(bs), "show route") => {
+                *close_panel = false;
+                // Arbitrarily pick the first route serving the stop
+                app.layer = Some(Box::new(crate::layer::bus::ShowBusRoute::new(
+                    ctx,
+                    app,
+                    app.primary.map.get_routes_serving_stop(bs)[0].id,
+                )));
+                Transition::Keep
+            }
+            (id, "inspect") => {
+                Transition::Push(AgentInspector::new(ctx, app, id.agent_id().unwrap()))
+            }
+            (id, "add watchpoint") => Transition::Push(watchpoints::new_watchpoint(Some(id))),
+            (ID::Car(c), "edit route") => {
+                let route = app.primary.sim.bus_route_id(c).unwrap();
+                Transition::PushTwice(
+                    Box::new(EditMode::new(ctx, app, self.gameplay.clone())),
+                    RouteEditor::new(ctx, app, route, self.gameplay.clone()),
+                )
+            }
+            (ID::BusStop(bs), "edit route") => {
+                let route = app.primary.map.get_routes_serving_stop(bs)[0].id;
+                Transition::PushTwice(
+                    Box::new(EditMode::new(ctx, app, self.gameplay.clone())),
+                    RouteEditor::new(ctx, app, route, self.gameplay.clone()),
+                )
+            }
+            (ID::Building(b), "show trips to and from here") => {
+                *close_panel = false;
+                Transition::KeepWithData(Box::new(move |state, ctx, app| {
+                    let mode = state.downcast_mut::<SandboxMode>().unwrap();
+                    let mut actions = mode.contextual_actions();
+                    mode.controls.common.as_mut().unwrap().launch_info_panel(
+                        ctx,
+                        app,
+                        Tab::BldgTrips(b),
+                        &mut actions,
+                    );
+                }))
+            }
             (_, "follow (run the simulation)") => {
                 *close_panel = false;
                 Transition::KeepWithData(Box::new(|state, ctx, app| {
@@ -2,16 +2,20 @@ use crate::app::{App, FindDelayedIntersections};
 use crate::common::Warping;
 use crate::game::{msg, State, Transition};
 use crate::helpers::ID;
+use crate::sandbox::watchpoints::{check_watchpoints, WatchpointList};
 use crate::sandbox::{GameplayMode, SandboxMode};
-use abstutil::prettyprint_usize;
+use abstutil::{prettyprint_usize, Severity, Timer};
 use ezgui::{
     hotkey, AreaSlider, Btn, Choice, Color, Composite, EventCtx, EventLoopMode, GeomBatch, GfxCtx,
-    HorizontalAlignment, Key, Line, Outcome, PersistentSplit, RewriteColor, Text,
-    VerticalAlignment, Widget,
+    HorizontalAlignment, Key, Line, Outcome, OverlayPanel, PanelAnchor, PersistentSplit,
+    RewriteColor, Text, VerticalAlignment, Widget,
 };
 use geom::{Duration, Polygon, Pt2D, Time};
 use instant::Instant;
-use sim::AlertLocation;
+use sim::{AlertLocation, SimEvent};
+
+// While recording or replaying input, a time warp covers this much per frame
+const RECORDED_TIME_WARP_STEP: Duration = Duration::const_seconds(60.0);
 
 pub struct SpeedControls {
     pub composite: Composite,
@@ -107,6 +111,9 @@ impl SpeedControls {
                 Btn::svg_def("../data/system/assets/speed/reset.svg")
                     .pad(9)
                     .build(ctx, "reset to midnight", hotkey(Key::X)),
+                Btn::svg_def("../data/system/assets/tools/pin.svg")
+                    .pad(9)
+                    .build(ctx, "watchpoints", None),
             ])
             .bg(app.cs.section_bg),
         );
@@ -128,6 +135,10 @@ impl SpeedControls {
         }
     }
 
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.composite = SpeedControls::make_panel(ctx, app, self.paused, self.setting);
+    }
+
     pub fn event(
         &mut self,
         ctx: &mut EventCtx,
@@ -178,6 +189,9 @@ impl SpeedControls {
                         )));
                     }
                 }
+                "watchpoints" => {
+                    return Some(Transition::Push(WatchpointList::new(ctx, app)));
+                }
                 "jump to specific time" => {
                     return Some(Transition::Push(Box::new(JumpToTime::new(
                         ctx,
@@ -208,7 +222,7 @@ impl SpeedControls {
         // Just kind of constantly scrape this
         app.opts.time_increment = self.composite.persistent_split_value("step forwards");
 
-        if ctx.input.new_was_pressed(&hotkey(Key::LeftArrow).unwrap()) {
+        if ctx.action_pressed("slow down", hotkey(Key::LeftArrow)) {
             match self.setting {
                 SpeedSetting::Realtime => self.pause(ctx, app),
                 SpeedSetting::Fast => {
@@ -225,7 +239,7 @@ impl SpeedControls {
                 }
             }
         }
-        if ctx.input.new_was_pressed(&hotkey(Key::RightArrow).unwrap()) {
+        if ctx.action_pressed("speed up", hotkey(Key::RightArrow)) {
             match self.setting {
                 SpeedSetting::Realtime => {
                     if self.paused {
@@ -260,22 +274,36 @@ impl SpeedControls {
                     SpeedSetting::Fastest => 3600.0,
                 };
                 let dt = multiplier * real_dt;
-                // TODO This should match the update frequency in ezgui. Plumb along the deadline
-                // or frequency to here.
-                app.primary.sim.time_limited_step(
-                    &app.primary.map,
-                    dt,
-                    Duration::seconds(0.033),
-                    &mut app.primary.sim_cb,
-                );
+                let started = Instant::now();
+                step_sim(ctx, app, dt);
+                if let Some(ref timings) = app.frame_timings {
+                    timings.record_sim_step(abstutil::elapsed_seconds(started));
+                }
                 app.recalculate_current_selection(ctx);
             }
         }
 
+        let (pause, look_at) = check_watchpoints(app);
+        if pause {
+            self.pause(ctx, app);
+            if let Some(id) = look_at {
+                return Some(Transition::Push(Warping::new(
+                    ctx,
+                    id.canonical_point(&app.primary).unwrap(),
+                    Some(10.0),
+                    Some(id),
+                    &mut app.primary,
+                )));
+            }
+        }
+
         // TODO Need to do this anywhere that steps the sim, like TimeWarpScreen.
         let alerts = app.primary.sim.clear_alerts();
         if !alerts.is_empty() {
-            let popup = msg("Alerts", alerts.iter().map(|(_, _, msg)| msg).collect());
+            for (t, _, alert) in &alerts {
+                app.toasts
+                    .notify(Severity::Warn, format!("At {}, {}", t, alert));
+            }
             let maybe_id = match alerts[0].1 {
                 AlertLocation::Nil => None,
                 AlertLocation::Intersection(i) => Some(ID::Intersection(i)),
@@ -292,27 +320,91 @@ impl SpeedControls {
             }*/
             self.pause(ctx, app);
             if let Some(id) = maybe_id {
-                // Just go to the first one, but print all messages
-                return Some(Transition::PushTwice(
-                    popup,
-                    Warping::new(
-                        ctx,
-                        id.canonical_point(&app.primary).unwrap(),
-                        Some(10.0),
-                        None,
-                        &mut app.primary,
+                // Just go to the first one, but show all messages
+                return Some(Transition::Push(Warping::new(
+                    ctx,
+                    id.canonical_point(&app.primary).unwrap(),
+                    Some(10.0),
+                    None,
+                    &mut app.primary,
+                )));
+            }
+        }
+
+        // Aborted trips are bugs in the sim, so developers probably want to stop and look.
+        if app.opts.dev {
+            let mut any_aborted = false;
+            for (t, ev) in &app.primary.sim_events {
+                if let SimEvent::TripAborted(trip, person) = ev {
+                    app.toasts.notify(
+                        Severity::Warn,
+                        format!("At {}, {} of {} was aborted", t, trip, person),
+                    );
+                    any_aborted = true;
+                }
+            }
+            if any_aborted {
+                self.pause(ctx, app);
+            }
+        }
+
+        for (t, ev) in &app.primary.sim_events {
+            if let SimEvent::InterventionFired(intervention) = ev {
+                app.toasts.notify(
+                    Severity::Info,
+                    format!("At {}, {}", t, intervention.describe()),
+                );
+            }
+        }
+
+        // Stop and show the cycle of stuck agents
+        let mut gridlock = None;
+        for (t, ev) in &app.primary.sim_events {
+            if let SimEvent::Gridlock(intersections) = ev {
+                app.toasts.notify(
+                    Severity::Warn,
+                    format!(
+                        "At {}, gridlock near {} intersections",
+                        t,
+                        intersections.len()
                     ),
-                ));
-            } else {
-                return Some(Transition::Push(popup));
+                );
+                gridlock = intersections.get(0).cloned();
             }
         }
+        if let Some(i) = gridlock {
+            self.pause(ctx, app);
+            app.layer = Some(Box::new(crate::layer::traffic::Gridlock::new(ctx, app)));
+            return Some(Transition::Push(Warping::new(
+                ctx,
+                ID::Intersection(i).canonical_point(&app.primary).unwrap(),
+                Some(10.0),
+                None,
+                &mut app.primary,
+            )));
+        }
 
         None
     }
 
-    pub fn draw(&self, g: &mut GfxCtx) {
+    pub fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.composite.draw(g);
+
+        let upcoming = app.primary.sim.upcoming_interventions();
+        if !upcoming.is_empty() {
+            let mut txt = Text::from(Line("Upcoming interventions").small());
+            for (t, intervention) in upcoming.into_iter().take(3) {
+                txt.add(
+                    Line(format!(
+                        "{}: {}",
+                        t.ampm_tostring(),
+                        intervention.describe()
+                    ))
+                    .small(),
+                );
+            }
+            g.declare_panel(OverlayPanel::new(PanelAnchor::BottomLeft, txt));
+        }
     }
 
     pub fn pause(&mut self, ctx: &mut EventCtx, app: &App) {
@@ -523,18 +615,44 @@ impl State for TimeWarpScreen {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         if ctx.input.nonblocking_is_update_event().is_some() {
             ctx.input.use_update_event();
-            app.primary.sim.time_limited_step(
-                &app.primary.map,
-                self.target - app.primary.sim.time(),
-                Duration::seconds(0.033),
-                &mut app.primary.sim_cb,
+            let started = Instant::now();
+            let dt = self.target - app.primary.sim.time();
+            // Without a deadline, a long warp would freeze the screen. A fixed amount per frame
+            // still replays the same way.
+            step_sim(
+                ctx,
+                app,
+                if ctx.canvas.input_is_recorded() {
+                    dt.min(RECORDED_TIME_WARP_STEP)
+                } else {
+                    dt
+                },
             );
-            for (t, maybe_i, alert) in app.primary.sim.clear_alerts() {
-                // TODO Just the first :(
-                return Transition::Replace(msg(
-                    "Alert",
-                    vec![format!("At {}, near {:?}, {}", t, maybe_i, alert)],
-                ));
+            if let Some(ref timings) = app.frame_timings {
+                timings.record_sim_step(abstutil::elapsed_seconds(started));
+            }
+            let alerts = app.primary.sim.clear_alerts();
+            if !alerts.is_empty() {
+                for (t, maybe_i, alert) in alerts {
+                    app.toasts.notify(
+                        Severity::Warn,
+                        format!("At {}, near {:?}, {}", t, maybe_i, alert),
+                    );
+                }
+                return Transition::Pop;
+            }
+            let (pause, look_at) = check_watchpoints(app);
+            if pause {
+                if let Some(id) = look_at {
+                    return Transition::Replace(Warping::new(
+                        ctx,
+                        id.canonical_point(&app.primary).unwrap(),
+                        Some(10.0),
+                        Some(id),
+                        &mut app.primary,
+                    ));
+                }
+                return Transition::Pop;
             }
             if let Some(ref mut cb) = app.primary.sim_cb {
                 let di = cb.downcast_mut::<FindDelayedIntersections>().unwrap();
@@ -615,6 +733,7 @@ impl State for TimeWarpScreen {
             return Transition::Pop;
         }
 
+        ctx.request_animation_frames();
         Transition::KeepWithMode(EventLoopMode::Animation)
     }
 
@@ -680,6 +799,15 @@ impl TimePanel {
                             );
                         }
 
+                        // Mark when watchpoints fired
+                        for t in app.session.watchpoints.all_fired() {
+                            let x = t.to_percent(app.primary.sim.get_end_of_day()).min(1.0) * width;
+                            batch.push(
+                                Color::RED,
+                                Polygon::rectangle(2.0, height).translate(x - 1.0, 0.0),
+                            );
+                        }
+
                         Widget::draw_batch(ctx, batch)
                     },
                     Widget::row(vec![
@@ -734,6 +862,27 @@ fn area_under_curve(raw: Vec<(Time, usize)>, width: f64, height: f64) -> Polygon
     Polygon::new(&downsampled)
 }
 
+fn step_sim(ctx: &EventCtx, app: &mut App, dt: Duration) {
+    if ctx.canvas.input_is_recorded() {
+        // A replay has to reach exactly the same time, no matter how slowly it runs
+        app.primary.sim.timed_step(
+            &app.primary.map,
+            dt,
+            &mut app.primary.sim_cb,
+            &mut Timer::throwaway(),
+        );
+    } else {
+        // TODO This should match the update frequency in ezgui. Plumb along the deadline or
+        // frequency to here.
+        app.primary.sim.time_limited_step(
+            &app.primary.map,
+            dt,
+            Duration::seconds(0.033),
+            &mut app.primary.sim_cb,
+        );
+    }
+}
+
 // TODO Maybe color, put in helpers
 fn compare_count(after: usize, before: usize) -> String {
     if after == before {
@@ -0,0 +1,288 @@
+use crate::app::App;
+use crate::game::{DrawBaselayer, State, Transition, WizardState};
+use crate::helpers::ID;
+use abstutil::Severity;
+use ezgui::{
+    hotkey, Btn, Choice, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Text, TextExt, VerticalAlignment, Widget, WrappedWizard,
+};
+use geom::Duration;
+use sim::{longest_trip, upcoming_intersections, Comparison, Condition, Metric};
+
+pub struct WatchpointList {
+    composite: Composite,
+    // Rebuild the panel when a wizard changes these
+    num_watchpoints: usize,
+    interval: Duration,
+}
+
+impl WatchpointList {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        Box::new(WatchpointList {
+            composite: make_list(ctx, app),
+            num_watchpoints: app.session.watchpoints.list.len(),
+            interval: app.session.watchpoints.interval,
+        })
+    }
+}
+
+impl State for WatchpointList {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if self.num_watchpoints != app.session.watchpoints.list.len()
+            || self.interval != app.session.watchpoints.interval
+        {
+            self.num_watchpoints = app.session.watchpoints.list.len();
+            self.interval = app.session.watchpoints.interval;
+            self.composite = make_list(ctx, app);
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "add watchpoint" => {
+                    return Transition::Push(new_watchpoint(None));
+                }
+                "change interval" => {
+                    return Transition::Push(change_interval());
+                }
+                _ => {
+                    // Buttons are named "{action} {idx}", indexing into the watchpoints
+                    let (action, idx) = x.split_at(x.rfind(' ').unwrap());
+                    let idx = idx.trim().parse::<usize>().unwrap();
+                    let watchpoints = &mut app.session.watchpoints;
+                    match action {
+                        "toggle" => {
+                            watchpoints.toggle(idx, &app.primary.sim, &app.primary.map);
+                        }
+                        "toggle pause" => {
+                            watchpoints.list[idx].pause = !watchpoints.list[idx].pause;
+                        }
+                        "delete" => {
+                            watchpoints.list.remove(idx);
+                            self.num_watchpoints -= 1;
+                        }
+                        _ => unreachable!(),
+                    }
+                    self.composite = make_list(ctx, app);
+                }
+            },
+            None => {}
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+}
+
+fn make_list(ctx: &mut EventCtx, app: &App) -> Composite {
+    let watchpoints = &app.session.watchpoints;
+    let mut col = vec![Widget::row(vec![
+        Line("Watchpoints").small_heading().draw(ctx),
+        Btn::text_fg("X")
+            .build(ctx, "close", hotkey(Key::Escape))
+            .align_right(),
+    ])
+    .margin_below(10)];
+    if watchpoints.list.is_empty() {
+        col.push("Nothing is being watched yet".draw_text(ctx));
+    }
+    for (idx, w) in watchpoints.list.iter().enumerate() {
+        let mut txt = Text::from(Line(w.condition.describe()));
+        txt.add(
+            Line(match w.fired.last() {
+                Some(t) => format!("fired {} times, last at {}", w.fired.len(), t),
+                None => "hasn't fired yet".to_string(),
+            })
+            .secondary(),
+        );
+        col.push(
+            Widget::row(vec![
+                txt.wrap_to_pct(ctx, 30).draw(ctx).margin_right(10),
+                Btn::text_fg(if w.enabled { "disable" } else { "enable" })
+                    .build(ctx, format!("toggle {}", idx), None)
+                    .margin_right(5),
+                Btn::text_fg(if w.pause { "pauses" } else { "just notifies" })
+                    .build(ctx, format!("toggle pause {}", idx), None)
+                    .margin_right(5),
+                Btn::text_fg("delete").build(ctx, format!("delete {}", idx), None),
+            ])
+            .margin_below(10),
+        );
+    }
+    col.push(Widget::row(vec![
+        Btn::text_bg2("add watchpoint")
+            .build_def(ctx, hotkey(Key::A))
+            .margin_right(10),
+        Btn::text_fg(format!("check every {}", watchpoints.interval))
+            .build(ctx, "change interval", None)
+            .centered_vert(),
+    ]));
+
+    Composite::new(Widget::col(col).padding(10).bg(app.cs.panel_bg))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .max_size_percent(60, 80)
+        .build(ctx)
+}
+
+// Starting from something selected on the map, or from scratch if id is None
+pub fn new_watchpoint(id: Option<ID>) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let mut wizard = wiz.wrap(ctx);
+        let condition = match id {
+            Some(ID::Lane(l)) => Condition::Compare(
+                Metric::QueueLength(l),
+                Comparison::Above,
+                wizard.input_usize("Fire when more than how many vehicles are queued here?")?
+                    as f64,
+            ),
+            Some(ID::Intersection(i)) => Condition::Compare(
+                Metric::IntersectionDelay(i),
+                Comparison::Above,
+                input_minutes(
+                    &mut wizard,
+                    "Fire when somebody waits here for how many minutes?",
+                )?,
+            ),
+            Some(ref id) => {
+                // Anywhere not on the rest of the path already counts as reached, so the
+                // watchpoint would never fire
+                let agent = id.agent_id().unwrap();
+                let upcoming = upcoming_intersections(&app.primary.sim, agent);
+                if upcoming.is_empty() {
+                    wizard.acknowledge("Nothing to watch for", || {
+                        vec![format!(
+                            "{} doesn't go through any more intersections",
+                            agent
+                        )]
+                    })?;
+                    return Some(Transition::Pop);
+                }
+                let map = &app.primary.map;
+                let choices = upcoming
+                    .into_iter()
+                    .map(|i| Choice::new(format!("{} ({})", i, map.get_i(i).name(map)), i))
+                    .collect();
+                let query = "Fire when this agent reaches which intersection?";
+                let (_, i) = wizard.choose(query, || choices)?;
+                Condition::AgentReaches(agent, i)
+            }
+            None => {
+                let choice = wizard.choose_string("Watch for what?", || {
+                    vec![
+                        "any trip takes too long",
+                        "too many active agents",
+                        "too few active agents",
+                    ]
+                })?;
+                match choice.as_ref() {
+                    "any trip takes too long" => Condition::Compare(
+                        Metric::LongestTrip,
+                        Comparison::Above,
+                        input_minutes(&mut wizard, "Fire when a trip takes how many minutes?")?,
+                    ),
+                    "too many active agents" => Condition::Compare(
+                        Metric::ActiveAgents,
+                        Comparison::Above,
+                        wizard.input_usize("Fire above how many active agents?")? as f64,
+                    ),
+                    "too few active agents" => Condition::Compare(
+                        Metric::ActiveAgents,
+                        Comparison::Below,
+                        wizard.input_usize("Fire below how many active agents?")? as f64,
+                    ),
+                    _ => unreachable!(),
+                }
+            }
+        };
+        let pause = wizard.choose_string("When this fires...", || {
+            vec!["pause the simulation", "just notify me"]
+        })? == "pause the simulation";
+
+        app.toasts.notify(
+            Severity::Info,
+            format!("Watching for {}", condition.describe()),
+        );
+        app.session
+            .watchpoints
+            .add(condition, pause, &app.primary.sim, &app.primary.map);
+        Some(Transition::Pop)
+    }))
+}
+
+fn input_minutes(wizard: &mut WrappedWizard, query: &str) -> Option<f64> {
+    wizard.input_something(
+        query,
+        None,
+        Box::new(|line| line.parse::<f64>().ok().filter(|x| *x >= 0.0)),
+    )
+}
+
+fn change_interval() -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let mut wizard = wiz.wrap(ctx);
+        let secs = wizard.input_something(
+            "Check watchpoints every how many seconds of sim time?",
+            Some(app.session.watchpoints.interval.inner_seconds().to_string()),
+            Box::new(|line| line.parse::<f64>().ok().filter(|x| *x > 0.0)),
+        )?;
+        app.session.watchpoints.interval = Duration::seconds(secs);
+        Some(Transition::Pop)
+    }))
+}
+
+// Call after the sim steps. Tells the player about any watchpoints that just fired, and returns
+// true if one of them wants to pause, along with something relevant to look at.
+pub fn check_watchpoints(app: &mut App) -> (bool, Option<ID>) {
+    let fired = app
+        .session
+        .watchpoints
+        .check(&app.primary.sim, &app.primary.map);
+    let now = app.primary.sim.time();
+    let mut pause = false;
+    let mut look_at = None;
+    for idx in fired {
+        let w = app.session.watchpoints.list[idx].clone();
+        app.toasts.notify(
+            Severity::Info,
+            format!("At {}, watchpoint fired: {}", now, w.condition.describe()),
+        );
+        if w.pause {
+            pause = true;
+            if look_at.is_none() {
+                look_at = relevant_object(&w.condition, app);
+            }
+        }
+    }
+    (pause, look_at)
+}
+
+fn relevant_object(condition: &Condition, app: &App) -> Option<ID> {
+    let id = match condition {
+        Condition::Compare(Metric::QueueLength(l), _, _) => ID::Lane(*l),
+        Condition::Compare(Metric::IntersectionDelay(i), _, _) => ID::Intersection(*i),
+        Condition::Compare(Metric::LongestTrip, _, _) => {
+            ID::from_agent(longest_trip(&app.primary.sim)?.0)
+        }
+        Condition::Compare(Metric::ActiveAgents, _, _) => {
+            return None;
+        }
+        Condition::AgentReaches(a, i) => {
+            if app.primary.sim.does_agent_exist(*a) {
+                ID::from_agent(*a)
+            } else {
+                ID::Intersection(*i)
+            }
+        }
+    };
+    // Make sure there's somewhere to warp
+    id.canonical_point(&app.primary)?;
+    Some(id)
+}
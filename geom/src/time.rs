@@ -8,6 +8,7 @@ pub struct Time(f64);
 
 // By construction, Time is a finite f64 with trimmed precision.
 impl Eq for Time {}
+impl abstutil::Cloneable for Time {}
 impl Ord for Time {
     fn cmp(&self, other: &Time) -> cmp::Ordering {
         self.partial_cmp(other).unwrap()
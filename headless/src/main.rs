@@ -1,4 +1,5 @@
 use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::Duration;
 use map_model::Map;
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
@@ -12,26 +13,57 @@ fn main() {
     let num_days = args
         .optional_parse("--days", |s| s.parse::<usize>())
         .unwrap_or(1);
+    // Run the experiment twice side-by-side, checking that both runs match
+    let checkpoint_interval = if args.enabled("--verify_determinism") {
+        Some(
+            args.optional_parse("--checkpoint_interval", |s| {
+                s.parse::<f64>().map(Duration::seconds)
+            })
+            .unwrap_or_else(|| Duration::minutes(1)),
+        )
+    } else {
+        None
+    };
+    let num_threads = args.optional_parse("--num_threads", |s| s.parse::<usize>());
     args.done();
 
     let mut sim_flags = SimFlags::synthetic_test("montlake", "pandemic");
     sim_flags.opts.enable_pandemic_model = Some(XorShiftRng::from_seed([sim_flags.rng_seed; 16]));
     // Less spam
     sim_flags.opts.alerts = AlertHandler::Silence;
+    sim_flags.opts.checkpoint_interval = checkpoint_interval;
+    if let Some(n) = num_threads {
+        sim_flags.opts.num_threads = n;
+    }
     let mut timer = Timer::new("setup headless");
     let (mut map, _, mut rng) = sim_flags.load(&mut timer);
     map.hack_override_offstreet_spots(num_days);
-    // After the map changes, have to re-create the (empty) Sim, because things like
-    // ParkingSimState are out of sync.
-    let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
 
-    let base_scenario: Scenario = abstutil::read_binary(
+    let base_scenario: Scenario = abstutil::read_versioned(
         abstutil::path_scenario(map.get_name(), "weekday"),
         &mut timer,
     );
-    base_scenario
-        .repeat_days(num_days)
-        .instantiate(&mut sim, &map, &mut rng, &mut timer);
+    let scenario = base_scenario.repeat_days(num_days);
+    // After the map changes, have to re-create the (empty) Sim, because things like
+    // ParkingSimState are out of sync.
+    let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
+    scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+
+    if checkpoint_interval.is_some() {
+        // The second run is always serial, so this also checks that threading doesn't change
+        // anything.
+        let mut opts = sim_flags.opts.clone();
+        opts.num_threads = 1;
+        let mut other = Sim::new(&map, opts, &mut timer);
+        scenario.instantiate(&mut other, &map, &mut sim_flags.make_rng(), &mut timer);
+        timer.done();
+
+        if let Some(t) = sim.verify_determinism(&mut other, &map, None) {
+            println!("The two runs diverged at {}", t);
+            std::process::exit(1);
+        }
+        return;
+    }
     timer.done();
 
     run_experiment(&map, &mut sim);
@@ -19,6 +19,7 @@ struct Job {
 
     oneshot: Option<String>,
     oneshot_clip: Option<String>,
+    oneshot_elevation: Option<String>,
 }
 
 fn main() {
@@ -44,6 +45,8 @@ fn main() {
         // Ignore other arguments and just convert the given .osm file to a Map.
         oneshot: args.optional("--oneshot"),
         oneshot_clip: args.optional("--oneshot_clip"),
+        // An SRTM .hgt tile or a CSV grid of longitude,latitude,meters
+        oneshot_elevation: args.optional("--oneshot_elevation"),
     };
     args.done();
     if !job.osm_to_raw
@@ -60,7 +63,7 @@ fn main() {
     }
 
     if let Some(path) = job.oneshot {
-        oneshot(path, job.oneshot_clip);
+        oneshot(path, job.oneshot_clip, job.oneshot_elevation);
         return;
     }
 
@@ -147,7 +150,7 @@ fn main() {
     }
 }
 
-fn oneshot(osm_path: String, clip: Option<String>) {
+fn oneshot(osm_path: String, clip: Option<String>, elevation: Option<String>) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
     let name = abstutil::basename(&osm_path);
@@ -162,7 +165,7 @@ fn oneshot(osm_path: String, clip: Option<String>) {
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
             sidewalks: None,
             gtfs: None,
-            elevation: None,
+            elevation,
             clip,
             drive_on_right: true,
         },
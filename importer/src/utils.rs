@@ -115,7 +115,7 @@ pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::M
     if map.get_name() == "huge_seattle" {
         timer.start("generating city manifest");
         abstutil::write_binary(
-            format!("../data/system/cities/{}.bin", map.get_city_name()),
+            abstutil::path_city(map.get_city_name()),
             &map_model::City::new(&map),
         );
         timer.stop("generating city manifest");
@@ -379,6 +379,7 @@ impl Model {
                 osm_tags,
                 turn_restrictions: Vec::new(),
                 complicated_turn_restrictions: Vec::new(),
+                percent_grade: 0.0,
             },
         );
         self.road_added(id, prerender);
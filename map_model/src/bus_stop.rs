@@ -1,4 +1,6 @@
-use crate::{LaneID, Position};
+use crate::make::bus_stops::check_stops;
+use crate::{LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Position};
+use geom::{Distance, Duration, Time};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -34,9 +36,68 @@ pub struct BusStop {
     pub sidewalk_pos: Position,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BusRoute {
     pub id: BusRouteID,
     pub name: String,
+    // Buses drive these in a loop, so the last stop connects back to the first.
     pub stops: Vec<BusStopID>,
+    // When a new bus starts running the route
+    pub spawn_times: Vec<Time>,
+}
+
+impl BusRoute {
+    // Consecutive stops have to be connected by lanes buses can use.
+    pub fn validate_stops(stops: &Vec<BusStopID>, map: &Map) -> Result<(), String> {
+        if stops.len() < 2 {
+            return Err("A route needs at least 2 stops".to_string());
+        }
+        for idx1 in 0..stops.len() {
+            let idx2 = (idx1 + 1) % stops.len();
+            if !check_stops(stops[idx1], stops[idx2], map) {
+                return Err(format!(
+                    "Buses can't drive from stop {} to stop {}",
+                    idx1 + 1,
+                    idx2 + 1
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // The path from each stop to the next one. Assumes the stops are valid.
+    pub fn all_paths(&self, map: &Map) -> Vec<Path> {
+        (0..self.stops.len())
+            .map(|idx| {
+                let stop1 = map.get_bs(self.stops[idx]);
+                let stop2 = map.get_bs(self.stops[(idx + 1) % self.stops.len()]);
+                map.pathfind(PathRequest {
+                    start: stop1.driving_pos,
+                    end: stop2.driving_pos,
+                    constraints: PathConstraints::Bus,
+                })
+                .unwrap()
+            })
+            .collect()
+    }
+
+    // The length of one loop, and how long it takes at the speed limit, not counting time spent
+    // at stops or waiting in traffic.
+    pub fn estimate_loop(&self, map: &Map) -> (Distance, Duration) {
+        let mut dist = Distance::ZERO;
+        let mut time = Duration::ZERO;
+        for path in self.all_paths(map) {
+            for step in path.get_steps() {
+                let (length, road) = match step {
+                    PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                        (map.get_l(*l).length(), map.get_l(*l).parent)
+                    }
+                    PathStep::Turn(t) => (map.get_t(*t).geom.length(), map.get_l(t.dst).parent),
+                };
+                dist += length;
+                time += length / map.get_r(road).speed_limit;
+            }
+        }
+        (dist, time)
+    }
 }
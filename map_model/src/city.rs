@@ -13,24 +13,21 @@ pub struct City {
 
 impl City {
     pub fn new(huge_map: &Map) -> City {
-        let mut regions = abstutil::list_all_objects(format!(
-            "../data/input/{}/polygons",
-            huge_map.get_city_name()
-        ))
-        .into_iter()
-        .map(|name| {
-            let pts = LonLat::read_osmosis_polygon(format!(
-                "../data/input/{}/polygons/{}.poly",
-                huge_map.get_city_name(),
-                name
-            ))
-            .unwrap();
-            (
-                name,
-                Polygon::new(&huge_map.get_gps_bounds().must_convert(&pts)),
-            )
-        })
-        .collect::<Vec<_>>();
+        let mut regions =
+            abstutil::list_all_objects(abstutil::path_all_neighborhoods(huge_map.get_city_name()))
+                .into_iter()
+                .map(|name| {
+                    let pts = LonLat::read_osmosis_polygon(abstutil::path_neighborhood(
+                        huge_map.get_city_name(),
+                        &name,
+                    ))
+                    .unwrap();
+                    (
+                        name,
+                        Polygon::new(&huge_map.get_gps_bounds().must_convert(&pts)),
+                    )
+                })
+                .collect::<Vec<_>>();
         // Just a sort of z-ordering hack so that the largest encompassing region isn't first
         // later in the UI picker.
         regions.sort_by_key(|(_, poly)| poly.get_bounds().width() as usize);
@@ -1,4 +1,4 @@
-use crate::{LaneID, Map, PathConstraints};
+use crate::{BuildingID, LaneID, Map, PathConstraints};
 use abstutil::Timer;
 use petgraph::graphmap::DiGraphMap;
 use std::collections::{HashSet, VecDeque};
@@ -39,6 +39,33 @@ pub fn find_scc(map: &Map, constraints: PathConstraints) -> (HashSet<LaneID>, Ha
     (largest_group, disconnected)
 }
 
+// Buildings that cars can't reach, because the driving lane trips would use for them isn't in the
+// main component. Useful to see what closing a lane cuts off.
+pub fn find_unreachable_buildings(map: &Map) -> HashSet<BuildingID> {
+    let (main_component, _) = find_scc(map, PathConstraints::Car);
+    map.all_buildings()
+        .iter()
+        .filter(|b| !main_component.contains(&map.find_driving_lane_near_building(b.id)))
+        .map(|b| b.id)
+        .collect()
+}
+
+// Lanes vehicles can enter an intersection from, but not leave by any turn. Banning turns can cause
+// this. Borders and closed intersections don't count.
+pub fn find_stranded_lanes(map: &Map) -> HashSet<LaneID> {
+    map.all_lanes()
+        .iter()
+        .filter(|l| {
+            let i = map.get_i(l.dst_i);
+            (l.is_driving() || l.is_biking() || l.is_bus())
+                && !i.is_border()
+                && !i.is_closed()
+                && map.get_turns_from_lane(l.id).is_empty()
+        })
+        .map(|l| l.id)
+        .collect()
+}
+
 // Returns list of (driving lane, redirect here instead for parking)
 //
 // It's a bit weird to never attempt parking on roads not part of the largest SCC of the graph.
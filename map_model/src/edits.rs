@@ -1,9 +1,12 @@
 use crate::raw::{OriginalIntersection, OriginalRoad};
 use crate::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType, Map, RoadID, TurnID,
+    BusRouteID, BusStopID, ControlStopSign, ControlTrafficSignal, ExportedTrafficSignal,
+    IntersectionID, LaneID, LaneType, Map, RoadID, TurnID,
 };
-use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer};
-use geom::Speed;
+use abstutil::{
+    deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer, Versioned,
+};
+use geom::{Speed, Time};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -11,12 +14,21 @@ use std::collections::{BTreeMap, BTreeSet};
 pub struct MapEdits {
     pub edits_name: String,
     pub commands: Vec<EditCmd>,
+    // Applied after the commands, but never saved. The sim uses these for interventions, so they
+    // don't wind up in the player's edits.
+    pub temporary: Vec<EditCmd>,
 
     // Derived from commands, kept up to date by update_derived
     pub original_lts: BTreeMap<LaneID, LaneType>,
     pub reversed_lanes: BTreeSet<LaneID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_speed_limits: BTreeSet<RoadID>,
+    pub original_route_stops: BTreeMap<BusRouteID, Vec<BusStopID>>,
+    pub original_route_schedules: BTreeMap<BusRouteID, Vec<Time>>,
+    pub banned_turns: BTreeSet<TurnID>,
+    // Turns that OSM turn restrictions leave out, but the player allowed anyway
+    pub allowed_turns: BTreeSet<TurnID>,
+    pub closed_lanes: BTreeSet<LaneID>,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
@@ -24,14 +36,16 @@ pub struct MapEdits {
     pub proposal_link: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Savestates remember the edits made by sim interventions, so these are serializable too. Player
+// edits are saved as PermanentMapEdits instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EditIntersection {
     StopSign(ControlStopSign),
     TrafficSignal(ControlTrafficSignal),
     Closed,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EditCmd {
     ChangeLaneType {
         id: LaneID,
@@ -53,6 +67,27 @@ pub enum EditCmd {
         new: EditIntersection,
         old: EditIntersection,
     },
+    // The new stops must be valid; see BusRoute::validate_stops.
+    ChangeRouteStops {
+        id: BusRouteID,
+        new: Vec<BusStopID>,
+        old: Vec<BusStopID>,
+    },
+    ChangeRouteSchedule {
+        id: BusRouteID,
+        new: Vec<Time>,
+        old: Vec<Time>,
+    },
+    // Banned turns are left out whenever the intersection's turns are calculated.
+    ChangeTurnRestriction {
+        t: TurnID,
+        banned: bool,
+    },
+    // The lane keeps its type, but nothing new can route through it. Reopening a lane just
+    // removes this.
+    CloseLane {
+        id: LaneID,
+    },
 }
 
 impl EditCmd {
@@ -66,6 +101,58 @@ impl EditCmd {
                 EditIntersection::TrafficSignal(_) => format!("traffic signal #{}", i.0),
                 EditIntersection::Closed => format!("close {}", i),
             },
+            EditCmd::ChangeRouteStops { id, new, .. } => format!("{} stops on {}", new.len(), id),
+            EditCmd::ChangeRouteSchedule { id, new, .. } => {
+                format!("{} buses on {}", new.len(), id)
+            }
+            EditCmd::ChangeTurnRestriction { t, banned } => {
+                format!("{} {}", if *banned { "ban" } else { "allow" }, t)
+            }
+            EditCmd::CloseLane { id } => format!("close #{}", id.0),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EditCmd::ChangeLaneType { .. } => "lane type change",
+            EditCmd::ReverseLane { .. } => "lane reversal",
+            EditCmd::ChangeSpeedLimit { .. } => "speed limit change",
+            EditCmd::ChangeIntersection { new, old, .. } => match (old, new) {
+                (EditIntersection::StopSign(_), EditIntersection::StopSign(_)) => {
+                    "stop sign change"
+                }
+                (EditIntersection::TrafficSignal(_), EditIntersection::TrafficSignal(_)) => {
+                    "traffic signal timing change"
+                }
+                (_, EditIntersection::Closed) | (EditIntersection::Closed, _) => {
+                    "intersection closure"
+                }
+                _ => "intersection type change",
+            },
+            EditCmd::ChangeRouteStops { .. } => "bus stop change",
+            EditCmd::ChangeRouteSchedule { .. } => "bus schedule change",
+            EditCmd::ChangeTurnRestriction { .. } => "turn restriction",
+            EditCmd::CloseLane { .. } => "lane closure",
+        }
+    }
+
+    // These only change how existing roads and intersections are controlled, not the structure of
+    // the map, so a running simulation can absorb them without resetting.
+    pub fn is_live_safe(&self) -> bool {
+        match self {
+            EditCmd::ChangeSpeedLimit { .. } => true,
+            EditCmd::ChangeIntersection { new, old, .. } => match (old, new) {
+                (EditIntersection::StopSign(_), EditIntersection::StopSign(_))
+                | (EditIntersection::TrafficSignal(_), EditIntersection::TrafficSignal(_)) => true,
+                _ => false,
+            },
+            // Buses already on the road can't switch to a new route or schedule
+            EditCmd::ChangeLaneType { .. }
+            | EditCmd::ReverseLane { .. }
+            | EditCmd::ChangeRouteStops { .. }
+            | EditCmd::ChangeRouteSchedule { .. }
+            | EditCmd::ChangeTurnRestriction { .. }
+            | EditCmd::CloseLane { .. } => false,
         }
     }
 }
@@ -85,11 +172,17 @@ impl MapEdits {
             proposal_description: Vec::new(),
             proposal_link: None,
             commands: Vec::new(),
+            temporary: Vec::new(),
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            original_route_stops: BTreeMap::new(),
+            original_route_schedules: BTreeMap::new(),
+            banned_turns: BTreeSet::new(),
+            allowed_turns: BTreeSet::new(),
+            closed_lanes: BTreeSet::new(),
         }
     }
 
@@ -98,16 +191,18 @@ impl MapEdits {
             return Ok(MapEdits::new());
         }
         PermanentMapEdits::from_permanent(
-            abstutil::read_json(abstutil::path_edits(map.get_name(), edits_name), timer),
+            abstutil::maybe_read_versioned(
+                abstutil::path_edits(map.get_name(), edits_name),
+                timer,
+            )?,
             map,
         )
     }
 
-    // TODO Version these? Or it's unnecessary, since we have a command stack.
     pub(crate) fn save(&self, map: &Map) {
         assert_ne!(self.edits_name, "untitled edits");
 
-        abstutil::write_json(
+        abstutil::write_versioned(
             abstutil::path_edits(map.get_name(), &self.edits_name),
             &PermanentMapEdits::to_permanent(self, map),
         );
@@ -118,6 +213,8 @@ impl MapEdits {
         let mut reversed_lanes = BTreeSet::new();
         let mut orig_intersections: BTreeMap<IntersectionID, EditIntersection> = BTreeMap::new();
         let mut changed_speed_limits = BTreeSet::new();
+        let mut orig_route_stops = BTreeMap::new();
+        let mut orig_route_schedules = BTreeMap::new();
 
         for cmd in &self.commands {
             match cmd {
@@ -141,6 +238,17 @@ impl MapEdits {
                         orig_intersections.insert(*i, old.clone());
                     }
                 }
+                EditCmd::ChangeRouteStops { id, ref old, .. } => {
+                    if !orig_route_stops.contains_key(id) {
+                        orig_route_stops.insert(*id, old.clone());
+                    }
+                }
+                EditCmd::ChangeRouteSchedule { id, ref old, .. } => {
+                    if !orig_route_schedules.contains_key(id) {
+                        orig_route_schedules.insert(*id, old.clone());
+                    }
+                }
+                EditCmd::ChangeTurnRestriction { .. } | EditCmd::CloseLane { .. } => {}
             }
         }
 
@@ -151,11 +259,44 @@ impl MapEdits {
         retain_btreeset(&mut changed_speed_limits, |r| {
             map.get_r(*r).speed_limit != map.get_r(*r).speed_limit_from_osm()
         });
+        retain_btreemap(&mut orig_route_stops, |r, orig| {
+            &map.get_br(*r).stops != orig
+        });
+        retain_btreemap(&mut orig_route_schedules, |r, orig| {
+            &map.get_br(*r).spawn_times != orig
+        });
 
         self.original_lts = orig_lts;
         self.reversed_lanes = reversed_lanes;
         self.original_intersections = orig_intersections;
         self.changed_speed_limits = changed_speed_limits;
+        self.original_route_stops = orig_route_stops;
+        self.original_route_schedules = orig_route_schedules;
+        let (banned_turns, allowed_turns) = MapEdits::find_turn_restrictions(&self.commands);
+        self.banned_turns = banned_turns;
+        self.allowed_turns = allowed_turns;
+        self.closed_lanes = MapEdits::find_closed_lanes(&self.commands);
+    }
+
+    // Returns (banned, allowed) turns. The last command for a turn wins. The map needs these before
+    // applying the commands, so it can recalculate turns correctly along the way.
+    pub(crate) fn find_turn_restrictions(
+        commands: &Vec<EditCmd>,
+    ) -> (BTreeSet<TurnID>, BTreeSet<TurnID>) {
+        let mut banned_turns = BTreeSet::new();
+        let mut allowed_turns = BTreeSet::new();
+        for cmd in commands {
+            if let EditCmd::ChangeTurnRestriction { t, banned } = cmd {
+                if *banned {
+                    banned_turns.insert(*t);
+                    allowed_turns.remove(t);
+                } else {
+                    banned_turns.remove(t);
+                    allowed_turns.insert(*t);
+                }
+            }
+        }
+        (banned_turns, allowed_turns)
     }
 
     // Assumes update_derived has been called.
@@ -187,6 +328,62 @@ impl MapEdits {
                 old: map.get_r(*r).speed_limit_from_osm(),
             });
         }
+        for (r, old) in &self.original_route_stops {
+            self.commands.push(EditCmd::ChangeRouteStops {
+                id: *r,
+                new: map.get_br(*r).stops.clone(),
+                old: old.clone(),
+            });
+        }
+        for (r, old) in &self.original_route_schedules {
+            self.commands.push(EditCmd::ChangeRouteSchedule {
+                id: *r,
+                new: map.get_br(*r).spawn_times.clone(),
+                old: old.clone(),
+            });
+        }
+        for t in &self.banned_turns {
+            self.commands.push(EditCmd::ChangeTurnRestriction {
+                t: *t,
+                banned: true,
+            });
+        }
+        for t in &self.allowed_turns {
+            self.commands.push(EditCmd::ChangeTurnRestriction {
+                t: *t,
+                banned: false,
+            });
+        }
+        for l in &self.closed_lanes {
+            self.commands.push(EditCmd::CloseLane { id: *l });
+        }
+    }
+}
+
+// Closing a lane is its own command, so it's easy to undo without disturbing anything else.
+impl MapEdits {
+    // The map needs these before applying the commands, so pathfinding can avoid them
+    pub(crate) fn find_closed_lanes(commands: &Vec<EditCmd>) -> BTreeSet<LaneID> {
+        commands
+            .iter()
+            .filter_map(|cmd| match cmd {
+                EditCmd::CloseLane { id } => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Closed by the player or temporarily
+    pub fn is_lane_closed(&self, l: LaneID) -> bool {
+        self.closed_lanes.contains(&l) || self.temporary.contains(&EditCmd::CloseLane { id: l })
+    }
+
+    // Drops the commands that closed the lane. Returns false if these edits didn't close it.
+    pub fn reopen_lane(&mut self, l: LaneID) -> bool {
+        let before = self.commands.len();
+        self.commands
+            .retain(|cmd| !matches!(cmd, EditCmd::CloseLane { id } if *id == l));
+        self.commands.len() != before
     }
 }
 
@@ -231,7 +428,7 @@ enum PermanentEditIntersection {
         )]
         must_stop: BTreeMap<OriginalRoad, bool>,
     },
-    TrafficSignal(seattle_traffic_signals::TrafficSignal),
+    TrafficSignal(ExportedTrafficSignal),
     Closed,
 }
 
@@ -267,6 +464,44 @@ enum PermanentEditCmd {
         new: PermanentEditIntersection,
         old: PermanentEditIntersection,
     },
+    // Routes are identified by name
+    ChangeRouteStops {
+        route: String,
+        new: Vec<OriginalBusStop>,
+        old: Vec<OriginalBusStop>,
+    },
+    ChangeRouteSchedule {
+        route: String,
+        new: Vec<Time>,
+        old: Vec<Time>,
+    },
+    ChangeTurnRestriction {
+        i: OriginalIntersection,
+        src: OriginalLane,
+        dst: OriginalLane,
+        banned: bool,
+    },
+    CloseLane {
+        id: OriginalLane,
+    },
+}
+
+// Bus stops are numbered along their sidewalk
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OriginalBusStop {
+    pub sidewalk: OriginalLane,
+    pub idx: usize,
+}
+
+// Version 0 is before edits were versioned. Every new command bumps the version, so older builds
+// refuse newer files clearly instead of failing to parse them. Older files just don't have the
+// new commands, so they load as-is.
+// - Version 2 added bus route stop and schedule changes
+// - Version 3 added turn restrictions
+// - Version 4 added lane closures
+impl Versioned for PermanentMapEdits {
+    const NAME: &'static str = "map edits";
+    const VERSION: u32 = 4;
 }
 
 impl PermanentMapEdits {
@@ -305,6 +540,37 @@ impl PermanentMapEdits {
                             old: old.to_permanent(map),
                         }
                     }
+                    EditCmd::ChangeRouteStops { id, new, old } => {
+                        PermanentEditCmd::ChangeRouteStops {
+                            route: map.get_br(*id).name.clone(),
+                            new: new
+                                .iter()
+                                .map(|bs| OriginalBusStop::to_permanent(*bs, map))
+                                .collect(),
+                            old: old
+                                .iter()
+                                .map(|bs| OriginalBusStop::to_permanent(*bs, map))
+                                .collect(),
+                        }
+                    }
+                    EditCmd::ChangeRouteSchedule { id, new, old } => {
+                        PermanentEditCmd::ChangeRouteSchedule {
+                            route: map.get_br(*id).name.clone(),
+                            new: new.clone(),
+                            old: old.clone(),
+                        }
+                    }
+                    EditCmd::ChangeTurnRestriction { t, banned } => {
+                        PermanentEditCmd::ChangeTurnRestriction {
+                            i: map.get_i(t.parent).orig_id,
+                            src: OriginalLane::to_permanent(t.src, map),
+                            dst: OriginalLane::to_permanent(t.dst, map),
+                            banned: *banned,
+                        }
+                    }
+                    EditCmd::CloseLane { id } => PermanentEditCmd::CloseLane {
+                        id: OriginalLane::to_permanent(*id, map),
+                    },
                 })
                 .collect(),
         }
@@ -350,13 +616,63 @@ impl PermanentMapEdits {
                                 .ok_or(format!("old ChangeIntersection of {} invalid", i))?,
                         })
                     }
+                    PermanentEditCmd::ChangeRouteStops { route, new, old } => {
+                        let id = map
+                            .get_bus_route(&route)
+                            .ok_or(format!("bus route {} doesn't exist", route))?
+                            .id;
+                        let new = new
+                            .into_iter()
+                            .map(|bs| bs.from_permanent(map))
+                            .collect::<Result<Vec<BusStopID>, String>>()?;
+                        let old = old
+                            .into_iter()
+                            .map(|bs| bs.from_permanent(map))
+                            .collect::<Result<Vec<BusStopID>, String>>()?;
+                        Ok(EditCmd::ChangeRouteStops { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeRouteSchedule { route, new, old } => {
+                        let id = map
+                            .get_bus_route(&route)
+                            .ok_or(format!("bus route {} doesn't exist", route))?
+                            .id;
+                        Ok(EditCmd::ChangeRouteSchedule { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeTurnRestriction {
+                        i,
+                        src,
+                        dst,
+                        banned,
+                    } => {
+                        let t = TurnID {
+                            parent: map.find_i_by_osm_id(i.osm_node_id)?,
+                            src: src.from_permanent(map)?,
+                            dst: dst.from_permanent(map)?,
+                        };
+                        for l in vec![t.src, t.dst] {
+                            let lane = map.get_l(l);
+                            if lane.src_i != t.parent && lane.dst_i != t.parent {
+                                return Err(format!("{} doesn't touch {}", l, i));
+                            }
+                        }
+                        Ok(EditCmd::ChangeTurnRestriction { t, banned })
+                    }
+                    PermanentEditCmd::CloseLane { id } => Ok(EditCmd::CloseLane {
+                        id: id.from_permanent(map)?,
+                    }),
                 })
                 .collect::<Result<Vec<EditCmd>, String>>()?,
+            temporary: Vec::new(),
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            original_route_stops: BTreeMap::new(),
+            original_route_schedules: BTreeMap::new(),
+            banned_turns: BTreeSet::new(),
+            allowed_turns: BTreeSet::new(),
+            closed_lanes: BTreeSet::new(),
         };
         edits.update_derived(map);
         Ok(edits)
@@ -442,3 +758,206 @@ impl OriginalLane {
         }
     }
 }
+
+impl OriginalBusStop {
+    pub fn to_permanent(bs: BusStopID, map: &Map) -> OriginalBusStop {
+        OriginalBusStop {
+            sidewalk: OriginalLane::to_permanent(bs.sidewalk, map),
+            idx: bs.idx,
+        }
+    }
+
+    pub fn from_permanent(self, map: &Map) -> Result<BusStopID, String> {
+        let id = BusStopID {
+            sidewalk: self.sidewalk.clone().from_permanent(map)?,
+            idx: self.idx,
+        };
+        if map.maybe_get_bs(id).is_none() {
+            return Err(format!("bus stop {:?} doesn't exist", self));
+        }
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_edits() -> PermanentMapEdits {
+        PermanentMapEdits {
+            map_name: "montlake".to_string(),
+            edits_name: "test".to_string(),
+            commands: Vec::new(),
+            proposal_description: vec!["a proposal".to_string()],
+            proposal_link: None,
+        }
+    }
+
+    #[test]
+    fn test_reopen_lane() {
+        let (l1, l2) = (LaneID(1), LaneID(2));
+        let mut edits = MapEdits::new();
+        edits.commands = vec![
+            EditCmd::ChangeLaneType {
+                id: l1,
+                lt: LaneType::Biking,
+                orig_lt: LaneType::Driving,
+            },
+            EditCmd::CloseLane { id: l1 },
+            EditCmd::CloseLane { id: l2 },
+        ];
+        assert_eq!(
+            MapEdits::find_closed_lanes(&edits.commands),
+            vec![l1, l2].into_iter().collect()
+        );
+
+        // The earlier conversion to a bike lane stays
+        assert!(edits.reopen_lane(l1));
+        assert_eq!(edits.commands.len(), 2);
+        assert!(!MapEdits::find_closed_lanes(&edits.commands).contains(&l1));
+        assert!(!edits.reopen_lane(l1));
+        assert_eq!(MapEdits::find_closed_lanes(&edits.commands).len(), 1);
+    }
+
+    #[test]
+    fn test_ban_turns() {
+        let mut timer = Timer::throwaway();
+        let mut map = crate::SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.is_traffic_signal())
+            .unwrap()
+            .id;
+        let from = map
+            .get_turns_in_intersection(i)
+            .into_iter()
+            .find(|t| !t.between_sidewalks())
+            .unwrap()
+            .id
+            .src;
+        let turns: Vec<TurnID> = map
+            .get_turns_from_lane(from)
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+
+        let mut edits = map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeTurnRestriction {
+            t: turns[0],
+            banned: true,
+        });
+        map.apply_edits(edits.clone(), &mut timer);
+        assert!(map.maybe_get_t(turns[0]).is_none());
+        assert!(map.maybe_get_t(turns[1]).is_some());
+        assert!(!crate::connectivity::find_stranded_lanes(&map).contains(&from));
+
+        // Survives saving and loading
+        let perma = PermanentMapEdits::to_permanent(map.get_edits(), &map);
+        let loaded = PermanentMapEdits::from_permanent(perma, &map).unwrap();
+        assert_eq!(loaded.banned_turns, vec![turns[0]].into_iter().collect());
+
+        // The last command for a turn wins
+        let mut allow = edits.clone();
+        allow.commands.push(EditCmd::ChangeTurnRestriction {
+            t: turns[0],
+            banned: false,
+        });
+        map.apply_edits(allow, &mut timer);
+        assert!(map.maybe_get_t(turns[0]).is_some());
+        assert!(map.get_edits().banned_turns.is_empty());
+        assert_eq!(
+            map.get_edits().allowed_turns,
+            vec![turns[0]].into_iter().collect()
+        );
+
+        for t in &turns[1..] {
+            edits.commands.push(EditCmd::ChangeTurnRestriction {
+                t: *t,
+                banned: true,
+            });
+        }
+        map.apply_edits(edits.clone(), &mut timer);
+        assert!(map.get_turns_from_lane(from).is_empty());
+        assert!(crate::connectivity::find_stranded_lanes(&map).contains(&from));
+
+        // Undoing everything brings back all the turns
+        map.apply_edits(MapEdits::new(), &mut timer);
+        assert_eq!(map.get_turns_from_lane(from).len(), turns.len());
+    }
+
+    #[test]
+    fn test_signal_crosswalk_timing_saved() {
+        let mut timer = Timer::throwaway();
+        let map = crate::SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.is_traffic_signal())
+            .unwrap()
+            .id;
+        let mut signal = map.get_traffic_signal(i).clone();
+        signal.phases[0].crosswalk_timing = crate::CrosswalkTiming::Actuated;
+
+        // Survives saving and loading
+        let mut edits = MapEdits::new();
+        edits.commands.push(EditCmd::ChangeIntersection {
+            i,
+            new: EditIntersection::TrafficSignal(signal.clone()),
+            old: map.get_i_edit(i),
+        });
+        let perma = PermanentMapEdits::to_permanent(&edits, &map);
+        let json = abstutil::to_versioned_json(&perma);
+        let perma: PermanentMapEdits = abstutil::from_versioned_json(json.as_bytes()).unwrap();
+        let loaded = PermanentMapEdits::from_permanent(perma, &map).unwrap();
+        match loaded.commands[0] {
+            EditCmd::ChangeIntersection {
+                new: EditIntersection::TrafficSignal(ref ts),
+                ..
+            } => {
+                assert_eq!(ts.phases, signal.phases);
+            }
+            _ => unreachable!(),
+        }
+
+        // Plans from seattle_traffic_signals don't say anything about crosswalks
+        let mut raw = signal.export(&map);
+        raw.crosswalk_timing.clear();
+        let loaded = ControlTrafficSignal::import(raw, i, &map).unwrap();
+        assert!(loaded
+            .phases
+            .iter()
+            .all(|p| p.crosswalk_timing == crate::CrosswalkTiming::Fixed));
+    }
+
+    #[test]
+    fn test_versions() {
+        let json = abstutil::to_versioned_json(&empty_edits());
+        let edits: PermanentMapEdits = abstutil::from_versioned_json(json.as_bytes()).unwrap();
+        assert_eq!(edits.edits_name, "test");
+        assert_eq!(edits.proposal_description, vec!["a proposal".to_string()]);
+
+        // Before versioning
+        let v0 = r#"{
+            "map_name": "montlake",
+            "edits_name": "old",
+            "commands": [],
+            "proposal_description": [],
+            "proposal_link": null
+        }"#;
+        let edits: PermanentMapEdits = abstutil::from_versioned_json(v0.as_bytes()).unwrap();
+        assert_eq!(edits.edits_name, "old");
+
+        let future = json.replace(
+            &format!("\"version\": {}", PermanentMapEdits::VERSION),
+            "\"version\": 99",
+        );
+        assert!(abstutil::from_versioned_json::<PermanentMapEdits>(future.as_bytes()).is_err());
+    }
+}
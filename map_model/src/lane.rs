@@ -103,6 +103,9 @@ pub struct Lane {
     // If set, cars trying to park near here should actually start their search at this other lane.
     // Only populated for driving lanes inevitably leading to borders.
     pub parking_blackhole: Option<LaneID>,
+
+    // Average grade in the direction of travel. Positive is uphill, 0 without elevation data.
+    pub percent_grade: f64,
 }
 
 impl Lane {
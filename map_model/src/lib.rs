@@ -14,6 +14,7 @@ mod pathfind;
 pub mod raw;
 mod road;
 mod stop_signs;
+mod synthetic;
 mod traffic_signals;
 mod traversable;
 mod turn;
@@ -23,7 +24,8 @@ pub use crate::building::{Building, BuildingID, FrontPath, OffstreetParking};
 pub use crate::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
+    EditCmd, EditEffects, EditIntersection, MapEdits, OriginalBusStop, OriginalLane,
+    PermanentMapEdits,
 };
 pub use crate::intersection::{Intersection, IntersectionID, IntersectionType};
 pub use crate::lane::{Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH};
@@ -34,9 +36,12 @@ pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGro
 pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
 pub use crate::road::{DirectedRoadID, Road, RoadID};
 pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::traffic_signals::{ControlTrafficSignal, Phase};
+pub use crate::synthetic::{SyntheticMap, CANNED_SYNTHETIC_MAPS};
+pub use crate::traffic_signals::{
+    ControlTrafficSignal, CrosswalkTiming, ExportedTrafficSignal, Phase,
+};
 pub use crate::traversable::{Position, Traversable};
-pub use crate::turn::{Turn, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType};
+pub use crate::turn::{MovementType, Turn, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType};
 use abstutil::Cloneable;
 use geom::Distance;
 
@@ -4,7 +4,7 @@ use crate::{
     Position,
 };
 use abstutil::{MultiMap, Timer};
-use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Pt2D};
+use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Pt2D, Time};
 use gtfs;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
@@ -93,6 +93,8 @@ pub fn make_bus_stops(
             id,
             name: route_name.to_string(),
             stops,
+            // Just one bus, until somebody edits the schedule
+            spawn_times: vec![Time::START_OF_DAY],
         });
     }
     timer.stop("make bus stops");
@@ -123,7 +125,7 @@ pub fn fix_bus_route(map: &Map, r: &mut BusRoute) -> bool {
     r.stops.len() >= 2
 }
 
-fn check_stops(stop1: BusStopID, stop2: BusStopID, map: &Map) -> bool {
+pub(crate) fn check_stops(stop1: BusStopID, stop2: BusStopID, map: &Map) -> bool {
     let bs1 = map.get_bs(stop1);
     let bs2 = map.get_bs(stop2);
     // This is coming up because the dist_along's are in a bad order. But why should
@@ -1,6 +1,6 @@
 use crate::{
-    ControlTrafficSignal, IntersectionID, Map, Phase, RoadID, TurnGroup, TurnGroupID, TurnPriority,
-    TurnType,
+    ControlTrafficSignal, ExportedTrafficSignal, IntersectionID, Map, Phase, RoadID, TurnGroup,
+    TurnGroupID, TurnPriority, TurnType,
 };
 use abstutil::Timer;
 use geom::Duration;
@@ -19,6 +19,10 @@ pub fn get_possible_policies(
         .unwrap()
         .remove(&map.get_i(id).orig_id.osm_node_id)
     {
+        let raw = ExportedTrafficSignal {
+            signal: raw,
+            crosswalk_timing: Vec::new(),
+        };
         if let Some(ts) = ControlTrafficSignal::import(raw, id, map) {
             results.push(("hand-mapped current real settings".to_string(), ts));
         } else {
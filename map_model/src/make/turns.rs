@@ -9,11 +9,13 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 
 // TODO Add proper warnings when the geometry is too small to handle.
 
+// Turns in allowed_turns skip the OSM turn restrictions.
 pub fn make_all_turns(
     driving_side: DrivingSide,
     i: &Intersection,
     roads: &Vec<Road>,
     lanes: &Vec<Lane>,
+    allowed_turns: &BTreeSet<TurnID>,
     timer: &mut Timer,
 ) -> Vec<Turn> {
     assert!(!i.is_border());
@@ -26,6 +28,10 @@ pub fn make_all_turns(
     let mut final_turns: Vec<Turn> = Vec::new();
     let mut filtered_turns: HashMap<LaneID, Vec<Turn>> = HashMap::new();
     for turn in unique_turns {
+        if allowed_turns.contains(&turn.id) {
+            final_turns.push(turn);
+            continue;
+        }
         if !does_turn_pass_restrictions(&turn, &i.roads, roads, lanes) {
             continue;
         }
@@ -83,6 +89,29 @@ pub fn make_all_turns(
     final_turns
 }
 
+// Every vehicle turn the lanes could support, ignoring all turn restrictions
+pub fn make_unrestricted_vehicle_turns(
+    i: &Intersection,
+    roads: &Vec<Road>,
+    lanes: &Vec<Lane>,
+    timer: &mut Timer,
+) -> Vec<Turn> {
+    if i.is_border() {
+        return Vec::new();
+    }
+    ensure_unique(make_vehicle_turns(i, roads, lanes, timer))
+}
+
+// False if OSM turn restrictions would leave out this turn
+pub fn passes_turn_restrictions(
+    turn: &Turn,
+    i: &Intersection,
+    roads: &Vec<Road>,
+    lanes: &Vec<Lane>,
+) -> bool {
+    does_turn_pass_restrictions(turn, &i.roads, roads, lanes) && is_turn_allowed(turn, roads, lanes)
+}
+
 fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
     let mut ids = HashSet::new();
     let mut keep: Vec<Turn> = Vec::new();
@@ -4,8 +4,8 @@ use crate::{
     connectivity, make, osm, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop,
     BusStopID, ControlStopSign, ControlTrafficSignal, EditCmd, EditEffects, EditIntersection,
     Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, MapEdits, ParkingLot,
-    ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, Turn, TurnGroupID,
-    TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
+    ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, SyntheticMap, Turn,
+    TurnGroupID, TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer, Warn};
 use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Speed};
@@ -108,7 +108,10 @@ impl Map {
             abstutil::read_binary(path, timer)
         } else {
             // Synthetic
-            abstutil::read_json(path, timer)
+            match SyntheticMap::load_raw(path, timer) {
+                Ok(raw) => raw,
+                Err(err) => panic!("{}", err),
+            }
         };
         Map::create_from_raw(raw, true, timer)
     }
@@ -373,6 +376,27 @@ impl Map {
             .collect()
     }
 
+    // Every vehicle turn the lanes here could support, including ones banned by the player or left
+    // out by OSM turn restrictions. These aren't necessarily in the map.
+    pub fn get_possible_vehicle_turns(&self, id: IntersectionID) -> Vec<Turn> {
+        make::turns::make_unrestricted_vehicle_turns(
+            self.get_i(id),
+            &self.roads,
+            &self.lanes,
+            &mut Timer::throwaway(),
+        )
+    }
+
+    // Every other turn in the same intersection that crosses this one
+    pub fn get_conflicting_turns(&self, t: TurnID) -> Vec<TurnID> {
+        let turn = self.get_t(t);
+        self.get_turns_in_intersection(t.parent)
+            .into_iter()
+            .filter(|other| turn.conflicts_with(other))
+            .map(|other| other.id)
+            .collect()
+    }
+
     // The turns may belong to two different intersections!
     pub fn get_turns_from_lane(&self, l: LaneID) -> Vec<&Turn> {
         let lane = self.get_l(l);
@@ -791,6 +815,12 @@ impl Map {
     }
 
     pub fn save_edits(&self) {
+        // Temporary edits change the map too, so it can't be used to compress the commands. Just
+        // save them as they are.
+        if !self.edits.temporary.is_empty() {
+            self.edits.save(self);
+            return;
+        }
         // Don't overwrite the current edits with the compressed first. Otherwise, undo/redo order
         // in the UI gets messed up.
         let mut edits = self.edits.clone();
@@ -814,9 +844,15 @@ impl Map {
         // TODO More efficient ways to do this: given two sets of edits, produce a smaller diff.
         // Simplest strategy: Remove common prefix.
         let mut effects = EditEffects::new();
+        // Turns recalculated while undoing and applying should already reflect the new
+        // restrictions.
+        let (banned_turns, allowed_turns) = MapEdits::find_turn_restrictions(&new_edits.commands);
+        self.edits.banned_turns = banned_turns;
+        self.edits.allowed_turns = allowed_turns;
 
         // First undo all existing edits.
         let mut undo = std::mem::replace(&mut self.edits.commands, Vec::new());
+        undo.extend(std::mem::replace(&mut self.edits.temporary, Vec::new()));
         undo.reverse();
         let mut undid = 0;
         for cmd in &undo {
@@ -828,7 +864,7 @@ impl Map {
 
         // Apply new edits.
         let mut applied = 0;
-        for cmd in &new_edits.commands {
+        for cmd in new_edits.commands.iter().chain(&new_edits.temporary) {
             if cmd.apply(&mut effects, self, timer) {
                 applied += 1;
             }
@@ -836,7 +872,7 @@ impl Map {
         timer.note(format!(
             "Applied {} / {} new edits",
             applied,
-            new_edits.commands.len()
+            new_edits.commands.len() + new_edits.temporary.len()
         ));
 
         // Might need to update bus stops.
@@ -1047,6 +1083,11 @@ fn make_half_map(
                 building_paths: Vec::new(),
                 bus_stops: Vec::new(),
                 parking_blackhole: None,
+                percent_grade: if lane.reverse_pts {
+                    -raw.roads[&r.id].percent_grade
+                } else {
+                    raw.roads[&r.id].percent_grade
+                },
             });
         }
         if road.get_name() == "???" {
@@ -1092,7 +1133,14 @@ fn make_half_map(
             continue;
         }
 
-        for t in make::turns::make_all_turns(map.driving_side, i, &map.roads, &map.lanes, timer) {
+        for t in make::turns::make_all_turns(
+            map.driving_side,
+            i,
+            &map.roads,
+            &map.lanes,
+            &BTreeSet::new(),
+            timer,
+        ) {
             assert!(!map.turns.contains_key(&t.id));
             i.turns.insert(t.id);
             if t.geom.length() < geom::EPSILON_DIST {
@@ -1215,6 +1263,7 @@ impl EditCmd {
                 std::mem::swap(&mut lane.src_i, &mut lane.dst_i);
                 assert_eq!(lane.dst_i, *dst_i);
                 lane.lane_center_pts = lane.lane_center_pts.reversed();
+                lane.percent_grade = -lane.percent_grade;
 
                 map.intersections[lane.src_i.0].outgoing_lanes.push(l);
                 map.intersections[lane.dst_i.0].incoming_lanes.push(l);
@@ -1276,6 +1325,29 @@ impl EditCmd {
                 }
                 true
             }
+            // Only the pathfinding for pedestrians riding buses changes.
+            EditCmd::ChangeRouteStops { id, ref new, .. } => {
+                if &map.bus_routes[id.0].stops == new {
+                    return false;
+                }
+                map.bus_routes[id.0].stops = new.clone();
+                true
+            }
+            EditCmd::ChangeRouteSchedule { id, ref new, .. } => {
+                if &map.bus_routes[id.0].spawn_times == new {
+                    return false;
+                }
+                map.bus_routes[id.0].spawn_times = new.clone();
+                true
+            }
+            EditCmd::ChangeTurnRestriction { t, .. } => {
+                update_turn_restriction(*t, map, effects, timer)
+            }
+            // The lane itself doesn't change; pathfinding just has to avoid it
+            EditCmd::CloseLane { id } => {
+                effects.changed_roads.insert(map.get_l(*id).parent);
+                true
+            }
         }
     }
 
@@ -1320,10 +1392,65 @@ impl EditCmd {
                 new: old.clone(),
             }
             .apply(effects, map, timer),
+            EditCmd::ChangeRouteStops {
+                id,
+                ref old,
+                ref new,
+            } => EditCmd::ChangeRouteStops {
+                id: *id,
+                old: new.clone(),
+                new: old.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeRouteSchedule {
+                id,
+                ref old,
+                ref new,
+            } => EditCmd::ChangeRouteSchedule {
+                id: *id,
+                old: new.clone(),
+                new: old.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeTurnRestriction { t, .. } => {
+                update_turn_restriction(*t, map, effects, timer)
+            }
+            EditCmd::CloseLane { .. } => self.apply(effects, map, timer),
         }
     }
 }
 
+// Banning and allowing a turn both just recalculate the intersection, once the map knows the final
+// set of banned and allowed turns. A turn that's allowed but doesn't exist might not be possible
+// anymore, so this can recalculate for nothing.
+fn update_turn_restriction(
+    t: TurnID,
+    map: &mut Map,
+    effects: &mut EditEffects,
+    timer: &mut Timer,
+) -> bool {
+    let stale = if map.edits.banned_turns.contains(&t) {
+        map.turns.contains_key(&t)
+    } else if let Some(turn) = map.turns.get(&t) {
+        // Left over from undoing an allow
+        !map.edits.allowed_turns.contains(&t)
+            && !make::turns::passes_turn_restrictions(
+                turn,
+                &map.intersections[t.parent.0],
+                &map.roads,
+                &map.lanes,
+            )
+    } else {
+        true
+    };
+    if !stale {
+        return false;
+    }
+    effects.changed_intersections.insert(t.parent);
+    recalculate_turns(t.parent, map, effects, timer);
+    true
+}
+
 // This clobbers previously set traffic signal overrides.
 // TODO Step 1: Detect and warn about that
 // TODO Step 2: Avoid when possible
@@ -1350,7 +1477,17 @@ fn recalculate_turns(
         return;
     }
 
-    for t in make::turns::make_all_turns(map.driving_side, i, &map.roads, &map.lanes, timer) {
+    for t in make::turns::make_all_turns(
+        map.driving_side,
+        i,
+        &map.roads,
+        &map.lanes,
+        &map.edits.allowed_turns,
+        timer,
+    ) {
+        if map.edits.banned_turns.contains(&t.id) {
+            continue;
+        }
         effects.added_turns.insert(t.id);
         i.turns.insert(t.id);
         if let Some(_existing_t) = old_turns.iter().find(|turn| turn.id == t.id) {
@@ -208,6 +208,26 @@ impl Path {
         }
     }
 
+    // Keeps the steps up to idx, then follows another path. That path has to start with step idx.
+    pub fn splice(&mut self, idx: usize, other: Path, map: &Map) {
+        assert_eq!(self.steps[idx], other.steps[0]);
+        for step in self.steps.drain(idx + 1..) {
+            self.total_length -= step.as_traversable().length(map);
+            match step {
+                PathStep::Lane(_) | PathStep::ContraflowLane(_) => self.total_lanes -= 1,
+                _ => {}
+            };
+        }
+        for step in other.steps.into_iter().skip(1) {
+            self.add(step, map);
+        }
+        self.end_dist = other.end_dist;
+    }
+
+    pub fn get_end_dist(&self) -> Distance {
+        self.end_dist
+    }
+
     pub fn current_step(&self) -> PathStep {
         self.steps[0]
     }
@@ -342,6 +362,9 @@ impl PathConstraints {
     }
 
     pub fn can_use(self, l: &Lane, map: &Map) -> bool {
+        if map.get_edits().is_lane_closed(l.id) {
+            return false;
+        }
         match self {
             PathConstraints::Pedestrian => l.is_sidewalk(),
             PathConstraints::Car => l.is_driving(),
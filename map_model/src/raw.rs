@@ -293,6 +293,10 @@ pub struct RawRoad {
     pub turn_restrictions: Vec<(RestrictionType, OriginalRoad)>,
     // (via, to). For turn restrictions where 'via' is an entire road. Only BanTurns.
     pub complicated_turn_restrictions: Vec<(OriginalRoad, OriginalRoad)>,
+    // Averaged from elevation sampled along center_points. Positive is uphill going forwards. 0
+    // without elevation data, and not updated when the points move. Older maps don't have this.
+    #[serde(default)]
+    pub percent_grade: f64,
 }
 
 impl RawRoad {
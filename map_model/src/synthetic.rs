@@ -0,0 +1,466 @@
+use crate::raw::{
+    OriginalBuilding, OriginalIntersection, OriginalRoad, RawBuilding, RawIntersection, RawMap,
+    RawRoad,
+};
+use crate::{osm, IntersectionID, IntersectionType, Map, RoadSpec};
+use abstutil::Timer;
+use geom::{Bounds, Distance, GPSBounds, Polygon, Pt2D};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const BUILDING_LENGTH: Distance = Distance::const_meters(15.0);
+// Between intersections in canned maps
+const BLOCK_LENGTH: f64 = 100.0;
+
+// Names accepted by SyntheticMap::canned
+pub const CANNED_SYNTHETIC_MAPS: [&str; 5] = [
+    "grid_2x2",
+    "four_way_signal",
+    "one_way_couplet",
+    "gridlock_loop",
+    "detour_square",
+];
+
+// A tiny hand-described map, for tests and demos. Much less verbose than a RawMap, so it's
+// reasonable to write the JSON by hand. Coordinates are in meters and get shifted to start at
+// (0, 0).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyntheticMap {
+    pub name: String,
+    pub intersections: Vec<SyntheticIntersection>,
+    pub roads: Vec<SyntheticRoad>,
+    #[serde(default)]
+    pub buildings: Vec<SyntheticBuilding>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyntheticIntersection {
+    // Only used to refer to the intersection from roads
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub intersection_type: IntersectionType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyntheticRoad {
+    pub from: String,
+    pub to: String,
+    // A RoadSpec like "dps/dps", listing lanes from the center outwards
+    pub lanes: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyntheticBuilding {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl SyntheticMap {
+    pub fn new(name: &str) -> SyntheticMap {
+        SyntheticMap {
+            name: name.to_string(),
+            intersections: Vec::new(),
+            roads: Vec::new(),
+            buildings: Vec::new(),
+        }
+    }
+
+    pub fn intersection(mut self, name: &str, x: f64, y: f64, it: IntersectionType) -> Self {
+        self.intersections.push(SyntheticIntersection {
+            name: name.to_string(),
+            x,
+            y,
+            intersection_type: it,
+        });
+        self
+    }
+
+    pub fn road(mut self, from: &str, to: &str, lanes: &str) -> Self {
+        self.roads.push(SyntheticRoad {
+            from: from.to_string(),
+            to: to.to_string(),
+            lanes: lanes.to_string(),
+            name: None,
+        });
+        self
+    }
+
+    pub fn building(mut self, x: f64, y: f64) -> Self {
+        self.buildings.push(SyntheticBuilding { x, y });
+        self
+    }
+
+    // cols x rows blocks, with one building in each. Every intersection on the edge (besides the
+    // corners) gets a short road out to a border, so agents can enter and leave.
+    pub fn grid(name: &str, cols: usize, rows: usize, it: IntersectionType, lanes: &str) -> Self {
+        let node = |x: usize, y: usize| format!("{}_{}", x, y);
+        let mut map = SyntheticMap::new(name);
+        for x in 0..=cols {
+            for y in 0..=rows {
+                map = map.intersection(
+                    &node(x, y),
+                    (x as f64) * BLOCK_LENGTH,
+                    (y as f64) * BLOCK_LENGTH,
+                    it,
+                );
+                if x < cols {
+                    map = map.road(&node(x, y), &node(x + 1, y), lanes);
+                }
+                if y < rows {
+                    map = map.road(&node(x, y), &node(x, y + 1), lanes);
+                }
+                if x < cols && y < rows {
+                    map = map.building(
+                        ((x as f64) + 0.5) * BLOCK_LENGTH,
+                        ((y as f64) + 0.5) * BLOCK_LENGTH,
+                    );
+                }
+
+                let on_x_edge = x == 0 || x == cols;
+                let on_y_edge = y == 0 || y == rows;
+                if on_x_edge == on_y_edge {
+                    continue;
+                }
+                let (dx, dy) = if on_x_edge {
+                    (if x == 0 { -1.0 } else { 1.0 }, 0.0)
+                } else {
+                    (0.0, if y == 0 { -1.0 } else { 1.0 })
+                };
+                let border = format!("border_{}", node(x, y));
+                map = map
+                    .intersection(
+                        &border,
+                        (x as f64) * BLOCK_LENGTH + dx * BLOCK_LENGTH / 2.0,
+                        (y as f64) * BLOCK_LENGTH + dy * BLOCK_LENGTH / 2.0,
+                        IntersectionType::Border,
+                    )
+                    .road(&border, &node(x, y), lanes);
+            }
+        }
+        map
+    }
+
+    pub fn canned(name: &str) -> Option<SyntheticMap> {
+        match name {
+            "grid_2x2" => Some(SyntheticMap::grid(
+                name,
+                2,
+                2,
+                IntersectionType::StopSign,
+                "dps/dps",
+            )),
+            "four_way_signal" => {
+                let mut map = SyntheticMap::new(name).intersection(
+                    "center",
+                    BLOCK_LENGTH,
+                    BLOCK_LENGTH,
+                    IntersectionType::TrafficSignal,
+                );
+                for (dir, x, y) in vec![
+                    ("north", 1.0, 0.0),
+                    ("east", 2.0, 1.0),
+                    ("south", 1.0, 2.0),
+                    ("west", 0.0, 1.0),
+                ] {
+                    map = map
+                        .intersection(
+                            dir,
+                            x * BLOCK_LENGTH,
+                            y * BLOCK_LENGTH,
+                            IntersectionType::Border,
+                        )
+                        .road(dir, "center", "ds/ds");
+                }
+                Some(
+                    map.building(0.6 * BLOCK_LENGTH, 0.6 * BLOCK_LENGTH)
+                        .building(1.4 * BLOCK_LENGTH, 1.4 * BLOCK_LENGTH),
+                )
+            }
+            // A one-way pair heading in opposite directions, tied together by two cross streets
+            "one_way_couplet" => {
+                let (north, south) = (0.0, 0.6 * BLOCK_LENGTH);
+                let mut map = SyntheticMap::new(name);
+                for (i, x) in vec![0.0, 1.0, 2.0, 3.0].into_iter().enumerate() {
+                    let it = if i == 0 || i == 3 {
+                        IntersectionType::Border
+                    } else {
+                        IntersectionType::StopSign
+                    };
+                    map = map
+                        .intersection(&format!("n{}", i), x * BLOCK_LENGTH, north, it)
+                        .intersection(&format!("s{}", i), x * BLOCK_LENGTH, south, it);
+                }
+                for i in 0..3 {
+                    // Eastbound on the north street, westbound on the south
+                    map = map
+                        .road(&format!("n{}", i), &format!("n{}", i + 1), "dds/s")
+                        .road(&format!("s{}", i + 1), &format!("s{}", i), "dds/s");
+                }
+                for i in 1..3 {
+                    map = map.road(&format!("n{}", i), &format!("s{}", i), "ds/ds");
+                }
+                Some(map.building(1.5 * BLOCK_LENGTH, (north + south) / 2.0))
+            }
+            // Four stop signs around a square, each with a spur out to a border. In gridlock_loop,
+            // the square is a one-way loop, so it's easy to fill up.
+            "gridlock_loop" | "detour_square" => {
+                let loop_lanes = if name == "gridlock_loop" {
+                    "ds/s"
+                } else {
+                    "ds/ds"
+                };
+                let corners = vec![
+                    ("nw", 0.4, 0.4),
+                    ("ne", 1.2, 0.4),
+                    ("se", 1.2, 1.2),
+                    ("sw", 0.4, 1.2),
+                ];
+                let mut map = SyntheticMap::new(name);
+                for (corner, x, y) in &corners {
+                    map = map.intersection(
+                        corner,
+                        x * BLOCK_LENGTH,
+                        y * BLOCK_LENGTH,
+                        IntersectionType::StopSign,
+                    );
+                }
+                for (corner, x, y) in &corners {
+                    // Diagonally away from the middle of the square
+                    let border = format!("{}_border", corner);
+                    map = map.intersection(
+                        &border,
+                        (2.0 * x - 0.8) * BLOCK_LENGTH,
+                        (2.0 * y - 0.8) * BLOCK_LENGTH,
+                        IntersectionType::Border,
+                    );
+                }
+                for idx in 0..corners.len() {
+                    let (corner, _, _) = corners[idx];
+                    let (next, _, _) = corners[(idx + 1) % corners.len()];
+                    map = map.road(corner, next, loop_lanes).road(
+                        corner,
+                        &format!("{}_border", corner),
+                        "ds/ds",
+                    );
+                }
+                Some(map)
+            }
+            _ => None,
+        }
+    }
+
+    // The intersection that the one with this name turned into, for maps built from this
+    pub fn find_i(&self, map: &Map, name: &str) -> Option<IntersectionID> {
+        let idx = self.intersections.iter().position(|i| i.name == name)?;
+        map.find_i_by_osm_id(orig_intersection(idx).osm_node_id)
+            .ok()
+    }
+
+    pub fn to_raw(&self) -> Result<RawMap, String> {
+        let mut raw = RawMap::blank("synthetic", &self.name);
+
+        // Shift everything to start at (0, 0)
+        let mut bounds = Bounds::new();
+        for i in &self.intersections {
+            bounds.update(Pt2D::new(i.x, i.y));
+        }
+        for b in &self.buildings {
+            let half = BUILDING_LENGTH.inner_meters() / 2.0;
+            bounds.update(Pt2D::new(b.x - half, b.y - half));
+            bounds.update(Pt2D::new(b.x + half, b.y + half));
+        }
+        if self.intersections.is_empty() {
+            return Err(format!("{} has no intersections", self.name));
+        }
+        let shift = |x: f64, y: f64| Pt2D::new(x - bounds.min_x, y - bounds.min_y);
+
+        let mut ids: BTreeMap<&str, OriginalIntersection> = BTreeMap::new();
+        for (idx, i) in self.intersections.iter().enumerate() {
+            let id = orig_intersection(idx);
+            if ids.insert(&i.name, id).is_some() {
+                return Err(format!("Intersection {} defined twice", i.name));
+            }
+            raw.intersections.insert(
+                id,
+                RawIntersection {
+                    point: shift(i.x, i.y),
+                    intersection_type: i.intersection_type,
+                    elevation: Distance::ZERO,
+                },
+            );
+        }
+
+        let mut next_way_id = -1;
+        for r in &self.roads {
+            let lookup = |name: &str| {
+                ids.get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("Road refers to unknown intersection {}", name))
+            };
+            let (i1, i2) = (lookup(&r.from)?, lookup(&r.to)?);
+            if i1 == i2 {
+                return Err(format!("Road from {} to itself", r.from));
+            }
+            if raw
+                .roads
+                .keys()
+                .any(|id| (id.i1 == i1 && id.i2 == i2) || (id.i1 == i2 && id.i2 == i1))
+            {
+                return Err(format!("Two roads between {} and {}", r.from, r.to));
+            }
+            let spec = RoadSpec::parse(r.lanes.clone())
+                .ok_or_else(|| format!("Bad lanes {} from {} to {}", r.lanes, r.from, r.to))?;
+
+            let id = OriginalRoad {
+                osm_way_id: next_way_id,
+                i1,
+                i2,
+            };
+            next_way_id -= 1;
+            let mut osm_tags = BTreeMap::new();
+            osm_tags.insert(osm::SYNTHETIC.to_string(), "true".to_string());
+            osm_tags.insert(osm::SYNTHETIC_LANES.to_string(), spec.to_string());
+            osm_tags.insert(osm::ENDPT_FWD.to_string(), "true".to_string());
+            osm_tags.insert(osm::ENDPT_BACK.to_string(), "true".to_string());
+            osm_tags.insert(osm::OSM_WAY_ID.to_string(), id.osm_way_id.to_string());
+            osm_tags.insert(
+                osm::NAME.to_string(),
+                r.name
+                    .clone()
+                    .unwrap_or_else(|| format!("{} to {}", r.from, r.to)),
+            );
+            osm_tags.insert(osm::MAXSPEED.to_string(), "25 mph".to_string());
+            raw.roads.insert(
+                id,
+                RawRoad {
+                    center_points: vec![raw.intersections[&i1].point, raw.intersections[&i2].point],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                    percent_grade: 0.0,
+                },
+            );
+        }
+
+        for (name, id) in &ids {
+            let num_roads = raw.roads_per_intersection(*id).len();
+            if num_roads == 0 {
+                return Err(format!("Intersection {} has no roads", name));
+            }
+            if raw.intersections[id].intersection_type == IntersectionType::Border && num_roads != 1
+            {
+                return Err(format!("Border {} has {} roads, not 1", name, num_roads));
+            }
+        }
+
+        for b in &self.buildings {
+            raw.buildings.insert(
+                OriginalBuilding {
+                    osm_way_id: next_way_id,
+                },
+                RawBuilding {
+                    polygon: Polygon::rectangle_centered(
+                        shift(b.x, b.y),
+                        BUILDING_LENGTH,
+                        BUILDING_LENGTH,
+                    ),
+                    osm_tags: BTreeMap::new(),
+                    public_garage_name: None,
+                    num_parking_spots: 0,
+                    amenities: BTreeSet::new(),
+                },
+            );
+            next_way_id -= 1;
+        }
+
+        // Same as map_editor does when exporting
+        let max = shift(bounds.max_x, bounds.max_y);
+        raw.boundary_polygon = Bounds::from(&vec![Pt2D::new(0.0, 0.0), max]).get_rectangle();
+        raw.gps_bounds = GPSBounds::new();
+        raw.gps_bounds
+            .update(Pt2D::new(0.0, 0.0).forcibly_to_gps(&GPSBounds::seattle_bounds()));
+        raw.gps_bounds
+            .update(max.forcibly_to_gps(&GPSBounds::seattle_bounds()));
+
+        Ok(raw)
+    }
+
+    pub fn build(&self, timer: &mut Timer) -> Result<Map, String> {
+        Ok(Map::create_from_raw(self.to_raw()?, true, timer))
+    }
+
+    // Files in the synthetic maps directory are either exported RawMaps or these descriptions.
+    // Canned maps are always built from SyntheticMap::canned, so their names are reserved and no
+    // file needs to exist for them.
+    pub fn load_raw(path: String, timer: &mut Timer) -> Result<RawMap, String> {
+        if let Some(canned) = SyntheticMap::canned(&abstutil::basename(&path)) {
+            return canned.to_raw();
+        }
+        match abstutil::maybe_read_json::<RawMap>(path.clone(), timer) {
+            Ok(raw) => Ok(raw),
+            Err(raw_err) => match abstutil::maybe_read_json::<SyntheticMap>(path.clone(), timer) {
+                Ok(synthetic) => synthetic.to_raw(),
+                Err(err) => Err(format!(
+                    "{} isn't a RawMap ({}) or SyntheticMap ({})",
+                    path, raw_err, err
+                )),
+            },
+        }
+    }
+}
+
+fn orig_intersection(idx: usize) -> OriginalIntersection {
+    OriginalIntersection {
+        osm_node_id: -(idx as i64) - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canned_maps() {
+        let mut timer = Timer::throwaway();
+        for name in CANNED_SYNTHETIC_MAPS.iter() {
+            let synthetic = SyntheticMap::canned(name).unwrap();
+            // The JSON format should describe the same thing as the builder
+            let path = std::env::temp_dir()
+                .join(format!("synthetic_{}.json", name))
+                .to_string_lossy()
+                .to_string();
+            abstutil::write_json(path.clone(), &synthetic);
+            assert_eq!(
+                abstutil::to_json(&SyntheticMap::load_raw(path, &mut timer).unwrap()),
+                abstutil::to_json(&synthetic.to_raw().unwrap())
+            );
+            // Canned maps load by name, whether or not a file exists
+            assert_eq!(
+                abstutil::to_json(
+                    &SyntheticMap::load_raw(abstutil::path_synthetic_map(name), &mut timer)
+                        .unwrap()
+                ),
+                abstutil::to_json(&synthetic.to_raw().unwrap())
+            );
+
+            let map = synthetic.build(&mut timer).unwrap();
+            assert_eq!(map.get_name(), *name);
+            assert!(map.all_intersections().iter().any(|i| i.is_border()));
+            assert_eq!(map.all_buildings().len(), synthetic.buildings.len());
+        }
+    }
+
+    #[test]
+    fn test_bad_descriptions() {
+        let map = SyntheticMap::new("bad")
+            .intersection("a", 0.0, 0.0, IntersectionType::Border)
+            .intersection("b", 100.0, 0.0, IntersectionType::Border);
+        assert!(map.clone().to_raw().is_err());
+        assert!(map.clone().road("a", "c", "ds/ds").to_raw().is_err());
+        assert!(map.clone().road("a", "b", "xyz").to_raw().is_err());
+        assert!(map.road("a", "b", "ds/ds").to_raw().is_ok());
+    }
+}
@@ -25,6 +25,35 @@ pub struct Phase {
     pub protected_groups: BTreeSet<TurnGroupID>,
     pub yield_groups: BTreeSet<TurnGroupID>,
     pub duration: Duration,
+    // Signals saved before this existed always showed walk.
+    #[serde(default)]
+    pub crosswalk_timing: CrosswalkTiming,
+}
+
+// When do the protected crosswalks in a phase show walk?
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum CrosswalkTiming {
+    // For the whole phase
+    Fixed,
+    // Only if somebody pressed the beg button before the phase started
+    Actuated,
+}
+
+// A signal plan in the format of seattle_traffic_signals, plus what that format can't express.
+// Plans from there read in fine, with every crosswalk Fixed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExportedTrafficSignal {
+    #[serde(flatten)]
+    pub signal: seattle_traffic_signals::TrafficSignal,
+    // One per phase
+    #[serde(default)]
+    pub crosswalk_timing: Vec<CrosswalkTiming>,
+}
+
+impl std::default::Default for CrosswalkTiming {
+    fn default() -> CrosswalkTiming {
+        CrosswalkTiming::Fixed
+    }
 }
 
 impl ControlTrafficSignal {
@@ -48,6 +77,25 @@ impl ControlTrafficSignal {
         brute_force(map, id)
     }
 
+    // The index of every phase where this turn is protected or yields. Empty if the turn isn't
+    // controlled by this signal.
+    pub fn phases_with_turn(&self, t: TurnID) -> Vec<(usize, TurnPriority)> {
+        let g = match self.turn_groups.values().find(|g| g.members.contains(&t)) {
+            Some(g) => g.id,
+            None => {
+                return Vec::new();
+            }
+        };
+        self.phases
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, phase)| match phase.get_priority_of_group(g) {
+                TurnPriority::Banned => None,
+                pri => Some((idx, pri)),
+            })
+            .collect()
+    }
+
     pub fn cycle_length(&self) -> Duration {
         let mut cycle_length = Duration::ZERO;
         for p in &self.phases {
@@ -107,7 +155,12 @@ impl ControlTrafficSignal {
 
             // Do any of the crosswalks yield?
             for g in phase.yield_groups.iter().map(|g| &self.turn_groups[g]) {
-                assert!(g.turn_type != TurnType::Crosswalk);
+                if g.turn_type == TurnType::Crosswalk {
+                    return Err(format!(
+                        "Traffic signal has a yielding crosswalk: {:?}",
+                        g.id
+                    ));
+                }
             }
         }
 
@@ -166,6 +219,7 @@ impl Phase {
             protected_groups: BTreeSet::new(),
             yield_groups: BTreeSet::new(),
             duration: Duration::seconds(30.0),
+            crosswalk_timing: CrosswalkTiming::Fixed,
         }
     }
 
@@ -183,6 +237,11 @@ impl Phase {
         true
     }
 
+    // Crosswalks are only ever protected
+    pub fn crosswalk_groups(&self) -> impl Iterator<Item = &TurnGroupID> {
+        self.protected_groups.iter().filter(|g| g.crosswalk)
+    }
+
     pub fn get_priority_of_turn(&self, t: TurnID, parent: &ControlTrafficSignal) -> TurnPriority {
         // TODO Cache this?
         let g = parent
@@ -227,8 +286,8 @@ impl Phase {
 }
 
 impl ControlTrafficSignal {
-    pub fn export(&self, map: &Map) -> seattle_traffic_signals::TrafficSignal {
-        seattle_traffic_signals::TrafficSignal {
+    pub fn export(&self, map: &Map) -> ExportedTrafficSignal {
+        let signal = seattle_traffic_signals::TrafficSignal {
             intersection_osm_node_id: map.get_i(self.id).orig_id.osm_node_id,
             phases: self
                 .phases
@@ -247,16 +306,30 @@ impl ControlTrafficSignal {
                     duration_seconds: p.duration.inner_seconds() as usize,
                 })
                 .collect(),
+        };
+        ExportedTrafficSignal {
+            signal,
+            crosswalk_timing: self.phases.iter().map(|p| p.crosswalk_timing).collect(),
         }
     }
 
+    // Read a plan written by export. None if it doesn't match this intersection.
+    pub fn import_from_file(
+        path: String,
+        id: IntersectionID,
+        map: &Map,
+    ) -> Option<ControlTrafficSignal> {
+        let raw = abstutil::maybe_read_json(path, &mut Timer::throwaway()).ok()?;
+        ControlTrafficSignal::import(raw, id, map)
+    }
+
     pub fn import(
-        raw: seattle_traffic_signals::TrafficSignal,
+        raw: ExportedTrafficSignal,
         id: IntersectionID,
         map: &Map,
     ) -> Option<ControlTrafficSignal> {
         let mut phases = Vec::new();
-        for p in raw.phases {
+        for (idx, p) in raw.signal.phases.into_iter().enumerate() {
             let num_protected = p.protected_turns.len();
             let num_permitted = p.permitted_turns.len();
             let protected_groups = p
@@ -274,6 +347,11 @@ impl ControlTrafficSignal {
                     protected_groups,
                     yield_groups,
                     duration: Duration::seconds(p.duration_seconds as f64),
+                    crosswalk_timing: raw
+                        .crosswalk_timing
+                        .get(idx)
+                        .copied()
+                        .unwrap_or(CrosswalkTiming::Fixed),
                 });
             } else {
                 return None;
@@ -49,6 +49,44 @@ impl TurnType {
     }
 }
 
+// Coarser than TurnType, and distinguishing U-turns. This is what somebody looking at an
+// intersection would call each movement.
+#[derive(Clone, Copy, Debug, Eq, PartialOrd, Ord, PartialEq, Serialize, Deserialize)]
+pub enum MovementType {
+    Straight,
+    Left,
+    Right,
+    UTurn,
+    Crosswalk,
+}
+
+impl MovementType {
+    // The direction of travel entering and leaving the intersection
+    pub fn from_angles(from: Angle, to: Angle) -> MovementType {
+        // Same convention as TurnType::from_angles
+        let diff = from.shortest_rotation_towards(to).normalized_degrees();
+        if diff < 10.0 || diff > 350.0 {
+            MovementType::Straight
+        } else if diff > 150.0 && diff < 210.0 {
+            MovementType::UTurn
+        } else if diff > 180.0 {
+            MovementType::Right
+        } else {
+            MovementType::Left
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            MovementType::Straight => "straight",
+            MovementType::Left => "left",
+            MovementType::Right => "right",
+            MovementType::UTurn => "U-turn",
+            MovementType::Crosswalk => "crosswalk",
+        }
+    }
+}
+
 // TODO This concept may be dated, now that TurnGroups exist. Within a group, the lane-changing
 // turns should be treated as less important.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, PartialOrd)]
@@ -78,6 +116,18 @@ pub struct Turn {
 }
 
 impl Turn {
+    // None for the short connections between sidewalks at a corner
+    pub fn movement_type(&self, map: &Map) -> Option<MovementType> {
+        match self.turn_type {
+            TurnType::Crosswalk => Some(MovementType::Crosswalk),
+            TurnType::SharedSidewalkCorner => None,
+            _ => Some(MovementType::from_angles(
+                map.get_l(self.id.src).last_line().angle(),
+                map.get_l(self.id.dst).first_line().angle(),
+            )),
+        }
+    }
+
     pub fn conflicts_with(&self, other: &Turn) -> bool {
         if self.turn_type == TurnType::SharedSidewalkCorner
             || other.turn_type == TurnType::SharedSidewalkCorner
@@ -300,3 +350,88 @@ fn turn_group_geom(
     }
     PolyLine::new(pts)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every approach to a four-way intersection, with a slightly skewed cross street
+    #[test]
+    fn test_four_way() {
+        for heading in vec![0.0, 90.0, 180.0, 270.0, 33.0] {
+            let from = Angle::new_degs(heading);
+            let movement =
+                |rotate: f64| MovementType::from_angles(from, Angle::new_degs(heading + rotate));
+            assert_eq!(movement(0.0), MovementType::Straight);
+            assert_eq!(movement(5.0), MovementType::Straight);
+            assert_eq!(movement(-5.0), MovementType::Straight);
+            // Screen coordinates, so rotating clockwise is a right turn
+            assert_eq!(movement(90.0), MovementType::Right);
+            assert_eq!(movement(80.0), MovementType::Right);
+            assert_eq!(movement(-90.0), MovementType::Left);
+            assert_eq!(movement(-100.0), MovementType::Left);
+            assert_eq!(movement(180.0), MovementType::UTurn);
+            assert_eq!(movement(170.0), MovementType::UTurn);
+            assert_eq!(movement(-170.0), MovementType::UTurn);
+        }
+    }
+
+    // Besides U-turns, this should agree with how turns are classified when the map is built
+    #[test]
+    fn test_matches_turn_type() {
+        for rotate in (0..360).step_by(5) {
+            let from = Angle::new_degs(45.0);
+            let to = Angle::new_degs(45.0 + (rotate as f64));
+            let expected = match TurnType::from_angles(from, to) {
+                TurnType::Straight => MovementType::Straight,
+                TurnType::Left => MovementType::Left,
+                TurnType::Right => MovementType::Right,
+                _ => unreachable!(),
+            };
+            let actual = MovementType::from_angles(from, to);
+            if actual != MovementType::UTurn {
+                assert_eq!(actual, expected, "rotating by {}", rotate);
+            }
+        }
+    }
+
+    // On a real map, every approach to a plain four-way can go straight, left, and right
+    #[test]
+    fn test_four_way_signal_movements() {
+        let mut timer = abstutil::Timer::throwaway();
+        let map = crate::SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.is_traffic_signal())
+            .unwrap();
+        let mut per_lane: BTreeMap<LaneID, Vec<MovementType>> = BTreeMap::new();
+        for t in map.get_turns_in_intersection(i.id) {
+            if t.between_sidewalks() {
+                continue;
+            }
+            per_lane
+                .entry(t.id.src)
+                .or_insert_with(Vec::new)
+                .push(t.movement_type(&map).unwrap());
+        }
+        assert_eq!(per_lane.len(), 4);
+        for (l, mut movements) in per_lane {
+            movements.retain(|m| *m != MovementType::UTurn);
+            movements.sort();
+            assert_eq!(
+                movements,
+                vec![
+                    MovementType::Straight,
+                    MovementType::Left,
+                    MovementType::Right
+                ],
+                "from {}",
+                l
+            );
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType};
+use crate::{AlertLocation, CarID, Event, ParkingSpot, TimeSpent, TripID, TripMode, TripPhaseType};
 use abstutil::Counter;
 use geom::{Distance, Duration, Histogram, Time};
 use map_model::{
@@ -6,7 +6,33 @@ use map_model::{
     Traversable, TurnGroupID,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+pub struct TripComparison {
+    pub num_faster: usize,
+    pub sum_faster: Duration,
+    pub num_slower: usize,
+    pub sum_slower: Duration,
+    pub num_same: usize,
+}
+
+impl TripComparison {
+    pub fn avg_faster(&self) -> Duration {
+        if self.num_faster == 0 {
+            Duration::ZERO
+        } else {
+            self.sum_faster / (self.num_faster as f64)
+        }
+    }
+
+    pub fn avg_slower(&self) -> Duration {
+        if self.num_slower == 0 {
+            Duration::ZERO
+        } else {
+            self.sum_slower / (self.num_slower as f64)
+        }
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
@@ -230,6 +256,102 @@ impl Analytics {
         results
     }
 
+    // Sums up how trips finished by now spent their time, per mode. Aborted trips are skipped.
+    pub fn time_spent_in_finished_trips(
+        &self,
+        now: Time,
+    ) -> BTreeMap<TripMode, BTreeMap<TimeSpent, Duration>> {
+        let mut modes = BTreeMap::new();
+        for (t, id, maybe_mode, _) in &self.finished_trips {
+            if *t > now {
+                break;
+            }
+            if let Some(mode) = maybe_mode {
+                modes.insert(*id, *mode);
+            }
+        }
+
+        let mut results: BTreeMap<TripMode, BTreeMap<TimeSpent, Duration>> = BTreeMap::new();
+        let mut last_phase: BTreeMap<TripID, (Time, TripPhaseType)> = BTreeMap::new();
+        for (t, id, _, phase_type) in &self.trip_log {
+            if *t > now {
+                break;
+            }
+            let mode = if let Some(m) = modes.get(id) {
+                *m
+            } else {
+                continue;
+            };
+            if let Some((t1, prev)) = last_phase.insert(*id, (*t, *phase_type)) {
+                if let Some(spent) = prev.time_spent() {
+                    *results
+                        .entry(mode)
+                        .or_insert_with(BTreeMap::new)
+                        .entry(spent)
+                        .or_insert(Duration::ZERO) += *t - t1;
+                }
+            }
+        }
+        results
+    }
+
+    // Sums up time_spent_in_finished_trips over some modes
+    pub fn total_time_spent(
+        &self,
+        now: Time,
+        modes: &BTreeSet<TripMode>,
+    ) -> BTreeMap<TimeSpent, Duration> {
+        let mut total = BTreeMap::new();
+        for (mode, per_category) in self.time_spent_in_finished_trips(now) {
+            if !modes.contains(&mode) {
+                continue;
+            }
+            for (category, dt) in per_category {
+                *total.entry(category).or_insert(Duration::ZERO) += dt;
+            }
+        }
+        total
+    }
+
+    // Counts trips of some modes that got faster or slower since the baseline. If changes_pct is
+    // set, trips that changed by at most that fraction count as unchanged.
+    pub fn compare_trips(
+        &self,
+        now: Time,
+        before: &Analytics,
+        modes: &BTreeSet<TripMode>,
+        changes_pct: Option<f64>,
+    ) -> TripComparison {
+        let mut cmp = TripComparison {
+            num_faster: 0,
+            sum_faster: Duration::ZERO,
+            num_slower: 0,
+            sum_slower: Duration::ZERO,
+            num_same: 0,
+        };
+        for (b, a, mode) in self.both_finished_trips(now, before) {
+            if !modes.contains(&mode) {
+                continue;
+            }
+            let same = if let Some(pct) = changes_pct {
+                pct_diff(a, b) <= pct
+            } else {
+                a == b
+            };
+
+            if same {
+                cmp.num_same += 1;
+            } else if a < b {
+                cmp.num_faster += 1;
+                cmp.sum_faster += b - a;
+            } else {
+                cmp.num_slower += 1;
+                cmp.sum_slower += a - b;
+            }
+        }
+        cmp
+    }
+
     // Find intersections where the cumulative sum of delay has changed. Negative means faster.
     pub fn compare_delay(&self, now: Time, before: &Analytics) -> Vec<(IntersectionID, Duration)> {
         let mut results = Vec::new();
@@ -679,3 +801,12 @@ impl Window {
         self.times.len()
     }
 }
+
+// The relative difference between two durations, always positive
+pub fn pct_diff(a: Duration, b: Duration) -> f64 {
+    if a >= b {
+        (a / b) - 1.0
+    } else {
+        (b / a) - 1.0
+    }
+}
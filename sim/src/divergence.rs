@@ -0,0 +1,193 @@
+use crate::{TripID, TripPositions};
+use geom::{Distance, Time, EPSILON_DIST};
+use std::io::Write;
+
+// Where the same trips are in two runs of a simulation at the same time. Trips are matched up by
+// ID, so both runs should come from the same scenario.
+#[derive(Clone)]
+pub struct Divergence {
+    pub time: Time,
+    // Trips active in both runs that aren't in the same place, furthest apart first
+    pub displaced: Vec<(TripID, Distance)>,
+    pub num_same: usize,
+    // Trips active in the first run, and why they aren't in the second
+    pub only_in_a: Vec<(TripID, Inactive)>,
+    // And the other way around
+    pub only_in_b: Vec<(TripID, Inactive)>,
+}
+
+// Why a trip doesn't have a position in one run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Inactive {
+    Finished(Time),
+    Aborted,
+    NotStarted,
+    // Between modes, or something else
+    Other,
+}
+
+impl Divergence {
+    pub fn new<F: Fn(TripID) -> Inactive, G: Fn(TripID) -> Inactive>(
+        a: &TripPositions,
+        b: &TripPositions,
+        inactive_in_a: F,
+        inactive_in_b: G,
+    ) -> Divergence {
+        assert_eq!(a.time, b.time);
+        let mut displaced = Vec::new();
+        let mut num_same = 0;
+        let mut only_in_a = Vec::new();
+        for (trip, pt1) in &a.canonical_pt_per_trip {
+            if let Some(pt2) = b.canonical_pt_per_trip.get(trip) {
+                let dist = pt1.dist_to(*pt2);
+                if dist > EPSILON_DIST {
+                    displaced.push((*trip, dist));
+                } else {
+                    num_same += 1;
+                }
+            } else {
+                only_in_a.push((*trip, inactive_in_b(*trip)));
+            }
+        }
+        let only_in_b = b
+            .canonical_pt_per_trip
+            .keys()
+            .filter(|t| !a.canonical_pt_per_trip.contains_key(t))
+            .map(|t| (*t, inactive_in_a(*t)))
+            .collect();
+        // Break ties by ID, so the ranking is stable
+        displaced.sort_by_key(|(t, dist)| (std::cmp::Reverse(*dist), *t));
+
+        Divergence {
+            time: a.time,
+            displaced,
+            num_same,
+            only_in_a,
+            only_in_b,
+        }
+    }
+
+    pub fn max_displacement(&self) -> Distance {
+        self.displaced
+            .get(0)
+            .map(|(_, dist)| *dist)
+            .unwrap_or(Distance::ZERO)
+    }
+
+    // Over the displaced trips only
+    pub fn mean_displacement(&self) -> Distance {
+        if self.displaced.is_empty() {
+            return Distance::ZERO;
+        }
+        let total = self
+            .displaced
+            .iter()
+            .fold(Distance::ZERO, |sum, (_, dist)| sum + *dist);
+        total / (self.displaced.len() as f64)
+    }
+
+    pub fn worst(&self, n: usize) -> Vec<TripID> {
+        self.displaced.iter().take(n).map(|(t, _)| *t).collect()
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        vec![
+            format!(
+                "At {}, {} trips diverged, {} match",
+                self.time,
+                abstutil::prettyprint_usize(self.displaced.len()),
+                abstutil::prettyprint_usize(self.num_same)
+            ),
+            format!(
+                "Displacement: mean {}, max {}",
+                self.mean_displacement(),
+                self.max_displacement()
+            ),
+            describe_only_in(&self.only_in_a, "first", "second"),
+            describe_only_in(&self.only_in_b, "second", "first"),
+        ]
+    }
+
+    // Adds the summary to the end of a report file, creating it if needed
+    pub fn append_to(&self, path: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        for line in self.describe() {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f)
+    }
+}
+
+fn describe_only_in(trips: &[(TripID, Inactive)], active: &str, inactive: &str) -> String {
+    let mut finished = 0;
+    let mut aborted = 0;
+    let mut not_started = 0;
+    let mut other = 0;
+    for (_, why) in trips {
+        match why {
+            Inactive::Finished(_) => {
+                finished += 1;
+            }
+            Inactive::Aborted => {
+                aborted += 1;
+            }
+            Inactive::NotStarted => {
+                not_started += 1;
+            }
+            Inactive::Other => {
+                other += 1;
+            }
+        }
+    }
+    format!(
+        "{} trips only active in the {} run. In the {}, {} finished, {} aborted, {} haven't \
+         started, {} other",
+        abstutil::prettyprint_usize(trips.len()),
+        active,
+        inactive,
+        abstutil::prettyprint_usize(finished),
+        abstutil::prettyprint_usize(aborted),
+        abstutil::prettyprint_usize(not_started),
+        abstutil::prettyprint_usize(other)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Pt2D;
+
+    #[test]
+    fn test_ranking() {
+        let mut a = TripPositions::new(Time::START_OF_DAY);
+        let mut b = TripPositions::new(Time::START_OF_DAY);
+        a.canonical_pt_per_trip
+            .insert(TripID(0), Pt2D::new(0.0, 0.0));
+        b.canonical_pt_per_trip
+            .insert(TripID(0), Pt2D::new(0.0, 0.0));
+        a.canonical_pt_per_trip
+            .insert(TripID(1), Pt2D::new(0.0, 0.0));
+        b.canonical_pt_per_trip
+            .insert(TripID(1), Pt2D::new(3.0, 4.0));
+        a.canonical_pt_per_trip
+            .insert(TripID(2), Pt2D::new(0.0, 0.0));
+        b.canonical_pt_per_trip
+            .insert(TripID(2), Pt2D::new(0.0, 15.0));
+        a.canonical_pt_per_trip
+            .insert(TripID(3), Pt2D::new(0.0, 0.0));
+        b.canonical_pt_per_trip
+            .insert(TripID(4), Pt2D::new(0.0, 0.0));
+
+        let d = Divergence::new(&a, &b, |_| Inactive::NotStarted, |_| Inactive::Aborted);
+        assert_eq!(d.worst(10), vec![TripID(2), TripID(1)]);
+        assert_eq!(d.num_same, 1);
+        assert_eq!(d.max_displacement(), Distance::meters(15.0));
+        assert_eq!(d.mean_displacement(), Distance::meters(10.0));
+        assert_eq!(d.only_in_a, vec![(TripID(3), Inactive::Aborted)]);
+        assert_eq!(d.only_in_b, vec![(TripID(4), Inactive::NotStarted)]);
+    }
+}
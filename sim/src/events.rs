@@ -1,5 +1,6 @@
 use crate::{
-    AgentID, CarID, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID, TripMode,
+    AgentID, CarID, Intervention, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID,
+    TripMode,
 };
 use geom::Duration;
 use map_model::{
@@ -39,8 +40,13 @@ pub enum Event {
         blocked_time: Duration,
     },
     TripAborted(TripID),
+    TripStarted(TripID, PersonID),
     TripPhaseStarting(TripID, PersonID, Option<PathRequest>, TripPhaseType),
 
+    // When an agent starts or stops doing some leg of a trip
+    AgentSpawned(AgentID),
+    AgentDespawned(AgentID),
+
     // Just use for parking replanning. Not happy about copying the full path in here, but the way
     // to plumb info into Analytics is Event.
     PathAmended(Path),
@@ -48,6 +54,27 @@ pub enum Event {
     Alert(AlertLocation, String),
 }
 
+// The subset of events that the UI cares about. Instead of polling the sim to notice these, call
+// Sim::collect_events once per frame.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum SimEvent {
+    TripStarted(TripID, PersonID),
+    TripFinished {
+        trip: TripID,
+        person: PersonID,
+        mode: TripMode,
+        total_time: Duration,
+    },
+    TripAborted(TripID, PersonID),
+    // Buses don't count; they're not doing a trip.
+    AgentSpawned(AgentID),
+    AgentDespawned(AgentID),
+    BusArrivedAtStop(CarID, BusRouteID, BusStopID),
+    // Agents near these intersections are stuck waiting on each other
+    Gridlock(Vec<IntersectionID>),
+    InterventionFired(Intervention),
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum AlertLocation {
     Nil,
@@ -86,4 +113,56 @@ impl TripPhaseType {
             TripPhaseType::Remote => "remote trip outside the map boundaries".to_string(),
         }
     }
+
+    // None for phases that aren't spent moving
+    pub fn mode(self) -> Option<TripMode> {
+        match self {
+            TripPhaseType::Driving | TripPhaseType::Parking => Some(TripMode::Drive),
+            TripPhaseType::Walking => Some(TripMode::Walk),
+            TripPhaseType::Biking => Some(TripMode::Bike),
+            TripPhaseType::RidingBus(_, _, _) => Some(TripMode::Transit),
+            TripPhaseType::WaitingForBus(_, _)
+            | TripPhaseType::DelayedStart
+            | TripPhaseType::Remote
+            | TripPhaseType::Aborted
+            | TripPhaseType::Finished => None,
+        }
+    }
+
+    pub fn time_spent(self) -> Option<TimeSpent> {
+        match self {
+            TripPhaseType::Driving
+            | TripPhaseType::Parking
+            | TripPhaseType::Biking
+            | TripPhaseType::RidingBus(_, _, _) => Some(TimeSpent::InVehicle),
+            TripPhaseType::Walking => Some(TimeSpent::Walking),
+            TripPhaseType::WaitingForBus(_, _) | TripPhaseType::DelayedStart => {
+                Some(TimeSpent::Waiting)
+            }
+            // Off-map trips don't say how they spend their time
+            TripPhaseType::Remote | TripPhaseType::Aborted | TripPhaseType::Finished => None,
+        }
+    }
+}
+
+// How a trip's duration breaks down. Biking counts as being in a vehicle.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum TimeSpent {
+    InVehicle,
+    Walking,
+    Waiting,
+}
+
+impl TimeSpent {
+    pub fn all() -> Vec<TimeSpent> {
+        vec![TimeSpent::InVehicle, TimeSpent::Walking, TimeSpent::Waiting]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            TimeSpent::InVehicle => "in vehicles",
+            TimeSpent::Walking => "walking",
+            TimeSpent::Waiting => "waiting",
+        }
+    }
 }
@@ -0,0 +1,50 @@
+use crate::{TripEndpoint, TripMode};
+use geom::Speed;
+use map_model::{IntersectionID, LaneID, RoadID};
+use serde::{Deserialize, Serialize};
+
+// Something to change partway through a simulation, for experiments like "close this road at
+// 8am". Most of these are applied as map edits.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Intervention {
+    // Only driving, bike, and bus lanes
+    CloseLane(LaneID),
+    // Only lanes closed by an earlier intervention
+    ReopenLane(LaneID),
+    ChangeSpeedLimit(RoadID, Speed),
+    // The path to a signal plan exported from the traffic signal editor
+    SwitchSignalPlan(IntersectionID, String),
+    InjectTrips {
+        from: TripEndpoint,
+        to: TripEndpoint,
+        mode: TripMode,
+        num: usize,
+    },
+}
+
+impl Intervention {
+    pub fn describe(&self) -> String {
+        match self {
+            Intervention::CloseLane(l) => format!("close {}", l),
+            Intervention::ReopenLane(l) => format!("reopen {}", l),
+            Intervention::ChangeSpeedLimit(r, speed) => {
+                format!("change the speed limit of {} to {}", r, speed)
+            }
+            Intervention::SwitchSignalPlan(i, path) => {
+                format!("switch the signal at {} to {}", i, path)
+            }
+            Intervention::InjectTrips {
+                from,
+                to,
+                mode,
+                num,
+            } => format!(
+                "start {} {} trips from {:?} to {:?}",
+                num,
+                mode.ongoing_verb(),
+                from,
+                to
+            ),
+        }
+    }
+}
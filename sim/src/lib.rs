@@ -1,39 +1,53 @@
 mod analytics;
+mod divergence;
 mod events;
+mod interventions;
 mod make;
 mod mechanics;
 mod pandemic;
 mod render;
+mod report;
 mod router;
 mod scheduler;
 mod sim;
 mod transit;
 mod trips;
+mod watchpoints;
 
-pub use self::analytics::{Analytics, TripPhase};
+pub use self::analytics::{pct_diff, Analytics, TripComparison, TripPhase};
+pub use self::divergence::{Divergence, Inactive};
 pub(crate) use self::events::Event;
-pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::events::{AlertLocation, SimEvent, TimeSpent, TripPhaseType};
+pub use self::interventions::Intervention;
 pub use self::make::{
     BorderSpawnOverTime, IndividTrip, OffMapLocation, OriginDestination, PersonSpec, Scenario,
     ScenarioGenerator, SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
-    DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
+    DrivingSimState, IntersectionSimState, ParkingSimState, SignalUpdate, WalkingSimState,
 };
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::report::make_report;
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
-pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
+pub use self::sim::{
+    AgentProperties, AlertHandler, Gridlock, LaneStats, Sim, SimCallback, SimOptions,
+};
 pub(crate) use self::transit::TransitSimState;
-pub use self::trips::{Person, PersonState, TripResult};
+pub use self::trips::{
+    Person, PersonState, TripDetail, TripLegDetail, TripResult, TripState,
+};
 pub use self::trips::{TripEndpoint, TripMode};
 pub(crate) use self::trips::{TripLeg, TripManager};
+pub use self::watchpoints::{
+    longest_trip, upcoming_intersections, Comparison, Condition, Metric, Watchpoint, Watchpoints,
+};
 pub use crate::render::{
     CarStatus, DontDrawAgents, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, GetDrawAgents,
     PedCrowdLocation, UnzoomedAgent,
 };
 use abstutil::Cloneable;
-use geom::{Distance, Pt2D, Speed, Time};
+use geom::{Distance, Duration, Pt2D, Speed, Time};
 use map_model::{
     BuildingID, BusStopID, DirectedRoadID, IntersectionID, LaneID, Map, ParkingLotID, Path,
     PathConstraints, PathRequest, Position,
@@ -49,6 +63,8 @@ pub const MIN_CAR_LENGTH: Distance = Distance::const_meters(4.5);
 pub const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub const BUS_LENGTH: Distance = Distance::const_meters(12.5);
+// Buses always wait this long at each stop
+pub const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
 
 // At all speeds (including at rest), cars must be at least this far apart, measured from front of
 // one car to the back of the other.
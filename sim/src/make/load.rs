@@ -1,10 +1,12 @@
-use crate::{AlertHandler, Scenario, Sim, SimOptions};
+use crate::{AlertHandler, Intervention, Scenario, Sim, SimOptions};
 use abstutil::CmdArgs;
+use geom::{Duration, Time};
 use map_model::{Map, MapEdits};
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 
 const RNG_SEED: u8 = 42;
+const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::const_seconds(60.0);
 
 #[derive(Clone)]
 pub struct SimFlags {
@@ -15,6 +17,10 @@ pub struct SimFlags {
 
 impl SimFlags {
     pub fn from_args(args: &mut CmdArgs) -> SimFlags {
+        // Before anything else builds a path
+        if let Some(dir) = args.optional("--data_dir") {
+            abstutil::set_data_dir(dir);
+        }
         let rng_seed = args
             .optional_parse("--rng_seed", |s| s.parse())
             .unwrap_or(RNG_SEED);
@@ -22,7 +28,7 @@ impl SimFlags {
         SimFlags {
             load: args
                 .optional_free()
-                .unwrap_or_else(|| "../data/system/maps/montlake.bin".to_string()),
+                .unwrap_or_else(|| abstutil::path_map("montlake")),
             rng_seed,
             opts: SimOptions {
                 run_name: args
@@ -47,6 +53,37 @@ impl SimFlags {
                     })
                     .unwrap_or(AlertHandler::Print),
                 pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+                checkpoint_interval: if args.enabled("--verify_determinism") {
+                    Some(
+                        args.optional_parse("--checkpoint_interval", |s| {
+                            s.parse::<f64>().map(Duration::seconds)
+                        })
+                        .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+                    )
+                } else {
+                    None
+                },
+                gridlock_threshold: if args.enabled("--disable_gridlock_detection") {
+                    None
+                } else {
+                    Some(
+                        args.optional_parse("--gridlock_threshold", |s| {
+                            s.parse::<f64>().map(Duration::seconds)
+                        })
+                        .unwrap_or(SimOptions::DEFAULT_GRIDLOCK_THRESHOLD),
+                    )
+                },
+                interventions: args
+                    .optional("--interventions")
+                    .map(|path| {
+                        let schedule: Vec<(Time, Intervention)> =
+                            abstutil::read_json(path, &mut abstutil::Timer::throwaway());
+                        schedule
+                    })
+                    .unwrap_or_else(Vec::new),
+                num_threads: args
+                    .optional_parse("--num_threads", |s| s.parse())
+                    .unwrap_or_else(num_cpus::get_physical),
             },
         }
     }
@@ -74,10 +111,10 @@ impl SimFlags {
 
         let mut opts = self.opts.clone();
 
-        if self.load.starts_with("../data/player/saves/") {
+        if self.load.starts_with(&abstutil::path_all_savestates()) {
             timer.note(format!("Resuming from {}", self.load));
 
-            let mut sim: Sim = abstutil::read_binary(self.load.clone(), timer);
+            let mut sim: Sim = abstutil::read_versioned(self.load.clone(), timer);
 
             let mut map = Map::new(abstutil::path_map(&sim.map_name), timer);
             if sim.edits_name != "untitled edits" {
@@ -85,15 +122,16 @@ impl SimFlags {
                 map.recalculate_pathfinding_after_edits(timer);
             }
             sim.restore_paths(&map, timer);
+            sim.set_gridlock_threshold(opts.gridlock_threshold);
 
             (map, sim, rng)
-        } else if self.load.starts_with("../data/system/scenarios/") {
+        } else if self.load.starts_with(&abstutil::path("system/scenarios")) {
             timer.note(format!(
                 "Seeding the simulation from scenario {}",
                 self.load
             ));
 
-            let scenario: Scenario = abstutil::read_binary(self.load.clone(), timer);
+            let scenario: Scenario = abstutil::read_versioned(self.load.clone(), timer);
 
             let map = Map::new(abstutil::path_map(&scenario.map_name), timer);
 
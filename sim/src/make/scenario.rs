@@ -3,7 +3,7 @@ use crate::{
     TripEndpoint, TripMode, TripSpec, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
     MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
-use abstutil::{prettyprint_usize, Counter, Timer};
+use abstutil::{prettyprint_usize, Counter, Timer, Versioned};
 use geom::{Distance, Duration, LonLat, Speed, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, DirectedRoadID, Map, PathConstraints, Position, RoadID,
@@ -25,6 +25,12 @@ pub struct Scenario {
     pub only_seed_buses: Option<BTreeSet<String>>,
 }
 
+// Version 0 is before scenarios were versioned.
+impl Versioned for Scenario {
+    const NAME: &'static str = "scenario";
+    const VERSION: u32 = 1;
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PersonSpec {
     pub id: PersonID,
@@ -83,16 +89,28 @@ impl Scenario {
         if let Some(ref routes) = self.only_seed_buses {
             for route in map.get_all_bus_routes() {
                 if routes.contains(&route.name) {
-                    sim.seed_bus_route(route, map, timer);
+                    sim.seed_bus_route(route, map);
                 }
             }
         } else {
             // All of them
             for route in map.get_all_bus_routes() {
-                sim.seed_bus_route(route, map, timer);
+                sim.seed_bus_route(route, map);
             }
         }
 
+        self.instantiate_people(sim, map, rng, timer);
+        timer.stop(format!("Instantiating {}", self.scenario_name));
+    }
+
+    // Add everybody to a sim that might already be running.
+    pub(crate) fn instantiate_people(
+        &self,
+        sim: &mut Sim,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) {
         timer.start_iter("trips for People", self.people.len());
         let mut spawner = sim.make_spawner();
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
@@ -133,11 +151,10 @@ impl Scenario {
         seed_parked_cars(parked_cars, sim, map, rng, timer);
 
         sim.flush_spawner(spawner, map, timer);
-        timer.stop(format!("Instantiating {}", self.scenario_name));
     }
 
     pub fn save(&self) {
-        abstutil::write_binary(
+        abstutil::write_versioned(
             abstutil::path_scenario(&self.map_name, &self.scenario_name),
             self,
         );
@@ -2,19 +2,18 @@ use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
     ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState, PersonID, Scheduler,
-    TimeInterval, TransitSimState, TripManager, TripPositions, UnzoomedAgent, Vehicle,
-    WalkingSimState, FOLLOWING_DISTANCE,
+    DrawCarInput, Event, IntersectionSimState, LaneStats, ParkedCar, ParkingSimState, PersonID,
+    Scheduler, TimeInterval, TransitSimState, TripManager, TripPositions, UnzoomedAgent, Vehicle,
+    WalkingSimState, FOLLOWING_DISTANCE, TIME_TO_WAIT_AT_STOP,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{LaneID, Map, Path, PathStep, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
+use map_model::{LaneID, Map, Path, PathRequest, PathStep, Position, Traversable, TurnID};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
 const TIME_TO_UNPARK: Duration = Duration::const_seconds(10.0);
 const TIME_TO_PARK: Duration = Duration::const_seconds(15.0);
-const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
 
 // TODO Do something else.
 pub(crate) const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
@@ -33,6 +32,12 @@ pub struct DrivingSimState {
     )]
     queues: BTreeMap<Traversable, Queue>,
     events: Vec<Event>,
+    // Only for lanes with some vehicle on them. Kept up-to-date every time a car is updated.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    lane_stats: BTreeMap<LaneID, LaneStats>,
 
     recalc_lanechanging: bool,
 }
@@ -43,6 +48,7 @@ impl DrivingSimState {
             cars: BTreeMap::new(),
             queues: BTreeMap::new(),
             events: Vec::new(),
+            lane_stats: BTreeMap::new(),
             recalc_lanechanging,
         };
 
@@ -142,6 +148,7 @@ impl DrivingSimState {
                 queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
             }
             self.cars.insert(car.vehicle.id, car);
+            self.recalc_lane_stats(vec![first_lane]);
             return true;
         }
         false
@@ -158,6 +165,35 @@ impl DrivingSimState {
         scheduler: &mut Scheduler,
         transit: &mut TransitSimState,
         walking: &mut WalkingSimState,
+    ) {
+        // The car and any follower it affects must be on one of these before or after the update
+        let mut touched = self.lanes_touched_by(id);
+        self.inner_update_car(
+            id,
+            now,
+            map,
+            parking,
+            intersections,
+            trips,
+            scheduler,
+            transit,
+            walking,
+        );
+        touched.extend(self.lanes_touched_by(id));
+        self.recalc_lane_stats(touched);
+    }
+
+    fn inner_update_car(
+        &mut self,
+        id: CarID,
+        now: Time,
+        map: &Map,
+        parking: &mut ParkingSimState,
+        intersections: &mut IntersectionSimState,
+        trips: &mut TripManager,
+        scheduler: &mut Scheduler,
+        transit: &mut TransitSimState,
+        walking: &mut WalkingSimState,
     ) {
         // State transitions for this car:
         //
@@ -567,6 +603,7 @@ impl DrivingSimState {
         scheduler: &mut Scheduler,
         intersections: &mut IntersectionSimState,
     ) -> Vehicle {
+        let touched = self.lanes_touched_by(c);
         let dists = self.queues[&self.cars[&c].router.head()].get_car_positions(
             now,
             &self.cars,
@@ -587,6 +624,7 @@ impl DrivingSimState {
         self.delete_car(&mut car, dists, idx, now, map, scheduler, intersections);
         // delete_car cancels UpdateLaggyHead
         scheduler.cancel(Command::UpdateCar(c));
+        self.recalc_lane_stats(touched);
         car.vehicle
     }
 
@@ -670,6 +708,20 @@ impl DrivingSimState {
         map: &Map,
         intersections: &mut IntersectionSimState,
         scheduler: &mut Scheduler,
+    ) {
+        // Waking up a follower on the old lane changes its stats
+        let touched = self.lanes_touched_by(id);
+        self.inner_update_laggy_head(id, now, map, intersections, scheduler);
+        self.recalc_lane_stats(touched);
+    }
+
+    fn inner_update_laggy_head(
+        &mut self,
+        id: CarID,
+        now: Time,
+        map: &Map,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
     ) {
         let currently_on = self.cars[&id].router.head();
         let current_dists =
@@ -909,13 +961,22 @@ impl DrivingSimState {
         }
     }
 
+    pub fn car_json(&self, id: CarID) -> Option<String> {
+        self.cars.get(&id).map(|car| abstutil::to_json(car))
+    }
+
     pub fn debug_lane(&self, id: LaneID) {
         if let Some(ref queue) = self.queues.get(&Traversable::Lane(id)) {
             println!("{}", abstutil::to_json(queue));
         }
     }
 
-    pub fn agent_properties(&self, id: CarID, now: Time) -> AgentProperties {
+    pub fn agent_properties(
+        &self,
+        id: CarID,
+        now: Time,
+        intersections: &IntersectionSimState,
+    ) -> AgentProperties {
         let car = self.cars.get(&id).unwrap();
         let path = car.router.get_path();
 
@@ -926,6 +987,37 @@ impl DrivingSimState {
             _ => Duration::ZERO,
         };
 
+        let on = car.router.head();
+        let queue = &self.queues[&on];
+        let dist_along = queue
+            .get_car_positions(now, &self.cars, &self.queues)
+            .into_iter()
+            .find(|(c, _)| *c == id)
+            .map(|(_, dist)| dist)
+            .unwrap_or(Distance::ZERO);
+        let speed = match car.state {
+            CarState::Crossing(ref time_int, ref dist_int) if time_int.end > time_int.start => {
+                Speed::from_dist_time(dist_int.end - dist_int.start, time_int.end - time_int.start)
+            }
+            _ => Speed::ZERO,
+        };
+        // The car ahead, the one whose back is still on the lane, or whoever's using up the space
+        // the car needs to turn
+        let waiting_for = match car.state {
+            CarState::Queued { .. } | CarState::WaitingToAdvance { .. } => {
+                match queue.cars.iter().position(|c| *c == id) {
+                    Some(idx) if idx > 0 => Some(AgentID::Car(queue.cars[idx - 1])),
+                    _ => queue.laggy_head.map(AgentID::Car).or_else(|| {
+                        intersections
+                            .get_blocked_by(AgentID::Car(id))
+                            .into_iter()
+                            .min()
+                    }),
+                }
+            }
+            _ => None,
+        };
+
         AgentProperties {
             total_time: now - car.started_at,
             waiting_here: time_spent_waiting,
@@ -934,6 +1026,10 @@ impl DrivingSimState {
             total_dist: path.total_length(),
             lanes_crossed: path.lanes_crossed_so_far(),
             total_lanes: path.total_lanes(),
+            on,
+            dist_along,
+            speed,
+            waiting_for,
         }
     }
 
@@ -947,6 +1043,60 @@ impl DrivingSimState {
             .map(|car| car.router.get_path())
             .collect()
     }
+    // Cars currently on the lane, or planning to use it or a turn to or from it later
+    pub fn cars_routed_over(&self, l: LaneID) -> Vec<CarID> {
+        self.cars
+            .values()
+            .filter(|car| {
+                car.router
+                    .get_path()
+                    .get_steps()
+                    .iter()
+                    .any(|step| match step {
+                        PathStep::Lane(x) | PathStep::ContraflowLane(x) => *x == l,
+                        PathStep::Turn(t) => t.src == l || t.dst == l,
+                    })
+            })
+            .map(|car| car.vehicle.id)
+            .collect()
+    }
+
+    // When a lane closes partway through, cars that'd use it look for another way. Cars in the
+    // middle of a turn or waiting to make one are committed to the next lane. Returns the cars
+    // that can't get around the closure.
+    pub fn reroute_around(&mut self, closed: LaneID, map: &Map) -> Vec<CarID> {
+        let mut stuck = Vec::new();
+        for car in self.cars.values_mut() {
+            let path = car.router.get_path();
+            let steps = path.get_steps();
+            // Reroute starting from this lane
+            let idx = match (steps[0], &car.state) {
+                (PathStep::Turn(_), _) => 1,
+                (_, CarState::WaitingToAdvance { .. }) => 2,
+                _ => 0,
+            };
+            if !steps
+                .iter()
+                .skip(idx + 1)
+                .any(|step| *step == PathStep::Lane(closed))
+            {
+                continue;
+            }
+            let req = PathRequest {
+                start: Position::new(steps[idx].as_lane(), Distance::ZERO),
+                end: Position::new(path.last_step().as_lane(), path.get_end_dist()),
+                constraints: car.vehicle.vehicle_type.to_constraints(),
+            };
+            if let Some(new_path) = map.pathfind(req) {
+                car.router.reroute(idx, new_path, map);
+                self.events
+                    .push(Event::PathAmended(car.router.get_path().clone()));
+            } else {
+                stuck.push(car.vehicle.id);
+            }
+        }
+        stuck
+    }
 
     pub fn trace_route(
         &self,
@@ -1036,7 +1186,244 @@ impl DrivingSimState {
         }
     }
 
+    // Look for a cycle of cars, each waiting on the next one, where everybody has been stuck for
+    // at least the threshold. Returns the cars in the cycle, along with the turn each one is
+    // waiting to make, if it's at the front of its lane.
+    // With a pool, the queues are scanned in parallel. The answer is the same either way.
+    pub fn find_gridlock(
+        &self,
+        now: Time,
+        threshold: Duration,
+        intersections: &IntersectionSimState,
+        pool: Option<&mut scoped_threadpool::Pool>,
+    ) -> Option<Vec<(CarID, Option<TurnID>)>> {
+        // Who is each stuck car waiting on?
+        let queues: Vec<&Queue> = self.queues.values().collect();
+        let waiting_on: BTreeMap<CarID, BTreeSet<CarID>> = if let Some(pool) = pool {
+            let chunk_size = (queues.len() / pool.thread_count() as usize).max(1);
+            pool.scoped(|scope| {
+                let (tx, rx) = std::sync::mpsc::channel();
+                for chunk in queues.chunks(chunk_size) {
+                    let tx = tx.clone();
+                    scope.execute(move || {
+                        for queue in chunk {
+                            for pair in self.stuck_in_queue(queue, now, threshold, intersections) {
+                                tx.send(pair).unwrap();
+                            }
+                        }
+                    });
+                }
+                drop(tx);
+                rx.iter().collect()
+            })
+        } else {
+            queues
+                .into_iter()
+                .flat_map(|q| self.stuck_in_queue(q, now, threshold, intersections))
+                .collect()
+        };
+
+        // Only stuck cars can be part of the cycle. Reversed so popping visits in order.
+        let next_of = |c: CarID| -> Vec<CarID> {
+            waiting_on[&c]
+                .iter()
+                .rev()
+                .filter(|x| waiting_on.contains_key(x))
+                .cloned()
+                .collect()
+        };
+
+        // Depth-first search, in a deterministic order
+        let mut finished: BTreeSet<CarID> = BTreeSet::new();
+        for start in waiting_on.keys() {
+            if finished.contains(start) {
+                continue;
+            }
+            let mut stack: Vec<(CarID, Vec<CarID>)> = vec![(*start, next_of(*start))];
+            while !stack.is_empty() {
+                let maybe_next = stack.last_mut().unwrap().1.pop();
+                if let Some(next) = maybe_next {
+                    if let Some(idx) = stack.iter().position(|(c, _)| *c == next) {
+                        return Some(
+                            stack[idx..]
+                                .iter()
+                                .map(|(c, _)| (*c, self.waiting_for_turn(*c)))
+                                .collect(),
+                        );
+                    }
+                    if !finished.contains(&next) {
+                        stack.push((next, next_of(next)));
+                    }
+                } else {
+                    finished.insert(stack.pop().unwrap().0);
+                }
+            }
+        }
+        None
+    }
+
+    // The stuck cars in one queue, and who each is waiting on
+    fn stuck_in_queue(
+        &self,
+        queue: &Queue,
+        now: Time,
+        threshold: Duration,
+        intersections: &IntersectionSimState,
+    ) -> Vec<(CarID, BTreeSet<CarID>)> {
+        let mut results = Vec::new();
+        for (idx, id) in queue.cars.iter().enumerate() {
+            let car = &self.cars[id];
+            match car.state {
+                CarState::Queued { blocked_since }
+                | CarState::WaitingToAdvance { blocked_since } => {
+                    if now - blocked_since < threshold {
+                        continue;
+                    }
+                }
+                _ => {
+                    continue;
+                }
+            }
+
+            let mut deps = BTreeSet::new();
+            if idx > 0 {
+                deps.insert(queue.cars[idx - 1]);
+            } else if let Some(c) = queue.laggy_head {
+                deps.insert(c);
+            } else if let Some(next) = car.router.maybe_next() {
+                // The front car can't fit into whatever's next
+                let target = match next {
+                    Traversable::Turn(t) => Traversable::Lane(t.dst),
+                    Traversable::Lane(l) => Traversable::Lane(l),
+                };
+                if let Some(q) = self.queues.get(&target) {
+                    if !q.room_for_car(car) {
+                        deps.extend(q.cars.back().cloned());
+                        deps.extend(q.laggy_head);
+                    }
+                }
+            }
+            for a in intersections.get_blocked_by(AgentID::Car(*id)) {
+                if let AgentID::Car(c) = a {
+                    deps.insert(c);
+                }
+            }
+            results.push((*id, deps));
+        }
+        results
+    }
+
+    // If the car is at the front of a lane, the turn it'll do next
+    fn waiting_for_turn(&self, id: CarID) -> Option<TurnID> {
+        let car = &self.cars[&id];
+        if self.queues[&car.router.head()].cars.get(0) != Some(&id) {
+            return None;
+        }
+        match car.router.maybe_next() {
+            Some(Traversable::Turn(t)) => Some(t),
+            _ => None,
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
+
+    pub fn get_lane_stats(&self, l: LaneID) -> LaneStats {
+        self.lane_stats
+            .get(&l)
+            .cloned()
+            .unwrap_or_else(LaneStats::empty)
+    }
+
+    // Lanes without any vehicles are omitted.
+    pub fn get_all_lane_stats(&self) -> &BTreeMap<LaneID, LaneStats> {
+        &self.lane_stats
+    }
+
+    // The lane the car is on, plus any lanes its back is still on. Empty if the car doesn't exist.
+    fn lanes_touched_by(&self, id: CarID) -> Vec<LaneID> {
+        let mut lanes = Vec::new();
+        if let Some(car) = self.cars.get(&id) {
+            for on in std::iter::once(car.router.head()).chain(car.last_steps.iter().cloned()) {
+                if let Traversable::Lane(l) = on {
+                    lanes.push(l);
+                }
+            }
+        }
+        lanes
+    }
+
+    // Only looks at the cars on these lanes, not everything in the sim.
+    fn recalc_lane_stats(&mut self, lanes: Vec<LaneID>) {
+        for l in lanes.into_iter().collect::<BTreeSet<_>>() {
+            let stats = LaneStats::from_cars(
+                self.queues[&Traversable::Lane(l)]
+                    .cars
+                    .iter()
+                    .map(|c| &self.cars[c]),
+            );
+            if stats.occupancy == 0 {
+                self.lane_stats.remove(&l);
+            } else {
+                self.lane_stats.insert(l, stats);
+            }
+        }
+    }
+
+    // Scans every car, to check the stats maintained incrementally.
+    #[cfg(test)]
+    pub(crate) fn recount_lane_stats(&self) -> BTreeMap<LaneID, LaneStats> {
+        let mut cars_per_lane: BTreeMap<LaneID, Vec<&Car>> = BTreeMap::new();
+        for car in self.cars.values() {
+            if let Traversable::Lane(l) = car.router.head() {
+                cars_per_lane.entry(l).or_insert_with(Vec::new).push(car);
+            }
+        }
+        cars_per_lane
+            .into_iter()
+            .map(|(l, cars)| (l, LaneStats::from_cars(cars.into_iter())))
+            .collect()
+    }
+}
+
+impl LaneStats {
+    fn empty() -> LaneStats {
+        LaneStats {
+            occupancy: 0,
+            queue_len: 0,
+            avg_speed: None,
+        }
+    }
+
+    fn from_cars<'a, I: Iterator<Item = &'a Car>>(cars: I) -> LaneStats {
+        let mut stats = LaneStats::empty();
+        let mut total_speed = 0.0;
+        for car in cars {
+            stats.occupancy += 1;
+            match car.state {
+                CarState::Queued { .. } | CarState::WaitingToAdvance { .. } => {
+                    stats.queue_len += 1;
+                }
+                CarState::Crossing(ref time_int, ref dist_int) => {
+                    let dt = time_int.end - time_int.start;
+                    if dt > Duration::ZERO {
+                        total_speed += Speed::from_dist_time(dist_int.end - dist_int.start, dt)
+                            .inner_meters_per_second();
+                    }
+                }
+                // Parking, unparking, and buses waiting at a stop aren't moving, but they're not
+                // stuck in a queue either.
+                CarState::Unparking(_, _, _)
+                | CarState::Parking(_, _, _)
+                | CarState::Idling(_, _) => {}
+            }
+        }
+        if stats.occupancy > 0 {
+            stats.avg_speed = Some(Speed::meters_per_second(
+                total_speed / (stats.occupancy as f64),
+            ));
+        }
+        stats
+    }
 }
@@ -4,8 +4,8 @@ use crate::{AgentID, AlertLocation, CarID, Command, Event, Scheduler, Speed, Tri
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap};
 use geom::{Duration, Time};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, Map, RoadID, Traversable,
-    TurnID, TurnPriority, TurnType,
+    ControlStopSign, ControlTrafficSignal, CrosswalkTiming, IntersectionID, LaneID, Map, RoadID,
+    Traversable, TurnGroupID, TurnID, TurnPriority, TurnType,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
@@ -35,6 +35,14 @@ struct State {
         deserialize_with = "deserialize_btreemap"
     )]
     waiting: BTreeMap<Request, Time>,
+    // Only for traffic signals. The crosswalks showing walk during the current phase, and the ones
+    // where somebody's pressed the beg button.
+    walk: BTreeSet<TurnGroupID>,
+    walk_requests: BTreeSet<TurnGroupID>,
+    // Bumped whenever waiting, walk_requests, or the signal timing change, so a SignalUpdate can
+    // tell if it's stale.
+    #[serde(skip_serializing, skip_deserializing)]
+    generation: usize,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -43,6 +51,19 @@ struct Request {
     turn: TurnID,
 }
 
+// What update_intersection will do at one traffic signal
+#[derive(Clone)]
+pub struct SignalUpdate {
+    id: IntersectionID,
+    now: Time,
+    generation: usize,
+    walk: BTreeSet<TurnGroupID>,
+    walk_requests: BTreeSet<TurnGroupID>,
+    protected: Vec<AgentID>,
+    yielding: Vec<AgentID>,
+    remaining: Duration,
+}
+
 impl IntersectionSimState {
     pub fn new(
         map: &Map,
@@ -66,10 +87,13 @@ impl IntersectionSimState {
                     id: i.id,
                     accepted: BTreeSet::new(),
                     waiting: BTreeMap::new(),
+                    walk: BTreeSet::new(),
+                    walk_requests: BTreeSet::new(),
+                    generation: 0,
                 },
             );
             if i.is_traffic_signal() && !use_freeform_policy_everywhere {
-                sim.update_intersection(Time::START_OF_DAY, i.id, map, scheduler);
+                sim.update_intersection(Time::START_OF_DAY, i.id, map, scheduler, None);
             }
         }
         sim
@@ -106,6 +130,7 @@ impl IntersectionSimState {
     pub fn cancel_request(&mut self, agent: AgentID, turn: TurnID) {
         let state = self.state.get_mut(&turn.parent).unwrap();
         state.waiting.remove(&Request { agent, turn });
+        state.generation += 1;
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
                 retain_btreeset(&mut self.blocked_by, |(c1, c2)| *c1 != car && *c2 != car);
@@ -133,6 +158,17 @@ impl IntersectionSimState {
         /*if i == IntersectionID(64) {
             println!("at {}: wakeup_waiting -----------------", now);
         }*/
+        let (protected, yielding) = self.waiting_to_wake(now, i, map);
+        schedule_wakeups(now, protected, yielding, scheduler);
+    }
+
+    // Returns the agents who should retry right away, then the ones who should yield first.
+    fn waiting_to_wake(
+        &self,
+        now: Time,
+        i: IntersectionID,
+        map: &Map,
+    ) -> (Vec<AgentID>, Vec<AgentID>) {
         let mut all: Vec<(Request, Time)> = self.state[&i]
             .waiting
             .iter()
@@ -149,17 +185,17 @@ impl IntersectionSimState {
 
         if self.use_freeform_policy_everywhere {
             for (req, _) in all {
-                protected.push(req);
+                protected.push(req.agent);
             }
         } else if let Some(ref signal) = map.maybe_get_traffic_signal(i) {
             let (_, phase, _) = signal.current_phase_and_remaining_time(now);
             for (req, _) in all {
                 match phase.get_priority_of_turn(req.turn, signal) {
                     TurnPriority::Protected => {
-                        protected.push(req);
+                        protected.push(req.agent);
                     }
                     TurnPriority::Yield => {
-                        yielding.push(req);
+                        yielding.push(req.agent);
                     }
                     // No need to wake up
                     TurnPriority::Banned => {}
@@ -169,43 +205,112 @@ impl IntersectionSimState {
             for (req, _) in all {
                 // Banned is impossible
                 if sign.get_priority(req.turn, map) == TurnPriority::Protected {
-                    protected.push(req);
+                    protected.push(req.agent);
                 } else {
-                    yielding.push(req);
+                    yielding.push(req.agent);
                 }
             }
         } else {
             assert!(map.get_i(i).is_border());
         };
 
-        for req in protected {
-            // Use update because multiple agents could finish a turn at the same time, before the
-            // waiting one has a chance to try again.
-            scheduler.update(now, Command::update_agent(req.agent));
-        }
-        // Make sure the protected group gets first dibs. The scheduler arbitrarily (but
-        // deterministically) orders commands with the same time.
-        for req in yielding {
-            scheduler.update(
-                now + Duration::seconds(0.1),
-                Command::update_agent(req.agent),
-            );
-        }
+        (protected, yielding)
     }
 
-    // This is only triggered for traffic signals.
+    // This is only triggered for traffic signals, when a new phase starts.
+    // If the caller already planned this update and nothing's changed since, it's used as-is.
     pub fn update_intersection(
-        &self,
+        &mut self,
         now: Time,
         id: IntersectionID,
         map: &Map,
         scheduler: &mut Scheduler,
+        planned: Option<SignalUpdate>,
     ) {
-        self.wakeup_waiting(now, id, scheduler, map);
-        let (_, _, remaining) = map
+        let update = match planned {
+            Some(u) if u.now == now && u.generation == self.state[&id].generation => u,
+            _ => self.plan_signal_update(now, id, map),
+        };
+
+        let state = self.state.get_mut(&id).unwrap();
+        state.walk = update.walk;
+        state.walk_requests = update.walk_requests;
+        state.generation += 1;
+
+        schedule_wakeups(now, update.protected, update.yielding, scheduler);
+        scheduler.push(now + update.remaining, Command::UpdateIntersection(id));
+    }
+
+    // Only reads this intersection's state, so it's safe to run for many signals at once.
+    pub fn plan_signal_update(&self, now: Time, id: IntersectionID, map: &Map) -> SignalUpdate {
+        let (_, phase, remaining) = map
             .get_traffic_signal(id)
             .current_phase_and_remaining_time(now);
-        scheduler.push(now + remaining, Command::UpdateIntersection(id));
+
+        let state = &self.state[&id];
+        let mut walk = BTreeSet::new();
+        let mut walk_requests = state.walk_requests.clone();
+        for g in phase.crosswalk_groups() {
+            // The button is pressed even for fixed timing, so clear it either way
+            let requested = walk_requests.remove(g);
+            if phase.crosswalk_timing == CrosswalkTiming::Fixed || requested {
+                walk.insert(*g);
+            }
+        }
+
+        let (protected, yielding) = self.waiting_to_wake(now, id, map);
+        SignalUpdate {
+            id,
+            now,
+            generation: state.generation,
+            walk,
+            walk_requests,
+            protected,
+            yielding,
+            remaining,
+        }
+    }
+
+    // The results don't depend on how the work gets split up or which thread finishes first.
+    pub fn plan_signal_updates(
+        &self,
+        now: Time,
+        ids: Vec<IntersectionID>,
+        map: &Map,
+        pool: &mut scoped_threadpool::Pool,
+    ) -> BTreeMap<IntersectionID, SignalUpdate> {
+        let chunk_size = (ids.len() / pool.thread_count() as usize).max(1);
+        pool.scoped(|scope| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            for chunk in ids.chunks(chunk_size) {
+                let tx = tx.clone();
+                scope.execute(move || {
+                    for id in chunk {
+                        tx.send(self.plan_signal_update(now, *id, map)).unwrap();
+                    }
+                });
+            }
+            drop(tx);
+            rx.iter().map(|update| (update.id, update)).collect()
+        })
+    }
+
+    // The signal's timing was edited while running, so work out the current phase right away.
+    pub fn handle_live_edited_traffic_signal(
+        &mut self,
+        now: Time,
+        id: IntersectionID,
+        scheduler: &mut Scheduler,
+    ) {
+        // Any update already planned for this signal used the old timing
+        self.state.get_mut(&id).unwrap().generation += 1;
+        if !self.use_freeform_policy_everywhere {
+            scheduler.update(now, Command::UpdateIntersection(id));
+        }
+    }
+
+    pub fn get_walk_signals(&self, i: IntersectionID) -> &BTreeSet<TurnGroupID> {
+        &self.state[&i].walk
     }
 
     // For cars: The head car calls this when they're at the end of the lane WaitingToAdvance. If
@@ -230,12 +335,11 @@ impl IntersectionSimState {
         )>,
     ) -> bool {
         let req = Request { agent, turn };
-        self.state
-            .get_mut(&turn.parent)
-            .unwrap()
-            .waiting
-            .entry(req.clone())
-            .or_insert(now);
+        {
+            let state = self.state.get_mut(&turn.parent).unwrap();
+            state.waiting.entry(req.clone()).or_insert(now);
+            state.generation += 1;
+        }
 
         let readonly_pair = maybe_cars_and_queues.as_ref().map(|(_, c, q)| (*c, &**q));
         let allowed = if self.use_freeform_policy_everywhere {
@@ -290,6 +394,7 @@ impl IntersectionSimState {
         // for stop signs too.
         let state = self.state.get_mut(&turn.parent).unwrap();
         let delay = now - state.waiting.remove(&req).unwrap();
+        state.generation += 1;
         if map.maybe_get_traffic_signal(state.id).is_some() {
             self.events.push(Event::IntersectionDelayMeasured(
                 turn.parent,
@@ -473,6 +578,31 @@ impl IntersectionSimState {
 
         // Can't go at all this phase.
         let our_priority = phase.get_priority_of_turn(req.turn, signal);
+        if turn.turn_type == TurnType::Crosswalk {
+            let g = signal
+                .turn_groups
+                .values()
+                .find(|g| g.members.contains(&req.turn))
+                .unwrap()
+                .id;
+            let state = self.state.get_mut(&req.turn.parent).unwrap();
+            if !state.walk.contains(&g) {
+                // Press the button. It works for both directions of the crosswalk, if the other
+                // direction is its own group.
+                state.generation += 1;
+                state.walk_requests.insert(g);
+                let reverse = TurnGroupID {
+                    from: g.to,
+                    to: g.from,
+                    parent: g.parent,
+                    crosswalk: true,
+                };
+                if signal.turn_groups.contains_key(&reverse) {
+                    state.walk_requests.insert(reverse);
+                }
+                return false;
+            }
+        }
         if our_priority == TurnPriority::Banned {
             return false;
         }
@@ -619,6 +749,24 @@ impl IntersectionSimState {
     }
 }
 
+fn schedule_wakeups(
+    now: Time,
+    protected: Vec<AgentID>,
+    yielding: Vec<AgentID>,
+    scheduler: &mut Scheduler,
+) {
+    for agent in protected {
+        // Use update because multiple agents could finish a turn at the same time, before the
+        // waiting one has a chance to try again.
+        scheduler.update(now, Command::update_agent(agent));
+    }
+    // Make sure the protected group gets first dibs. The scheduler arbitrarily (but
+    // deterministically) orders commands with the same time.
+    for agent in yielding {
+        scheduler.update(now + Duration::seconds(0.1), Command::update_agent(agent));
+    }
+}
+
 // TODO Sometimes a traffic signal is surrounded by tiny lanes with almost no capacity. Workaround
 // for now.
 fn allow_block_the_box(osm_node_id: i64) -> bool {
@@ -6,7 +6,7 @@ mod queue;
 mod walking;
 
 pub use self::driving::DrivingSimState;
-pub use self::intersection::IntersectionSimState;
+pub use self::intersection::{IntersectionSimState, SignalUpdate};
 pub use self::parking::ParkingSimState;
 pub use self::queue::Queue;
 pub use self::walking::WalkingSimState;
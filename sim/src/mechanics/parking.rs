@@ -363,6 +363,25 @@ impl ParkingSimState {
         self.parked_cars.get(&id)
     }
 
+    // (Filled, capacity), or None if this isn't an on-street parking lane. Reserved spots count as
+    // filled.
+    pub fn get_lane_occupancy(&self, l: LaneID) -> Option<(usize, usize)> {
+        let spots = self.onstreet_lanes.get(&l)?.spots();
+        let filled = spots.iter().filter(|spot| !self.is_free(**spot)).count();
+        Some((filled, spots.len()))
+    }
+
+    pub fn get_parked_cars_on_lane(&self, l: LaneID) -> Vec<&ParkedCar> {
+        if let Some(lane) = self.onstreet_lanes.get(&l) {
+            lane.spots()
+                .into_iter()
+                .filter_map(|spot| self.get_car_at_spot(spot))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
     // (Filled, available)
     pub fn get_all_parking_spots(&self) -> (Vec<ParkingSpot>, Vec<ParkingSpot>) {
         let mut spots = Vec::new();
@@ -328,7 +328,11 @@ impl WalkingSimState {
         }
     }
 
-    pub fn agent_properties(&self, id: PedestrianID, now: Time) -> AgentProperties {
+    pub fn ped_json(&self, id: PedestrianID) -> Option<String> {
+        self.peds.get(&id).map(|ped| abstutil::to_json(ped))
+    }
+
+    pub fn agent_properties(&self, id: PedestrianID, now: Time, map: &Map) -> AgentProperties {
         let p = &self.peds[&id];
         let time_spent_waiting = match p.state {
             PedState::WaitingToTurn(_, blocked_since)
@@ -349,6 +353,14 @@ impl WalkingSimState {
             total_dist: p.path.total_length(),
             lanes_crossed: p.path.lanes_crossed_so_far(),
             total_lanes: p.path.total_lanes(),
+            on: p.path.current_step().as_traversable(),
+            dist_along: p.get_dist_along(now, map),
+            speed: match p.state {
+                PedState::Crossing(_, _) => p.speed,
+                _ => Speed::ZERO,
+            },
+            // Pedestrians never queue behind each other
+            waiting_for: None,
         }
     }
 
@@ -655,7 +667,10 @@ impl Pedestrian {
                 PedState::WaitingToTurn(_, _) => Some(self.path.next_step().as_turn()),
                 _ => None,
             },
-            preparing_bike: matches!(self.state, PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)),
+            preparing_bike: matches!(
+                self.state,
+                PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)
+            ),
             waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _)),
             on,
         }
@@ -90,6 +90,24 @@ impl PandemicModel {
         }
     }
 
+    // For Sim's determinism checksums. State has floats, so compare the Debug output instead of
+    // deriving Hash everywhere. The RNG's Debug output is empty, so peek at its next value from a
+    // copy.
+    pub(crate) fn fingerprint(&self) -> Vec<u8> {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {} {}",
+            self.pop,
+            self.bldgs,
+            self.remote_bldgs,
+            self.bus_stops,
+            self.buses,
+            self.person_to_bus,
+            self.rng.clone().gen::<u64>(),
+            self.initialized
+        )
+        .into_bytes()
+    }
+
     pub fn count_sane(&self) -> usize {
         self.pop
             .iter()
@@ -352,7 +370,7 @@ impl PandemicModel {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct SharedSpace<T: Ord> {
     // Since when has a person been in some shared space?
     // TODO This is an awkward data structure; abstutil::MultiMap is also bad, because key removal
@@ -0,0 +1,420 @@
+use crate::{Analytics, Sim, TimeSpent, TripMode};
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map, RoadID};
+use std::collections::{BTreeMap, BTreeSet};
+
+// How many of the worst intersections to list
+const NUM_CHOKEPOINTS: usize = 10;
+
+const STYLE: &str = "<style>
+body { font-family: sans-serif; max-width: 60em; margin: auto; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #999; padding: 0.2em 0.6em; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+img { width: 100%; height: auto; }
+</style>
+";
+
+// A self-contained HTML page about a set of edits and how the simulation has gone with them so
+// far, for people who won't run the game themselves. If there's a baseline (the same scenario
+// without edits), the two runs are compared up to the current time. Sections without any data are
+// left out.
+pub fn make_report(
+    map: &Map,
+    sim: &Sim,
+    baseline: Option<&Analytics>,
+    overview_png: Option<&[u8]>,
+) -> String {
+    let now = sim.time();
+    let edits = map.get_edits();
+    let title = format!("{} with {}", map.get_name(), edits.edits_name);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(&title)));
+    html.push_str(STYLE);
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape(&title)));
+    if now == Time::START_OF_DAY {
+        html.push_str("<p>The simulation hasn't run yet.</p>\n");
+    } else {
+        html.push_str(&format!(
+            "<p>Simulated until {}.</p>\n",
+            now.ampm_tostring()
+        ));
+    }
+
+    if let Some(png) = overview_png {
+        overview(&mut html, map, png);
+    }
+    if !edits.commands.is_empty() {
+        html.push_str("<h2>Edits</h2>\n");
+        for line in &edits.proposal_description {
+            html.push_str(&format!("<p>{}</p>\n", escape(line)));
+        }
+        html.push_str("<ul>\n");
+        for cmd in &edits.commands {
+            html.push_str(&format!(
+                "<li>{}: {}</li>\n",
+                cmd.kind(),
+                escape(&cmd.short_name())
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if now > Time::START_OF_DAY {
+        let analytics = sim.get_analytics();
+        trips(&mut html, analytics, baseline, now);
+        active_agents(&mut html, analytics, baseline, now);
+        chokepoints(&mut html, map, analytics, baseline, now);
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// A picture of the whole map with the edits layer showing, taken by the caller
+fn overview(html: &mut String, map: &Map, png: &[u8]) {
+    let (edited_roads, edited_intersections) = edited_objects(map);
+    html.push_str("<h2>Overview</h2>\n");
+    html.push_str(&format!(
+        "<img src=\"data:image/png;base64,{}\" alt=\"{}\">\n",
+        base64(png),
+        escape(map.get_name())
+    ));
+    if !edited_roads.is_empty() || !edited_intersections.is_empty() {
+        html.push_str(&format!(
+            "<p>{} edited roads and {} edited intersections are highlighted.</p>\n",
+            prettyprint_usize(edited_roads.len()),
+            prettyprint_usize(edited_intersections.len())
+        ));
+    }
+}
+
+// The same things the edits layer highlights
+fn edited_objects(map: &Map) -> (BTreeSet<RoadID>, BTreeSet<IntersectionID>) {
+    let edits = map.get_edits();
+    let mut edited_roads: BTreeSet<RoadID> = edits.changed_speed_limits.clone();
+    for l in edits
+        .original_lts
+        .keys()
+        .chain(edits.reversed_lanes.iter())
+        .chain(edits.closed_lanes.iter())
+    {
+        edited_roads.insert(map.get_l(*l).parent);
+    }
+    for route in edits
+        .original_route_stops
+        .keys()
+        .chain(edits.original_route_schedules.keys())
+    {
+        for bs in &map.get_br(*route).stops {
+            edited_roads.insert(map.get_l(map.get_bs(*bs).sidewalk_pos.lane()).parent);
+        }
+    }
+    let mut edited_intersections: BTreeSet<IntersectionID> =
+        edits.original_intersections.keys().cloned().collect();
+    for t in edits.banned_turns.iter().chain(edits.allowed_turns.iter()) {
+        edited_intersections.insert(t.parent);
+    }
+    (edited_roads, edited_intersections)
+}
+
+// The same numbers as the trip summaries dashboard, for all modes
+fn trips(html: &mut String, analytics: &Analytics, baseline: Option<&Analytics>, now: Time) {
+    html.push_str("<h2>Trips</h2>\n");
+    let modes: BTreeSet<TripMode> = TripMode::all().into_iter().collect();
+
+    if let Some(baseline) = baseline {
+        let cmp = analytics.compare_trips(now, baseline, &modes, None);
+        table(
+            html,
+            vec![
+                "Compared to before edits",
+                "Trips",
+                "Total difference",
+                "Average difference",
+            ],
+            vec![
+                vec![
+                    "Faster".to_string(),
+                    prettyprint_usize(cmp.num_faster),
+                    format!("{} saved", cmp.sum_faster),
+                    cmp.avg_faster().to_string(),
+                ],
+                vec![
+                    "Slower".to_string(),
+                    prettyprint_usize(cmp.num_slower),
+                    format!("{} lost", cmp.sum_slower),
+                    cmp.avg_slower().to_string(),
+                ],
+                vec![
+                    "Unchanged".to_string(),
+                    prettyprint_usize(cmp.num_same),
+                    String::new(),
+                    String::new(),
+                ],
+            ],
+        );
+    }
+
+    let after = analytics.total_time_spent(now, &modes);
+    let before = baseline.map(|b| b.total_time_spent(now, &modes));
+    let mut headers = vec!["Time spent in finished trips", "Total"];
+    if before.is_some() {
+        headers.push("Total before edits");
+    }
+    let mut rows = Vec::new();
+    for category in TimeSpent::all() {
+        let mut row = vec![
+            category.describe().to_string(),
+            after
+                .get(&category)
+                .cloned()
+                .unwrap_or(Duration::ZERO)
+                .to_string(),
+        ];
+        if let Some(ref before) = before {
+            row.push(
+                before
+                    .get(&category)
+                    .cloned()
+                    .unwrap_or(Duration::ZERO)
+                    .to_string(),
+            );
+        }
+        rows.push(row);
+    }
+    table(html, headers, rows);
+}
+
+// Sampled every hour, for anyone who wants to chart it
+fn active_agents(
+    html: &mut String,
+    analytics: &Analytics,
+    baseline: Option<&Analytics>,
+    now: Time,
+) {
+    let (hours, _, _, _) = now.get_parts();
+    if hours == 0 {
+        return;
+    }
+    html.push_str("<h2>Active trips over time</h2>\n");
+    let after = analytics.active_agents(now);
+    let before = baseline.map(|b| b.active_agents(now));
+    let mut headers = vec!["Time", "Active trips"];
+    if before.is_some() {
+        headers.push("Active trips before edits");
+    }
+    let mut rows = Vec::new();
+    for hour in 1..=hours {
+        let t = Time::START_OF_DAY + Duration::hours(hour);
+        let mut row = vec![t.ampm_tostring(), prettyprint_usize(value_at(&after, t))];
+        if let Some(ref before) = before {
+            row.push(prettyprint_usize(value_at(before, t)));
+        }
+        rows.push(row);
+    }
+    table(html, headers, rows);
+}
+
+// The intersections where trips have spent the most time waiting in total
+fn chokepoints(
+    html: &mut String,
+    map: &Map,
+    analytics: &Analytics,
+    baseline: Option<&Analytics>,
+    now: Time,
+) {
+    let after = delay_per_intersection(analytics, now);
+    if after.is_empty() {
+        return;
+    }
+    let before = baseline.map(|b| delay_per_intersection(b, now));
+    let mut worst: Vec<(&IntersectionID, &(usize, Duration))> = after.iter().collect();
+    // Break ties by ID, so the ranking is stable
+    worst.sort_by_key(|(i, (_, total))| (std::cmp::Reverse(*total), **i));
+
+    html.push_str("<h2>Chokepoints</h2>\n");
+    let mut headers = vec!["Intersection", "Trips delayed", "Total delay"];
+    if before.is_some() {
+        headers.push("Total delay before edits");
+    }
+    let mut rows = Vec::new();
+    for (i, (count, total)) in worst.into_iter().take(NUM_CHOKEPOINTS) {
+        let mut row = vec![
+            format!("{} ({})", map.get_i(*i).name(map), i),
+            prettyprint_usize(*count),
+            total.to_string(),
+        ];
+        if let Some(ref before) = before {
+            row.push(
+                before
+                    .get(i)
+                    .map(|(_, dt)| *dt)
+                    .unwrap_or(Duration::ZERO)
+                    .to_string(),
+            );
+        }
+        rows.push(row);
+    }
+    table(html, headers, rows);
+}
+
+// (Number of delays, total delay)
+fn delay_per_intersection(
+    analytics: &Analytics,
+    now: Time,
+) -> BTreeMap<IntersectionID, (usize, Duration)> {
+    let mut results = BTreeMap::new();
+    for (i, delays) in &analytics.intersection_delays {
+        let mut count = 0;
+        let mut total = Duration::ZERO;
+        for (t, dt, _) in delays {
+            if *t > now {
+                break;
+            }
+            count += 1;
+            total += *dt;
+        }
+        if count > 0 {
+            results.insert(*i, (count, total));
+        }
+    }
+    results
+}
+
+// The points describe a step function
+fn value_at(pts: &Vec<(Time, usize)>, t: Time) -> usize {
+    pts.iter()
+        .take_while(|(t1, _)| *t1 <= t)
+        .last()
+        .map(|(_, cnt)| *cnt)
+        .unwrap_or(0)
+}
+
+fn table(html: &mut String, headers: Vec<&str>, rows: Vec<Vec<String>>) {
+    html.push_str("<table>\n<tr>");
+    for header in headers {
+        html.push_str(&format!("<th>{}</th>", escape(header)));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+// Just enough to embed images; not worth a dependency
+fn base64(raw: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((raw.len() + 2) / 3 * 4);
+    for chunk in raw.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | (bytes[2] as u32);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimOptions;
+    use abstutil::Timer;
+    use map_model::{EditCmd, SyntheticMap};
+
+    #[test]
+    fn test_sections() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let mut sim = Sim::new(&map, SimOptions::new("test_report"), &mut timer);
+
+        // Nothing has happened yet
+        let html = make_report(&map, &sim, None, None);
+        assert!(!html.contains("<h2>Overview</h2>"));
+        assert!(!html.contains("<h2>Edits</h2>"));
+        assert!(!html.contains("<h2>Trips</h2>"));
+
+        sim.timed_step(&map, Duration::hours(2), &mut None, &mut timer);
+        let html = make_report(&map, &sim, Some(sim.get_analytics()), Some(b"png"));
+        assert!(html.contains("<img src=\"data:image/png;base64,cG5n\""));
+        assert!(html.contains("<h2>Trips</h2>"));
+        assert!(html.contains("<h2>Active trips over time</h2>"));
+        assert!(html.contains("Compared to before edits"));
+        assert!(html.contains("Total before edits"));
+        // Nobody's been delayed without any trips
+        assert!(!html.contains("<h2>Chokepoints</h2>"));
+    }
+
+    #[test]
+    fn test_edited_objects() {
+        let mut timer = Timer::throwaway();
+        let mut map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let l = map.all_lanes().iter().find(|l| l.is_driving()).unwrap().id;
+        let mut edits = map.get_edits().clone();
+        edits.commands.push(EditCmd::CloseLane { id: l });
+        map.apply_edits(edits, &mut timer);
+
+        let (roads, intersections) = edited_objects(&map);
+        assert_eq!(roads, vec![map.get_l(l).parent].into_iter().collect());
+        assert!(intersections.is_empty());
+
+        let t = map
+            .all_turns()
+            .values()
+            .find(|t| !t.between_sidewalks())
+            .unwrap()
+            .id;
+        let mut edits = map.get_edits().clone();
+        edits
+            .commands
+            .push(EditCmd::ChangeTurnRestriction { t, banned: true });
+        map.apply_edits(edits, &mut timer);
+        let (_, intersections) = edited_objects(&map);
+        assert_eq!(intersections, vec![t.parent].into_iter().collect());
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<Pike & Pine>"), "&lt;Pike &amp; Pine&gt;");
+    }
+}
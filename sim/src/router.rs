@@ -153,10 +153,13 @@ impl Router {
             );
         }
 
-        // Sanity check laws haven't been broken
+        // Sanity check laws haven't been broken. Lanes closed partway through might still have some
+        // vehicles finishing up on them, so only check the lane type.
         if let Traversable::Lane(l) = self.head() {
             let lane = map.get_l(l);
-            if !vehicle.vehicle_type.to_constraints().can_use(lane, map) {
+            if !vehicle.vehicle_type.to_constraints().can_use(lane, map)
+                && !map.get_edits().is_lane_closed(l)
+            {
                 panic!(
                     "{} just wound up on {}, a {:?} (check the OSM tags)",
                     vehicle.id, l, lane.lane_type
@@ -349,7 +352,10 @@ impl Router {
                     src: current_turn.src,
                     dst: *l,
                 };
-                if orig_lt == *lt && map.maybe_get_t(turn1).is_some() {
+                if orig_lt == *lt
+                    && map.maybe_get_t(turn1).is_some()
+                    && (*l == orig_target_lane || !map.get_edits().is_lane_closed(*l))
+                {
                     // Now make sure we can go from this lane to next_lane.
                     let turn2 = TurnID {
                         parent: next_parent,
@@ -379,6 +385,11 @@ impl Router {
         self.path.modify_step(3, PathStep::Turn(turn2), map);
     }
 
+    // Switches to a new path starting from step idx. It has to end at the same place.
+    pub fn reroute(&mut self, idx: usize, path: Path, map: &Map) {
+        self.path.splice(idx, path, map);
+    }
+
     pub fn replace_path_for_serialization(&mut self, path: Path) -> Path {
         std::mem::replace(&mut self.path, path)
     }
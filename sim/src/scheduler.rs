@@ -3,7 +3,7 @@ use crate::{
 };
 use derivative::Derivative;
 use geom::{Duration, Histogram, Time};
-use map_model::{IntersectionID, Path, PathRequest};
+use map_model::{BusRouteID, IntersectionID, Path, PathRequest};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::btree_map::Entry;
@@ -23,6 +23,11 @@ pub enum Command {
     Callback(Duration),
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    CheckForGridlock,
+    // Index into the schedule of interventions
+    Intervention(usize),
+    // The time is when the bus was scheduled to start, to tell buses on the same route apart
+    StartBus(BusRouteID, Time),
 }
 
 impl Command {
@@ -46,6 +51,9 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::FinishRemoteTrip(t) => CommandType::FinishRemoteTrip(*t),
+            Command::CheckForGridlock => CommandType::CheckForGridlock,
+            Command::Intervention(idx) => CommandType::Intervention(*idx),
+            Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
         }
     }
 }
@@ -62,6 +70,9 @@ pub enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    CheckForGridlock,
+    Intervention(usize),
+    StartBus(BusRouteID, Time),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -214,6 +225,20 @@ impl Scheduler {
         }
     }
 
+    // All of the UpdateIntersection commands still queued for exactly this time
+    pub fn intersection_updates_at(&self, time: Time) -> Vec<IntersectionID> {
+        self.queued_commands
+            .range(
+                CommandType::Intersection(IntersectionID(0))
+                    ..=CommandType::Intersection(IntersectionID(std::usize::MAX)),
+            )
+            .filter_map(|(_, (cmd, t))| match cmd {
+                Command::UpdateIntersection(i) if *t == time => Some(*i),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn describe_stats(&self) -> String {
         format!("delta times for events: {}", self.delta_times.describe())
     }
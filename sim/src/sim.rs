@@ -1,26 +1,39 @@
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents, IntersectionSimState, OrigPersonID,
-    PandemicModel, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Person, PersonID,
-    PersonState, Router, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripEndpoint,
-    TripID, TripManager, TripMode, TripPhaseType, TripPositions, TripResult, TripSpawner,
-    UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, Divergence, DrawCarInput,
+    DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents, Inactive,
+    IndividTrip, IntersectionSimState, Intervention, OrigPersonID, PandemicModel, ParkedCar,
+    ParkingSimState, ParkingSpot, PedestrianID, Person, PersonID, PersonSpec, PersonState, Router,
+    Scenario, Scheduler, SidewalkPOI, SidewalkSpot, SignalUpdate, SimEvent, SpawnTrip,
+    TransitSimState, TripDetail, TripEndpoint, TripID, TripManager, TripMode, TripPhaseType,
+    TripPositions, TripResult, TripSpawner, UnzoomedAgent, Vehicle, VehicleSpec, VehicleType,
+    WalkingSimState, BUS_LENGTH, MIN_CAR_LENGTH,
 };
-use abstutil::Timer;
+use abstutil::{Timer, Versioned};
 use derivative::Derivative;
 use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
 use instant::Instant;
 use map_model::{
-    BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, ParkingLotID, Path,
-    PathConstraints, PathRequest, PathStep, Position, RoadID, Traversable,
+    BuildingID, BusRoute, BusRouteID, ControlTrafficSignal, EditCmd, EditIntersection,
+    IntersectionID, LaneID, Map, MapEdits, ParkingLotID, Path, PathConstraints, PathRequest,
+    PathStep, Position, RoadID, Traversable, TurnGroupID, TurnID,
 };
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::panic;
 
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
+// If nothing calls collect_events, the oldest are dropped past this.
+const MAX_PENDING_SIM_EVENTS: usize = 10_000;
+const CHECK_FOR_GRIDLOCK_FREQUENCY: Duration = Duration::const_seconds(60.0);
+// Below this many traffic signals changing at once, spreading the work across threads costs more
+// than it saves.
+const MIN_PARALLEL_SIGNAL_UPDATES: usize = 16;
+// Likewise, small maps scan their lanes for gridlock on one thread.
+const MIN_PARALLEL_GRIDLOCK_LANES: usize = 1000;
 
 #[derive(Serialize, Deserialize, Clone, Derivative)]
 #[derivative(PartialEq)]
@@ -57,9 +70,73 @@ pub struct Sim {
     #[serde(skip_serializing, skip_deserializing)]
     analytics: Analytics,
 
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    sim_events: VecDeque<(Time, SimEvent)>,
+
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+
+    // Only used to verify that runs are deterministic
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    checkpoint_interval: Option<Duration>,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    checksums: Vec<(Time, u64)>,
+    // Every turn one trip makes, for comparing runs
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    tracked_turns: Option<(TripID, Vec<TurnID>)>,
+
+    // Map edits applied without resetting. A run from scratch won't reproduce these.
+    live_edits: Vec<(Time, String)>,
+
+    // Changes to make partway through the run, sorted by time
+    interventions: Vec<(Time, Intervention)>,
+    // Indices into interventions that've come due, but are waiting on the map to be changed
+    due_interventions: Vec<usize>,
+    // The map edits that interventions made so far. They're kept apart from the player's edits,
+    // so resetting the sim drops them.
+    intervention_edits: Vec<EditCmd>,
+
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    gridlock_threshold: Option<Duration>,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    gridlock: Option<Gridlock>,
+
+    // Savestates don't remember this, so they run on one thread after loading.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    num_threads: usize,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    workers: Workers,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    signal_updates: Option<(Time, BTreeMap<IntersectionID, SignalUpdate>)>,
+}
+
+// A thread pool that lasts between steps. Clones of the sim get their own.
+#[derive(Default)]
+struct Workers {
+    pool: Option<scoped_threadpool::Pool>,
+}
+
+impl Workers {
+    fn get(&mut self, num_threads: usize) -> &mut scoped_threadpool::Pool {
+        self.pool
+            .get_or_insert_with(|| scoped_threadpool::Pool::new(num_threads as u32))
+    }
+}
+
+impl Clone for Workers {
+    fn clone(&self) -> Workers {
+        Workers::default()
+    }
 }
 
 #[derive(Clone)]
@@ -72,6 +149,16 @@ pub struct SimOptions {
     pub enable_pandemic_model: Option<XorShiftRng>,
     pub alerts: AlertHandler,
     pub pathfinding_upfront: bool,
+    // If set, record a checksum of the entire sim state this often. Two runs with the same flags
+    // should produce the same checksums.
+    pub checkpoint_interval: Option<Duration>,
+    // If set, look for cycles of vehicles that've all been stuck for at least this long.
+    pub gridlock_threshold: Option<Duration>,
+    // Scheduled changes to the map or demand, like closing a lane at some time
+    pub interventions: Vec<(Time, Intervention)>,
+    // Some independent work within a step is spread across this many threads. The results are
+    // the same for any number.
+    pub num_threads: usize,
 }
 
 #[derive(Clone)]
@@ -101,14 +188,31 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            checkpoint_interval: None,
+            gridlock_threshold: Some(SimOptions::DEFAULT_GRIDLOCK_THRESHOLD),
+            interventions: Vec::new(),
+            num_threads: num_cpus::get_physical(),
         }
     }
+
+    pub const DEFAULT_GRIDLOCK_THRESHOLD: Duration = Duration::const_seconds(300.0);
 }
 
 // Setup
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
         let mut scheduler = Scheduler::new();
+        if opts.gridlock_threshold.is_some() {
+            scheduler.push(
+                Time::START_OF_DAY + CHECK_FOR_GRIDLOCK_FREQUENCY,
+                Command::CheckForGridlock,
+            );
+        }
+        let mut interventions = opts.interventions;
+        interventions.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        for (idx, (t, _)) in interventions.iter().enumerate() {
+            scheduler.push(*t, Command::Intervention(idx));
+        }
         Sim {
             driving: DrivingSimState::new(map, opts.recalc_lanechanging),
             parking: ParkingSimState::new(map, timer),
@@ -139,6 +243,24 @@ impl Sim {
             alerts: opts.alerts,
 
             analytics: Analytics::new(),
+            sim_events: VecDeque::new(),
+            checkpoint_interval: opts.checkpoint_interval,
+            checksums: Vec::new(),
+            tracked_turns: None,
+            live_edits: Vec::new(),
+            interventions,
+            due_interventions: Vec::new(),
+            intervention_edits: Vec::new(),
+            gridlock_threshold: opts.gridlock_threshold,
+            gridlock: None,
+            // No threads in the browser
+            num_threads: if cfg!(target_arch = "wasm32") {
+                1
+            } else {
+                opts.num_threads
+            },
+            workers: Workers::default(),
+            signal_updates: None,
         }
     }
 
@@ -172,6 +294,24 @@ impl Sim {
         self.parking.get_all_parking_spots()
     }
 
+    // (Filled, capacity) of an on-street parking lane
+    pub fn get_parking_lane_occupancy(&self, l: LaneID) -> Option<(usize, usize)> {
+        self.parking.get_lane_occupancy(l)
+    }
+
+    pub fn get_parked_cars_on_lane(&self, l: LaneID) -> Vec<&ParkedCar> {
+        self.parking.get_parked_cars_on_lane(l)
+    }
+
+    // Only the person's vehicles that're parked right now
+    pub fn get_parked_cars_by_owner(&self, p: PersonID) -> Vec<&ParkedCar> {
+        self.get_person(p)
+            .vehicles
+            .iter()
+            .filter_map(|v| self.parking.lookup_parked_car(v.id))
+            .collect()
+    }
+
     // Also returns the start distance of the building. TODO Do that in the Path properly.
     pub fn walking_path_to_nearest_parking_spot(
         &self,
@@ -236,13 +376,20 @@ impl Sim {
         self.parking.add_parked_car(ParkedCar { vehicle, spot });
     }
 
-    pub fn seed_bus_route(&mut self, route: &BusRoute, map: &Map, timer: &mut Timer) -> Vec<CarID> {
-        let mut results: Vec<CarID> = Vec::new();
+    // Every bus in the route's schedule starts at its time, or right away if that's already passed.
+    pub fn seed_bus_route(&mut self, route: &BusRoute, map: &Map) {
+        self.transit.create_empty_route(route, map);
+        for t in &route.spawn_times {
+            self.scheduler
+                .push((*t).max(self.time), Command::StartBus(route.id, *t));
+        }
+    }
 
-        // Try to spawn just ONE bus anywhere.
-        // TODO Be more realistic. One bus per stop is too much, one is too little.
+    fn start_bus(&mut self, route: &BusRoute, map: &Map) {
+        // Spawn the bus anywhere along the route that has room.
+        // TODO Be more realistic and always start at the first stop.
         for (next_stop_idx, req, mut path, end_dist) in
-            self.transit.create_empty_route(route, map).into_iter()
+            self.transit.bus_spawn_points(route.id).into_iter()
         {
             // For now, no desire for randomness. Caller can pass in list of specs if that ever
             // changes.
@@ -256,10 +403,10 @@ impl Sim {
 
             loop {
                 if path.is_last_step() {
-                    timer.warn(format!(
-                        "Giving up on seeding a bus headed towards stop {} of {} ({})",
+                    println!(
+                        "Giving up on starting a bus headed towards stop {} of {} ({})",
                         next_stop_idx, route.name, route.id
-                    ));
+                    );
                     break;
                 }
                 let start_lane = if let PathStep::Lane(l) = path.current_step() {
@@ -293,18 +440,14 @@ impl Sim {
                 ) {
                     self.transit.bus_created(id, route.id, next_stop_idx);
                     self.analytics.record_demand(&path, map);
-                    results.push(id);
-                    return results;
+                    return;
                 } else {
                     path.shift(map);
                 }
             }
         }
-        if results.is_empty() {
-            // TODO Bigger failure
-            timer.warn(format!("Failed to make ANY buses for {}!", route.name));
-        }
-        results
+        // TODO Bigger failure
+        println!("Failed to start a bus for {} at {}!", route.name, self.time);
     }
 
     pub fn set_name(&mut self, name: String) {
@@ -378,31 +521,41 @@ impl GetDrawAgents for Sim {
 // Running
 impl Sim {
     // Advances time as minimally as possible, also limited by max_dt. Returns true if the callback
-    // said to halt the sim.
+    // said to halt the sim, or an intervention is due.
     fn minimal_step(
         &mut self,
         map: &Map,
         max_dt: Duration,
         maybe_cb: &mut Option<Box<dyn SimCallback>>,
     ) -> bool {
+        // Nothing happens until the map catches up
+        if !self.due_interventions.is_empty() {
+            return true;
+        }
         self.step_count += 1;
 
         let max_time = if let Some(t) = self.scheduler.peek_next_time() {
             if t > self.time + max_dt {
                 // Next event is after when we want to stop.
                 self.time += max_dt;
+                self.record_checksums(self.time, true);
                 return false;
             }
             t
         } else {
             // No events left at all
             self.time += max_dt;
+            self.record_checksums(self.time, true);
             return false;
         };
+        // Nothing has happened since the last batch of events, so this is the state at every
+        // checkpoint before the next batch.
+        self.record_checksums(max_time, false);
 
         let mut halt = false;
         while let Some(time) = self.scheduler.peek_next_time() {
             if time > max_time {
+                self.record_checksums(max_time, true);
                 return false;
             }
             if let Some(cmd) = self.scheduler.get_next() {
@@ -412,6 +565,9 @@ impl Sim {
                 }
             }
         }
+        if !halt {
+            self.record_checksums(max_time, true);
+        }
 
         self.trip_positions = None;
         halt
@@ -575,8 +731,14 @@ impl Sim {
                 );
             }
             Command::UpdateIntersection(i) => {
-                self.intersections
-                    .update_intersection(self.time, i, map, &mut self.scheduler);
+                let planned = self.plan_signal_updates(i, map);
+                self.intersections.update_intersection(
+                    self.time,
+                    i,
+                    map,
+                    &mut self.scheduler,
+                    planned,
+                );
             }
             Command::Callback(frequency) => {
                 self.scheduler
@@ -600,6 +762,23 @@ impl Sim {
                     &mut self.scheduler,
                 );
             }
+            Command::CheckForGridlock => {
+                // The check stops for good if the threshold was cleared after loading a savestate.
+                if let Some(threshold) = self.gridlock_threshold {
+                    self.scheduler.push(
+                        self.time + CHECK_FOR_GRIDLOCK_FREQUENCY,
+                        Command::CheckForGridlock,
+                    );
+                    self.check_for_gridlock(threshold, map);
+                }
+            }
+            Command::Intervention(idx) => {
+                self.due_interventions.push(idx);
+                halt = true;
+            }
+            Command::StartBus(r, _) => {
+                self.start_bus(map.get_br(r), map);
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -616,14 +795,130 @@ impl Sim {
         events.extend(self.intersections.collect_events());
         events.extend(self.parking.collect_events());
         for ev in events {
+            if let Event::TripPhaseStarting(trip, _, _, phase_type) = ev {
+                self.trips.trip_phase_starting(self.time, trip, phase_type);
+            }
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
             }
+            if let Event::AgentEntersTraversable(a, Traversable::Turn(t)) = ev {
+                if let Some((trip, ref mut turns)) = self.tracked_turns {
+                    if self.trips.agent_to_trip(a) == Some(trip) {
+                        turns.push(t);
+                    }
+                }
+            }
+
+            let sim_ev = match ev {
+                Event::TripStarted(trip, person) => Some(SimEvent::TripStarted(trip, person)),
+                Event::TripFinished {
+                    trip,
+                    mode,
+                    total_time,
+                    ..
+                } => Some(SimEvent::TripFinished {
+                    trip,
+                    person: self.trips.trip_to_person(trip),
+                    mode,
+                    total_time,
+                }),
+                Event::TripAborted(trip) => {
+                    Some(SimEvent::TripAborted(trip, self.trips.trip_to_person(trip)))
+                }
+                Event::AgentSpawned(a) => Some(SimEvent::AgentSpawned(a)),
+                Event::AgentDespawned(a) => Some(SimEvent::AgentDespawned(a)),
+                Event::BusArrivedAtStop(bus, route, stop) => {
+                    Some(SimEvent::BusArrivedAtStop(bus, route, stop))
+                }
+                _ => None,
+            };
+            if let Some(sim_ev) = sim_ev {
+                self.push_sim_event(sim_ev);
+            }
 
             self.analytics.event(ev, self.time, map);
         }
     }
 
+    // Lots of traffic signals tend to change phase at the same moment. Work out what they'll all do
+    // in parallel the first time one comes up. They're still applied one at a time, in the usual
+    // order, and any plan made stale by something that happened in between gets redone.
+    fn plan_signal_updates(&mut self, i: IntersectionID, map: &Map) -> Option<SignalUpdate> {
+        if self.num_threads <= 1 {
+            return None;
+        }
+        if self.signal_updates.as_ref().map(|(t, _)| *t) != Some(self.time) {
+            // The command for this signal has already been removed from the scheduler
+            let mut ids = self.scheduler.intersection_updates_at(self.time);
+            ids.push(i);
+            let plans = if ids.len() >= MIN_PARALLEL_SIGNAL_UPDATES {
+                let pool = self.workers.get(self.num_threads);
+                self.intersections
+                    .plan_signal_updates(self.time, ids, map, pool)
+            } else {
+                BTreeMap::new()
+            };
+            self.signal_updates = Some((self.time, plans));
+        }
+        self.signal_updates.as_mut().unwrap().1.remove(&i)
+    }
+
+    fn check_for_gridlock(&mut self, threshold: Duration, map: &Map) {
+        let pool = if self.num_threads > 1 && map.all_lanes().len() >= MIN_PARALLEL_GRIDLOCK_LANES {
+            Some(self.workers.get(self.num_threads))
+        } else {
+            None
+        };
+        let cycle = if let Some(cycle) =
+            self.driving
+                .find_gridlock(self.time, threshold, &self.intersections, pool)
+        {
+            cycle
+        } else {
+            self.gridlock = None;
+            return;
+        };
+
+        let agents: Vec<AgentID> = cycle.iter().map(|(c, _)| AgentID::Car(*c)).collect();
+        // Only report each gridlock once
+        if self.gridlock.as_ref().map(|g| &g.agents) == Some(&agents) {
+            return;
+        }
+        let turns: Vec<TurnID> = cycle.iter().filter_map(|(_, t)| *t).collect();
+        let mut intersections = BTreeSet::new();
+        for (c, t) in &cycle {
+            if let Some(t) = t {
+                intersections.insert(t.parent);
+            } else {
+                intersections.insert(match self.driving.get_path(*c).unwrap().current_step() {
+                    PathStep::Lane(l) | PathStep::ContraflowLane(l) => map.get_l(l).dst_i,
+                    PathStep::Turn(t) => t.parent,
+                });
+            }
+        }
+        let intersections: Vec<IntersectionID> = intersections.into_iter().collect();
+        self.push_sim_event(SimEvent::Gridlock(intersections.clone()));
+        self.gridlock = Some(Gridlock {
+            agents,
+            turns,
+            intersections,
+        });
+    }
+
+    fn push_sim_event(&mut self, ev: SimEvent) {
+        if self.sim_events.len() == MAX_PENDING_SIM_EVENTS {
+            self.sim_events.pop_front();
+        }
+        self.sim_events.push_back((self.time, ev));
+    }
+
+    // Everything interesting that's happened since the last call, in order. The UI should call
+    // this once per frame. Nothing's lost if it isn't called for a while, unless the backlog gets
+    // huge.
+    pub fn collect_events(&mut self) -> Vec<(Time, SimEvent)> {
+        self.sim_events.drain(..).collect()
+    }
+
     pub fn timed_step(
         &mut self,
         map: &Map,
@@ -725,6 +1020,435 @@ impl Sim {
     }
 }
 
+// Verifying determinism
+impl Sim {
+    // Record a checksum for every checkpoint up to the given time. Only call this when nothing
+    // else will happen before that time.
+    fn record_checksums(&mut self, until: Time, inclusive: bool) {
+        let interval = if let Some(dt) = self.checkpoint_interval {
+            dt
+        } else {
+            return;
+        };
+        let mut checksum = None;
+        loop {
+            let next = self.next_checkpoint(interval);
+            if next > until || (next == until && !inclusive) {
+                break;
+            }
+            // The state is the same at all of these checkpoints
+            if checksum.is_none() {
+                checksum = Some(self.checksum());
+            }
+            self.checksums.push((next, checksum.unwrap()));
+        }
+    }
+
+    fn next_checkpoint(&self, interval: Duration) -> Time {
+        self.checksums
+            .last()
+            .map(|(t, _)| *t)
+            .unwrap_or(Time::START_OF_DAY)
+            + interval
+    }
+
+    // Covers agent positions, intersection queues, everything scheduled to happen later, and the
+    // pandemic model if there is one. The current time is skipped, since it depends on how the
+    // caller steps the sim.
+    fn checksum(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (_, bytes) in self.serialized_parts() {
+            bytes.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn serialized_parts(&self) -> Vec<(&'static str, Vec<u8>)> {
+        let mut parts = vec![
+            ("driving", abstutil::to_binary(&self.driving)),
+            ("parking", abstutil::to_binary(&self.parking)),
+            ("walking", abstutil::to_binary(&self.walking)),
+            ("intersections", abstutil::to_binary(&self.intersections)),
+            ("transit", abstutil::to_binary(&self.transit)),
+            ("trips", abstutil::to_binary(&self.trips)),
+            ("scheduler", abstutil::to_binary(&self.scheduler)),
+        ];
+        if let Some(ref m) = self.pandemic {
+            parts.push(("pandemic", m.fingerprint()));
+        }
+        parts
+    }
+
+    pub fn get_checksums(&self) -> &Vec<(Time, u64)> {
+        &self.checksums
+    }
+
+    // Runs two sims that were set up the same way side-by-side, comparing the checksums at every
+    // checkpoint. On the first mismatch, both states are saved for diffing, and the divergent
+    // checkpoint is returned.
+    pub fn verify_determinism(
+        &mut self,
+        other: &mut Sim,
+        map: &Map,
+        // Interpreted as a relative time
+        time_limit: Option<Duration>,
+    ) -> Option<Time> {
+        let interval = self
+            .checkpoint_interval
+            .expect("verify_determinism needs SimOptions::checkpoint_interval");
+        let end_time = time_limit.map(|dt| self.time + dt);
+        for (name, sim) in vec![("first", &*self), ("second", &*other)] {
+            if let Some((t, _)) = sim.live_edits.get(0) {
+                println!(
+                    "Warning: the {} run had map edits applied live at {}, so a fresh run won't \
+                     match it",
+                    name, t
+                );
+            }
+        }
+
+        loop {
+            // Step exactly to the next checkpoint, so a divergent state is saved right when it
+            // happens
+            let dt = self.next_checkpoint(interval) - self.time;
+            self.timed_step(map, dt, &mut None, &mut Timer::throwaway());
+            other.timed_step(map, dt, &mut None, &mut Timer::throwaway());
+
+            let diverged = self
+                .checksums
+                .iter()
+                .zip(other.checksums.iter())
+                .find(|(a, b)| a != b)
+                .map(|((t, _), _)| *t);
+            if let Some(t) = diverged {
+                println!("Runs diverged at {}", t);
+                let a = self.serialized_parts();
+                let b = other.serialized_parts();
+                for ((name, bytes1), (_, bytes2)) in a.into_iter().zip(b.into_iter()) {
+                    if bytes1 != bytes2 {
+                        println!("- {} differs", name);
+                    }
+                }
+                let divergence = self.compare_trips(other, map);
+                for line in divergence.describe() {
+                    println!("- {}", line);
+                }
+                let path = self.divergence_report_path();
+                if let Err(err) = divergence.append_to(&path) {
+                    println!("Couldn't write {}: {}", path, err);
+                }
+                self.save_divergence(t, "a");
+                other.save_divergence(t, "b");
+                return Some(t);
+            }
+
+            if self.is_done() && other.is_done() {
+                break;
+            }
+            if let Some(t) = end_time {
+                if self.time >= t {
+                    break;
+                }
+            }
+        }
+        println!(
+            "Runs matched at all {} checkpoints",
+            abstutil::prettyprint_usize(self.checksums.len())
+        );
+        None
+    }
+
+    // Both sims must be at the same time.
+    pub fn compare_trips(&mut self, other: &mut Sim, map: &Map) -> Divergence {
+        self.get_trip_positions(map);
+        other.get_trip_positions(map);
+        Divergence::new(
+            self.trip_positions.as_ref().unwrap(),
+            other.trip_positions.as_ref().unwrap(),
+            |t| self.why_trip_inactive(t),
+            |t| other.why_trip_inactive(t),
+        )
+    }
+
+    // Only meaningful for trips that don't have a position right now
+    pub fn why_trip_inactive(&self, trip: TripID) -> Inactive {
+        match self.trips.trip_to_agent(trip) {
+            TripResult::TripDone => {
+                let (departure, _, _, _) = self.trips.trip_info(trip);
+                let (duration, _) = self.trips.finished_trip_time(trip).unwrap();
+                Inactive::Finished(departure + duration)
+            }
+            TripResult::TripAborted => Inactive::Aborted,
+            TripResult::TripNotStarted => Inactive::NotStarted,
+            _ => Inactive::Other,
+        }
+    }
+
+    // Start recording every turn this trip makes from now on, forgetting any other trip
+    pub fn track_turns(&mut self, trip: Option<TripID>) {
+        self.tracked_turns = trip.map(|t| (t, Vec::new()));
+    }
+
+    pub fn get_tracked_turns(&self) -> &[TurnID] {
+        self.tracked_turns
+            .as_ref()
+            .map(|(_, turns)| turns.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn divergence_report_path(&self) -> String {
+        format!("{}/divergence_report.txt", self.save_dir())
+    }
+
+    fn save_divergence(&mut self, t: Time, suffix: &str) {
+        let restore = self.scheduler.before_savestate();
+        abstutil::write_binary(
+            format!(
+                "{}/diverged_at_{}_{}.bin",
+                self.save_dir(),
+                t.as_filename(),
+                suffix
+            ),
+            self,
+        );
+        self.scheduler.after_savestate(restore);
+    }
+}
+
+// Live edits
+impl Sim {
+    // The kinds of edits that prevent applying these commands to the running sim
+    pub fn live_edit_blockers(cmds: &[EditCmd]) -> Vec<&'static str> {
+        let mut kinds = Vec::new();
+        for cmd in cmds {
+            if !cmd.is_live_safe() && !kinds.contains(&cmd.kind()) {
+                kinds.push(cmd.kind());
+            }
+        }
+        kinds
+    }
+
+    // The map must already have these edits applied. Agents pick up the changes the next time
+    // they reach the affected road or intersection; nobody's path is recalculated.
+    pub fn handle_live_edits(
+        &mut self,
+        map: &Map,
+        cmds: &[EditCmd],
+    ) -> Result<(), Vec<&'static str>> {
+        let blockers = Sim::live_edit_blockers(cmds);
+        if !blockers.is_empty() {
+            return Err(blockers);
+        }
+        // Plans made earlier this step might be for the old signal timing
+        self.signal_updates = None;
+        for cmd in cmds {
+            if let EditCmd::ChangeIntersection { i, .. } = cmd {
+                if map.maybe_get_traffic_signal(*i).is_some() {
+                    self.intersections.handle_live_edited_traffic_signal(
+                        self.time,
+                        *i,
+                        &mut self.scheduler,
+                    );
+                }
+            }
+            self.live_edits.push((self.time, cmd.short_name()));
+        }
+        Ok(())
+    }
+
+    pub fn get_live_edits(&self) -> &Vec<(Time, String)> {
+        &self.live_edits
+    }
+}
+
+// Scheduled interventions. When one comes due, the sim won't advance until the caller applies it,
+// since most need to change the map.
+impl Sim {
+    pub fn has_due_interventions(&self) -> bool {
+        !self.due_interventions.is_empty()
+    }
+
+    pub fn upcoming_interventions(&self) -> Vec<(Time, &Intervention)> {
+        self.interventions
+            .iter()
+            .filter(|(t, _)| *t > self.time)
+            .map(|(t, x)| (*t, x))
+            .collect()
+    }
+
+    // For interventions decided on partway through. They don't survive a reset; the caller has to
+    // pass them in SimOptions again.
+    pub fn schedule_intervention(&mut self, time: Time, intervention: Intervention) {
+        let time = time.max(self.time);
+        self.scheduler
+            .push(time, Command::Intervention(self.interventions.len()));
+        self.interventions.push((time, intervention));
+    }
+
+    pub fn get_intervention_edits(&self) -> &Vec<EditCmd> {
+        &self.intervention_edits
+    }
+
+    // When the map catches up to the intervention edits some other way, like after a reset, any
+    // signals it changed have to start over.
+    pub fn restart_traffic_signals(&mut self, map: &Map, signals: BTreeSet<IntersectionID>) {
+        self.signal_updates = None;
+        for i in signals {
+            if map.maybe_get_traffic_signal(i).is_some() {
+                self.intersections.handle_live_edited_traffic_signal(
+                    self.time,
+                    i,
+                    &mut self.scheduler,
+                );
+            }
+        }
+    }
+
+    // Returns the edits that the due interventions need. The caller has to apply them, recalculate
+    // pathfinding, then call finish_interventions.
+    pub fn start_interventions(&mut self, map: &Map) -> MapEdits {
+        for idx in self.due_interventions.clone() {
+            match self.interventions[idx].1.clone() {
+                Intervention::CloseLane(l) => {
+                    let lane = map.get_l(l);
+                    let cmd = EditCmd::CloseLane { id: l };
+                    if self.intervention_edits.contains(&cmd) {
+                        continue;
+                    }
+                    if !lane.is_driving() && !lane.is_biking() && !lane.is_bus() {
+                        self.intervention_failed(format!(
+                            "Can't close {}; only driving, bike, and bus lanes can be",
+                            l
+                        ));
+                        continue;
+                    }
+                    // Buses can't leave their route
+                    if self
+                        .driving
+                        .cars_routed_over(l)
+                        .iter()
+                        .any(|c| c.1 == VehicleType::Bus)
+                    {
+                        self.intervention_failed(format!("Can't close {}; a bus is using it", l));
+                        continue;
+                    }
+                    self.intervention_edits.push(cmd);
+                }
+                Intervention::ReopenLane(l) => {
+                    let cmd = EditCmd::CloseLane { id: l };
+                    if self.intervention_edits.contains(&cmd) {
+                        self.intervention_edits.retain(|x| x != &cmd);
+                    } else {
+                        self.intervention_failed(format!(
+                            "Can't reopen {}; no intervention closed it",
+                            l
+                        ));
+                    }
+                }
+                Intervention::ChangeSpeedLimit(r, speed) => {
+                    self.intervention_edits.push(EditCmd::ChangeSpeedLimit {
+                        id: r,
+                        new: speed,
+                        old: map.get_r(r).speed_limit,
+                    });
+                }
+                Intervention::SwitchSignalPlan(i, path) => {
+                    if map.maybe_get_traffic_signal(i).is_none() {
+                        self.intervention_failed(format!("{} doesn't have a traffic signal", i));
+                    } else if let Some(signal) =
+                        ControlTrafficSignal::import_from_file(path.clone(), i, map)
+                    {
+                        self.intervention_edits.push(EditCmd::ChangeIntersection {
+                            i,
+                            new: EditIntersection::TrafficSignal(signal),
+                            old: map.get_i_edit(i),
+                        });
+                    } else {
+                        self.intervention_failed(format!("{} doesn't fit {}", path, i));
+                    }
+                }
+                Intervention::InjectTrips { .. } => {}
+            }
+        }
+        let mut edits = map.get_edits().clone();
+        edits.temporary = self.intervention_edits.clone();
+        edits
+    }
+
+    pub fn finish_interventions(&mut self, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) {
+        // The map just changed underneath any signal updates planned for this step
+        self.signal_updates = None;
+        for idx in std::mem::replace(&mut self.due_interventions, Vec::new()) {
+            let intervention = self.interventions[idx].1.clone();
+            match intervention {
+                Intervention::SwitchSignalPlan(i, _) => {
+                    if map.maybe_get_traffic_signal(i).is_some() {
+                        self.intersections.handle_live_edited_traffic_signal(
+                            self.time,
+                            i,
+                            &mut self.scheduler,
+                        );
+                    }
+                }
+                Intervention::InjectTrips {
+                    ref from,
+                    ref to,
+                    mode,
+                    num,
+                } => {
+                    let first_id = self.trips.get_all_people().len();
+                    let scenario = Scenario {
+                        scenario_name: format!("injected trips at {}", self.time),
+                        map_name: self.map_name.clone(),
+                        people: (0..num)
+                            .map(|i| PersonSpec {
+                                id: PersonID(first_id + i),
+                                orig_id: None,
+                                trips: vec![IndividTrip {
+                                    depart: self.time,
+                                    trip: SpawnTrip::new(from.clone(), to.clone(), mode, map),
+                                }],
+                            })
+                            .collect(),
+                        only_seed_buses: None,
+                    };
+                    scenario.instantiate_people(self, map, rng, timer);
+                }
+                Intervention::CloseLane(l) => {
+                    if map.get_edits().is_lane_closed(l) {
+                        // Anybody with no other way to go gives up.
+                        // TODO Trips that already found their path upfront will still use it.
+                        for c in self.driving.reroute_around(l, map) {
+                            self.kill_stuck_car(c, map);
+                        }
+                    }
+                }
+                Intervention::ReopenLane(_) | Intervention::ChangeSpeedLimit(_, _) => {}
+            }
+            self.push_sim_event(SimEvent::InterventionFired(intervention));
+        }
+    }
+
+    // For callers that don't need to do anything else when the map changes
+    pub fn apply_due_interventions(
+        &mut self,
+        map: &mut Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) {
+        let edits = self.start_interventions(map);
+        map.apply_edits(edits, timer);
+        map.recalculate_pathfinding_after_edits(timer);
+        self.finish_interventions(map, rng, timer);
+    }
+
+    fn intervention_failed(&mut self, msg: String) {
+        self.analytics
+            .alerts
+            .push((self.time, AlertLocation::Nil, msg));
+    }
+}
+
 // Helpers to run the sim
 // TODO Old and gunky
 impl Sim {
@@ -737,6 +1461,8 @@ impl Sim {
     ) {
         let mut last_print = Instant::now();
         let mut last_sim_time = self.time();
+        let started = Instant::now();
+        let started_steps = self.step_count;
 
         loop {
             // TODO Regular printing doesn't happen if we use a time_limit :\
@@ -772,6 +1498,9 @@ impl Sim {
                 last_sim_time = self.time();
             }
             callback(self, map);
+            if self.has_due_interventions() {
+                panic!("Interventions need to change the map, so use apply_due_interventions");
+            }
             if self.is_done() {
                 println!(
                     "{}: speed = {:.2}x, {}",
@@ -779,6 +1508,13 @@ impl Sim {
                     (self.time() - last_sim_time) / dt_real,
                     self.scheduler.describe_stats()
                 );
+                let steps = self.step_count - started_steps;
+                println!(
+                    "{} steps on {} threads, {} per step",
+                    abstutil::prettyprint_usize(steps),
+                    self.num_threads,
+                    Duration::realtime_elapsed(started) / (steps.max(1) as f64)
+                );
                 break;
             }
 
@@ -789,6 +1525,14 @@ impl Sim {
     }
 }
 
+// Version 0 is before savestates were versioned.
+// - Version 2 closes lanes with a map edit instead of changing their type
+// - Version 3 remembers the map edits made by interventions
+impl Versioned for Sim {
+    const NAME: &'static str = "savestate";
+    const VERSION: u32 = 3;
+}
+
 // Savestating
 impl Sim {
     pub fn save_dir(&self) -> String {
@@ -842,7 +1586,7 @@ impl Sim {
         }
 
         let path = self.save_path(self.time);
-        abstutil::write_binary(path.clone(), self);
+        abstutil::write_versioned(path.clone(), self);
 
         self.scheduler.after_savestate(restore);
 
@@ -857,16 +1601,19 @@ impl Sim {
         abstutil::find_next_file(self.save_path(base_time))
     }
 
-    pub fn load_savestate(
-        path: String,
-        map: &Map,
-        timer: &mut Timer,
-    ) -> Result<Sim, std::io::Error> {
-        let mut sim: Sim = abstutil::maybe_read_binary(path, timer)?;
+    pub fn load_savestate(path: String, map: &Map, timer: &mut Timer) -> Result<Sim, String> {
+        let mut sim: Sim = abstutil::maybe_read_versioned(path, timer)?;
         sim.restore_paths(map, timer);
+        sim.set_gridlock_threshold(Some(SimOptions::DEFAULT_GRIDLOCK_THRESHOLD));
         Ok(sim)
     }
 
+    // Savestates don't remember the gridlock threshold, so set it again after loading. This only
+    // matters if the savestate was already checking for gridlock; it doesn't start checking.
+    pub fn set_gridlock_threshold(&mut self, threshold: Option<Duration>) {
+        self.gridlock_threshold = threshold;
+    }
+
     pub fn restore_paths(&mut self, map: &Map, timer: &mut Timer) {
         let paths = timer.parallelize(
             "calculate paths",
@@ -918,24 +1665,64 @@ impl Sim {
         self.driving.debug_lane(id);
     }
 
+    // The full internal state of an active agent, as JSON. Riders are just part of their bus.
+    pub fn agent_state_json(&self, id: AgentID) -> Option<String> {
+        match id {
+            AgentID::Car(c) => self.driving.car_json(c),
+            AgentID::Pedestrian(p) => self.walking.ped_json(p),
+            AgentID::BusPassenger(_, _) => None,
+        }
+    }
+
+    // False once a car parks or a pedestrian reaches their destination
+    pub fn does_agent_exist(&self, id: AgentID) -> bool {
+        match id {
+            AgentID::Car(id) => self.driving.does_car_exist(id),
+            AgentID::Pedestrian(id) => self.walking.does_ped_exist(id),
+            AgentID::BusPassenger(_, bus) => self.driving.does_car_exist(bus),
+        }
+    }
+
     // Only call for active agents, will panic otherwise
-    pub fn agent_properties(&self, id: AgentID) -> AgentProperties {
+    pub fn agent_properties(&self, id: AgentID, map: &Map) -> AgentProperties {
         match id {
-            AgentID::Pedestrian(id) => self.walking.agent_properties(id, self.time),
-            AgentID::Car(id) => self.driving.agent_properties(id, self.time),
-            // TODO Harder to measure some of this stuff
-            AgentID::BusPassenger(_, _) => AgentProperties {
-                total_time: Duration::ZERO,
-                waiting_here: Duration::ZERO,
-                total_waiting: Duration::ZERO,
-                dist_crossed: Distance::ZERO,
-                total_dist: Distance::meters(0.1),
-                lanes_crossed: 0,
-                total_lanes: 0,
-            },
+            AgentID::Pedestrian(id) => self.walking.agent_properties(id, self.time, map),
+            AgentID::Car(id) => self
+                .driving
+                .agent_properties(id, self.time, &self.intersections),
+            // TODO Harder to measure some of this stuff. At least riders are wherever their bus is.
+            AgentID::BusPassenger(_, bus) => {
+                let bus = self
+                    .driving
+                    .agent_properties(bus, self.time, &self.intersections);
+                AgentProperties {
+                    total_time: Duration::ZERO,
+                    waiting_here: Duration::ZERO,
+                    total_waiting: Duration::ZERO,
+                    dist_crossed: Distance::ZERO,
+                    total_dist: Distance::meters(0.1),
+                    lanes_crossed: 0,
+                    total_lanes: 0,
+                    on: bus.on,
+                    dist_along: bus.dist_along,
+                    speed: bus.speed,
+                    waiting_for: None,
+                }
+            }
         }
     }
 
+    // Cheap; the stats are recalculated for a lane whenever a car on it changes state, so they're
+    // exact as of the current time, even between steps.
+    pub fn lane_stats(&self, l: LaneID) -> LaneStats {
+        self.driving.get_lane_stats(l)
+    }
+
+    // Every lane with some vehicle on it. Any lane missing is empty.
+    pub fn all_lane_stats(&self) -> &BTreeMap<LaneID, LaneStats> {
+        self.driving.get_all_lane_stats()
+    }
+
     // TODO Temporary until we figure out all the info to expose
     pub fn bus_properties(&self, car: CarID, map: &Map) -> Vec<(String, String)> {
         let passengers = self.transit.get_passengers(car);
@@ -980,6 +1767,28 @@ impl Sim {
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips.trip_to_person(id)
     }
+    // For IDs typed in by the player, which might not exist
+    pub fn maybe_trip_to_person(&self, id: TripID) -> Option<PersonID> {
+        self.trips.maybe_trip_to_person(id)
+    }
+
+    // These use indices built when the scenario is instantiated, so they're cheap.
+    pub fn trips_from_bldg(&self, b: BuildingID) -> &[TripID] {
+        self.trips.trips_from_bldg(b)
+    }
+    pub fn trips_to_bldg(&self, b: BuildingID) -> &[TripID] {
+        self.trips.trips_to_bldg(b)
+    }
+    pub fn trips_by_mode(&self, mode: TripMode) -> &[TripID] {
+        self.trips.trips_by_mode(mode)
+    }
+    pub fn trips_departing_between(&self, t1: Time, t2: Time) -> Vec<TripID> {
+        self.trips.trips_departing_between(t1, t2)
+    }
+    pub fn trip_detail(&self, id: TripID) -> TripDetail {
+        self.trips.trip_detail(id)
+    }
+
     // TODO This returns None for parked cars owned by people! That's confusing. Dedupe with
     // get_owner_of_car.
     pub fn agent_to_person(&self, id: AgentID) -> Option<PersonID> {
@@ -1102,6 +1911,14 @@ impl Sim {
     pub fn get_accepted_agents(&self, id: IntersectionID) -> HashSet<AgentID> {
         self.intersections.get_accepted_agents(id)
     }
+    // The crosswalks at a traffic signal showing walk right now
+    pub fn get_walk_signals(&self, i: IntersectionID) -> &BTreeSet<TurnGroupID> {
+        self.intersections.get_walk_signals(i)
+    }
+    // The most recent gridlock found, if it's still there
+    pub fn get_gridlock(&self) -> Option<&Gridlock> {
+        self.gridlock.as_ref()
+    }
     pub fn get_blocked_by(&self, a: AgentID) -> HashSet<AgentID> {
         self.intersections.get_blocked_by(a)
     }
@@ -1232,4 +2049,653 @@ pub struct AgentProperties {
 
     pub lanes_crossed: usize,
     pub total_lanes: usize,
+
+    // Where the agent is right now
+    pub on: Traversable,
+    pub dist_along: Distance,
+    // The sim doesn't model acceleration; agents jump straight to their speed.
+    pub speed: Speed,
+    // Who the agent is stuck behind, if anyone
+    pub waiting_for: Option<AgentID>,
+}
+
+// A cycle of agents, each waiting on the next one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gridlock {
+    pub agents: Vec<AgentID>,
+    // The turns that agents at the front of their lane are waiting to make
+    pub turns: Vec<TurnID>,
+    pub intersections: Vec<IntersectionID>,
+}
+
+// How full a lane is right now. Only vehicles are counted, so sidewalks are always empty.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LaneStats {
+    // Vehicles whose front is on the lane
+    pub occupancy: usize,
+    // Vehicles stopped behind someone or waiting to enter the next intersection
+    pub queue_len: usize,
+    // Stopped vehicles count as 0. None if the lane is empty.
+    pub avg_speed: Option<Speed>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrivingGoal, TripSpec, FOLLOWING_DISTANCE, MAX_CAR_LENGTH};
+    use map_model::{CrosswalkTiming, IntersectionType, Lane, SyntheticMap, TurnType};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_lane_stats_match_recount() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let mut sim = Sim::new(&map, SimOptions::new("test_lane_stats"), &mut timer);
+
+        // Drive from every border into the intersection and out somewhere else, a few cars at a
+        // time, so there's some queueing at the signal.
+        let exits: Vec<(IntersectionID, LaneID)> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.is_driving() && map.get_i(l.dst_i).is_border())
+            .map(|l| (l.dst_i, l.id))
+            .collect();
+        let mut spawner = sim.make_spawner();
+        for lane in map.all_lanes() {
+            if !lane.is_driving() || !map.get_i(lane.src_i).is_border() {
+                continue;
+            }
+            let (exit_i, exit_l) = *exits
+                .iter()
+                .find(|(_, l)| map.get_l(*l).parent != lane.parent)
+                .unwrap();
+            for i in 1..=3 {
+                let dist = (MAX_CAR_LENGTH + FOLLOWING_DISTANCE) * (2 * i) as f64;
+                if dist >= lane.length() {
+                    continue;
+                }
+                let person = sim.random_person(
+                    Speed::miles_per_hour(3.0),
+                    vec![VehicleSpec {
+                        vehicle_type: VehicleType::Car,
+                        length: MIN_CAR_LENGTH,
+                        max_speed: None,
+                    }],
+                );
+                spawner.schedule_trip(
+                    person,
+                    Time::START_OF_DAY,
+                    TripSpec::VehicleAppearing {
+                        start_pos: Position::new(lane.id, dist),
+                        goal: DrivingGoal::Border(exit_i, exit_l, None),
+                        use_vehicle: person.vehicles[0].id,
+                        retry_if_no_room: false,
+                        origin: None,
+                    },
+                    TripEndpoint::Border(lane.src_i, None),
+                    &map,
+                );
+            }
+        }
+        sim.flush_spawner(spawner, &map, &mut timer);
+
+        let mut saw_queue = false;
+        for _ in 0..900 {
+            sim.timed_step(&map, Duration::seconds(1.0), &mut None, &mut timer);
+
+            let expected = sim.driving.recount_lane_stats();
+            let actual = sim.all_lane_stats();
+            assert_eq!(
+                expected.keys().collect::<Vec<_>>(),
+                actual.keys().collect::<Vec<_>>(),
+                "at {}",
+                sim.time()
+            );
+            for (l, stats) in actual {
+                let recount = &expected[l];
+                assert_eq!(
+                    stats.occupancy,
+                    recount.occupancy,
+                    "{} at {}",
+                    l,
+                    sim.time()
+                );
+                assert_eq!(
+                    stats.queue_len,
+                    recount.queue_len,
+                    "{} at {}",
+                    l,
+                    sim.time()
+                );
+                // The sums happen in a different order
+                let (x, y) = (stats.avg_speed.unwrap(), recount.avg_speed.unwrap());
+                assert!(
+                    (x - y).inner_meters_per_second().abs() < 1e-6,
+                    "{} at {}: {} vs {}",
+                    l,
+                    sim.time(),
+                    x,
+                    y
+                );
+                assert_eq!(sim.lane_stats(*l), *stats);
+                if stats.queue_len > 0 {
+                    saw_queue = true;
+                }
+            }
+
+            if sim.is_done() {
+                break;
+            }
+        }
+        assert!(saw_queue);
+        assert!(sim.is_done());
+        assert!(sim.all_lane_stats().is_empty());
+    }
+
+    #[test]
+    fn test_old_versions_load() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+
+        // Before versioning, savestates and scenarios were plain bincode
+        let sim = Sim::new(&map, SimOptions::new("test_versions"), &mut timer);
+        for raw in vec![
+            abstutil::to_binary(&sim),
+            abstutil::to_versioned_binary(&sim),
+        ] {
+            let loaded: Sim = abstutil::from_versioned_binary(&raw).unwrap();
+            assert_eq!(loaded.run_name, "test_versions");
+            assert_eq!(loaded.map_name, sim.map_name);
+            assert_eq!(loaded.time, sim.time);
+        }
+
+        let scenario = crate::Scenario::empty(&map, "test_versions");
+        for raw in vec![
+            abstutil::to_binary(&scenario),
+            abstutil::to_versioned_binary(&scenario),
+        ] {
+            let loaded: crate::Scenario = abstutil::from_versioned_binary(&raw).unwrap();
+            assert_eq!(loaded.scenario_name, "test_versions");
+            assert_eq!(loaded.only_seed_buses, Some(BTreeSet::new()));
+        }
+    }
+
+    // A one-way loop around four stop signs. Pack every lane on the loop, and make everybody drive
+    // most of the way around, so the car at the front of each lane needs to enter the next full
+    // lane.
+    fn gridlock_loop_sim(map: &Map, run_name: &str, timer: &mut Timer) -> Sim {
+        let mut opts = SimOptions::new(run_name);
+        opts.gridlock_threshold = Some(Duration::seconds(30.0));
+        let mut sim = Sim::new(map, opts, timer);
+
+        let ring: Vec<&Lane> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| {
+                l.is_driving() && !map.get_i(l.src_i).is_border() && !map.get_i(l.dst_i).is_border()
+            })
+            .collect();
+        assert_eq!(ring.len(), 4);
+        let spacing = MIN_CAR_LENGTH + FOLLOWING_DISTANCE + Distance::meters(0.1);
+        let mut spawner = sim.make_spawner();
+        for lane in &ring {
+            // The corner just before this lane's start
+            let goal_i = ring.iter().find(|l| l.dst_i == lane.src_i).unwrap().src_i;
+            let exit = map
+                .all_lanes()
+                .iter()
+                .find(|l| l.is_driving() && l.src_i == goal_i && map.get_i(l.dst_i).is_border())
+                .unwrap();
+
+            let mut dist = lane.length() - Distance::meters(0.1);
+            while dist >= MIN_CAR_LENGTH {
+                let person = sim.random_person(
+                    Speed::miles_per_hour(3.0),
+                    vec![VehicleSpec {
+                        vehicle_type: VehicleType::Car,
+                        length: MIN_CAR_LENGTH,
+                        max_speed: None,
+                    }],
+                );
+                spawner.schedule_trip(
+                    person,
+                    Time::START_OF_DAY,
+                    TripSpec::VehicleAppearing {
+                        start_pos: Position::new(lane.id, dist),
+                        goal: DrivingGoal::Border(exit.dst_i, exit.id, None),
+                        use_vehicle: person.vehicles[0].id,
+                        retry_if_no_room: false,
+                        origin: None,
+                    },
+                    TripEndpoint::Border(exit.dst_i, None),
+                    map,
+                );
+                dist -= spacing;
+            }
+        }
+        sim.flush_spawner(spawner, map, timer);
+        sim
+    }
+
+    // Steps until gridlock is reported, giving up after some minutes
+    fn run_until_gridlock(
+        sim: &mut Sim,
+        map: &Map,
+        minutes: usize,
+        timer: &mut Timer,
+    ) -> Option<Vec<IntersectionID>> {
+        for _ in 0..minutes {
+            sim.timed_step(map, Duration::minutes(1), &mut None, timer);
+            for (_, ev) in sim.collect_events() {
+                if let SimEvent::Gridlock(intersections) = ev {
+                    return Some(intersections);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_gridlock_detected() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("gridlock_loop")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let mut sim = gridlock_loop_sim(&map, "test_gridlock", &mut timer);
+        let found = run_until_gridlock(&mut sim, &map, 10, &mut timer);
+
+        let corners: Vec<IntersectionID> = map
+            .all_intersections()
+            .iter()
+            .filter(|i| !i.is_border())
+            .map(|i| i.id)
+            .collect();
+        assert_eq!(found, Some(corners.clone()));
+        let gridlock = sim.get_gridlock().unwrap();
+        assert_eq!(gridlock.intersections, corners);
+        // Each lane's leader is waiting to turn onto the next lane
+        assert_eq!(gridlock.turns.len(), 4);
+        assert!(gridlock.turns.iter().all(|t| corners.contains(&t.parent)));
+        assert_eq!(sim.num_trips().0, 0);
+    }
+
+    #[test]
+    fn test_gridlock_detected_after_savestate() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("gridlock_loop")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let mut sim = gridlock_loop_sim(&map, "test_gridlock_savestate", &mut timer);
+
+        // Save before anybody has been stuck for long
+        sim.timed_step(&map, Duration::seconds(10.0), &mut None, &mut timer);
+        let path = sim.save();
+        let mut loaded = Sim::load_savestate(path.clone(), &map, &mut timer).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        // The loaded sim uses the default threshold, which is longer than the original one
+        assert!(run_until_gridlock(&mut loaded, &map, 15, &mut timer).is_some());
+    }
+
+    #[test]
+    fn test_lane_closure_reroutes_trips() {
+        let mut timer = Timer::throwaway();
+        let synthetic = SyntheticMap::canned("detour_square").unwrap();
+        let mut map = synthetic.build(&mut timer).unwrap();
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+
+        // Drive between the spurs on two adjacent corners of the square. The direct route is one
+        // side; the detour goes around the other three.
+        let find = |name: &str| synthetic.find_i(&map, name).unwrap();
+        let (start_border, corner1, corner2, end_border) =
+            (find("nw_border"), find("nw"), find("ne"), find("ne_border"));
+        let driving_lane = |src: IntersectionID, dst: IntersectionID| {
+            map.all_lanes()
+                .iter()
+                .find(|l| l.is_driving() && l.src_i == src && l.dst_i == dst)
+                .unwrap()
+                .id
+        };
+        let start = driving_lane(start_border, corner1);
+        let direct = driving_lane(corner1, corner2);
+        let end = driving_lane(corner2, end_border);
+
+        // The first car is still on the spur by then
+        let closed_at = Time::START_OF_DAY + Duration::seconds(2.0);
+        let mut opts = SimOptions::new("test_lane_closure");
+        opts.interventions = vec![(closed_at, Intervention::CloseLane(direct))];
+        let mut sim = Sim::new(&map, opts, &mut timer);
+
+        let spawn_car = |sim: &mut Sim, map: &Map| -> CarID {
+            spawn_car_between_borders(sim, map, (start_border, start), (end_border, end))
+        };
+        let uses_direct = |sim: &Sim, car: CarID| {
+            sim.get_path(AgentID::Car(car))
+                .unwrap()
+                .get_steps()
+                .contains(&PathStep::Lane(direct))
+        };
+
+        let before = spawn_car(&mut sim, &map);
+        sim.timed_step(&map, Duration::seconds(1.0), &mut None, &mut timer);
+        assert!(uses_direct(&sim, before));
+
+        // The sim stops right when the intervention is due, and waits for the map to change.
+        sim.timed_step(&map, Duration::minutes(10), &mut None, &mut timer);
+        assert_eq!(sim.time(), closed_at);
+        assert!(sim.has_due_interventions());
+        sim.timed_step(&map, Duration::minutes(1), &mut None, &mut timer);
+        assert_eq!(sim.time(), closed_at);
+
+        sim.apply_due_interventions(&mut map, &mut rng, &mut timer);
+        assert!(!sim.has_due_interventions());
+        // Interventions don't touch the player's edits
+        assert!(map.get_edits().is_lane_closed(direct));
+        assert!(map.get_edits().commands.is_empty());
+        // The car already underway finds another way, instead of giving up
+        assert!(sim.does_agent_exist(AgentID::Car(before)));
+        assert!(!uses_direct(&sim, before));
+        assert!(sim.collect_events().contains(&(
+            closed_at,
+            SimEvent::InterventionFired(Intervention::CloseLane(direct))
+        )));
+
+        let after = spawn_car(&mut sim, &map);
+        sim.timed_step(&map, Duration::seconds(1.0), &mut None, &mut timer);
+        assert!(!uses_direct(&sim, after));
+        sim.timed_step(&map, Duration::minutes(10), &mut None, &mut timer);
+        assert!(sim.is_done());
+        assert_eq!(sim.num_trips().0, 2);
+    }
+
+    #[test]
+    fn test_divergence_when_only_one_run_finishes() {
+        let mut timer = Timer::throwaway();
+        let synthetic = SyntheticMap::canned("detour_square").unwrap();
+        let map = synthetic.build(&mut timer).unwrap();
+        let (start_border, end_border) = (
+            synthetic.find_i(&map, "nw_border").unwrap(),
+            synthetic.find_i(&map, "ne_border").unwrap(),
+        );
+        let start = map
+            .all_lanes()
+            .iter()
+            .find(|l| l.is_driving() && l.src_i == start_border)
+            .unwrap()
+            .id;
+        let end = map
+            .all_lanes()
+            .iter()
+            .find(|l| l.is_driving() && l.dst_i == end_border)
+            .unwrap()
+            .id;
+
+        // The same trip, except it leaves later in the second run
+        let mut a = Sim::new(&map, SimOptions::new("test_divergence_a"), &mut timer);
+        let mut b = Sim::new(&map, SimOptions::new("test_divergence_b"), &mut timer);
+        spawn_car_between_borders(&mut a, &map, (start_border, start), (end_border, end));
+        for sim in vec![&mut a, &mut b] {
+            sim.timed_step(&map, Duration::minutes(1), &mut None, &mut timer);
+        }
+        spawn_car_between_borders(&mut b, &map, (start_border, start), (end_border, end));
+        let trip = TripID(0);
+
+        while !a.is_done() {
+            assert!(a.time() < Time::START_OF_DAY + Duration::hours(1));
+            for sim in vec![&mut a, &mut b] {
+                sim.timed_step(&map, Duration::seconds(10.0), &mut None, &mut timer);
+            }
+        }
+        let finished_at = match a.why_trip_inactive(trip) {
+            Inactive::Finished(t) => t,
+            x => panic!(
+                "{} should've finished in the first run, but it's {:?}",
+                trip, x
+            ),
+        };
+        assert!(finished_at <= a.time());
+        assert!(b.trip_to_agent(trip).ok().is_some());
+
+        let d = a.compare_trips(&mut b, &map);
+        assert!(d.displaced.is_empty());
+        assert!(d.only_in_a.is_empty());
+        assert_eq!(d.only_in_b, vec![(trip, Inactive::Finished(finished_at))]);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let mut timer = Timer::throwaway();
+        // Big enough that the signals and the gridlock check both use the thread pool
+        let map = SyntheticMap::grid(
+            "test_parallel",
+            12,
+            12,
+            IntersectionType::TrafficSignal,
+            "dd/dd",
+        )
+        .build(&mut timer)
+        .unwrap();
+        assert!(map.all_lanes().len() >= MIN_PARALLEL_GRIDLOCK_LANES);
+
+        let starts: Vec<(IntersectionID, LaneID)> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.is_driving() && map.get_i(l.src_i).is_border())
+            .map(|l| (l.src_i, l.id))
+            .collect();
+        let ends: Vec<(IntersectionID, LaneID)> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.is_driving() && map.get_i(l.dst_i).is_border())
+            .map(|l| (l.dst_i, l.id))
+            .collect();
+
+        let mut checksums = Vec::new();
+        for num_threads in vec![1, 4] {
+            let mut opts = SimOptions::new("test_parallel");
+            opts.num_threads = num_threads;
+            opts.checkpoint_interval = Some(Duration::seconds(30.0));
+            opts.gridlock_threshold = Some(Duration::seconds(30.0));
+            let mut sim = Sim::new(&map, opts, &mut timer);
+            // Everyone crosses to the other side of the grid
+            for (idx, start) in starts.iter().enumerate() {
+                let end = ends[(idx + ends.len() / 2) % ends.len()];
+                if end.0 != start.0 {
+                    spawn_car_between_borders(&mut sim, &map, *start, end);
+                }
+            }
+            sim.timed_step(&map, Duration::minutes(10), &mut None, &mut timer);
+            checksums.push(sim.get_checksums().clone());
+        }
+        assert!(!checksums[0].is_empty());
+        assert_eq!(checksums[0], checksums[1]);
+    }
+
+    #[test]
+    fn test_checksums_cover_pandemic() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let make_sim = |seed: u64| {
+            let mut opts = SimOptions::new("test_checksums_pandemic");
+            opts.checkpoint_interval = Some(Duration::seconds(30.0));
+            opts.enable_pandemic_model = Some(XorShiftRng::seed_from_u64(seed));
+            let mut sim = Sim::new(&map, opts, &mut Timer::throwaway());
+            // Initializes the pandemic model, with nobody in it
+            let spawner = sim.make_spawner();
+            sim.flush_spawner(spawner, &map, &mut Timer::throwaway());
+            sim
+        };
+        let first_mismatch = |a: &Sim, b: &Sim| {
+            a.get_checksums()
+                .iter()
+                .zip(b.get_checksums().iter())
+                .find(|(x, y)| x != y)
+                .map(|((t, _), _)| *t)
+        };
+
+        let mut a = make_sim(42);
+        let mut b = make_sim(42);
+        let mut perturbed = make_sim(42);
+        let mut other_seed = make_sim(43);
+        for sim in vec![&mut a, &mut b, &mut perturbed, &mut other_seed] {
+            sim.timed_step(&map, Duration::seconds(45.0), &mut None, &mut timer);
+        }
+        // Only the pandemic model notices this
+        let now = perturbed.time();
+        perturbed.pandemic.as_mut().unwrap().handle_event(
+            now,
+            &Event::PersonEntersBuilding(PersonID(0), BuildingID(0)),
+            &mut perturbed.scheduler,
+        );
+        for sim in vec![&mut a, &mut b, &mut perturbed, &mut other_seed] {
+            sim.timed_step(&map, Duration::minutes(2), &mut None, &mut timer);
+        }
+
+        assert!(a.get_checksums().len() >= 4);
+        assert_eq!(a.get_checksums(), b.get_checksums());
+        assert_eq!(
+            first_mismatch(&a, &perturbed),
+            Some(Time::START_OF_DAY + Duration::seconds(60.0))
+        );
+        // Nothing has used the RNG yet, but it's still part of the state
+        assert_eq!(
+            first_mismatch(&a, &other_seed),
+            Some(Time::START_OF_DAY + Duration::seconds(30.0))
+        );
+    }
+
+    #[test]
+    fn test_ped_waits_for_walk() {
+        let mut timer = Timer::throwaway();
+        let mut map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.is_traffic_signal())
+            .unwrap()
+            .id;
+        // Nobody gets a walk signal without pressing the button
+        let mut signal = map.get_traffic_signal(i).clone();
+        for phase in &mut signal.phases {
+            phase.crosswalk_timing = CrosswalkTiming::Actuated;
+        }
+        let mut edits = map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeIntersection {
+            i,
+            new: EditIntersection::TrafficSignal(signal),
+            old: map.get_i_edit(i),
+        });
+        map.apply_edits(edits, &mut timer);
+
+        let g = map
+            .get_traffic_signal(i)
+            .turn_groups
+            .values()
+            .find(|g| g.turn_type == TurnType::Crosswalk)
+            .unwrap()
+            .clone();
+        let crosswalk = map.get_t(g.members[0]);
+        // Start right at the corner and end at the far end of the sidewalk across the street
+        let src = map.get_l(crosswalk.id.src);
+        let start_dist = if src.dst_i == i {
+            src.length() - Distance::meters(1.0)
+        } else {
+            Distance::meters(1.0)
+        };
+        let dst = map.get_l(crosswalk.id.dst);
+        let (end_dist, end_border) = if dst.src_i == i {
+            (dst.length(), dst.dst_i)
+        } else {
+            (Distance::ZERO, dst.src_i)
+        };
+
+        let mut sim = Sim::new(&map, SimOptions::new("test_ped_waits_for_walk"), &mut timer);
+        let mut spawner = sim.make_spawner();
+        let person = sim.random_person(Speed::miles_per_hour(3.0), Vec::new());
+        let ped = person.ped;
+        spawner.schedule_trip(
+            person,
+            Time::START_OF_DAY,
+            TripSpec::JustWalking {
+                start: SidewalkSpot::suddenly_appear(src.id, start_dist, &map),
+                goal: SidewalkSpot {
+                    connection: SidewalkPOI::Border(end_border, None),
+                    sidewalk_pos: Position::new(dst.id, end_dist),
+                },
+            },
+            TripEndpoint::Border(end_border, None),
+            &map,
+        );
+        sim.flush_spawner(spawner, &map, &mut timer);
+
+        // Nothing's showing walk yet, so they press the button and wait
+        sim.timed_step(&map, Duration::seconds(5.0), &mut None, &mut timer);
+        let draw = sim.get_draw_ped(ped, &map).unwrap();
+        assert_eq!(draw.waiting_for_turn, Some(crosswalk.id));
+        assert!(!sim.get_walk_signals(i).contains(&g.id));
+
+        // When the next phase with this crosswalk starts, it shows walk and they go
+        let mut walk_since = None;
+        loop {
+            assert!(sim.time() < Time::START_OF_DAY + Duration::minutes(5));
+            sim.timed_step(&map, Duration::seconds(1.0), &mut None, &mut timer);
+            let walking = sim.get_walk_signals(i).contains(&g.id);
+            if walking && walk_since.is_none() {
+                walk_since = Some(sim.time());
+            }
+            if sim.get_draw_ped(ped, &map).unwrap().on == Traversable::Turn(crosswalk.id) {
+                assert!(walking);
+                assert!(sim.time() - walk_since.unwrap() <= Duration::seconds(1.0));
+                break;
+            }
+        }
+    }
+
+    fn spawn_car_between_borders(
+        sim: &mut Sim,
+        map: &Map,
+        (start_border, start): (IntersectionID, LaneID),
+        (end_border, end): (IntersectionID, LaneID),
+    ) -> CarID {
+        let now = sim.time();
+        let mut spawner = sim.make_spawner();
+        let person = sim.random_person(
+            Speed::miles_per_hour(3.0),
+            vec![VehicleSpec {
+                vehicle_type: VehicleType::Car,
+                length: MIN_CAR_LENGTH,
+                max_speed: None,
+            }],
+        );
+        let car = person.vehicles[0].id;
+        spawner.schedule_trip(
+            person,
+            now,
+            TripSpec::VehicleAppearing {
+                start_pos: Position::new(start, MIN_CAR_LENGTH),
+                goal: DrivingGoal::Border(end_border, end, None),
+                use_vehicle: car,
+                retry_if_no_room: false,
+                origin: None,
+            },
+            TripEndpoint::Border(start_border, None),
+            map,
+        );
+        sim.flush_spawner(spawner, map, &mut Timer::throwaway());
+        car
+    }
 }
@@ -76,13 +76,7 @@ impl TransitSimState {
         }
     }
 
-    // Returns (next stop, first path, end distance for next stop) for all of the stops in the
-    // route.
-    pub fn create_empty_route(
-        &mut self,
-        bus_route: &BusRoute,
-        map: &Map,
-    ) -> Vec<(StopIdx, PathRequest, Path, Distance)> {
+    pub fn create_empty_route(&mut self, bus_route: &BusRoute, map: &Map) {
         assert!(bus_route.stops.len() > 1);
 
         let route = Route {
@@ -117,8 +111,14 @@ impl TransitSimState {
                 })
                 .collect(),
         };
+        self.routes.insert(bus_route.id, route);
+    }
 
-        let stops = route
+    // Returns (next stop, first path, end distance for next stop) for all of the stops in the
+    // route, to start a new bus somewhere.
+    pub fn bus_spawn_points(&self, id: BusRouteID) -> Vec<(StopIdx, PathRequest, Path, Distance)> {
+        let route = &self.routes[&id];
+        route
             .stops
             .iter()
             .map(|s| {
@@ -129,9 +129,7 @@ impl TransitSimState {
                     route.stops[s.next_stop_idx].driving_pos.dist_along(),
                 )
             })
-            .collect();
-        self.routes.insert(bus_route.id, route);
-        stops
+            .collect()
     }
 
     pub fn bus_created(&mut self, bus: CarID, route: BusRouteID, next_stop_idx: StopIdx) {
@@ -28,6 +28,28 @@ pub struct TripManager {
 
     car_id_counter: usize,
 
+    // Indices to look up trips, filled out as trips are created
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    trips_from_bldg: BTreeMap<BuildingID, Vec<TripID>>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    trips_to_bldg: BTreeMap<BuildingID, Vec<TripID>>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    trips_by_mode: BTreeMap<TripMode, Vec<TripID>>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    trips_by_departure: BTreeMap<Time, Vec<TripID>>,
+
     events: Vec<Event>,
 }
 
@@ -39,6 +61,10 @@ impl TripManager {
             active_trip_mode: BTreeMap::new(),
             unfinished_trips: 0,
             car_id_counter: 0,
+            trips_from_bldg: BTreeMap::new(),
+            trips_to_bldg: BTreeMap::new(),
+            trips_by_mode: BTreeMap::new(),
+            trips_by_departure: BTreeMap::new(),
             events: Vec::new(),
             pathfinding_upfront,
         }
@@ -113,6 +139,15 @@ impl TripManager {
             }
             _ => unreachable!(),
         };
+        let leg_modes = legs
+            .iter()
+            .map(|leg| match leg {
+                TripLeg::Walk(_) => TripMode::Walk,
+                TripLeg::Drive(c, _) => TripMode::from_agent(AgentID::Car(*c)),
+                TripLeg::RideBus(_, _) => TripMode::Transit,
+                TripLeg::Remote(_) => mode,
+            })
+            .collect();
         let trip = Trip {
             id,
             person,
@@ -123,6 +158,8 @@ impl TripManager {
             aborted: false,
             mode,
             legs: VecDeque::from(legs),
+            leg_modes,
+            phases: Vec::new(),
             start,
             end,
         };
@@ -154,6 +191,28 @@ impl TripManager {
             }
         }
         person.trips.push(id);
+
+        if let TripEndpoint::Bldg(b) = trip.start {
+            self.trips_from_bldg
+                .entry(b)
+                .or_insert_with(Vec::new)
+                .push(id);
+        }
+        if let TripEndpoint::Bldg(b) = trip.end {
+            self.trips_to_bldg
+                .entry(b)
+                .or_insert_with(Vec::new)
+                .push(id);
+        }
+        self.trips_by_mode
+            .entry(trip.mode)
+            .or_insert_with(Vec::new)
+            .push(id);
+        self.trips_by_departure
+            .entry(trip.departure)
+            .or_insert_with(Vec::new)
+            .push(id);
+
         self.trips.push(trip);
         id
     }
@@ -163,6 +222,7 @@ impl TripManager {
             panic!("{} is doing both {} and {}?", agent, t, other);
         }
         self.active_trip_mode.insert(agent, t);
+        self.events.push(Event::AgentSpawned(agent));
     }
 
     pub fn car_reached_parking_spot(
@@ -176,6 +236,7 @@ impl TripManager {
         scheduler: &mut Scheduler,
     ) {
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        self.events.push(Event::AgentDespawned(AgentID::Car(car)));
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front() {
@@ -238,6 +299,8 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
         trip.total_blocked_time += blocked_time;
 
         trip.assert_walking_leg(SidewalkSpot::deferred_parking_spot());
@@ -320,6 +383,8 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
         trip.total_blocked_time += blocked_time;
 
         trip.assert_walking_leg(spot.clone());
@@ -384,6 +449,7 @@ impl TripManager {
             bike_rack.sidewalk_pos.lane(),
         ));
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(bike)).unwrap().0];
+        self.events.push(Event::AgentDespawned(AgentID::Car(bike)));
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front() {
@@ -420,6 +486,8 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
         trip.total_blocked_time += blocked_time;
 
         trip.assert_walking_leg(SidewalkSpot::building(bldg, map));
@@ -480,8 +548,12 @@ impl TripManager {
                     self.active_trip_mode
                         .remove(&AgentID::Pedestrian(ped))
                         .unwrap();
+                    self.events
+                        .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
                     self.active_trip_mode
                         .insert(AgentID::BusPassenger(trip.person, bus), trip.id);
+                    self.events
+                        .push(Event::AgentSpawned(AgentID::BusPassenger(trip.person, bus)));
                     self.people[trip.person.0].on_bus = Some(bus);
                     None
                 } else {
@@ -505,12 +577,16 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
         trip.total_blocked_time += blocked_time;
 
         trip.legs.pop_front();
         walking.ped_boarded_bus(now, ped);
         self.active_trip_mode
             .insert(AgentID::BusPassenger(trip.person, bus), trip.id);
+        self.events
+            .push(Event::AgentSpawned(AgentID::BusPassenger(trip.person, bus)));
         self.people[trip.person.0].on_bus = Some(bus);
         (trip.id, trip.person)
     }
@@ -529,6 +605,8 @@ impl TripManager {
             .remove(&AgentID::BusPassenger(person, bus))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::BusPassenger(person, bus)));
         let start = match trip.legs.pop_front().unwrap() {
             TripLeg::RideBus(_, stop) => SidewalkSpot::bus_stop(stop, map),
             _ => unreachable!(),
@@ -562,6 +640,8 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
+        self.events
+            .push(Event::AgentDespawned(AgentID::Pedestrian(ped)));
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front() {
@@ -605,6 +685,7 @@ impl TripManager {
         scheduler: &mut Scheduler,
     ) {
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        self.events.push(Event::AgentDespawned(AgentID::Car(car)));
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front().unwrap() {
@@ -749,6 +830,7 @@ impl TripManager {
             if let TripLeg::Drive(c, _) = &trip.legs[0] {
                 if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
                     assert_eq!(t, trip.id);
+                    self.events.push(Event::AgentDespawned(AgentID::Car(*c)));
                 }
             }
         }
@@ -861,6 +943,78 @@ impl TripManager {
         Some((t.finished_at? - t.departure, t.total_blocked_time))
     }
 
+    pub fn trips_from_bldg(&self, b: BuildingID) -> &[TripID] {
+        self.trips_from_bldg
+            .get(&b)
+            .map(|trips| trips.as_slice())
+            .unwrap_or(&[])
+    }
+    pub fn trips_to_bldg(&self, b: BuildingID) -> &[TripID] {
+        self.trips_to_bldg
+            .get(&b)
+            .map(|trips| trips.as_slice())
+            .unwrap_or(&[])
+    }
+    pub fn trips_by_mode(&self, mode: TripMode) -> &[TripID] {
+        self.trips_by_mode
+            .get(&mode)
+            .map(|trips| trips.as_slice())
+            .unwrap_or(&[])
+    }
+    // Inclusive of both ends
+    pub fn trips_departing_between(&self, t1: Time, t2: Time) -> Vec<TripID> {
+        if t1 > t2 {
+            return Vec::new();
+        }
+        self.trips_by_departure
+            .range(t1..=t2)
+            .flat_map(|(_, trips)| trips.iter().cloned())
+            .collect()
+    }
+
+    pub fn trip_detail(&self, id: TripID) -> TripDetail {
+        let t = &self.trips[id.0];
+        let state = if let Some(time) = t.finished_at {
+            TripState::Finished(time)
+        } else if t.aborted {
+            TripState::Aborted
+        } else if !t.started {
+            TripState::NotStarted
+        } else {
+            TripState::Ongoing(self.trip_to_agent(id).ok())
+        };
+        TripDetail {
+            id,
+            person: t.person,
+            mode: t.mode,
+            start: t.start.clone(),
+            end: t.end.clone(),
+            departure: t.departure,
+            leg_modes: t.leg_modes.clone(),
+            legs: t
+                .phases
+                .iter()
+                .enumerate()
+                .map(|(idx, (start_time, phase_type))| TripLegDetail {
+                    phase_type: *phase_type,
+                    mode: phase_type.mode(),
+                    start_time: *start_time,
+                    end_time: t
+                        .phases
+                        .get(idx + 1)
+                        .map(|(time, _)| *time)
+                        .or(t.finished_at),
+                })
+                .collect(),
+            state,
+            total_blocked_time: t.total_blocked_time,
+        }
+    }
+
+    pub fn trip_phase_starting(&mut self, now: Time, id: TripID, phase_type: TripPhaseType) {
+        self.trips[id.0].phases.push((now, phase_type));
+    }
+
     pub fn bldg_to_people(&self, b: BuildingID) -> Vec<PersonID> {
         let mut people = Vec::new();
         for p in &self.people {
@@ -881,6 +1035,9 @@ impl TripManager {
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips[id.0].person
     }
+    pub fn maybe_trip_to_person(&self, id: TripID) -> Option<PersonID> {
+        Some(self.trips.get(id.0)?.person)
+    }
 
     fn person_finished_trip(
         &mut self,
@@ -944,6 +1101,7 @@ impl TripManager {
             return;
         }
         self.trips[trip.0].started = true;
+        self.events.push(Event::TripStarted(trip, person.id));
 
         match spec {
             TripSpec::VehicleAppearing {
@@ -1251,6 +1409,10 @@ struct Trip {
     total_blocked_time: Duration,
     aborted: bool,
     legs: VecDeque<TripLeg>,
+    // The mode of every leg, even after it's done
+    leg_modes: Vec<TripMode>,
+    // When each phase started
+    phases: Vec<(Time, TripPhaseType)>,
     mode: TripMode,
     start: TripEndpoint,
     end: TripEndpoint,
@@ -1395,6 +1557,48 @@ pub enum TripEndpoint {
     Border(IntersectionID, Option<OffMapLocation>),
 }
 
+// Everything about one trip, for callers outside the sim
+#[derive(Clone, Debug)]
+pub struct TripDetail {
+    pub id: TripID,
+    pub person: PersonID,
+    pub mode: TripMode,
+    pub start: TripEndpoint,
+    pub end: TripEndpoint,
+    pub departure: Time,
+    // The mode of each planned leg, including the ones already done
+    pub leg_modes: Vec<TripMode>,
+    // What's actually happened so far
+    pub legs: Vec<TripLegDetail>,
+    pub state: TripState,
+    pub total_blocked_time: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct TripLegDetail {
+    pub phase_type: TripPhaseType,
+    // None when the leg is spent waiting
+    pub mode: Option<TripMode>,
+    pub start_time: Time,
+    // None if the leg is ongoing, or the trip was aborted during it
+    pub end_time: Option<Time>,
+}
+
+impl TripLegDetail {
+    pub fn duration(&self, now: Time) -> Duration {
+        self.end_time.unwrap_or(now) - self.start_time
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TripState {
+    NotStarted,
+    // The agent currently doing the trip, if there is one at this moment
+    Ongoing(Option<AgentID>),
+    Finished(Time),
+    Aborted,
+}
+
 pub enum TripResult<T> {
     Ok(T),
     ModeChange,
@@ -0,0 +1,257 @@
+use crate::{AgentID, Sim};
+use geom::{Duration, Time};
+use map_model::{IntersectionID, LaneID, Map, PathStep};
+
+// Something to keep an eye on while the sim runs. It fires when its condition goes from false to
+// true, not the whole time the condition holds.
+#[derive(Clone)]
+pub struct Watchpoint {
+    pub condition: Condition,
+    pub enabled: bool,
+    // Pause the sim when this fires
+    pub pause: bool,
+    // Every time this fired, in order
+    pub fired: Vec<Time>,
+    was_true: bool,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Condition {
+    Compare(Metric, Comparison, f64),
+    // True once the agent's path doesn't cross the intersection anymore
+    AgentReaches(AgentID, IntersectionID),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Metric {
+    // Vehicles stopped on the lane
+    QueueLength(LaneID),
+    // Minutes taken so far by the longest trip still happening
+    LongestTrip,
+    // Minutes the agent waiting longest at the intersection has been there
+    IntersectionDelay(IntersectionID),
+    ActiveAgents,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+pub struct Watchpoints {
+    pub list: Vec<Watchpoint>,
+    // How much sim time passes between checks
+    pub interval: Duration,
+    last_checked: Option<Time>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Watchpoints {
+        Watchpoints {
+            list: Vec::new(),
+            interval: Duration::seconds(10.0),
+            last_checked: None,
+        }
+    }
+
+    // If the condition is already true, this waits for it to become false and true again.
+    pub fn add(&mut self, condition: Condition, pause: bool, sim: &Sim, map: &Map) {
+        let was_true = condition.check(sim, map);
+        self.list.push(Watchpoint {
+            condition,
+            enabled: true,
+            pause,
+            fired: Vec::new(),
+            was_true,
+        });
+    }
+
+    pub fn toggle(&mut self, idx: usize, sim: &Sim, map: &Map) {
+        let w = &mut self.list[idx];
+        w.enabled = !w.enabled;
+        // Anything that happened while disabled doesn't count
+        w.was_true = w.condition.check(sim, map);
+    }
+
+    // Call after every step; this only does work once the interval has passed. Returns the
+    // indices of the watchpoints that just fired.
+    pub fn check(&mut self, sim: &Sim, map: &Map) -> Vec<usize> {
+        let now = sim.time();
+        if let Some(last) = self.last_checked {
+            if now < last {
+                // The sim was reset or rewound, so forget the future
+                for w in &mut self.list {
+                    w.fired.retain(|t| *t <= now);
+                    w.was_true = w.condition.check(sim, map);
+                }
+                self.last_checked = Some(now);
+                return Vec::new();
+            }
+            if now - last < self.interval {
+                return Vec::new();
+            }
+        }
+        self.last_checked = Some(now);
+
+        let mut fired = Vec::new();
+        for (idx, w) in self.list.iter_mut().enumerate() {
+            if !w.enabled {
+                continue;
+            }
+            let is_true = w.condition.check(sim, map);
+            if is_true && !w.was_true {
+                w.fired.push(now);
+                fired.push(idx);
+            }
+            w.was_true = is_true;
+        }
+        fired
+    }
+
+    pub fn all_fired(&self) -> Vec<Time> {
+        let mut times: Vec<Time> = self
+            .list
+            .iter()
+            .flat_map(|w| w.fired.iter().cloned())
+            .collect();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        times
+    }
+}
+
+impl Condition {
+    pub fn check(&self, sim: &Sim, map: &Map) -> bool {
+        match self {
+            Condition::Compare(metric, cmp, value) => {
+                let current = metric.measure(sim, map);
+                match cmp {
+                    Comparison::Above => current > *value,
+                    Comparison::Below => current < *value,
+                }
+            }
+            Condition::AgentReaches(agent, i) => {
+                if !sim.does_agent_exist(*agent) || sim.get_path(*agent).is_none() {
+                    return false;
+                }
+                !upcoming_intersections(sim, *agent).contains(i)
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::Compare(metric, cmp, value) => format!(
+                "{} {} {}",
+                metric.describe(),
+                match cmp {
+                    Comparison::Above => "above",
+                    Comparison::Below => "below",
+                },
+                value
+            ),
+            Condition::AgentReaches(agent, i) => format!("{} reaches {}", agent, i),
+        }
+    }
+}
+
+impl Metric {
+    pub fn measure(&self, sim: &Sim, map: &Map) -> f64 {
+        match self {
+            Metric::QueueLength(l) => sim.lane_stats(*l).queue_len as f64,
+            Metric::LongestTrip => longest_trip(sim)
+                .map(|(_, dt)| dt.inner_seconds() / 60.0)
+                .unwrap_or(0.0),
+            Metric::IntersectionDelay(i) => sim
+                .worst_delay(map)
+                .1
+                .get(i)
+                .map(|dt| dt.inner_seconds() / 60.0)
+                .unwrap_or(0.0),
+            Metric::ActiveAgents => sim.active_agents().len() as f64,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Metric::QueueLength(l) => format!("vehicles queued on {}", l),
+            Metric::LongestTrip => "minutes the longest trip has taken".to_string(),
+            Metric::IntersectionDelay(i) => format!("minutes of delay at {}", i),
+            Metric::ActiveAgents => "active agents".to_string(),
+        }
+    }
+}
+
+// The intersections an agent's path still crosses, in order. Only these make sense for
+// Condition::AgentReaches; any other intersection already counts as reached.
+pub fn upcoming_intersections(sim: &Sim, agent: AgentID) -> Vec<IntersectionID> {
+    let mut results = Vec::new();
+    if let Some(path) = sim.get_path(agent) {
+        // The first step is where the agent is now
+        for step in path.get_steps().iter().skip(1) {
+            if let PathStep::Turn(t) = step {
+                if results.last() != Some(&t.parent) {
+                    results.push(t.parent);
+                }
+            }
+        }
+    }
+    results
+}
+
+// The agent whose trip has been going on longest, and for how long
+pub fn longest_trip(sim: &Sim) -> Option<(AgentID, Duration)> {
+    let now = sim.time();
+    sim.active_agents()
+        .into_iter()
+        .filter_map(|a| {
+            let trip = sim.agent_to_trip(a)?;
+            Some((a, now - sim.trip_info(trip).0))
+        })
+        .max_by(|(_, dt1), (_, dt2)| dt1.partial_cmp(dt2).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimOptions;
+    use abstutil::Timer;
+    use map_model::SyntheticMap;
+
+    #[test]
+    fn test_fires_once() {
+        let mut timer = Timer::throwaway();
+        let map = SyntheticMap::canned("four_way_signal")
+            .unwrap()
+            .build(&mut timer)
+            .unwrap();
+        let mut sim = Sim::new(&map, SimOptions::new("test_watchpoints"), &mut timer);
+
+        // Already true, so this waits for it to change
+        let mut watchpoints = Watchpoints::new();
+        watchpoints.add(
+            Condition::Compare(Metric::ActiveAgents, Comparison::Below, 1.0),
+            true,
+            &sim,
+            &map,
+        );
+        assert!(watchpoints.check(&sim, &map).is_empty());
+
+        // Pretend it was false at the last check
+        watchpoints.list[0].was_true = false;
+        sim.timed_step(&map, Duration::seconds(5.0), &mut None, &mut timer);
+        assert!(watchpoints.check(&sim, &map).is_empty());
+        sim.timed_step(&map, Duration::seconds(5.0), &mut None, &mut timer);
+        assert_eq!(watchpoints.check(&sim, &map), vec![0]);
+        assert_eq!(watchpoints.all_fired(), vec![sim.time()]);
+
+        // Staying true doesn't fire again
+        sim.timed_step(&map, Duration::seconds(10.0), &mut None, &mut timer);
+        assert!(watchpoints.check(&sim, &map).is_empty());
+
+        // Resetting the sim forgets about the future
+        let sim = Sim::new(&map, SimOptions::new("test_watchpoints"), &mut timer);
+        assert!(watchpoints.check(&sim, &map).is_empty());
+        assert!(watchpoints.all_fired().is_empty());
+    }
+}
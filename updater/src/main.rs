@@ -188,6 +188,11 @@ impl Manifest {
             {
                 continue;
             }
+            // Backups and half-written temporary files left behind by abstutil
+            let filename = entry.file_name().to_string_lossy();
+            if filename.starts_with('.') || filename.ends_with(".bak") {
+                continue;
+            }
 
             println!("> compute md5sum of {}", path);
 